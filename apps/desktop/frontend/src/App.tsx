@@ -13,7 +13,7 @@ import { SnapshotDialog } from './components/SnapshotDialog'
 import { TaskQueueDialog } from './components/TaskQueueDialog'
 import type { Snapshot } from './services/snapshot'
 import { readStorageFile, writeStorageFile } from './services/storage'
-import { type Task, createMigrateTask } from './services/taskQueue'
+import { type Task, type CloudError, createMigrateTask } from './services/taskQueue'
 import type { CloudStorageConfig } from './services/settings'
 import { notifyMigrateSuccess, notifyMigrateFailed } from './services/notification'
 
@@ -211,6 +211,8 @@ function App() {
           file_id: string | null
           message: string
           source_deleted: boolean
+          skipped?: boolean
+          error?: CloudError
         }
 
         const results = await invoke<UploadResult[]>('upload_to_cloud', {
@@ -1,5 +1,6 @@
 import { useState, useEffect } from 'react'
 import { useTranslation } from 'react-i18next'
+import { invoke } from '@tauri-apps/api/core'
 import { X, Eye, EyeOff, Check, AlertCircle, ChevronDown, ChevronUp, Brain, Cloud, Settings, Wifi, Shield, Trash2, BookmarkPlus } from 'lucide-react'
 import { showNotification } from '../services/notification'
 import {
@@ -90,6 +91,11 @@ function TabPanel(props: TabPanelProps) {
 
 const THEME_STORAGE_FILE = 'theme.txt'
 
+// validate_llm_key 的返回值
+type KeyStatus =
+  | { status: 'valid'; remaining_requests: number | null; remaining_tokens: number | null }
+  | { status: 'quota_exhausted'; message: string }
+
 export function AISettings({ onClose, initialTab = 0, onSaved, themePreference: externalThemePreference, onThemeChange, currentLanguage: externalCurrentLanguage, onLanguageChange }: Props) {
   const { t, i18n } = useTranslation()
   const theme = useTheme()
@@ -207,9 +213,25 @@ export function AISettings({ onClose, initialTab = 0, onSaved, themePreference:
   const handleSave = async () => {
     try {
       // 保存时带上按厂商解析后的 apiKey（供 loadSettings 之外使用）及 providerApiKeys
+      const apiKey = (settings.providerApiKeys ?? {})[getPresetId(settings.apiUrl, customApiPresets)] ?? ''
+      // 保存前校验 Key 与额度，失败只提示，不阻止保存
+      if (apiKey.trim() && settings.apiUrl?.trim() && settings.model?.trim()) {
+        try {
+          const status = await invoke<KeyStatus>('validate_llm_key', {
+            apiUrl: settings.apiUrl,
+            apiKey,
+            model: settings.model,
+          })
+          if (status.status === 'quota_exhausted') {
+            showNotification(t('settings.keyQuotaExhausted'), status.message)
+          }
+        } catch (err) {
+          showNotification(t('settings.keyInvalid'), String(err))
+        }
+      }
       await saveSettings({
         ...settings,
-        apiKey: (settings.providerApiKeys ?? {})[getPresetId(settings.apiUrl, customApiPresets)] ?? '',
+        apiKey,
       })
       await saveAppSettings(appSettings)
       setSaved(true)
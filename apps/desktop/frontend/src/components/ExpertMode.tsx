@@ -41,6 +41,27 @@ interface ScanResult {
     top_files?: TopFileEntry[] | null
 }
 
+/** 扫描进度事件（scan://progress），结构见 domain-model 的 progress_event */
+interface ScanProgressEvent {
+    version: number
+    scanned: number
+    current_path: string
+    percent?: number
+    /** 递增的事件编号，编号不大于已处理事件的为晚到的旧事件 */
+    seq?: number
+}
+
+/** 扫描完成事件（scan://done） */
+interface ScanDoneEvent {
+    version: number
+    path: string
+    strategy: 'mft' | 'walk'
+    file_count: number
+    total_size: number
+    scan_time_ms: number
+    warning?: string
+}
+
 const PROMPT_INSTRUCTION_FILE = 'prompt-instruction.txt'
 
 /** Windows 下将 "C:" 规范为 "C:\"，便于后端识别为卷根并走 MFT 全量扫描 */
@@ -294,6 +315,16 @@ function AIPromptPanel({ result }: { result: ScanResult }) {
     )
 }
 
+/** 在扫描树中按路径查找节点 */
+function findNodeByPath(node: TreemapNode, path: string): TreemapNode | null {
+    if (node.path === path) return node
+    for (const child of node.children ?? []) {
+        const found = findNodeByPath(child, path)
+        if (found) return found
+    }
+    return null
+}
+
 function displayPath(raw: string): string {
     return raw.replace(/^\\\\\?\\/, '')
 }
@@ -372,6 +403,8 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
     const [viewMode, setViewMode] = useState<'disk' | 'ai-prompt'>('disk')
     const [shallowDirs, setShallowDirs] = useState(true)
     const openedSettingsForStandardRef = useRef(false)
+    // 已处理的最大进度事件编号，每次扫描开始时归零
+    const progressSeqRef = useRef(0)
 
     // 标准模式 AI 分析状态
     const [aiAnalyzing, setAiAnalyzing] = useState(false)
@@ -402,14 +435,19 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
     useEffect(() => {
         let unlistenProgress: (() => void) | undefined
         let unlistenMftStatus: (() => void) | undefined
-        getCurrentWindow().listen<[number, string]>('scan-progress', (ev) => {
-            setProgressFiles(ev.payload[0])
-            if (ev.payload[1]) setProgressMessage(ev.payload[1])
+        getCurrentWindow().listen<ScanProgressEvent>('scan://progress', (ev) => {
+            const { seq } = ev.payload
+            if (seq !== undefined) {
+                if (seq <= progressSeqRef.current) return
+                progressSeqRef.current = seq
+            }
+            if (ev.payload.scanned) setProgressFiles(ev.payload.scanned)
+            if (ev.payload.current_path) setProgressMessage(ev.payload.current_path)
         })
             .then((fn) => { unlistenProgress = fn })
-        getCurrentWindow().listen<[string, boolean]>('scan-mft-status', (ev) => {
-            const [path, usedMft] = ev.payload
-            if (usedMft) {
+        getCurrentWindow().listen<ScanDoneEvent>('scan://done', (ev) => {
+            const { path, strategy } = ev.payload
+            if (strategy === 'mft') {
                 console.log('[DiskRookie] 本次扫描已成功使用 MFT 技术，路径:', path)
             } else {
                 console.log('[DiskRookie] 本次扫描未使用 MFT（普通目录遍历），路径:', path)
@@ -424,7 +462,7 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
     const runScan = useCallback(async (targetPath: string) => {
         if (!targetPath) return
         const pathToScan = normalizeScanPath(targetPath)
-        setStatus('scanning'); setErrorMsg(''); setResult(null); setProgressFiles(0); setProgressMessage(''); setAnalysisResult(null); setActionFilter('all'); setActionOverrides(new Map());
+        progressSeqRef.current = 0; setStatus('scanning'); setErrorMsg(''); setResult(null); setProgressFiles(0); setProgressMessage(''); setAnalysisResult(null); setActionFilter('all'); setActionOverrides(new Map());
         try {
             const appSettings = await loadAppSettings()
             const useMft = appSettings.useMftScan !== false
@@ -467,8 +505,13 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
         }
     }, [runScan])
 
-    const handleDelete = useCallback(async (itemPath: string) => {
-        await deleteItem(itemPath)
+    // 单项删除时以通知显示后端的结果说明；批量执行由调用方汇总通知
+    const handleDelete = useCallback(async (itemPath: string, notify = true) => {
+        const node = result ? findNodeByPath(result.root, itemPath) : null
+        const deleted = await deleteItem(itemPath, { rawPath: node?.raw_path, toTrash: true })
+        if (notify) {
+            await showNotification(t('common.delete'), deleted.message)
+        }
         setDeletedPaths(prev => new Set([...prev, itemPath]))
         // 从选中项中移除已删除的项
         setSelectedItems(prev => {
@@ -476,7 +519,7 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
             next.delete(itemPath)
             return next
         })
-    }, [])
+    }, [result, t])
 
     // 处理选中状态变化
     const handleSelectChange = useCallback((path: string, selected: boolean) => {
@@ -681,7 +724,7 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
             try {
                 const actualAction = getActualAction(item.path, item.action)
                 if (actualAction === 'delete') {
-                    await handleDelete(item.path)
+                    await handleDelete(item.path, false)
                     successCount++
                 } else if (actualAction === 'move') {
                     // 执行迁移操作
@@ -1537,6 +1580,9 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
                                     <div className="px-3 py-1.5 bg-secondary text-primary rounded-lg text-[11px] font-semibold flex gap-2 items-center">
                                         <span className="truncate max-w-[200px] text-white/90">{hoverNode.name}</span>
                                         <span className="bg-primary/20 px-1.5 rounded text-[10px] shrink-0">{formatBytes(hoverNode.size)}</span>
+                                        {hoverNode.system_managed && (
+                                            <span className="text-white/70 font-normal shrink-0">{t('expertMode.systemManagedTip')}</span>
+                                        )}
                                     </div>
                                 )}
                             </div>
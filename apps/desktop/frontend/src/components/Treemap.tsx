@@ -7,7 +7,15 @@ export interface TreemapNode {
   is_dir?: boolean
   /** Unix 时间戳（秒），最近修改时间 */
   modified?: number | null
+  /** 页面文件、休眠文件等由系统管理的文件：显示大小但不可清理 */
+  system_managed?: boolean
   children?: TreemapNode[]
+  /** 子节点已被后端截断（浅层树），展开时需再加载 */
+  has_more?: boolean
+  /** 截断前的直接子节点数 */
+  children_count?: number
+  /** 文件名含未配对代理项时的原始 UTF-16 路径，删除时需随 path 一并传给后端 */
+  raw_path?: number[]
 }
 
 interface Block {
@@ -310,10 +310,32 @@ Please carefully analyze the data and provide reasonable, safe suggestions. All
   }
 }
 
+export interface DeleteItemOptions {
+  /** 扫描结果中节点的原始 UTF-16 路径（文件名含未配对代理项时才有），按显示路径找不到文件时需要 */
+  rawPath?: number[]
+  /** 为 false 时永久删除，默认移到系统回收站 */
+  toTrash?: boolean
+  /** 设置后移入隔离区，宽限期（天）内可恢复 */
+  quarantineDays?: number
+}
+
+/** 后端 `delete_item` 的结果 */
+export interface DeleteItemResult {
+  outcome: 'quarantined' | 'trashed' | 'deleted'
+  is_dir: boolean
+  /** 给用户看的说明 */
+  message: string
+}
+
 // 删除文件/目录
-export async function deleteItem(path: string): Promise<void> {
+export async function deleteItem(path: string, options: DeleteItemOptions = {}): Promise<DeleteItemResult> {
   try {
-    await invoke('delete_item', { path })
+    return await invoke<DeleteItemResult>('delete_item', {
+      path,
+      rawPath: options.rawPath,
+      toTrash: options.toTrash,
+      quarantineDays: options.quarantineDays,
+    })
   } catch (error) {
     throw new Error(`删除失败: ${error}`)
   }
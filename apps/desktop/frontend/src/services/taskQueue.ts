@@ -61,6 +61,12 @@ export function createMigrateTask(
   }
 }
 
+// 上传失败原因（对应后端 CloudError）
+export interface CloudError {
+  kind: 'auth_expired' | 'quota_exceeded' | 'network' | 'not_found' | 'server' | 'cancelled' | 'local'
+  message?: string
+}
+
 // 上传结果类型
 interface UploadResult {
   success: boolean
@@ -68,6 +74,27 @@ interface UploadResult {
   file_id: string | null
   message: string
   source_deleted: boolean
+  skipped?: boolean
+  /** 目标中已有相同文件（同名且校验和一致），未重新上传 */
+  already_uploaded?: boolean
+  error?: CloudError
+}
+
+// 云存储目标连接测试结果（对应后端 TargetStatus）
+export interface TargetStatus {
+  provider: string
+  name: string
+  target: string
+  writable: boolean
+  free_bytes: number | null
+  message?: string
+}
+
+// 测试云存储目标：校验登录状态、目标文件夹与写入权限；失败时抛出 CloudError
+export async function testCloudTarget(
+  config: { provider: string; name: string; access_token: string; target_path: string }
+): Promise<TargetStatus> {
+  return await invoke<TargetStatus>('test_cloud_target', { config })
 }
 
 // 执行上传任务
@@ -125,6 +152,9 @@ export async function executeUploadTask(
 
     if (!allSuccess) {
       const failedResults = results.filter(r => !r.success)
+      if (failedResults.some(r => r.error?.kind === 'auth_expired')) {
+        throw new Error(`${failedResults.map(r => r.message).join('; ')}（请在设置中重新登录）`)
+      }
       throw new Error(failedResults.map(r => r.message).join('; '))
     }
     
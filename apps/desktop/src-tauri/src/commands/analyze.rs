@@ -1,5 +1,42 @@
+use std::path::Path;
+
+use ai_disk_common::SharedConfig;
+use ai_disk_engine::ReportFormat;
+use ai_disk_scanner::{is_system_volume_root, ScanOptions};
+use tauri::{async_runtime, State};
+
+use super::plan::{JunkRulesState, KeepListState};
+
 #[tauri::command]
 pub async fn analyze_disk(path: String) -> Result<String, String> {
     let _ = path;
     Ok(r#"{"status":"ok","message":"分析功能待实现"}"#.to_string())
 }
+
+/// 一次完成扫描、规则清理计划与统计，返回可贴进工单的报告（Markdown 或 HTML）
+#[tauri::command]
+pub async fn analyze_and_report(
+    config: State<'_, SharedConfig>,
+    rules: State<'_, JunkRulesState>,
+    keep: State<'_, KeepListState>,
+    path: String,
+    format: Option<ReportFormat>,
+) -> Result<String, String> {
+    let path = path.trim().to_string();
+    let opts = ScanOptions {
+        realistic_sizes: is_system_volume_root(Path::new(&path)),
+        exclude_dirs: config.current().scan_exclude_dirs(),
+        ..ScanOptions::default()
+    };
+    let scan = async_runtime::spawn_blocking(move || ai_disk_scanner::scan(&path, &opts))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.diagnostic())?;
+    let keep = keep.list.lock().unwrap();
+    Ok(ai_disk_engine::analyze_and_report(
+        &scan,
+        &rules.0,
+        &keep,
+        format.unwrap_or_default(),
+    ))
+}
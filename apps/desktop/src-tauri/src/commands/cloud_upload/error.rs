@@ -0,0 +1,52 @@
+//! 云存储上传的结构化错误：前端按 `kind` 区分「重新登录」「换一个目标」「重试」等处理方式
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 上传失败的原因，序列化为 `{ "kind": "auth_expired", "message": "..." }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum CloudError {
+    /// 令牌过期、被撤销或权限不足，需要重新授权
+    AuthExpired(String),
+    /// 目标存储空间不足
+    QuotaExceeded(String),
+    /// 连接失败、超时等网络问题，可重试
+    Network(String),
+    /// 目标文件夹、存储桶或上传会话不存在
+    NotFound(String),
+    /// 服务端错误或无法识别的响应
+    Server(String),
+    /// 用户取消
+    Cancelled,
+    /// 本地错误：读取文件失败、配置缺失等
+    Local(String),
+}
+
+impl CloudError {
+    /// 请求未得到响应，或响应体无法解析
+    pub(crate) fn request(action: &str, e: &reqwest::Error) -> Self {
+        let message = format!("{}失败: {}", action, e);
+        if e.is_decode() {
+            CloudError::Server(message)
+        } else {
+            CloudError::Network(message)
+        }
+    }
+}
+
+impl fmt::Display for CloudError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloudError::AuthExpired(msg) => write!(f, "授权已失效，请重新登录: {}", msg),
+            CloudError::QuotaExceeded(msg) => write!(f, "目标存储空间不足: {}", msg),
+            CloudError::Network(msg) => write!(f, "网络错误: {}", msg),
+            CloudError::NotFound(msg) => write!(f, "目标不存在: {}", msg),
+            CloudError::Server(msg) => write!(f, "服务端错误: {}", msg),
+            CloudError::Cancelled => f.write_str("上传已取消"),
+            CloudError::Local(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for CloudError {}
@@ -0,0 +1,1421 @@
+//! Google Drive 上传：Resumable Upload API，分块必须顺序上传
+
+use futures::lock::Mutex as AsyncMutex;
+use log::{debug, error, info, warn};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::{
+    check_target, send_with_retry, upload_from, upload_with_progress, CloudError, CloudStorage,
+    PartOutcome, ProgressEmitter, TargetStatus, UploadConfig, UploadPart, UploadSessionRecord,
+    UploadSessionStore, UploadedFile, PROBE_FILE_NAME,
+};
+
+/// Google API 地址
+const GOOGLE_API_BASE: &str = "https://www.googleapis.com";
+/// Google OAuth 令牌端点所在地址
+const GOOGLE_OAUTH_BASE: &str = "https://oauth2.googleapis.com";
+
+/// 进程内共享的目标文件夹 ID 缓存
+static FOLDER_CACHE: LazyLock<FolderCache> = LazyLock::new(FolderCache::default);
+
+/// 单个路径的解析结果，解析期间持锁实现单飞
+type FolderSlot = Arc<AsyncMutex<Option<String>>>;
+
+/// 目标路径 -> 文件夹 ID 的缓存，避免批量上传到同一目录时每个文件都逐级查询。
+/// 键包含 access token 的哈希，换账号/换 token 后自然失效；同一键的解析单飞执行，
+/// 并发上传到同一新路径时只会创建一次文件夹
+#[derive(Default)]
+struct FolderCache {
+    entries: Mutex<HashMap<(u64, String), FolderSlot>>,
+}
+
+impl FolderCache {
+    async fn get_or_resolve<F, Fut>(
+        &self,
+        access_token: &str,
+        path: &str,
+        resolve: F,
+    ) -> Result<String, CloudError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<String, CloudError>>,
+    {
+        let mut hasher = DefaultHasher::new();
+        access_token.hash(&mut hasher);
+        let key = (hasher.finish(), path.trim_matches('/').to_string());
+        let slot = self.entries.lock().unwrap().entry(key).or_default().clone();
+        // 持有该路径的锁直到解析完成，其他并发请求等待后直接读取结果
+        let mut slot = slot.lock().await;
+        if let Some(id) = slot.as_ref() {
+            debug!("命中文件夹缓存: {} -> {}", path, id);
+            return Ok(id.clone());
+        }
+        let id = resolve().await?;
+        *slot = Some(id.clone());
+        Ok(id)
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// 账号授权变化（刷新、撤销）时清空文件夹缓存
+pub fn invalidate_folder_cache() {
+    FOLDER_CACHE.clear();
+}
+
+/// 刷新访问令牌所需的 OAuth 凭据。访问令牌一小时即过期，配置后上传分块遇到 401 时
+/// 自动换取新令牌并重传该分块（上传会话不受影响）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenRefresh {
+    pub refresh_token: String,
+    pub client_id: String,
+    /// 桌面应用类型的 OAuth 客户端可以没有密钥
+    #[serde(default)]
+    pub client_secret: String,
+}
+
+/// 上传过程中刷新了访问令牌，前端应保存新令牌供后续上传使用
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenRefreshedEvent {
+    pub name: String,
+    pub access_token: String,
+}
+
+/// 把上传会话记入 `UploadSessionStore`，应用重启后可据此续传
+struct SessionJournal<'a> {
+    store: &'a UploadSessionStore,
+    task_id: String,
+    file_path: String,
+}
+
+/// 续传前向服务端查询到的会话状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ResumePoint {
+    /// 服务端已接收的字节数，从这里继续上传
+    Offset(u64),
+    /// 上传其实已经完成
+    Completed { file_id: String },
+}
+
+/// 308 响应的 `Range` 头（如 `bytes=0-42`）表示服务端已接收的字节数；没有该头表示尚未收到任何数据
+fn received_bytes(range: Option<&str>) -> Option<u64> {
+    let Some(range) = range else {
+        return Some(0);
+    };
+    let (_, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    end.parse::<u64>().ok().map(|end| end + 1)
+}
+
+/// Google Drive Resumable Upload：分块必须按顺序上传，最后一块的响应携带文件 ID
+struct GoogleDriveStorage<'a> {
+    client: reqwest::Client,
+    config: &'a UploadConfig,
+    /// 当前访问令牌，初始为配置中的令牌，刷新后更新
+    access_token: Mutex<String>,
+    /// API 地址，测试时指向 mock 服务
+    api_base: String,
+    /// OAuth 令牌端点地址，测试时指向 mock 服务
+    oauth_base: String,
+    folders: &'a FolderCache,
+    /// 配置后记录上传会话与进度，见 `SessionJournal`
+    journal: Option<SessionJournal<'a>>,
+}
+
+/// 按 HTTP 状态码与 Google API 错误体（`error.errors[].reason` / `error.status`）分类错误
+fn google_error(status: reqwest::StatusCode, body: &str, action: &str) -> CloudError {
+    let json: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let error = &json["error"];
+    let reason = error["errors"][0]["reason"]
+        .as_str()
+        .or_else(|| error["status"].as_str())
+        .unwrap_or_default();
+    let message = format!(
+        "{}失败 ({}): {}",
+        action,
+        status.as_u16(),
+        error["message"].as_str().unwrap_or(body)
+    );
+    match (status.as_u16(), reason) {
+        (_, "storageQuotaExceeded") => CloudError::QuotaExceeded(message),
+        (401, _) | (_, "authError" | "insufficientPermissions" | "UNAUTHENTICATED") => {
+            CloudError::AuthExpired(message)
+        }
+        (404, _) | (_, "notFound") => CloudError::NotFound(message),
+        _ => CloudError::Server(message),
+    }
+}
+
+/// 读取失败响应的内容并分类
+async fn error_from_response(response: reqwest::Response, action: &str) -> CloudError {
+    let status = response.status();
+    let error_text = response.text().await.unwrap_or_default();
+    error!("{}失败，状态码: {}，错误: {}", action, status, error_text);
+    google_error(status, &error_text, action)
+}
+
+/// Drive 查询语句中的字符串字面量转义
+fn query_literal(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// 本地文件的 MD5（十六进制小写），与 Drive 的 `md5Checksum` 比较
+fn local_md5(path: &Path) -> Result<String, CloudError> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| CloudError::Local(format!("打开文件失败: {}", e)))?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| CloudError::Local(format!("读取文件失败: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 从 `about.get` 的 `storageQuota` 计算剩余空间；没有 `limit` 表示无上限
+fn free_space_from_quota(quota: &serde_json::Value) -> Option<u64> {
+    let field = |name: &str| quota[name].as_str().and_then(|v| v.parse::<u64>().ok());
+    let limit = field("limit")?;
+    Some(limit.saturating_sub(field("usage").unwrap_or(0)))
+}
+
+impl CloudStorage for GoogleDriveStorage<'_> {
+    /// 上传 URI
+    type Session = String;
+
+    fn supports_parallel_parts(&self) -> bool {
+        false
+    }
+
+    async fn free_space(&self) -> Result<Option<u64>, CloudError> {
+        let response = self
+            .client
+            .get(format!(
+                "{}/drive/v3/about?fields=storageQuota",
+                self.api_base
+            ))
+            .header("Authorization", self.bearer())
+            .send()
+            .await
+            .map_err(|e| CloudError::request("查询存储配额", &e))?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response, "查询存储配额").await);
+        }
+        let about: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| CloudError::request("解析存储配额", &e))?;
+        Ok(free_space_from_quota(&about["storageQuota"]))
+    }
+
+    /// 目标文件夹 ID
+    async fn resolve_target(&self) -> Result<String, CloudError> {
+        let config = self.config;
+        debug!("获取或创建目标文件夹: {}", config.target_path);
+        if config.target_path == "/" {
+            debug!("使用根目录");
+            return Ok("root".to_string());
+        }
+        self.folders
+            .get_or_resolve(&config.access_token, &config.target_path, || {
+                self.create_or_get_folder(&config.target_path)
+            })
+            .await
+    }
+
+    async fn write_probe(&self) -> Result<(), CloudError> {
+        let data = b"ok".to_vec();
+        let upload_uri = self
+            .begin_upload(PROBE_FILE_NAME, data.len() as u64)
+            .await?;
+        let part = UploadPart {
+            index: 0,
+            offset: 0,
+            total_size: data.len() as u64,
+            data,
+        };
+        let PartOutcome::Completed { file_id } = self.upload_part(&upload_uri, part).await? else {
+            return Err(CloudError::Server("探测文件上传未完成".to_string()));
+        };
+
+        let response = self
+            .client
+            .delete(format!("{}/drive/v3/files/{}", self.api_base, file_id))
+            .header("Authorization", self.bearer())
+            .send()
+            .await
+            .map_err(|e| CloudError::request("删除探测文件", &e))?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response, "删除探测文件").await);
+        }
+        Ok(())
+    }
+
+    /// 按文件名列出目标文件夹中的文件，大小一致时才计算本地 MD5 与 `md5Checksum` 比较
+    async fn find_existing(
+        &self,
+        file_name: &str,
+        path: &Path,
+        file_size: u64,
+    ) -> Result<Option<String>, CloudError> {
+        let folder_id = self.resolve_target().await?;
+        let query = format!(
+            "name='{}' and '{}' in parents and trashed=false",
+            query_literal(file_name),
+            folder_id
+        );
+        let response = self
+            .client
+            .get(format!(
+                "{}/drive/v3/files?q={}&fields=files(id,size,md5Checksum)",
+                self.api_base,
+                urlencoding::encode(&query)
+            ))
+            .header("Authorization", self.bearer())
+            .send()
+            .await
+            .map_err(|e| CloudError::request("查询已有文件", &e))?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response, "查询已有文件").await);
+        }
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| CloudError::request("解析已有文件", &e))?;
+        let same_size: Vec<&serde_json::Value> = result["files"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|f| f["size"].as_str().and_then(|s| s.parse::<u64>().ok()) == Some(file_size))
+            .collect();
+        if same_size.is_empty() {
+            return Ok(None);
+        }
+        let md5 = local_md5(path)?;
+        Ok(same_size
+            .into_iter()
+            .find(|f| f["md5Checksum"].as_str() == Some(md5.as_str()))
+            .and_then(|f| f["id"].as_str())
+            .map(str::to_string))
+    }
+
+    async fn begin_upload(&self, file_name: &str, file_size: u64) -> Result<String, CloudError> {
+        // 第一步：获取或创建目标文件夹
+        let folder_id = self.resolve_target().await?;
+        info!("目标文件夹ID: {}", folder_id);
+
+        // 第二步：初始化 Resumable Upload Session
+        debug!("初始化 Resumable Upload Session");
+        let metadata = serde_json::json!({
+            "name": file_name,
+            "parents": [folder_id]
+        });
+
+        let init_response = self
+            .client
+            .post(format!(
+                "{}/upload/drive/v3/files?uploadType=resumable",
+                self.api_base
+            ))
+            .header("Authorization", self.bearer())
+            .header("Content-Type", "application/json; charset=UTF-8")
+            .header("X-Upload-Content-Type", "application/octet-stream")
+            .header("X-Upload-Content-Length", file_size.to_string())
+            .json(&metadata)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("初始化上传会话失败: {}", e);
+                CloudError::request("初始化上传会话", &e)
+            })?;
+
+        if !init_response.status().is_success() {
+            return Err(error_from_response(init_response, "初始化上传会话").await);
+        }
+
+        // 获取上传 URI
+        let upload_uri = init_response
+            .headers()
+            .get("location")
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| {
+                error!("响应中没有上传 URI");
+                CloudError::Server("响应中没有上传 URI".to_string())
+            })?
+            .to_string();
+
+        info!("获取到上传 URI: {}", upload_uri);
+        if let Some(journal) = &self.journal {
+            let record = UploadSessionRecord {
+                task_id: journal.task_id.clone(),
+                provider: self.config.provider.clone(),
+                target: self.config.name.clone(),
+                file_path: journal.file_path.clone(),
+                file_size,
+                upload_uri: upload_uri.clone(),
+                uploaded: 0,
+            };
+            if let Err(e) = journal.store.save(record) {
+                warn!("记录上传会话失败，中途退出后将无法续传: {}", e);
+            }
+        }
+        Ok(upload_uri)
+    }
+
+    /// 连接错误与 5xx 按 `UploadConfig::retry` 退避重试；308 响应以服务端 `Range` 头为准，
+    /// 只收到部分数据时补传剩余部分，确认整个分块已接收后才返回
+    async fn upload_part(
+        &self,
+        upload_uri: &String,
+        part: UploadPart,
+    ) -> Result<PartOutcome, CloudError> {
+        let end = part.offset + part.data.len() as u64;
+        let mut from = part.offset;
+        let mut resends = 0;
+        loop {
+            let mut response = self.send_part(upload_uri, &part, from).await?;
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED
+                && self.config.token_refresh.is_some()
+            {
+                info!("访问令牌已过期，刷新后重传分块 {}", part.index);
+                self.refresh_access_token().await?;
+                response = self.send_part(upload_uri, &part, from).await?;
+            }
+
+            let status = response.status();
+
+            // 200 或 201 表示上传完成
+            if status == reqwest::StatusCode::OK || status == reqwest::StatusCode::CREATED {
+                // 解析响应获取文件 ID
+                let result: serde_json::Value = response.json().await.map_err(|e| {
+                    error!("解析响应失败: {}", e);
+                    CloudError::request("解析响应", &e)
+                })?;
+
+                let file_id = result["id"]
+                    .as_str()
+                    .ok_or_else(|| {
+                        error!("响应中没有文件 ID，响应内容: {:?}", result);
+                        CloudError::Server("响应中没有文件 ID".to_string())
+                    })?
+                    .to_string();
+
+                info!("上传成功，文件ID: {}", file_id);
+                self.forget_session();
+                return Ok(PartOutcome::Completed { file_id });
+            }
+            if status != reqwest::StatusCode::PERMANENT_REDIRECT {
+                // 其他状态码表示错误
+                return Err(error_from_response(response, "上传块").await);
+            }
+
+            // 308 Resume Incomplete：按服务端确认的范围同步进度，而不是假定整块都已收到
+            let range = response
+                .headers()
+                .get("range")
+                .and_then(|v| v.to_str().ok());
+            let received = received_bytes(range)
+                .ok_or_else(|| CloudError::Server(format!("无法识别的 Range 头: {:?}", range)))?;
+            if received >= end {
+                self.record_progress(received);
+                return Ok(PartOutcome::Accepted { etag: None });
+            }
+            if received < part.offset {
+                return Err(CloudError::Server(format!(
+                    "服务端只确认收到 {} 字节，少于此前已确认的 {} 字节",
+                    received, part.offset
+                )));
+            }
+            self.record_progress(received);
+            resends += 1;
+            if resends >= self.config.retry.max_attempts {
+                return Err(CloudError::Server(format!(
+                    "分块 {} 补传 {} 次后服务端仍只收到 {}/{} 字节",
+                    part.index, resends, received, end
+                )));
+            }
+            warn!(
+                "服务端只收到 {}/{} 字节，补传分块 {} 的剩余部分",
+                received, end, part.index
+            );
+            from = received;
+        }
+    }
+
+    async fn complete_upload(
+        &self,
+        _upload_uri: &String,
+        _parts: Vec<PartOutcome>,
+    ) -> Result<String, CloudError> {
+        // 所有分块都返回 308 却没有得到文件 ID
+        Err(CloudError::Server("上传异常结束".to_string()))
+    }
+}
+
+/// 使用 Resumable Upload API 上传文件到 Google Drive（支持进度回调）
+pub(super) async fn upload_to_google_drive_resumable(
+    file_path: &str,
+    config: &UploadConfig,
+    app: &AppHandle,
+    task_id: &str,
+    cancel: &AtomicBool,
+) -> Result<UploadedFile, CloudError> {
+    debug!("准备上传文件到 Google Drive (Resumable): {}", file_path);
+    debug!("目标路径: {}", config.target_path);
+
+    let store = app
+        .path()
+        .home_dir()
+        .ok()
+        .map(|home| UploadSessionStore::in_home(&home));
+    let mut storage = GoogleDriveStorage::new(config, GOOGLE_API_BASE.to_string(), &FOLDER_CACHE);
+    if let Some(store) = &store {
+        storage = storage.with_journal(store, task_id, file_path);
+    }
+    let result = upload_with_progress(&storage, file_path, config, app, task_id, cancel).await;
+    if matches!(result, Err(CloudError::Cancelled)) {
+        storage.forget_session();
+    }
+    storage.emit_refreshed_token(app);
+    result
+}
+
+/// 续传 `record` 记录的上传，见 `resume_upload`
+pub(super) async fn resume_google_drive_upload(
+    record: &UploadSessionRecord,
+    config: &UploadConfig,
+    app: &AppHandle,
+    store: &UploadSessionStore,
+    cancel: &AtomicBool,
+) -> Result<UploadedFile, CloudError> {
+    let storage = GoogleDriveStorage::new(config, GOOGLE_API_BASE.to_string(), &FOLDER_CACHE)
+        .with_journal(store, &record.task_id, &record.file_path);
+    let mut progress =
+        ProgressEmitter::new(app, &record.task_id, &config.provider, record.file_size);
+    let result = resume_session(&storage, record, cancel, |uploaded, total| {
+        progress.on_part(uploaded, total)
+    })
+    .await;
+    storage.emit_refreshed_token(app);
+    let file_id = result?;
+    progress.finish();
+    Ok(UploadedFile {
+        file_id,
+        already_uploaded: false,
+    })
+}
+
+/// 查询服务端已接收的字节数，从该处继续上传。本地文件大小已变或会话已过期时删除记录并返回错误
+async fn resume_session(
+    storage: &GoogleDriveStorage<'_>,
+    record: &UploadSessionRecord,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(u64, u64) + Send,
+) -> Result<String, CloudError> {
+    let path = Path::new(&record.file_path);
+    let size = path.metadata().map(|m| m.len()).ok();
+    if size != Some(record.file_size) {
+        storage.forget_session();
+        return Err(CloudError::Local(format!(
+            "文件已删除或已改变，无法续传: {}",
+            record.file_path
+        )));
+    }
+    let point = match storage
+        .query_received(&record.upload_uri, record.file_size)
+        .await
+    {
+        Err(e @ CloudError::NotFound(_)) => {
+            storage.forget_session();
+            return Err(e);
+        }
+        other => other?,
+    };
+    let offset = match point {
+        ResumePoint::Completed { file_id } => {
+            storage.forget_session();
+            return Ok(file_id);
+        }
+        ResumePoint::Offset(offset) => offset,
+    };
+    info!(
+        "服务端已接收 {}/{} 字节，从断点继续上传",
+        offset, record.file_size
+    );
+    let result = upload_from(
+        storage,
+        &record.upload_uri,
+        path,
+        record.file_size,
+        offset,
+        cancel,
+        on_progress,
+    )
+    .await;
+    if matches!(result, Err(CloudError::Cancelled)) {
+        storage.forget_session();
+    }
+    result
+}
+
+/// 测试 Google Drive 目标，见 `test_cloud_target`
+pub(super) async fn test_google_drive_target(
+    config: &UploadConfig,
+) -> Result<TargetStatus, CloudError> {
+    let storage = GoogleDriveStorage::new(config, GOOGLE_API_BASE.to_string(), &FOLDER_CACHE);
+    check_target(&storage, config).await
+}
+
+impl<'a> GoogleDriveStorage<'a> {
+    fn new(config: &'a UploadConfig, api_base: String, folders: &'a FolderCache) -> Self {
+        GoogleDriveStorage {
+            client: reqwest::Client::new(),
+            config,
+            access_token: Mutex::new(config.access_token.clone()),
+            api_base,
+            oauth_base: GOOGLE_OAUTH_BASE.to_string(),
+            folders,
+            journal: None,
+        }
+    }
+
+    /// 把本次上传的会话记入 `store`，中途退出后可用 `resume_upload` 续传
+    fn with_journal(
+        mut self,
+        store: &'a UploadSessionStore,
+        task_id: &str,
+        file_path: &str,
+    ) -> Self {
+        self.journal = Some(SessionJournal {
+            store,
+            task_id: task_id.to_string(),
+            file_path: file_path.to_string(),
+        });
+        self
+    }
+
+    /// 删除本次上传的会话记录（已完成、已取消或无法续传）
+    fn forget_session(&self) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.store.remove(&journal.task_id, &self.config.name) {
+                warn!("删除上传会话记录失败: {}", e);
+            }
+        }
+    }
+
+    /// 更新会话记录中的已上传字节数
+    fn record_progress(&self, uploaded: u64) {
+        if let Some(journal) = &self.journal {
+            let store = journal.store;
+            if let Err(e) = store.set_uploaded(&journal.task_id, &self.config.name, uploaded) {
+                warn!("更新上传会话记录失败: {}", e);
+            }
+        }
+    }
+
+    /// 上传过程中刷新了令牌时通知前端保存
+    fn emit_refreshed_token(&self, app: &AppHandle) {
+        if let Some(access_token) = self.refreshed_token() {
+            let _ = app.emit(
+                "cloud-token-refreshed",
+                TokenRefreshedEvent {
+                    name: self.config.name.clone(),
+                    access_token,
+                },
+            );
+        }
+    }
+
+    /// 向上传 URI 发送空的 `PUT`（`Content-Range: bytes */<size>`），查询服务端已接收的范围
+    async fn query_received(
+        &self,
+        upload_uri: &str,
+        file_size: u64,
+    ) -> Result<ResumePoint, CloudError> {
+        let response = self
+            .client
+            .put(upload_uri)
+            .header("Authorization", self.bearer())
+            .header("Content-Length", "0")
+            .header("Content-Range", format!("bytes */{}", file_size))
+            .send()
+            .await
+            .map_err(|e| CloudError::request("查询上传进度", &e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::OK || status == reqwest::StatusCode::CREATED {
+            let result: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| CloudError::request("解析响应", &e))?;
+            let file_id = result["id"]
+                .as_str()
+                .ok_or_else(|| CloudError::Server("响应中没有文件 ID".to_string()))?;
+            return Ok(ResumePoint::Completed {
+                file_id: file_id.to_string(),
+            });
+        }
+        if status != reqwest::StatusCode::PERMANENT_REDIRECT {
+            return Err(error_from_response(response, "查询上传进度").await);
+        }
+        let range = response
+            .headers()
+            .get("range")
+            .and_then(|v| v.to_str().ok());
+        received_bytes(range)
+            .map(ResumePoint::Offset)
+            .ok_or_else(|| CloudError::Server(format!("无法识别的 Range 头: {:?}", range)))
+    }
+
+    fn bearer(&self) -> String {
+        format!("Bearer {}", self.access_token.lock().unwrap())
+    }
+
+    /// 上传过程中刷新得到的新令牌；未刷新时为 None
+    fn refreshed_token(&self) -> Option<String> {
+        let token = self.access_token.lock().unwrap();
+        (*token != self.config.access_token).then(|| token.clone())
+    }
+
+    /// 发送分块中从文件偏移 `from` 起的剩余数据，按 `UploadConfig::retry` 重试；
+    /// 重传时需要再次发送同一份数据，因此不转移 `part`
+    async fn send_part(
+        &self,
+        upload_uri: &str,
+        part: &UploadPart,
+        from: u64,
+    ) -> Result<reqwest::Response, CloudError> {
+        let data = &part.data[(from - part.offset) as usize..];
+        let content_range = &format!(
+            "bytes {}-{}/{}",
+            from,
+            from + data.len() as u64 - 1,
+            part.total_size
+        );
+        send_with_retry(&self.config.retry, "上传块", move || async move {
+            self.client
+                .put(upload_uri)
+                .header("Authorization", self.bearer())
+                .header("Content-Length", data.len().to_string())
+                .header("Content-Range", content_range.as_str())
+                .body(data.to_vec())
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("上传块失败: {}", e);
+                    CloudError::request("上传块", &e)
+                })
+        })
+        .await
+    }
+
+    /// 用刷新令牌换取新的访问令牌，之后的请求都使用新令牌。刷新令牌被撤销时返回 `AuthExpired`
+    async fn refresh_access_token(&self) -> Result<(), CloudError> {
+        let Some(refresh) = &self.config.token_refresh else {
+            return Err(CloudError::AuthExpired("未配置刷新令牌".to_string()));
+        };
+        let response = self
+            .client
+            .post(format!("{}/token", self.oauth_base))
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh.refresh_token.as_str()),
+                ("client_id", refresh.client_id.as_str()),
+                ("client_secret", refresh.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| CloudError::request("刷新访问令牌", &e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            error!("刷新访问令牌失败，状态码: {}，错误: {}", status, body);
+            return Err(CloudError::AuthExpired(format!(
+                "刷新访问令牌失败 ({}): {}",
+                status.as_u16(),
+                body
+            )));
+        }
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| CloudError::request("解析访问令牌", &e))?;
+        let token = result["access_token"]
+            .as_str()
+            .ok_or_else(|| CloudError::Server("响应中没有访问令牌".to_string()))?;
+        *self.access_token.lock().unwrap() = token.to_string();
+        info!("已刷新 {} 的访问令牌", self.config.name);
+        Ok(())
+    }
+
+    /// 创建或获取文件夹
+    async fn create_or_get_folder(&self, path: &str) -> Result<String, CloudError> {
+        debug!("创建或获取文件夹: {}", path);
+        let client = &self.client;
+
+        // 分割路径
+        let parts: Vec<&str> = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|p| !p.is_empty())
+            .collect();
+
+        debug!("路径分割为 {} 个部分: {:?}", parts.len(), parts);
+
+        let mut parent_id = "root".to_string();
+
+        // 逐级创建或查找文件夹
+        for folder_name in parts {
+            debug!("处理文件夹: {}，父文件夹ID: {}", folder_name, parent_id);
+            // 查找是否已存在
+            debug!("查询文件夹是否存在: {}", folder_name);
+            let query = format!(
+            "name='{}' and '{}' in parents and mimeType='application/vnd.google-apps.folder' and trashed=false",
+            folder_name, parent_id
+        );
+
+            let search_url = format!(
+                "{}/drive/v3/files?q={}&fields=files(id)",
+                self.api_base,
+                urlencoding::encode(&query)
+            );
+
+            let response = client
+                .get(&search_url)
+                .header("Authorization", self.bearer())
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("查询文件夹失败: {}", e);
+                    CloudError::request("查询文件夹", &e)
+                })?;
+
+            if !response.status().is_success() {
+                return Err(error_from_response(response, "查询文件夹").await);
+            }
+
+            let result: serde_json::Value = response.json().await.map_err(|e| {
+                error!("解析查询响应失败: {}", e);
+                CloudError::request("解析查询响应", &e)
+            })?;
+
+            // 如果找到了，使用现有的
+            if let Some(files) = result["files"].as_array() {
+                if !files.is_empty() {
+                    parent_id = files[0]["id"]
+                        .as_str()
+                        .ok_or_else(|| {
+                            error!("无效的文件夹 ID");
+                            CloudError::Server("无效的文件夹 ID".to_string())
+                        })?
+                        .to_string();
+                    debug!("找到现有文件夹，ID: {}", parent_id);
+                    continue;
+                }
+            }
+
+            // 没找到，创建新文件夹
+            debug!("文件夹不存在，创建新文件夹: {}", folder_name);
+            let metadata = serde_json::json!({
+                "name": folder_name,
+                "mimeType": "application/vnd.google-apps.folder",
+                "parents": [parent_id]
+            });
+
+            let response = client
+                .post(format!("{}/drive/v3/files", self.api_base))
+                .header("Authorization", self.bearer())
+                .header("Content-Type", "application/json")
+                .json(&metadata)
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("创建文件夹请求失败: {}", e);
+                    CloudError::request("创建文件夹", &e)
+                })?;
+
+            if !response.status().is_success() {
+                return Err(error_from_response(response, "创建文件夹").await);
+            }
+
+            let result: serde_json::Value = response.json().await.map_err(|e| {
+                error!("解析创建响应失败: {}", e);
+                CloudError::request("解析创建响应", &e)
+            })?;
+
+            parent_id = result["id"]
+                .as_str()
+                .ok_or_else(|| {
+                    error!("创建的文件夹没有 ID，响应: {:?}", result);
+                    CloudError::Server("创建的文件夹没有 ID".to_string())
+                })?
+                .to_string();
+            info!("成功创建文件夹: {}，ID: {}", folder_name, parent_id);
+        }
+
+        info!("文件夹路径处理完成，最终文件夹ID: {}", parent_id);
+        Ok(parent_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{upload_or_reuse, upload_parts, RetryPolicy};
+    use super::*;
+
+    fn config() -> UploadConfig {
+        UploadConfig {
+            provider: "google_drive".to_string(),
+            name: "Drive".to_string(),
+            access_token: "token".to_string(),
+            target_path: "/".to_string(),
+            s3: None,
+            endpoint: None,
+            bucket: None,
+            region: None,
+            secret_key: None,
+            token_refresh: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    fn temp_file(content: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("gdrive_test_{}", std::process::id()))
+            .join("f.bin");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    async fn mock_quota(
+        server: &mut mockito::ServerGuard,
+        limit: u64,
+        usage: u64,
+    ) -> mockito::Mock {
+        server
+            .mock("GET", "/drive/v3/about")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".to_string(),
+                "storageQuota".to_string(),
+            ))
+            .with_body(format!(
+                r#"{{"storageQuota":{{"limit":"{}","usage":"{}"}}}}"#,
+                limit, usage
+            ))
+            .create_async()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_target_without_quota_is_skipped() {
+        let path = temp_file(&[7u8; 100]);
+        let config = config();
+        let cancel = AtomicBool::new(false);
+
+        // 目标一：剩余 10 字节，不应发起上传会话
+        let mut full = mockito::Server::new_async().await;
+        let quota = mock_quota(&mut full, 1000, 990).await;
+        let init = full
+            .mock("POST", "/upload/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let folders = FolderCache::default();
+        let storage = GoogleDriveStorage::new(&config, full.url(), &folders);
+        let err = upload_parts(&storage, &path, "f.bin", 100, &cancel, |_, _| {})
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CloudError::QuotaExceeded("需要 100 字节，剩余 10 字节".to_string())
+        );
+        quota.assert_async().await;
+        init.assert_async().await;
+
+        // 目标二：空间充足，正常上传
+        let mut roomy = mockito::Server::new_async().await;
+        let _quota = mock_quota(&mut roomy, 1_000_000, 0).await;
+        let session = format!("{}/session", roomy.url());
+        let _init = roomy
+            .mock("POST", "/upload/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .with_header("location", &session)
+            .create_async()
+            .await;
+        let _put = roomy
+            .mock("PUT", "/session")
+            .with_status(200)
+            .with_body(r#"{"id":"file-1"}"#)
+            .create_async()
+            .await;
+        let folders = FolderCache::default();
+        let storage = GoogleDriveStorage::new(&config, roomy.url(), &folders);
+        let file_id = upload_parts(&storage, &path, "f.bin", 100, &cancel, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(file_id, "file-1");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_uploads_create_folder_once() {
+        let mut server = mockito::Server::new_async().await;
+        let search = server
+            .mock("GET", "/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"files":[]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let create = server
+            .mock("POST", "/drive/v3/files")
+            .with_body(r#"{"id":"folder-1"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let config = UploadConfig {
+            target_path: "/Backups".to_string(),
+            ..config()
+        };
+        let folders = FolderCache::default();
+        let storage = GoogleDriveStorage::new(&config, server.url(), &folders);
+        let resolve = || {
+            folders.get_or_resolve(&config.access_token, &config.target_path, || {
+                storage.create_or_get_folder(&config.target_path)
+            })
+        };
+
+        let (a, b) = futures::join!(resolve(), resolve());
+        assert_eq!(a.unwrap(), "folder-1");
+        assert_eq!(b.unwrap(), "folder-1");
+        assert_eq!(resolve().await.unwrap(), "folder-1");
+        search.assert_async().await;
+        create.assert_async().await;
+
+        // 换 token 后不再命中旧缓存
+        let other = folders
+            .get_or_resolve("new-token", "/Backups", || async {
+                Ok("folder-2".to_string())
+            })
+            .await;
+        assert_eq!(other.unwrap(), "folder-2");
+    }
+
+    #[tokio::test]
+    async fn test_identical_file_in_target_is_not_uploaded_again() {
+        // 与其他测试的临时文件分开，避免并行测试互相覆盖
+        let path = std::env::temp_dir()
+            .join(format!("gdrive_dedup_test_{}", std::process::id()))
+            .join("f.bin");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"same backup").unwrap();
+        let config = config();
+        let cancel = AtomicBool::new(false);
+        let md5 = local_md5(&path).unwrap();
+        assert_eq!(md5, format!("{:x}", Md5::digest(b"same backup")));
+
+        let mut server = mockito::Server::new_async().await;
+        let listing = server
+            .mock("GET", "/drive/v3/files")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "q".to_string(),
+                "name='f.bin' and 'root' in parents and trashed=false".to_string(),
+            ))
+            .with_body(format!(
+                r#"{{"files":[{{"id":"old-1","size":"11","md5Checksum":"0000"}},
+                    {{"id":"other-size","size":"5","md5Checksum":"{md5}"}},
+                    {{"id":"backup-1","size":"11","md5Checksum":"{md5}"}}]}}"#
+            ))
+            .expect(1)
+            .create_async()
+            .await;
+        let quota = server
+            .mock("GET", "/drive/v3/about")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let init = server
+            .mock("POST", "/upload/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let folders = FolderCache::default();
+        let storage = GoogleDriveStorage::new(&config, server.url(), &folders);
+        let mut progress = Vec::new();
+        let uploaded = upload_or_reuse(&storage, &path, "f.bin", 11, &cancel, |done, _| {
+            progress.push(done)
+        })
+        .await
+        .unwrap();
+
+        // 已有同名且校验和一致的文件：不建立上传会话，返回已有文件
+        assert_eq!(
+            uploaded,
+            UploadedFile {
+                file_id: "backup-1".to_string(),
+                already_uploaded: true,
+            }
+        );
+        assert!(progress.is_empty());
+        listing.assert_async().await;
+        quota.assert_async().await;
+        init.assert_async().await;
+
+        // 同名但内容不同时照常上传
+        let mut changed = mockito::Server::new_async().await;
+        let _listing = changed
+            .mock("GET", "/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"files":[{"id":"old-1","size":"11","md5Checksum":"0000"}]}"#)
+            .create_async()
+            .await;
+        let _quota = mock_quota(&mut changed, 1_000_000, 0).await;
+        let session = format!("{}/session", changed.url());
+        let _init = changed
+            .mock("POST", "/upload/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .with_header("location", &session)
+            .create_async()
+            .await;
+        let _put = changed
+            .mock("PUT", "/session")
+            .with_status(200)
+            .with_body(r#"{"id":"file-2"}"#)
+            .create_async()
+            .await;
+        let storage = GoogleDriveStorage::new(&config, changed.url(), &folders);
+        let uploaded = upload_or_reuse(&storage, &path, "f.bin", 11, &cancel, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(uploaded.file_id, "file-2");
+        assert!(!uploaded.already_uploaded);
+    }
+
+    #[test]
+    fn test_free_space_from_quota() {
+        let quota = serde_json::json!({ "limit": "100", "usage": "30" });
+        assert_eq!(free_space_from_quota(&quota), Some(70));
+        let unlimited = serde_json::json!({ "usage": "30" });
+        assert_eq!(free_space_from_quota(&unlimited), None);
+    }
+
+    #[test]
+    fn test_google_error_bodies_are_classified() {
+        use reqwest::StatusCode;
+
+        let quota = r#"{"error":{"errors":[{"domain":"usageLimits","reason":"storageQuotaExceeded",
+            "message":"The user's Drive storage quota has been exceeded."}],"code":403,
+            "message":"The user's Drive storage quota has been exceeded."}}"#;
+        assert_eq!(
+            google_error(StatusCode::FORBIDDEN, quota, "上传块"),
+            CloudError::QuotaExceeded(
+                "上传块失败 (403): The user's Drive storage quota has been exceeded.".to_string()
+            )
+        );
+
+        let expired = r#"{"error":{"code":401,"message":"Request had invalid authentication credentials.",
+            "errors":[{"message":"Invalid Credentials","domain":"global","reason":"authError"}],
+            "status":"UNAUTHENTICATED"}}"#;
+        assert!(matches!(
+            google_error(StatusCode::UNAUTHORIZED, expired, "查询文件夹"),
+            CloudError::AuthExpired(_)
+        ));
+
+        let scope = r#"{"error":{"code":403,"message":"Insufficient Permission",
+            "errors":[{"reason":"insufficientPermissions"}]}}"#;
+        assert!(matches!(
+            google_error(StatusCode::FORBIDDEN, scope, "创建文件夹"),
+            CloudError::AuthExpired(_)
+        ));
+
+        let missing = r#"{"error":{"code":404,"message":"File not found: abc.",
+            "errors":[{"reason":"notFound"}]}}"#;
+        assert!(matches!(
+            google_error(StatusCode::NOT_FOUND, missing, "上传块"),
+            CloudError::NotFound(_)
+        ));
+
+        let rate = r#"{"error":{"code":403,"message":"User Rate Limit Exceeded",
+            "errors":[{"reason":"userRateLimitExceeded"}]}}"#;
+        assert!(matches!(
+            google_error(StatusCode::FORBIDDEN, rate, "上传块"),
+            CloudError::Server(_)
+        ));
+        assert_eq!(
+            google_error(
+                StatusCode::BAD_GATEWAY,
+                "<html>bad gateway</html>",
+                "上传块"
+            ),
+            CloudError::Server("上传块失败 (502): <html>bad gateway</html>".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_refreshed_and_chunk_retried() {
+        let path = std::env::temp_dir()
+            .join(format!("gdrive_refresh_test_{}", std::process::id()))
+            .join("f.bin");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, [1u8; 100]).unwrap();
+        let config = UploadConfig {
+            access_token: "stale".to_string(),
+            token_refresh: Some(TokenRefresh {
+                refresh_token: "refresh-1".to_string(),
+                client_id: "client".to_string(),
+                client_secret: "secret".to_string(),
+            }),
+            ..config()
+        };
+        let cancel = AtomicBool::new(false);
+
+        let mut server = mockito::Server::new_async().await;
+        let _quota = mock_quota(&mut server, 1_000_000, 0).await;
+        let session = format!("{}/session", server.url());
+        let _init = server
+            .mock("POST", "/upload/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .with_header("location", &session)
+            .create_async()
+            .await;
+        let expired = server
+            .mock("PUT", "/session")
+            .match_header("authorization", "Bearer stale")
+            .with_status(401)
+            .with_body(r#"{"error":{"code":401,"status":"UNAUTHENTICATED"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let token = server
+            .mock("POST", "/token")
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("grant_type".to_string(), "refresh_token".to_string()),
+                mockito::Matcher::UrlEncoded("refresh_token".to_string(), "refresh-1".to_string()),
+            ]))
+            .with_body(r#"{"access_token":"fresh","expires_in":3599}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let retried = server
+            .mock("PUT", "/session")
+            .match_header("authorization", "Bearer fresh")
+            .with_status(200)
+            .with_body(r#"{"id":"file-1"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let folders = FolderCache::default();
+        let mut storage = GoogleDriveStorage::new(&config, server.url(), &folders);
+        storage.oauth_base = server.url();
+
+        let file_id = upload_parts(&storage, &path, "f.bin", 100, &cancel, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(file_id, "file-1");
+        assert_eq!(storage.refreshed_token().as_deref(), Some("fresh"));
+        expired.assert_async().await;
+        token.assert_async().await;
+        retried.assert_async().await;
+    }
+
+    #[test]
+    fn test_received_bytes_from_range_header() {
+        assert_eq!(received_bytes(None), Some(0));
+        assert_eq!(received_bytes(Some("bytes=0-42")), Some(43));
+        assert_eq!(received_bytes(Some("items=0-42")), None);
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_restart_continues_from_server_offset() {
+        let dir = std::env::temp_dir().join(format!("gdrive_resume_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("f.bin");
+        let content: Vec<u8> = (0..100u8).collect();
+        std::fs::write(&path, &content).unwrap();
+        let config = config();
+        let cancel = AtomicBool::new(false);
+
+        let mut server = mockito::Server::new_async().await;
+        let upload_uri = format!("{}/session", server.url());
+        // 上次运行留下的记录：本地只记到 20 字节，服务端实际已收到 40 字节
+        UploadSessionStore::new(dir.join("sessions.json"))
+            .save(UploadSessionRecord {
+                task_id: "task-1".to_string(),
+                provider: "google_drive".to_string(),
+                target: config.name.clone(),
+                file_path: path.display().to_string(),
+                file_size: 100,
+                upload_uri: upload_uri.clone(),
+                uploaded: 20,
+            })
+            .unwrap();
+
+        // 重启：重新打开记录文件
+        let store = UploadSessionStore::new(dir.join("sessions.json"));
+        let record = store.get("task-1", &config.name).unwrap();
+        let query = server
+            .mock("PUT", "/session")
+            .match_header("content-range", "bytes */100")
+            .with_status(308)
+            .with_header("range", "bytes=0-39")
+            .expect(1)
+            .create_async()
+            .await;
+        let rest = server
+            .mock("PUT", "/session")
+            .match_header("content-range", "bytes 40-99/100")
+            .match_body(content[40..].to_vec())
+            .with_status(200)
+            .with_body(r#"{"id":"file-1"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let folders = FolderCache::default();
+        let storage = GoogleDriveStorage::new(&config, server.url(), &folders).with_journal(
+            &store,
+            &record.task_id,
+            &record.file_path,
+        );
+        let mut progress = Vec::new();
+        let file_id = resume_session(&storage, &record, &cancel, |done, _| progress.push(done))
+            .await
+            .unwrap();
+
+        assert_eq!(file_id, "file-1");
+        assert_eq!(progress, [100]);
+        query.assert_async().await;
+        rest.assert_async().await;
+        // 完成后记录被删除
+        assert!(store.load().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_retried_after_503_but_not_after_400() {
+        let path = std::env::temp_dir()
+            .join(format!("gdrive_retry_test_{}", std::process::id()))
+            .join("f.bin");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, [3u8; 100]).unwrap();
+        let config = UploadConfig {
+            retry: RetryPolicy {
+                max_attempts: 3,
+                base_delay_ms: 1,
+                max_delay_ms: 1,
+            },
+            ..config()
+        };
+        let cancel = AtomicBool::new(false);
+
+        let mut server = mockito::Server::new_async().await;
+        let _quota = mock_quota(&mut server, 1_000_000, 0).await;
+        let session = format!("{}/session", server.url());
+        let _init = server
+            .mock("POST", "/upload/drive/v3/files")
+            .match_query(mockito::Matcher::Any)
+            .with_header("location", &session)
+            .create_async()
+            .await;
+        let unavailable = server
+            .mock("PUT", "/session")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("PUT", "/session")
+            .match_header("content-range", "bytes 0-99/100")
+            .with_status(200)
+            .with_body(r#"{"id":"file-1"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let folders = FolderCache::default();
+        let storage = GoogleDriveStorage::new(&config, server.url(), &folders);
+        let file_id = upload_parts(&storage, &path, "f.bin", 100, &cancel, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(file_id, "file-1");
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+
+        // 4xx 不重试
+        let mut rejecting = mockito::Server::new_async().await;
+        let bad = rejecting
+            .mock("PUT", "/session")
+            .with_status(400)
+            .with_body(r#"{"error":{"code":400,"message":"Bad Request"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let storage = GoogleDriveStorage::new(&config, rejecting.url(), &folders);
+        let part = UploadPart {
+            index: 0,
+            offset: 0,
+            data: vec![3u8; 100],
+            total_size: 100,
+        };
+        let err = storage
+            .upload_part(&format!("{}/session", rejecting.url()), part)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudError::Server(_)));
+        bad.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_partial_chunk_resent_from_server_range() {
+        let config = config();
+        let content: Vec<u8> = (0..50u8).collect();
+        let mut server = mockito::Server::new_async().await;
+        let partial = server
+            .mock("PUT", "/session")
+            .match_header("content-range", "bytes 0-49/100")
+            .with_status(308)
+            .with_header("range", "bytes=0-29")
+            .expect(1)
+            .create_async()
+            .await;
+        let rest = server
+            .mock("PUT", "/session")
+            .match_header("content-range", "bytes 30-49/100")
+            .match_body(content[30..].to_vec())
+            .with_status(308)
+            .with_header("range", "bytes=0-49")
+            .expect(1)
+            .create_async()
+            .await;
+        let folders = FolderCache::default();
+        let storage = GoogleDriveStorage::new(&config, server.url(), &folders);
+        let part = UploadPart {
+            index: 0,
+            offset: 0,
+            data: content,
+            total_size: 100,
+        };
+        let outcome = storage
+            .upload_part(&format!("{}/session", server.url()), part)
+            .await
+            .unwrap();
+        assert_eq!(outcome, PartOutcome::Accepted { etag: None });
+        partial.assert_async().await;
+        rest.assert_async().await;
+    }
+}
@@ -0,0 +1,1188 @@
+mod error;
+mod google_drive;
+mod retry;
+mod s3;
+mod session_store;
+
+use ai_disk_common::{CloudRouting, SharedConfig};
+use futures::{future, stream, StreamExt};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+pub use error::CloudError;
+pub use google_drive::{invalidate_folder_cache, TokenRefresh};
+pub use retry::RetryPolicy;
+pub use s3::S3Config;
+pub use session_store::{UploadSessionRecord, UploadSessionStore};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadConfig {
+    pub provider: String,
+    pub name: String,
+    /// OAuth 访问令牌（Google Drive 等）；S3 未提供 `s3` 时作为 Access Key ID
+    #[serde(default)]
+    pub access_token: String,
+    pub target_path: String,
+    /// S3 兼容存储的连接配置，仅 `"s3"` 提供商使用；优先于下面的 `endpoint` 等字段
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+    /// S3 服务端点，如 `https://<account>.r2.cloudflarestorage.com`
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// S3 区域，未提供时为 `us-east-1`
+    #[serde(default)]
+    pub region: Option<String>,
+    /// S3 Secret Access Key，与 `access_token` 配对
+    #[serde(default)]
+    pub secret_key: Option<String>,
+    /// 访问令牌过期时用于自动刷新的 OAuth 凭据（Google Drive）
+    #[serde(default)]
+    pub token_refresh: Option<TokenRefresh>,
+    /// 分块上传遇到网络错误或 5xx 时的重试策略
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadResult {
+    pub success: bool,
+    pub provider: String,
+    pub file_id: Option<String>,
+    pub message: String,
+    pub source_deleted: bool,
+    /// 未尝试上传（如目标剩余空间不足）
+    #[serde(default)]
+    pub skipped: bool,
+    /// 目标中已有同名且内容相同的文件，未重新上传
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub already_uploaded: bool,
+    /// 失败原因，前端据此提示重新登录、换目标或重试
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<CloudError>,
+}
+
+/// 云存储目标的连接检查结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetStatus {
+    pub provider: String,
+    pub name: String,
+    /// 解析后的目标位置，如 Google Drive 文件夹 ID、`s3://bucket/prefix`
+    pub target: String,
+    /// 探测文件能否写入并删除
+    pub writable: bool,
+    /// 剩余可用空间；无配额限制或无法查询时为 None
+    pub free_bytes: Option<u64>,
+    /// 不可写等问题的说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 上传进度事件的数据结构
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgressEvent {
+    pub task_id: String,
+    pub provider: String,
+    pub progress: u32, // 0-100
+    pub uploaded_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// 进行中的上传任务的取消标记，按 task_id 索引
+#[derive(Default)]
+pub struct UploadState {
+    cancel_flags: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl UploadState {
+    fn register(&self, task_id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Ok(mut flags) = self.cancel_flags.lock() {
+            flags.insert(task_id.to_string(), flag.clone());
+        }
+        flag
+    }
+
+    fn unregister(&self, task_id: &str) {
+        if let Ok(mut flags) = self.cancel_flags.lock() {
+            flags.remove(task_id);
+        }
+    }
+}
+
+/// 取消上传任务：尚未上传的分块不再发送，已开始的 multipart 上传会被中止
+#[tauri::command]
+pub async fn cancel_upload(state: State<'_, UploadState>, task_id: String) -> Result<bool, String> {
+    let flags = state
+        .cancel_flags
+        .lock()
+        .map_err(|e| format!("获取上传状态失败: {}", e))?;
+    match flags.get(&task_id) {
+        Some(flag) => {
+            info!("取消上传任务: {}", task_id);
+            flag.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 测试云存储目标：校验凭据、解析（必要时创建）目标文件夹，写入并删除一个探测小文件，
+/// 查询剩余空间。凭据失效、目标不存在或网络错误时返回错误；仅无法写入时返回 `writable: false`
+#[tauri::command]
+pub async fn test_cloud_target(config: UploadConfig) -> Result<TargetStatus, CloudError> {
+    info!("测试云存储目标: {} ({})", config.name, config.provider);
+    match config.provider.as_str() {
+        "google_drive" => google_drive::test_google_drive_target(&config).await,
+        "s3" => s3::test_s3_target(&config).await,
+        _ => Err(CloudError::Local(format!(
+            "不支持的云存储提供商: {}",
+            config.provider
+        ))),
+    }
+}
+
+/// 上传文件到云存储
+#[tauri::command]
+pub async fn upload_to_cloud(
+    app: AppHandle,
+    state: State<'_, UploadState>,
+    file_path: String,
+    configs: Vec<UploadConfig>,
+    delete_source: Option<bool>,
+    task_id: Option<String>,
+) -> Result<Vec<UploadResult>, String> {
+    info!("开始上传文件到云存储: {}", file_path);
+    info!("目标云存储数量: {}", configs.len());
+    info!("任务ID: {:?}", task_id);
+    debug!("删除源文件选项: {:?}", delete_source);
+
+    let task_id = task_id.unwrap_or_else(new_task_id);
+    let cancel = state.register(&task_id);
+    let results = upload_file(
+        &app,
+        &task_id,
+        &cancel,
+        file_path,
+        configs,
+        delete_source.unwrap_or(false),
+    )
+    .await;
+    state.unregister(&task_id);
+    Ok(results)
+}
+
+/// 应用上次退出时未完成、可续传的上传
+#[tauri::command]
+pub async fn list_pending_uploads(app: AppHandle) -> Result<Vec<UploadSessionRecord>, String> {
+    Ok(session_store(&app)?.load())
+}
+
+/// 续传应用退出前未完成的上传（目前支持 Google Drive）：按 `task_id` 与 `config.name` 找到会话记录，
+/// 向服务端查询已接收的字节数后从断点继续，进度照常通过 `upload-progress` 上报。
+/// 会话记录不含凭据，由前端传入当前的 `config`
+#[tauri::command]
+pub async fn resume_upload(
+    app: AppHandle,
+    state: State<'_, UploadState>,
+    task_id: String,
+    config: UploadConfig,
+) -> Result<UploadResult, String> {
+    let store = session_store(&app)?;
+    let record = store
+        .get(&task_id, &config.name)
+        .ok_or_else(|| format!("没有可续传的上传: {} ({})", task_id, config.name))?;
+    info!(
+        "续传 {} 到 {}，上次记录已上传 {}/{} 字节",
+        record.file_path, config.name, record.uploaded, record.file_size
+    );
+
+    let cancel = state.register(&task_id);
+    let result = match config.provider.as_str() {
+        "google_drive" => {
+            google_drive::resume_google_drive_upload(&record, &config, &app, &store, &cancel).await
+        }
+        _ => Err(CloudError::Local(format!("{} 不支持续传", config.provider))),
+    };
+    state.unregister(&task_id);
+
+    Ok(match result {
+        Ok(uploaded) => UploadResult {
+            success: true,
+            provider: config.provider.clone(),
+            file_id: Some(uploaded.file_id),
+            message: format!("成功续传到 {}", config.name),
+            source_deleted: false,
+            skipped: false,
+            already_uploaded: false,
+            error: None,
+        },
+        Err(e) => UploadResult {
+            success: false,
+            provider: config.provider.clone(),
+            file_id: None,
+            message: format!("续传失败: {}", e),
+            source_deleted: false,
+            skipped: false,
+            already_uploaded: false,
+            error: Some(e),
+        },
+    })
+}
+
+fn session_store(app: &AppHandle) -> Result<UploadSessionStore, String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("无法获取用户目录: {}", e))?;
+    Ok(UploadSessionStore::in_home(&home))
+}
+
+/// 批量上传中单个文件的结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileUploadResult {
+    pub file_path: String,
+    pub results: Vec<UploadResult>,
+}
+
+/// 批量上传（如清理计划中要迁移到云端的文件）：按配置中的 `cloud_routing` 为每个文件选择目标，
+/// 逐个文件上传，共用一个任务 ID，取消后剩余文件不再上传
+#[tauri::command]
+pub async fn upload_plan_to_cloud(
+    app: AppHandle,
+    state: State<'_, UploadState>,
+    config: State<'_, SharedConfig>,
+    files: Vec<String>,
+    configs: Vec<UploadConfig>,
+    delete_source: Option<bool>,
+    task_id: Option<String>,
+) -> Result<Vec<FileUploadResult>, String> {
+    info!("批量上传 {} 个文件到云存储", files.len());
+    let routing = config.current().cloud_routing.clone();
+    let task_id = task_id.unwrap_or_else(new_task_id);
+    let cancel = state.register(&task_id);
+    let mut uploaded = Vec::with_capacity(files.len());
+    for file_path in files {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let targets: Vec<UploadConfig> = route_configs(&routing, &file_path, &configs)
+            .into_iter()
+            .cloned()
+            .collect();
+        let results = upload_file(
+            &app,
+            &task_id,
+            &cancel,
+            file_path.clone(),
+            targets,
+            delete_source.unwrap_or(false),
+        )
+        .await;
+        uploaded.push(FileUploadResult { file_path, results });
+    }
+    state.unregister(&task_id);
+    Ok(uploaded)
+}
+
+fn new_task_id() -> String {
+    format!(
+        "upload_{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0)
+    )
+}
+
+/// 按路由规则选出文件的上传目标：依次尝试命中的目标与默认目标，名称须与某个配置的 `name` 一致；
+/// 未配置路由或都不存在时上传到全部目标
+fn route_configs<'a>(
+    routing: &CloudRouting,
+    file_path: &str,
+    configs: &'a [UploadConfig],
+) -> Vec<&'a UploadConfig> {
+    let by_name = |name: &str| configs.iter().find(|c| c.name == name);
+    let routed = routing
+        .route(file_path)
+        .and_then(by_name)
+        .or_else(|| routing.default_target.as_deref().and_then(by_name));
+    match routed {
+        Some(config) => vec![config],
+        None => configs.iter().collect(),
+    }
+}
+
+/// 把一个文件并行上传到 `configs` 中的所有目标；全部成功且 `delete_source` 时删除源文件
+async fn upload_file(
+    app: &AppHandle,
+    task_id: &str,
+    cancel: &Arc<AtomicBool>,
+    file_path: String,
+    configs: Vec<UploadConfig>,
+    delete_source: bool,
+) -> Vec<UploadResult> {
+    // 并行上传到所有配置的云存储
+    let upload_futures: Vec<_> = configs
+        .into_iter()
+        .map(|config| {
+            let file_path_clone = file_path.clone();
+            let app_clone = app.clone();
+            let task_id_clone = task_id.to_string();
+            let cancel_clone = cancel.clone();
+            tokio::spawn(async move {
+                info!("开始上传到 {} ({})", config.name, config.provider);
+                let result = match config.provider.as_str() {
+                    "google_drive" => {
+                        google_drive::upload_to_google_drive_resumable(
+                            &file_path_clone,
+                            &config,
+                            &app_clone,
+                            &task_id_clone,
+                            &cancel_clone,
+                        )
+                        .await
+                    }
+                    "s3" => {
+                        s3::upload_to_s3(
+                            &file_path_clone,
+                            &config,
+                            &app_clone,
+                            &task_id_clone,
+                            &cancel_clone,
+                        )
+                        .await
+                    }
+                    _ => Err(CloudError::Local(format!(
+                        "不支持的云存储提供商: {}",
+                        config.provider
+                    ))),
+                };
+
+                match &result {
+                    Ok(uploaded) => {
+                        info!(
+                            "成功上传到 {} ({})，文件ID: {}",
+                            config.name, config.provider, uploaded.file_id
+                        );
+                    }
+                    Err(e) => {
+                        error!("上传到 {} ({}) 失败: {}", config.name, config.provider, e);
+                    }
+                }
+
+                let upload_result = match result {
+                    Ok(uploaded) => UploadResult {
+                        success: true,
+                        provider: config.provider.clone(),
+                        file_id: Some(uploaded.file_id),
+                        message: if uploaded.already_uploaded {
+                            format!("{} 中已有相同文件，跳过上传", config.name)
+                        } else {
+                            format!("成功上传到 {}", config.name)
+                        },
+                        source_deleted: false,
+                        skipped: false,
+                        already_uploaded: uploaded.already_uploaded,
+                        error: None,
+                    },
+                    Err(e) => {
+                        let skipped = matches!(e, CloudError::QuotaExceeded(_));
+                        UploadResult {
+                            success: false,
+                            provider: config.provider.clone(),
+                            file_id: None,
+                            message: if skipped {
+                                format!("已跳过 {}: {}", config.name, e)
+                            } else {
+                                format!("上传失败: {}", e)
+                            },
+                            source_deleted: false,
+                            skipped,
+                            already_uploaded: false,
+                            error: Some(e),
+                        }
+                    }
+                };
+
+                (config.name.clone(), upload_result)
+            })
+        })
+        .collect();
+
+    // 等待所有上传任务完成
+    let upload_results: Vec<_> = future::join_all(upload_futures).await;
+
+    let mut results = Vec::new();
+    let mut all_success = true;
+
+    for result in upload_results {
+        match result {
+            Ok((_name, upload_result)) => {
+                if !upload_result.success {
+                    all_success = false;
+                }
+                results.push(upload_result);
+            }
+            Err(e) => {
+                error!("上传任务执行失败: {:?}", e);
+                all_success = false;
+                // 创建一个失败的结果
+                results.push(UploadResult {
+                    success: false,
+                    provider: "unknown".to_string(),
+                    file_id: None,
+                    message: format!("任务执行失败: {:?}", e),
+                    source_deleted: false,
+                    skipped: false,
+                    already_uploaded: false,
+                    error: None,
+                });
+            }
+        }
+    }
+
+    // 如果所有上传都成功且需要删除源文件
+    if all_success && delete_source {
+        info!("所有上传成功，准备删除源文件: {}", file_path);
+        let path = Path::new(&file_path);
+        if path.exists() {
+            let delete_result = if path.is_dir() {
+                debug!("删除目录: {}", file_path);
+                fs::remove_dir_all(path)
+            } else {
+                debug!("删除文件: {}", file_path);
+                fs::remove_file(path)
+            };
+
+            match delete_result {
+                Ok(_) => {
+                    info!("成功删除源文件: {}", file_path);
+                    // 更新所有结果，标记源文件已删除
+                    for result in &mut results {
+                        result.source_deleted = true;
+                        result.message = format!("{} (已删除源文件)", result.message);
+                    }
+                }
+                Err(e) => {
+                    warn!("删除源文件失败: {}，错误: {}", file_path, e);
+                    // 删除失败，但上传已成功，只在消息中记录
+                    for result in &mut results {
+                        result.message = format!("{} (删除源文件失败: {})", result.message, e);
+                    }
+                }
+            }
+        } else {
+            warn!("源文件不存在，无法删除: {}", file_path);
+        }
+    } else if !all_success {
+        warn!("部分上传失败，不删除源文件");
+    }
+
+    info!(
+        "上传任务完成，成功: {}，失败: {}",
+        results.iter().filter(|r| r.success).count(),
+        results.iter().filter(|r| !r.success).count()
+    );
+
+    results
+}
+
+/// 默认分块大小：5MB（Google Drive 要求分块为 256KB 的整数倍）
+const CHUNK_SIZE: u64 = 5 * 1024 * 1024;
+
+/// 支持并行分块的提供商同时在途的最大分块数
+const MAX_PARALLEL_PARTS: usize = 4;
+
+pub(crate) use retry::send_with_retry;
+
+/// 待上传的一个分块
+#[derive(Debug)]
+pub(crate) struct UploadPart {
+    /// 分块序号（从 0 开始）
+    pub index: usize,
+    /// 分块在文件中的起始偏移
+    pub offset: u64,
+    pub data: Vec<u8>,
+    pub total_size: u64,
+}
+
+impl UploadPart {
+    /// `Content-Range` 头的值，如 `bytes 0-5242879/10485760`
+    pub fn content_range(&self) -> String {
+        format!(
+            "bytes {}-{}/{}",
+            self.offset,
+            self.offset + self.data.len() as u64 - 1,
+            self.total_size
+        )
+    }
+}
+
+/// 单个分块的上传结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PartOutcome {
+    /// 服务端已接收该分块（S3 风格 multipart 会返回 ETag）
+    Accepted { etag: Option<String> },
+    /// 服务端确认整个文件已上传完成（如 Google Drive 最后一块），附带文件 ID
+    Completed { file_id: String },
+}
+
+/// 云存储分块上传抽象：开始会话、上传分块、收尾三个阶段，
+/// 并由各提供商声明是否接受并发、乱序到达的分块
+pub(crate) trait CloudStorage: Sync {
+    /// 上传会话（如 Google Drive 的上传 URI、S3 的 UploadId）
+    type Session: Send + Sync;
+
+    /// 是否支持分块并行上传（S3 multipart 支持；Google Drive resumable 必须顺序上传）
+    fn supports_parallel_parts(&self) -> bool;
+
+    fn part_size(&self) -> u64 {
+        CHUNK_SIZE
+    }
+
+    /// 目标剩余可用空间（字节）；无配额限制或提供商不支持查询时为 None
+    fn free_space(&self) -> impl Future<Output = Result<Option<u64>, CloudError>> + Send {
+        async { Ok(None) }
+    }
+
+    /// 校验凭据并解析（必要时创建）目标位置，返回其描述（见 `TargetStatus::target`）
+    fn resolve_target(&self) -> impl Future<Output = Result<String, CloudError>> + Send;
+
+    /// 在目标位置写入并删除名为 `PROBE_FILE_NAME` 的小文件
+    fn write_probe(&self) -> impl Future<Output = Result<(), CloudError>> + Send;
+
+    fn begin_upload(
+        &self,
+        file_name: &str,
+        file_size: u64,
+    ) -> impl Future<Output = Result<Self::Session, CloudError>> + Send;
+
+    fn upload_part(
+        &self,
+        session: &Self::Session,
+        part: UploadPart,
+    ) -> impl Future<Output = Result<PartOutcome, CloudError>> + Send;
+
+    /// 所有分块上传后收尾，`parts` 已按分块序号升序排列；返回云端文件 ID
+    fn complete_upload(
+        &self,
+        session: &Self::Session,
+        parts: Vec<PartOutcome>,
+    ) -> impl Future<Output = Result<String, CloudError>> + Send;
+
+    /// 目标位置中与本地文件同名且内容相同（大小与校验和一致）的已有文件 ID；默认不检查
+    fn find_existing(
+        &self,
+        _file_name: &str,
+        _path: &Path,
+        _file_size: u64,
+    ) -> impl Future<Output = Result<Option<String>, CloudError>> + Send {
+        async { Ok(None) }
+    }
+
+    /// 取消或失败时中止上传会话，释放服务端已接收的分块；默认无需处理
+    fn abort_upload(
+        &self,
+        _session: &Self::Session,
+    ) -> impl Future<Output = Result<(), CloudError>> + Send {
+        async { Ok(()) }
+    }
+}
+
+/// 读取文件中 `[offset, offset + len)` 的数据
+fn read_part(path: &Path, offset: u64, len: u64) -> Result<Vec<u8>, CloudError> {
+    let mut file = fs::File::open(path).map_err(|e| {
+        error!("打开文件失败: {}", e);
+        CloudError::Local(format!("打开文件失败: {}", e))
+    })?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| CloudError::Local(format!("定位文件块失败: {}", e)))?;
+    let mut buffer = vec![0u8; len as usize];
+    file.read_exact(&mut buffer).map_err(|e| {
+        error!("读取文件块失败: {}", e);
+        CloudError::Local(format!("读取文件块失败: {}", e))
+    })?;
+    Ok(buffer)
+}
+
+/// 上传前检查目标剩余空间，避免传到最后一块才失败；查询配额失败时只告警并继续上传。
+/// 空间不足时返回 `QuotaExceeded`，`upload_to_cloud` 据此把该目标标记为跳过
+async fn ensure_free_space<S: CloudStorage>(storage: &S, file_size: u64) -> Result<(), CloudError> {
+    match storage.free_space().await {
+        Ok(Some(free)) if free < file_size => {
+            warn!("目标剩余空间 {} 字节，不足以上传 {} 字节", free, file_size);
+            Err(CloudError::QuotaExceeded(format!(
+                "需要 {} 字节，剩余 {} 字节",
+                file_size, free
+            )))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            warn!("查询存储配额失败，继续上传: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// 连接测试时写入目标位置的探测文件名，写入后立即删除
+const PROBE_FILE_NAME: &str = ".diskrookie-probe";
+
+/// 按 `test_cloud_target` 的约定检查目标。探测写入失败（如只读权限、空间已满）不视为错误，
+/// 只把 `writable` 置为 false；网络错误仍返回错误
+async fn check_target<S: CloudStorage>(
+    storage: &S,
+    config: &UploadConfig,
+) -> Result<TargetStatus, CloudError> {
+    let target = storage.resolve_target().await?;
+    let free_bytes = match storage.free_space().await {
+        Ok(free) => free,
+        Err(e @ CloudError::AuthExpired(_)) => return Err(e),
+        Err(e) => {
+            warn!("查询存储配额失败: {}", e);
+            None
+        }
+    };
+    let (writable, message) = match storage.write_probe().await {
+        Ok(()) => (true, None),
+        Err(e @ (CloudError::Network(_) | CloudError::Cancelled)) => return Err(e),
+        Err(e) => {
+            warn!("目标 {} 不可写: {}", target, e);
+            (false, Some(e.to_string()))
+        }
+    };
+    Ok(TargetStatus {
+        provider: config.provider.clone(),
+        name: config.name.clone(),
+        target,
+        writable,
+        free_bytes,
+        message,
+    })
+}
+
+fn check_cancelled(cancel: &AtomicBool) -> Result<(), CloudError> {
+    if cancel.load(Ordering::Relaxed) {
+        Err(CloudError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// 上传到目标的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UploadedFile {
+    pub file_id: String,
+    /// 目标中已有相同文件，本次未重新上传
+    pub already_uploaded: bool,
+}
+
+/// 先查找目标中是否已有相同文件（见 `CloudStorage::find_existing`），有则直接返回，
+/// 避免重复上传同一份备份；否则按 `upload_parts` 上传
+pub(crate) async fn upload_or_reuse<S: CloudStorage>(
+    storage: &S,
+    path: &Path,
+    file_name: &str,
+    file_size: u64,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(u64, u64) + Send,
+) -> Result<UploadedFile, CloudError> {
+    check_cancelled(cancel)?;
+    if let Some(file_id) = storage.find_existing(file_name, path, file_size).await? {
+        info!("目标中已有相同文件 {}（{}），跳过上传", file_name, file_id);
+        return Ok(UploadedFile {
+            file_id,
+            already_uploaded: true,
+        });
+    }
+    let file_id = upload_parts(storage, path, file_name, file_size, cancel, on_progress).await?;
+    Ok(UploadedFile {
+        file_id,
+        already_uploaded: false,
+    })
+}
+
+/// 按提供商能力分块上传文件：顺序提供商逐块上传；支持并行的提供商最多
+/// `MAX_PARALLEL_PARTS` 块同时在途，完成后按分块序号重排结果再收尾。
+/// 每块完成后以 `(已上传字节, 总字节)` 调用 `on_progress`；
+/// 取消或出错时调用 `abort_upload` 清理服务端会话。开始前先检查目标剩余空间。
+pub(crate) async fn upload_parts<S: CloudStorage>(
+    storage: &S,
+    path: &Path,
+    file_name: &str,
+    file_size: u64,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(u64, u64) + Send,
+) -> Result<String, CloudError> {
+    check_cancelled(cancel)?;
+    ensure_free_space(storage, file_size).await?;
+    let session = storage.begin_upload(file_name, file_size).await?;
+    upload_from(storage, &session, path, file_size, 0, cancel, on_progress).await
+}
+
+/// 在已建立的会话中从 `start` 字节起上传其余分块并收尾，见 `upload_parts`。
+/// 续传时 `start` 为服务端已接收的字节数，只支持顺序上传的提供商
+pub(crate) async fn upload_from<S: CloudStorage>(
+    storage: &S,
+    session: &S::Session,
+    path: &Path,
+    file_size: u64,
+    start: u64,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(u64, u64) + Send,
+) -> Result<String, CloudError> {
+    let sent = send_parts(
+        storage,
+        session,
+        path,
+        file_size,
+        start,
+        cancel,
+        on_progress,
+    )
+    .await;
+    let result = match sent {
+        Ok(outcomes) => {
+            // 服务端已在某个分块的响应中确认完成（Google Drive 最后一块）时无需再收尾
+            let completed = outcomes.iter().find_map(|outcome| match outcome {
+                PartOutcome::Completed { file_id } => Some(file_id.clone()),
+                PartOutcome::Accepted { .. } => None,
+            });
+            match completed {
+                Some(file_id) => Ok(file_id),
+                None => storage.complete_upload(session, outcomes).await,
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = &result {
+        warn!("上传未完成，中止上传会话: {}", e);
+        if let Err(abort_err) = storage.abort_upload(session).await {
+            warn!("中止上传会话失败: {}", abort_err);
+        }
+    }
+    result
+}
+
+/// 上传 `start` 之后的全部分块，返回按分块序号升序排列的结果
+async fn send_parts<S: CloudStorage>(
+    storage: &S,
+    session: &S::Session,
+    path: &Path,
+    file_size: u64,
+    start: u64,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(u64, u64) + Send,
+) -> Result<Vec<PartOutcome>, CloudError> {
+    let part_size = storage.part_size().max(1);
+    let parts = (0..file_size.saturating_sub(start).div_ceil(part_size)).map(|i| {
+        let offset = start + i * part_size;
+        (i as usize, offset, part_size.min(file_size - offset))
+    });
+
+    let mut uploaded: u64 = start;
+    let mut outcomes: Vec<(usize, PartOutcome)> = Vec::new();
+
+    if storage.supports_parallel_parts() {
+        let mut in_flight = stream::iter(parts)
+            .map(|(index, offset, len)| async move {
+                check_cancelled(cancel)?;
+                let part = UploadPart {
+                    index,
+                    offset,
+                    data: read_part(path, offset, len)?,
+                    total_size: file_size,
+                };
+                let outcome = storage.upload_part(session, part).await?;
+                Ok::<_, CloudError>((index, len, outcome))
+            })
+            .buffer_unordered(MAX_PARALLEL_PARTS);
+
+        while let Some(result) = in_flight.next().await {
+            let (index, len, outcome) = result?;
+            uploaded += len;
+            on_progress(uploaded, file_size);
+            outcomes.push((index, outcome));
+            check_cancelled(cancel)?;
+        }
+        outcomes.sort_by_key(|(index, _)| *index);
+    } else {
+        for (index, offset, len) in parts {
+            check_cancelled(cancel)?;
+            debug!(
+                "上传块: bytes {}-{}/{}",
+                offset,
+                offset + len - 1,
+                file_size
+            );
+            let part = UploadPart {
+                index,
+                offset,
+                data: read_part(path, offset, len)?,
+                total_size: file_size,
+            };
+            let outcome = storage.upload_part(session, part).await?;
+            uploaded += len;
+            on_progress(uploaded, file_size);
+            outcomes.push((index, outcome));
+        }
+    }
+
+    Ok(outcomes.into_iter().map(|(_, outcome)| outcome).collect())
+}
+
+/// 通过 `upload-progress` 事件上报一个目标的上传进度
+struct ProgressEmitter<'a> {
+    app: &'a AppHandle,
+    task_id: &'a str,
+    provider: &'a str,
+    total_bytes: u64,
+    last_progress: u32,
+}
+
+impl<'a> ProgressEmitter<'a> {
+    fn new(app: &'a AppHandle, task_id: &'a str, provider: &'a str, total_bytes: u64) -> Self {
+        ProgressEmitter {
+            app,
+            task_id,
+            provider,
+            total_bytes,
+            last_progress: 0,
+        }
+    }
+
+    fn emit(&self, progress: u32, uploaded_bytes: u64) {
+        let _ = self.app.emit(
+            "upload-progress",
+            UploadProgressEvent {
+                task_id: self.task_id.to_string(),
+                provider: self.provider.to_string(),
+                progress,
+                uploaded_bytes,
+                total_bytes: self.total_bytes,
+            },
+        );
+    }
+
+    /// 分块完成后调用：百分比只增不减，100% 留到整个上传完成后由 `finish` 上报
+    fn on_part(&mut self, uploaded: u64, total: u64) {
+        let progress = ((uploaded as f64 / total as f64) * 100.0) as u32;
+        if progress > self.last_progress && progress < 100 {
+            self.last_progress = progress;
+            info!("上传进度: {}% ({}/{} bytes)", progress, uploaded, total);
+            self.emit(progress, uploaded);
+        }
+    }
+
+    fn finish(&self) {
+        info!("上传完成!");
+        self.emit(100, self.total_bytes);
+    }
+}
+
+/// 分块上传文件到指定云存储，并通过 `upload-progress` 事件报告进度
+async fn upload_with_progress<S: CloudStorage>(
+    storage: &S,
+    file_path: &str,
+    config: &UploadConfig,
+    app: &AppHandle,
+    task_id: &str,
+    cancel: &AtomicBool,
+) -> Result<UploadedFile, CloudError> {
+    let path = Path::new(file_path);
+
+    // 检查文件是否存在
+    if !path.exists() {
+        error!("文件不存在: {}", file_path);
+        return Err(CloudError::Local(format!("文件不存在: {}", file_path)));
+    }
+
+    // 获取文件大小
+    let file_size = path.metadata().map(|m| m.len()).unwrap_or(0);
+    info!(
+        "文件大小: {} 字节 ({:.2} MB)",
+        file_size,
+        file_size as f64 / 1024.0 / 1024.0
+    );
+
+    // 获取文件名
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| CloudError::Local("无法获取文件名".to_string()))?;
+
+    info!("文件名: {}", file_name);
+
+    let mut progress = ProgressEmitter::new(app, task_id, &config.provider, file_size);
+    // 发送初始进度 0%
+    progress.emit(0, 0);
+
+    let uploaded = upload_or_reuse(
+        storage,
+        path,
+        file_name,
+        file_size,
+        cancel,
+        |uploaded, total| progress.on_part(uploaded, total),
+    )
+    .await?;
+
+    progress.finish();
+    Ok(uploaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// S3 风格的 multipart 模拟提供商：分块可并发、乱序上传，收尾时提交按序排列的 ETag
+    struct MockMultipartStorage {
+        client: reqwest::Client,
+        base_url: String,
+        part_size: u64,
+        part_count: usize,
+        /// 服务端收到分块的顺序
+        arrivals: Mutex<Vec<usize>>,
+    }
+
+    impl CloudStorage for MockMultipartStorage {
+        type Session = String;
+
+        fn supports_parallel_parts(&self) -> bool {
+            true
+        }
+
+        fn part_size(&self) -> u64 {
+            self.part_size
+        }
+
+        async fn resolve_target(&self) -> Result<String, CloudError> {
+            Ok(self.base_url.clone())
+        }
+
+        async fn write_probe(&self) -> Result<(), CloudError> {
+            Ok(())
+        }
+
+        async fn begin_upload(
+            &self,
+            _file_name: &str,
+            _file_size: u64,
+        ) -> Result<String, CloudError> {
+            let response = self
+                .client
+                .post(format!("{}/uploads", self.base_url))
+                .send()
+                .await
+                .map_err(|e| CloudError::request("初始化", &e))?;
+            response
+                .text()
+                .await
+                .map_err(|e| CloudError::request("读取响应", &e))
+        }
+
+        async fn upload_part(
+            &self,
+            upload_id: &String,
+            part: UploadPart,
+        ) -> Result<PartOutcome, CloudError> {
+            // 越靠前的分块延迟越久，使其晚于后面的分块到达
+            let delay = (self.part_count - part.index) as u64 * 20;
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            self.arrivals.lock().unwrap().push(part.index);
+
+            let response = self
+                .client
+                .put(format!(
+                    "{}/uploads/{}/parts/{}",
+                    self.base_url,
+                    upload_id,
+                    part.index + 1
+                ))
+                .header("Content-Range", part.content_range())
+                .body(part.data)
+                .send()
+                .await
+                .map_err(|e| CloudError::request("上传分块", &e))?;
+            if !response.status().is_success() {
+                return Err(CloudError::Server(format!(
+                    "上传失败 ({})",
+                    response.status()
+                )));
+            }
+            let etag = response
+                .headers()
+                .get("etag")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            Ok(PartOutcome::Accepted { etag })
+        }
+
+        async fn complete_upload(
+            &self,
+            upload_id: &String,
+            parts: Vec<PartOutcome>,
+        ) -> Result<String, CloudError> {
+            let etags: Vec<Option<String>> = parts
+                .into_iter()
+                .map(|p| match p {
+                    PartOutcome::Accepted { etag } => etag,
+                    PartOutcome::Completed { .. } => None,
+                })
+                .collect();
+            let response = self
+                .client
+                .post(format!("{}/uploads/{}/complete", self.base_url, upload_id))
+                .json(&etags)
+                .send()
+                .await
+                .map_err(|e| CloudError::request("收尾", &e))?;
+            if !response.status().is_success() {
+                return Err(CloudError::Server(format!(
+                    "收尾失败 ({})",
+                    response.status()
+                )));
+            }
+            response
+                .text()
+                .await
+                .map_err(|e| CloudError::request("读取响应", &e))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_parts_out_of_order() {
+        let content = b"0123456789abcdefghij"; // 5 块，每块 4 字节
+        let file = std::env::temp_dir().join(format!("cloud_upload_test_{}", std::process::id()));
+        fs::write(&file, content).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let begin = server
+            .mock("POST", "/uploads")
+            .with_body("u1")
+            .create_async()
+            .await;
+        let mut part_mocks = Vec::new();
+        for (i, chunk) in content.chunks(4).enumerate() {
+            let offset = i * 4;
+            part_mocks.push(
+                server
+                    .mock("PUT", format!("/uploads/u1/parts/{}", i + 1).as_str())
+                    .match_header(
+                        "content-range",
+                        format!("bytes {}-{}/20", offset, offset + 3).as_str(),
+                    )
+                    .match_body(chunk.to_vec())
+                    .with_header("etag", &format!("e{}", i + 1))
+                    .create_async()
+                    .await,
+            );
+        }
+        let complete = server
+            .mock("POST", "/uploads/u1/complete")
+            .match_body(mockito::Matcher::Json(serde_json::json!([
+                "e1", "e2", "e3", "e4", "e5"
+            ])))
+            .with_body("file-1")
+            .create_async()
+            .await;
+
+        let storage = MockMultipartStorage {
+            client: reqwest::Client::new(),
+            base_url: server.url(),
+            part_size: 4,
+            part_count: 5,
+            arrivals: Mutex::new(Vec::new()),
+        };
+        let mut progress = Vec::new();
+        let cancel = AtomicBool::new(false);
+        let result = upload_parts(&storage, &file, "f.bin", 20, &cancel, |uploaded, _| {
+            progress.push(uploaded);
+        })
+        .await;
+        let _ = fs::remove_file(&file);
+
+        assert_eq!(result, Ok("file-1".to_string()));
+        begin.assert_async().await;
+        for m in &part_mocks {
+            m.assert_async().await;
+        }
+        complete.assert_async().await;
+
+        // 分块确实乱序到达，进度按完成顺序单调累加
+        let arrivals = storage.arrivals.lock().unwrap().clone();
+        assert_ne!(arrivals, vec![0, 1, 2, 3, 4]);
+        assert_eq!(progress, vec![4, 8, 12, 16, 20]);
+    }
+
+    #[test]
+    fn test_route_configs_by_extension_category() {
+        let target = |provider: &str, name: &str| UploadConfig {
+            provider: provider.to_string(),
+            name: name.to_string(),
+            access_token: String::new(),
+            target_path: "/backup".to_string(),
+            s3: None,
+            endpoint: None,
+            bucket: None,
+            region: None,
+            secret_key: None,
+            token_refresh: None,
+            retry: RetryPolicy::default(),
+        };
+        let configs = [
+            target("s3", "Video Bucket"),
+            target("google_drive", "Docs Drive"),
+            target("google_drive", "Everything"),
+        ];
+        let names = |routing: &CloudRouting, file: &str| -> Vec<String> {
+            route_configs(routing, file, &configs)
+                .into_iter()
+                .map(|c| c.name.clone())
+                .collect()
+        };
+        let routing = CloudRouting {
+            rules: vec![
+                ai_disk_common::RoutingRule {
+                    category: Some("video".to_string()),
+                    target: "Video Bucket".to_string(),
+                    ..Default::default()
+                },
+                ai_disk_common::RoutingRule {
+                    category: Some("document".to_string()),
+                    target: "Docs Drive".to_string(),
+                    ..Default::default()
+                },
+                ai_disk_common::RoutingRule {
+                    extensions: vec!["log".to_string()],
+                    target: "Removed Target".to_string(),
+                    ..Default::default()
+                },
+            ],
+            default_target: Some("Everything".to_string()),
+        };
+
+        assert_eq!(names(&routing, "/home/u/trip.mp4"), ["Video Bucket"]);
+        assert_eq!(names(&routing, "/home/u/report.docx"), ["Docs Drive"]);
+        assert_eq!(names(&routing, "/home/u/setup.exe"), ["Everything"]);
+        // 命中的目标不在本次上传配置中时退回默认目标
+        assert_eq!(names(&routing, "/home/u/app.log"), ["Everything"]);
+        // 未配置路由时保持原行为：上传到全部目标
+        assert_eq!(
+            names(&CloudRouting::default(), "/home/u/trip.mp4"),
+            ["Video Bucket", "Docs Drive", "Everything"]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! 分块上传的重试：连接失败与 5xx 响应按指数退避（带随机抖动）重试，4xx 等响应直接交给调用方处理，
+//! 一次网络抖动不再导致整个文件上传失败
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+
+use super::CloudError;
+
+/// 重试策略：第 n 次重试前等待 `base_delay_ms * 2^(n-1)`（不超过 `max_delay_ms`），
+/// 再乘以 [0.5, 1) 之间的随机系数，避免多个上传同时重试
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最多尝试次数（含第一次），为 1 时不重试
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `retry` 次重试（从 1 开始）前的等待时间，`jitter` 取 [0, 1)
+    pub fn delay(&self, retry: u32, jitter: f64) -> Duration {
+        let exp = self
+            .base_delay_ms
+            .saturating_mul(1u64 << retry.saturating_sub(1).min(32));
+        let capped = exp.min(self.max_delay_ms);
+        Duration::from_millis((capped as f64 * (0.5 + jitter.clamp(0.0, 1.0) * 0.5)) as u64)
+    }
+}
+
+/// 发送请求：连接错误与 5xx 响应按 `policy` 退避重试，用尽次数后返回最后一次的结果；
+/// 其他错误与非 5xx 响应（含 4xx）直接返回，由调用方分类
+pub(crate) async fn send_with_retry<F, Fut>(
+    policy: &RetryPolicy,
+    action: &str,
+    mut send: F,
+) -> Result<reqwest::Response, CloudError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<reqwest::Response, CloudError>>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = send().await;
+        let retryable = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(e) => matches!(e, CloudError::Network(_)),
+        };
+        if !retryable || attempt >= policy.max_attempts {
+            return result;
+        }
+        let delay = policy.delay(attempt, rand::random::<f64>());
+        match &result {
+            Ok(response) => warn!(
+                "{}返回 {}，{} ms 后第 {} 次重试",
+                action,
+                response.status(),
+                delay.as_millis(),
+                attempt
+            ),
+            Err(e) => warn!(
+                "{}: {}，{} ms 后第 {} 次重试",
+                action,
+                e,
+                delay.as_millis(),
+                attempt
+            ),
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_schedule_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay_ms: 100,
+            max_delay_ms: 1_000,
+        };
+        let schedule: Vec<u64> = (1..=6)
+            .map(|retry| policy.delay(retry, 1.0).as_millis() as u64)
+            .collect();
+        assert_eq!(schedule, [100, 200, 400, 800, 1_000, 1_000]);
+        // 抖动最多把等待时间减半
+        assert_eq!(policy.delay(3, 0.0), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, 0.5), Duration::from_millis(300));
+        assert_eq!(policy.delay(40, 1.0), Duration::from_millis(1_000));
+    }
+}
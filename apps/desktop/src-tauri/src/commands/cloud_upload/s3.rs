@@ -0,0 +1,871 @@
+//! S3 兼容对象存储上传（AWS S3、MinIO、NAS 等）：大文件用 multipart upload，分块可并行上传；
+//! 不超过一个分块的文件（含空文件）用单次 PutObject
+
+use hmac::{Hmac, Mac};
+use log::{debug, error, info};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::AtomicBool;
+use tauri::AppHandle;
+
+use super::{
+    check_target, upload_with_progress, CloudError, CloudStorage, PartOutcome, TargetStatus,
+    UploadConfig, UploadPart, UploadedFile, PROBE_FILE_NAME,
+};
+
+/// S3 默认分块大小：16MB（S3 要求除最后一块外不小于 5MB）
+const S3_PART_SIZE: u64 = 16 * 1024 * 1024;
+
+/// 一次 multipart 上传最多的分块数
+const S3_MAX_PARTS: u64 = 10_000;
+
+/// 按文件大小选择分块大小：默认 16MB，文件过大时增大分块（按 MB 取整），使分块数不超过 10000
+fn part_size_for(file_size: u64) -> u64 {
+    const MB: u64 = 1024 * 1024;
+    let min_part = file_size.div_ceil(S3_MAX_PARTS).div_ceil(MB) * MB;
+    S3_PART_SIZE.max(min_part)
+}
+
+/// 未指定区域时使用的默认值（MinIO 等自建服务通常不校验区域）
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// 参与 SigV4 签名的请求头
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// S3 兼容存储连接配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Config {
+    /// 服务端点，如 `https://s3.us-east-1.amazonaws.com` 或 `http://nas.local:9000`
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl S3Config {
+    /// 目标的连接配置：优先取 `config.s3`，否则由 `endpoint`、`bucket`、`region` 与
+    /// `access_token`（Access Key ID）、`secret_key` 组成
+    pub fn from_upload_config(config: &UploadConfig) -> Result<S3Config, CloudError> {
+        if let Some(s3) = &config.s3 {
+            return Ok(s3.clone());
+        }
+        let required = |value: &Option<String>, field: &str| {
+            value
+                .as_deref()
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    CloudError::Local(format!("{} 缺少 S3 配置: {}", config.name, field))
+                })
+        };
+        if config.access_token.is_empty() {
+            return Err(CloudError::Local(format!(
+                "{} 缺少 S3 配置: access_token",
+                config.name
+            )));
+        }
+        Ok(S3Config {
+            endpoint: required(&config.endpoint, "endpoint")?,
+            region: config
+                .region
+                .clone()
+                .filter(|r| !r.is_empty())
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+            bucket: required(&config.bucket, "bucket")?,
+            access_key_id: config.access_token.clone(),
+            secret_access_key: required(&config.secret_key, "secret_key")?,
+        })
+    }
+}
+
+/// 一次上传的会话
+struct S3Session {
+    key: String,
+    /// multipart 上传的 UploadId；文件不超过一个分块时为 None，直接 PutObject
+    upload_id: Option<String>,
+}
+
+/// S3 multipart 上传：init → 并行上传分块 → complete，取消时 abort；小文件单次 PutObject。
+/// 使用路径风格 URL（`{endpoint}/{bucket}/{key}`）以兼容 MinIO 等自建服务。
+struct S3Storage<'a> {
+    client: reqwest::Client,
+    config: &'a S3Config,
+    /// 目标目录（对象键前缀）
+    target_path: String,
+    part_size: u64,
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 签名密钥：`AWS4{secret}` 依次对日期、区域、服务、`aws4_request` 做 HMAC
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, region);
+    let k_service = hmac_sha256(&k_region, service);
+    hmac_sha256(&k_service, "aws4_request")
+}
+
+/// 按 SigV4 规则编码（仅保留 `A-Za-z0-9-._~`）
+fn uri_encode(s: &str) -> String {
+    urlencoding::encode(s).into_owned()
+}
+
+/// 目标目录与文件名拼成对象键，如 `/backups/2024/` + `a.zip` -> `backups/2024/a.zip`
+fn object_key(target_path: &str, file_name: &str) -> String {
+    let prefix = target_path.trim_matches('/');
+    if prefix.is_empty() {
+        file_name.to_string()
+    } else {
+        format!("{}/{}", prefix, file_name)
+    }
+}
+
+/// 从 S3 的 XML 响应中取出第一个 `<tag>` 的文本
+fn xml_tag<'a>(body: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = body.find(&open)? + open.len();
+    let end = body[start..].find(&format!("</{}>", tag))? + start;
+    Some(&body[start..end])
+}
+
+/// 按 HTTP 状态码与 S3 错误体中的 `<Code>` 分类错误
+fn s3_error(status: reqwest::StatusCode, body: &str, action: &str) -> CloudError {
+    let code = xml_tag(body, "Code").unwrap_or_default();
+    let message = format!(
+        "{}失败 ({}): {}",
+        action,
+        status.as_u16(),
+        xml_tag(body, "Message").unwrap_or(body)
+    );
+    match (status.as_u16(), code) {
+        (_, "QuotaExceeded" | "XMinioStorageFull") => CloudError::QuotaExceeded(message),
+        (_, "NoSuchBucket" | "NoSuchKey" | "NoSuchUpload") | (404, _) => {
+            CloudError::NotFound(message)
+        }
+        (
+            _,
+            "ExpiredToken"
+            | "InvalidAccessKeyId"
+            | "InvalidToken"
+            | "SignatureDoesNotMatch"
+            | "AccessDenied",
+        )
+        | (401 | 403, _) => CloudError::AuthExpired(message),
+        _ => CloudError::Server(message),
+    }
+}
+
+impl S3Storage<'_> {
+    /// 构造带 SigV4 签名的对象请求
+    fn signed_request(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder, CloudError> {
+        let config = self.config;
+        let endpoint = reqwest::Url::parse(&config.endpoint)
+            .map_err(|e| CloudError::Local(format!("无效的 S3 端点 {}: {}", config.endpoint, e)))?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(CloudError::Local(format!(
+                    "无效的 S3 端点: {}",
+                    config.endpoint
+                )))
+            }
+        };
+
+        let mut canonical_uri = endpoint.path().trim_end_matches('/').to_string();
+        canonical_uri.push('/');
+        canonical_uri.push_str(&uri_encode(&config.bucket));
+        // 空键表示存储桶本身（如 ListObjectsV2）
+        for segment in key.split('/').filter(|_| !key.is_empty()) {
+            canonical_uri.push('/');
+            canonical_uri.push_str(&uri_encode(segment));
+        }
+
+        let mut pairs: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k), uri_encode(v)))
+            .collect();
+        pairs.sort();
+        let canonical_query = pairs
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &amz_date[..8];
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method.as_str(),
+            canonical_uri,
+            canonical_query,
+            host,
+            payload_hash,
+            amz_date,
+            SIGNED_HEADERS,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&config.secret_access_key, date, &config.region, "s3");
+        let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            config.access_key_id, scope, SIGNED_HEADERS, signature
+        );
+
+        let mut url = format!("{}://{}{}", endpoint.scheme(), host, canonical_uri);
+        if !canonical_query.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query);
+        }
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("Authorization", authorization)
+            .body(body))
+    }
+
+    /// 以单次 PutObject 上传整个对象
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<String, CloudError> {
+        debug!(
+            "PutObject 上传: {}/{} ({} 字节)",
+            self.config.bucket,
+            key,
+            data.len()
+        );
+        let request = self.signed_request(Method::PUT, key, &[], data)?;
+        self.send(request, "上传文件").await?;
+        info!("S3 上传成功: {}/{}", self.config.bucket, key);
+        Ok(format!("s3://{}/{}", self.config.bucket, key))
+    }
+
+    /// 发送请求，非 2xx 状态码时返回带响应内容的错误
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        action: &str,
+    ) -> Result<reqwest::Response, CloudError> {
+        let response = request.send().await.map_err(|e| {
+            error!("{}失败: {}", action, e);
+            CloudError::request(action, &e)
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            error!("{}失败，状态码: {}，错误: {}", action, status, error_text);
+            return Err(s3_error(status, &error_text, action));
+        }
+        Ok(response)
+    }
+}
+
+impl CloudStorage for S3Storage<'_> {
+    type Session = S3Session;
+
+    fn supports_parallel_parts(&self) -> bool {
+        true
+    }
+
+    fn part_size(&self) -> u64 {
+        self.part_size
+    }
+
+    /// 列出目标前缀下的一个对象以校验凭据与存储桶，返回 `s3://bucket/prefix`
+    async fn resolve_target(&self) -> Result<String, CloudError> {
+        let prefix = object_key(&self.target_path, "");
+        let request = self.signed_request(
+            Method::GET,
+            "",
+            &[("list-type", "2"), ("max-keys", "1"), ("prefix", &prefix)],
+            Vec::new(),
+        )?;
+        self.send(request, "列出目标").await?;
+        Ok(format!("s3://{}/{}", self.config.bucket, prefix))
+    }
+
+    async fn write_probe(&self) -> Result<(), CloudError> {
+        let key = object_key(&self.target_path, PROBE_FILE_NAME);
+        let request = self.signed_request(Method::PUT, &key, &[], b"ok".to_vec())?;
+        self.send(request, "写入探测文件").await?;
+        let request = self.signed_request(Method::DELETE, &key, &[], Vec::new())?;
+        self.send(request, "删除探测文件").await.map(|_| ())
+    }
+
+    async fn begin_upload(&self, file_name: &str, file_size: u64) -> Result<S3Session, CloudError> {
+        let key = object_key(&self.target_path, file_name);
+        if file_size <= self.part_size {
+            return Ok(S3Session {
+                key,
+                upload_id: None,
+            });
+        }
+        debug!("初始化 S3 multipart 上传: {}/{}", self.config.bucket, key);
+        let request = self.signed_request(Method::POST, &key, &[("uploads", "")], Vec::new())?;
+        let body = self
+            .send(request, "初始化 multipart 上传")
+            .await?
+            .text()
+            .await
+            .map_err(|e| CloudError::request("读取响应", &e))?;
+        let upload_id = xml_tag(&body, "UploadId")
+            .ok_or_else(|| {
+                error!("响应中没有 UploadId，响应内容: {}", body);
+                CloudError::Server("响应中没有 UploadId".to_string())
+            })?
+            .to_string();
+        info!("获取到 UploadId: {}", upload_id);
+        Ok(S3Session {
+            key,
+            upload_id: Some(upload_id),
+        })
+    }
+
+    async fn upload_part(
+        &self,
+        session: &S3Session,
+        part: UploadPart,
+    ) -> Result<PartOutcome, CloudError> {
+        let Some(upload_id) = &session.upload_id else {
+            let file_id = self.put_object(&session.key, part.data).await?;
+            return Ok(PartOutcome::Completed { file_id });
+        };
+        let part_number = (part.index + 1).to_string();
+        debug!("上传分块 {}: {}", part_number, part.content_range());
+        let request = self.signed_request(
+            Method::PUT,
+            &session.key,
+            &[("partNumber", &part_number), ("uploadId", upload_id)],
+            part.data,
+        )?;
+        let response = self.send(request, "上传分块").await?;
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| CloudError::Server(format!("分块 {} 的响应中没有 ETag", part_number)))?;
+        Ok(PartOutcome::Accepted { etag: Some(etag) })
+    }
+
+    async fn complete_upload(
+        &self,
+        session: &S3Session,
+        parts: Vec<PartOutcome>,
+    ) -> Result<String, CloudError> {
+        // 空文件没有分块，收尾时写入空对象
+        let Some(upload_id) = &session.upload_id else {
+            return self.put_object(&session.key, Vec::new()).await;
+        };
+        let mut xml = String::from("<CompleteMultipartUpload>");
+        for (i, part) in parts.iter().enumerate() {
+            let PartOutcome::Accepted { etag: Some(etag) } = part else {
+                return Err(CloudError::Local(format!("分块 {} 缺少 ETag", i + 1)));
+            };
+            xml.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                i + 1,
+                etag
+            ));
+        }
+        xml.push_str("</CompleteMultipartUpload>");
+
+        let request = self.signed_request(
+            Method::POST,
+            &session.key,
+            &[("uploadId", upload_id)],
+            xml.into_bytes(),
+        )?;
+        // CompleteMultipartUpload 可能在 200 响应体中返回错误
+        let body = self
+            .send(request, "完成 multipart 上传")
+            .await?
+            .text()
+            .await
+            .map_err(|e| CloudError::request("读取响应", &e))?;
+        if body.contains("<Error>") {
+            error!("完成 multipart 上传失败: {}", body);
+            return Err(s3_error(
+                reqwest::StatusCode::OK,
+                &body,
+                "完成 multipart 上传",
+            ));
+        }
+        info!("S3 上传成功: {}/{}", self.config.bucket, session.key);
+        Ok(format!("s3://{}/{}", self.config.bucket, session.key))
+    }
+
+    async fn abort_upload(&self, session: &S3Session) -> Result<(), CloudError> {
+        let Some(upload_id) = &session.upload_id else {
+            return Ok(());
+        };
+        info!("中止 S3 multipart 上传: {}", upload_id);
+        let request = self.signed_request(
+            Method::DELETE,
+            &session.key,
+            &[("uploadId", upload_id)],
+            Vec::new(),
+        )?;
+        self.send(request, "中止 multipart 上传").await.map(|_| ())
+    }
+}
+
+/// 上传文件到 S3 兼容存储（支持进度回调与取消），分块大小见 `part_size_for`
+pub(super) async fn upload_to_s3(
+    file_path: &str,
+    config: &UploadConfig,
+    app: &AppHandle,
+    task_id: &str,
+    cancel: &AtomicBool,
+) -> Result<UploadedFile, CloudError> {
+    debug!("准备上传文件到 S3: {}", file_path);
+    let s3 = S3Config::from_upload_config(config)?;
+    debug!(
+        "目标: {} / {}{}",
+        s3.endpoint, s3.bucket, config.target_path
+    );
+
+    let file_size = std::fs::metadata(file_path).map_or(0, |m| m.len());
+    let storage = S3Storage {
+        client: reqwest::Client::new(),
+        config: &s3,
+        target_path: config.target_path.clone(),
+        part_size: part_size_for(file_size),
+    };
+    upload_with_progress(&storage, file_path, config, app, task_id, cancel).await
+}
+
+/// 测试 S3 兼容存储目标，见 `test_cloud_target`
+pub(super) async fn test_s3_target(config: &UploadConfig) -> Result<TargetStatus, CloudError> {
+    let s3 = S3Config::from_upload_config(config)?;
+    let storage = S3Storage {
+        client: reqwest::Client::new(),
+        config: &s3,
+        target_path: config.target_path.clone(),
+        part_size: S3_PART_SIZE,
+    };
+    check_target(&storage, config).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::upload_parts;
+    use super::*;
+    use mockito::Matcher;
+    use std::sync::atomic::Ordering;
+
+    const UPLOAD_PATH: &str = "/backups/f.bin";
+
+    fn storage(config: &S3Config) -> S3Storage<'_> {
+        S3Storage {
+            client: reqwest::Client::new(),
+            config,
+            target_path: "/".to_string(),
+            part_size: 4,
+        }
+    }
+
+    fn config(endpoint: String) -> S3Config {
+        S3Config {
+            endpoint,
+            region: "us-east-1".to_string(),
+            bucket: "backups".to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+        }
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> Matcher {
+        Matcher::AllOf(
+            pairs
+                .iter()
+                .map(|(k, v)| Matcher::UrlEncoded(k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    fn temp_file(name: &str, content: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("s3_test_{}_{}", std::process::id(), name))
+            .join("f.bin");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_signing_key() {
+        // AWS 文档中的 SigV4 签名密钥示例
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+        assert_eq!(object_key("/a/b/", "c.zip"), "a/b/c.zip");
+        assert_eq!(object_key("/", "c.zip"), "c.zip");
+    }
+
+    #[test]
+    fn test_part_size_keeps_part_count_within_limit() {
+        const MB: u64 = 1024 * 1024;
+        assert_eq!(part_size_for(0), S3_PART_SIZE);
+        assert_eq!(part_size_for(S3_PART_SIZE * S3_MAX_PARTS), S3_PART_SIZE);
+        for size in [S3_PART_SIZE * S3_MAX_PARTS + 1, 1 << 40, 5 << 40] {
+            let part = part_size_for(size);
+            assert_eq!(part % MB, 0);
+            assert!(size.div_ceil(part) <= S3_MAX_PARTS, "{}", size);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_small_and_empty_files_use_single_put() {
+        let mut server = mockito::Server::new_async().await;
+        let init = server
+            .mock("POST", UPLOAD_PATH)
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let config = config(server.url());
+        let cancel = AtomicBool::new(false);
+
+        for (name, content) in [("small", &b"0123"[..]), ("empty", &b""[..])] {
+            let file = temp_file(name, content);
+            let put = server
+                .mock("PUT", UPLOAD_PATH)
+                .match_query(Matcher::Missing)
+                .match_body(content.to_vec())
+                .create_async()
+                .await;
+            let result = upload_parts(
+                &storage(&config),
+                &file,
+                "f.bin",
+                content.len() as u64,
+                &cancel,
+                |_, _| {},
+            )
+            .await;
+            assert_eq!(result, Ok("s3://backups/f.bin".to_string()), "{}", name);
+            put.assert_async().await;
+            put.remove_async().await;
+        }
+        init.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_multipart_lifecycle() {
+        let file = temp_file("lifecycle", b"0123456789");
+        let mut server = mockito::Server::new_async().await;
+        let auth = Matcher::Regex(
+            r"^AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/\d{8}/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature=[0-9a-f]{64}$".to_string(),
+        );
+        let init = server
+            .mock("POST", UPLOAD_PATH)
+            .match_query(query(&[("uploads", "")]))
+            .match_header("authorization", auth)
+            .with_body("<InitiateMultipartUploadResult><UploadId>up-1</UploadId></InitiateMultipartUploadResult>")
+            .create_async()
+            .await;
+        let mut parts = Vec::new();
+        for (n, chunk) in ["0123", "4567", "89"].iter().enumerate() {
+            let n = (n + 1).to_string();
+            parts.push(
+                server
+                    .mock("PUT", UPLOAD_PATH)
+                    .match_query(query(&[("partNumber", &n), ("uploadId", "up-1")]))
+                    .match_body(*chunk)
+                    .with_header("etag", &format!("\"e{}\"", n))
+                    .create_async()
+                    .await,
+            );
+        }
+        let complete = server
+            .mock("POST", UPLOAD_PATH)
+            .match_query(query(&[("uploadId", "up-1")]))
+            .match_body(concat!(
+                "<CompleteMultipartUpload>",
+                "<Part><PartNumber>1</PartNumber><ETag>\"e1\"</ETag></Part>",
+                "<Part><PartNumber>2</PartNumber><ETag>\"e2\"</ETag></Part>",
+                "<Part><PartNumber>3</PartNumber><ETag>\"e3\"</ETag></Part>",
+                "</CompleteMultipartUpload>"
+            ))
+            .with_body(
+                "<CompleteMultipartUploadResult><Key>f.bin</Key></CompleteMultipartUploadResult>",
+            )
+            .create_async()
+            .await;
+        let abort = server
+            .mock("DELETE", UPLOAD_PATH)
+            .expect(0)
+            .create_async()
+            .await;
+
+        let config = config(server.url());
+        let cancel = AtomicBool::new(false);
+        let result = upload_parts(&storage(&config), &file, "f.bin", 10, &cancel, |_, _| {}).await;
+
+        assert_eq!(result, Ok("s3://backups/f.bin".to_string()));
+        init.assert_async().await;
+        for part in &parts {
+            part.assert_async().await;
+        }
+        complete.assert_async().await;
+        abort.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_flat_config_issues_init_parts_complete_in_order() {
+        let file = temp_file("ordered", b"0123456789");
+        let mut server = mockito::Server::new_async().await;
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let record = |name: &'static str, body: &'static str| {
+            let calls = calls.clone();
+            move |request: &mockito::Request| {
+                let part = request
+                    .path_and_query()
+                    .split(['?', '&'])
+                    .find_map(|p| p.strip_prefix("partNumber="))
+                    .map(|n| format!(" {}", n))
+                    .unwrap_or_default();
+                calls.lock().unwrap().push(format!("{}{}", name, part));
+                body.as_bytes().to_vec()
+            }
+        };
+        server
+            .mock("POST", UPLOAD_PATH)
+            .match_query(query(&[("uploads", "")]))
+            .match_header(
+                "authorization",
+                Matcher::Regex(r"Credential=minio-key/\d{8}/us-east-1/s3/".to_string()),
+            )
+            .with_body_from_request(record(
+                "init",
+                "<InitiateMultipartUploadResult><UploadId>up-3</UploadId></InitiateMultipartUploadResult>",
+            ))
+            .create_async()
+            .await;
+        server
+            .mock("PUT", UPLOAD_PATH)
+            .match_query(Matcher::UrlEncoded("uploadId".into(), "up-3".into()))
+            .with_header("etag", "\"e\"")
+            .with_body_from_request(record("part", ""))
+            .expect(3)
+            .create_async()
+            .await;
+        server
+            .mock("POST", UPLOAD_PATH)
+            .match_query(query(&[("uploadId", "up-3")]))
+            .with_body_from_request(record("complete", "<CompleteMultipartUploadResult/>"))
+            .create_async()
+            .await;
+
+        let upload_config = UploadConfig {
+            provider: "s3".to_string(),
+            name: "MinIO".to_string(),
+            access_token: "minio-key".to_string(),
+            target_path: "/".to_string(),
+            s3: None,
+            endpoint: Some(server.url()),
+            bucket: Some("backups".to_string()),
+            region: None,
+            secret_key: Some("minio-secret".to_string()),
+            token_refresh: None,
+            retry: Default::default(),
+        };
+        let s3 = S3Config::from_upload_config(&upload_config).unwrap();
+        let cancel = AtomicBool::new(false);
+        let mut progress = Vec::new();
+        let result = upload_parts(&storage(&s3), &file, "f.bin", 10, &cancel, |done, _| {
+            progress.push(done)
+        })
+        .await;
+
+        assert_eq!(result, Ok("s3://backups/f.bin".to_string()));
+        // 分块可并行，彼此顺序不定，但都在 init 之后、complete 之前
+        let mut calls = calls.lock().unwrap().clone();
+        assert_eq!(calls.first().map(String::as_str), Some("init"));
+        assert_eq!(calls.last().map(String::as_str), Some("complete"));
+        calls[1..4].sort();
+        assert_eq!(calls, ["init", "part 1", "part 2", "part 3", "complete"]);
+        // 每完成一块报告一次进度
+        assert_eq!((progress.len(), progress.last()), (3, Some(&10)));
+
+        let missing = UploadConfig {
+            secret_key: None,
+            ..upload_config
+        };
+        let err = S3Config::from_upload_config(&missing).unwrap_err();
+        assert!(matches!(err, CloudError::Local(m) if m.contains("secret_key")));
+    }
+
+    #[tokio::test]
+    async fn test_target_check_reports_auth_and_writability() {
+        const PROBE_PATH: &str = "/backups/archive/.diskrookie-probe";
+        let list_query = || {
+            query(&[
+                ("list-type", "2"),
+                ("max-keys", "1"),
+                ("prefix", "archive/"),
+            ])
+        };
+        let upload_config = |endpoint: String| UploadConfig {
+            provider: "s3".to_string(),
+            name: "NAS".to_string(),
+            access_token: String::new(),
+            target_path: "/archive".to_string(),
+            s3: Some(config(endpoint)),
+            endpoint: None,
+            bucket: None,
+            region: None,
+            secret_key: None,
+            token_refresh: None,
+            retry: Default::default(),
+        };
+
+        // 有效目标：写入并删除探测文件
+        let mut valid = mockito::Server::new_async().await;
+        let list = valid
+            .mock("GET", "/backups")
+            .match_query(list_query())
+            .with_body("<ListBucketResult><KeyCount>0</KeyCount></ListBucketResult>")
+            .create_async()
+            .await;
+        let put = valid
+            .mock("PUT", PROBE_PATH)
+            .match_body("ok")
+            .create_async()
+            .await;
+        let delete = valid
+            .mock("DELETE", PROBE_PATH)
+            .with_status(204)
+            .create_async()
+            .await;
+        let status = test_s3_target(&upload_config(valid.url())).await.unwrap();
+        assert_eq!(
+            status,
+            TargetStatus {
+                provider: "s3".to_string(),
+                name: "NAS".to_string(),
+                target: "s3://backups/archive/".to_string(),
+                writable: true,
+                free_bytes: None,
+                message: None,
+            }
+        );
+        list.assert_async().await;
+        put.assert_async().await;
+        delete.assert_async().await;
+
+        // 令牌过期：不尝试写入
+        let mut expired = mockito::Server::new_async().await;
+        expired
+            .mock("GET", "/backups")
+            .match_query(list_query())
+            .with_status(400)
+            .with_body("<Error><Code>ExpiredToken</Code><Message>The provided token has expired.</Message></Error>")
+            .create_async()
+            .await;
+        let probe = expired
+            .mock("PUT", PROBE_PATH)
+            .expect(0)
+            .create_async()
+            .await;
+        let err = test_s3_target(&upload_config(expired.url()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudError::AuthExpired(_)), "{:?}", err);
+        probe.assert_async().await;
+
+        // 只读权限：可以列出但无法写入
+        let mut read_only = mockito::Server::new_async().await;
+        read_only
+            .mock("GET", "/backups")
+            .match_query(list_query())
+            .with_body("<ListBucketResult><KeyCount>0</KeyCount></ListBucketResult>")
+            .create_async()
+            .await;
+        read_only
+            .mock("PUT", PROBE_PATH)
+            .with_status(403)
+            .with_body("<Error><Code>AccessDenied</Code><Message>Access Denied</Message></Error>")
+            .create_async()
+            .await;
+        let status = test_s3_target(&upload_config(read_only.url()))
+            .await
+            .unwrap();
+        assert!(!status.writable);
+        assert!(status.message.unwrap().contains("Access Denied"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_upload_is_aborted() {
+        let file = temp_file("cancel", b"0123456789abcdef");
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", UPLOAD_PATH)
+            .match_query(query(&[("uploads", "")]))
+            .with_body("<InitiateMultipartUploadResult><UploadId>up-2</UploadId></InitiateMultipartUploadResult>")
+            .create_async()
+            .await;
+        server
+            .mock("PUT", UPLOAD_PATH)
+            .match_query(Matcher::Any)
+            .with_header("etag", "\"e\"")
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let complete = server
+            .mock("POST", UPLOAD_PATH)
+            .match_query(query(&[("uploadId", "up-2")]))
+            .expect(0)
+            .create_async()
+            .await;
+        let abort = server
+            .mock("DELETE", UPLOAD_PATH)
+            .match_query(query(&[("uploadId", "up-2")]))
+            .with_status(204)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = config(server.url());
+        let cancel = AtomicBool::new(false);
+        // 第一个分块完成后用户点击取消
+        let result = upload_parts(&storage(&config), &file, "f.bin", 16, &cancel, |_, _| {
+            cancel.store(true, Ordering::Relaxed);
+        })
+        .await;
+
+        assert_eq!(result, Err(CloudError::Cancelled));
+        complete.assert_async().await;
+        abort.assert_async().await;
+    }
+}
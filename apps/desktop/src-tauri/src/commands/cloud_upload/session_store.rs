@@ -0,0 +1,120 @@
+//! 可续传上传会话的本地记录（`~/.disk-rookie/upload_sessions.json`）：应用在上传中途退出后，
+//! 凭记录中的上传 URI 向服务端查询已接收的字节数，从断点继续上传，而不必整个文件重传。
+//! 记录只含会话信息，不含访问令牌，续传时由前端重新提供凭据
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::CloudError;
+
+const SESSIONS_FILE: &str = "upload_sessions.json";
+
+/// 同一进程内多个目标并行上传时串行读写记录文件
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 一个未完成的上传会话，按 (`task_id`, `target`) 区分：同一任务可能同时上传到多个目标
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadSessionRecord {
+    pub task_id: String,
+    pub provider: String,
+    /// 目标名称，即 `UploadConfig::name`
+    pub target: String,
+    pub file_path: String,
+    pub file_size: u64,
+    /// 服务端的上传会话地址（Google Drive resumable 上传 URI）
+    pub upload_uri: String,
+    /// 最近一次确认服务端已接收的字节数，仅供展示；续传时以服务端查询结果为准
+    pub uploaded: u64,
+}
+
+/// 上传会话记录文件
+#[derive(Debug, Clone)]
+pub struct UploadSessionStore {
+    path: PathBuf,
+}
+
+impl UploadSessionStore {
+    pub fn new(path: PathBuf) -> Self {
+        UploadSessionStore { path }
+    }
+
+    /// 应用数据目录下的默认记录文件
+    pub fn in_home(home: &Path) -> Self {
+        Self::new(home.join(".disk-rookie").join(SESSIONS_FILE))
+    }
+
+    /// 全部未完成的会话；文件不存在或已损坏时为空
+    pub fn load(&self) -> Vec<UploadSessionRecord> {
+        let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        self.read()
+    }
+
+    pub fn get(&self, task_id: &str, target: &str) -> Option<UploadSessionRecord> {
+        self.load()
+            .into_iter()
+            .find(|r| r.task_id == task_id && r.target == target)
+    }
+
+    /// 新增或覆盖同一任务、同一目标的记录
+    pub fn save(&self, record: UploadSessionRecord) -> Result<(), CloudError> {
+        self.modify(|records| {
+            records.retain(|r| !(r.task_id == record.task_id && r.target == record.target));
+            records.push(record);
+        })
+    }
+
+    /// 更新已上传字节数；没有对应记录时不做任何事
+    pub fn set_uploaded(
+        &self,
+        task_id: &str,
+        target: &str,
+        uploaded: u64,
+    ) -> Result<(), CloudError> {
+        self.modify(|records| {
+            if let Some(record) = records
+                .iter_mut()
+                .find(|r| r.task_id == task_id && r.target == target)
+            {
+                record.uploaded = uploaded;
+            }
+        })
+    }
+
+    /// 上传完成、被取消或无法续传时删除记录
+    pub fn remove(&self, task_id: &str, target: &str) -> Result<(), CloudError> {
+        self.modify(|records| records.retain(|r| !(r.task_id == task_id && r.target == target)))
+    }
+
+    fn read(&self) -> Vec<UploadSessionRecord> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                warn!("读取上传会话记录失败: {}", e);
+                return Vec::new();
+            }
+        };
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            warn!("上传会话记录格式错误，已忽略: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// 读取、修改并写回（先写临时文件再替换，避免写到一半退出留下损坏的文件）
+    fn modify(&self, f: impl FnOnce(&mut Vec<UploadSessionRecord>)) -> Result<(), CloudError> {
+        let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut records = self.read();
+        f(&mut records);
+        let local = |e: std::io::Error| CloudError::Local(format!("保存上传会话记录失败: {}", e));
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(local)?;
+        }
+        let json = serde_json::to_string_pretty(&records)
+            .map_err(|e| CloudError::Local(format!("保存上传会话记录失败: {}", e)))?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(local)?;
+        std::fs::rename(&tmp, &self.path).map_err(local)
+    }
+}
@@ -1,44 +1,187 @@
+use ai_disk_common::path as disk_path;
+use ai_disk_domain::{explain, FileNode};
+use ai_disk_executor::{move_to_trash, Quarantine, QuarantineEntry, QuarantinePolicy};
+use serde::Serialize;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
 
+/// 隔离区默认宽限天数
+const DEFAULT_QUARANTINE_DAYS: u32 = 30;
+
+/// 应用管理的隔离目录（~/.disk-rookie/quarantine）
+pub fn quarantine_policy(home: &Path, grace_days: u32) -> QuarantinePolicy {
+    QuarantinePolicy {
+        dir: home.join(".disk-rookie").join("quarantine"),
+        grace_days,
+    }
+}
+
+fn open_quarantine(app: &AppHandle, grace_days: u32) -> Result<Quarantine, String> {
+    let home: PathBuf = app.path().home_dir().map_err(|e| e.to_string())?;
+    Quarantine::open(&quarantine_policy(&home, grace_days)).map_err(|e| e.to_string())
+}
+
+/// `path` 为卷根、系统目录或受保护文件（见 `RiskExplanation::is_protected`）时返回拒绝原因。
+/// 忽略 `canonicalize` 在 Windows 上产生的 `\\?\` 前缀
+fn forbidden_reason(path: &str, is_dir: bool) -> Option<String> {
+    let path = path
+        .strip_prefix(r"\\?\")
+        .filter(|rest| rest.as_bytes().get(1) == Some(&b':'))
+        .unwrap_or(path);
+    // 卷根：只有根本身（`C:\`、`\\server\share`、`/`）
+    if disk_path::components(path).len() <= usize::from(disk_path::root(path).is_some()) {
+        return Some(format!("禁止删除卷根目录: {}", path));
+    }
+    let node = FileNode {
+        path: path.to_string(),
+        name: disk_path::file_name(path).to_string(),
+        is_dir,
+        ..Default::default()
+    };
+    explain(&node)
+        .is_protected()
+        .then(|| format!("禁止删除系统目录或受保护文件: {}", path))
+}
+
+/// `delete_item` 实际执行的删除方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteItemOutcome {
+    /// 已移入隔离区，宽限期内可恢复
+    Quarantined,
+    /// 已移到系统回收站
+    Trashed,
+    /// 已永久删除
+    Deleted,
+}
+
+/// `delete_item` 的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeleteItemResult {
+    pub outcome: DeleteItemOutcome,
+    pub is_dir: bool,
+    /// 给用户看的说明
+    pub message: String,
+}
+
+/// 删除文件或目录；传入 `quarantine_days` 时移入隔离区，宽限期内可通过 `restore_quarantined` 恢复；
+/// 否则默认移到系统回收站，`to_trash` 为 false 时才永久删除。
+/// 节点带有 `raw_path`（文件名含未配对代理项）时应一并传入，否则按显示路径找不到文件
 #[tauri::command]
-pub async fn delete_item(path: String) -> Result<String, String> {
-    let path_buf = Path::new(&path);
+pub async fn delete_item(
+    app: AppHandle,
+    path: String,
+    raw_path: Option<Vec<u16>>,
+    quarantine_days: Option<u32>,
+    to_trash: Option<bool>,
+) -> Result<DeleteItemResult, String> {
+    let real_path = ai_disk_common::path::fs_path(&path, raw_path.as_deref());
+    let path_buf = real_path.as_path();
 
     if !path_buf.exists() {
         return Err(format!("路径不存在: {}", path));
     }
 
-    // 安全检查：禁止删除系统关键目录
-    let forbidden_paths = if cfg!(windows) {
-        vec![
-            "C:\\Windows",
-            "C:\\Program Files",
-            "C:\\Program Files (x86)",
-            "C:\\System Volume Information",
-        ]
-    } else {
-        vec![
-            "/System", "/Library", "/bin", "/sbin", "/usr", "/etc", "/var",
-        ]
-    };
-
+    // 安全检查：禁止删除卷根、系统关键目录与受保护文件
     let canonical = fs::canonicalize(path_buf).map_err(|e| format!("无法解析路径: {}", e))?;
+    if let Some(reason) = forbidden_reason(&canonical.to_string_lossy(), canonical.is_dir()) {
+        return Err(reason);
+    }
 
-    let canonical_str = canonical.to_string_lossy().to_string();
+    let is_dir = path_buf.is_dir();
+    let result = |outcome, message| DeleteItemResult {
+        outcome,
+        is_dir,
+        message,
+    };
 
-    for forbidden in forbidden_paths {
-        if canonical_str.starts_with(forbidden) {
-            return Err(format!("禁止删除系统目录: {}", forbidden));
-        }
+    if let Some(days) = quarantine_days {
+        let entry = open_quarantine(&app, days)?
+            .quarantine(path_buf)
+            .map_err(|e| format!("移入隔离区失败: {}", e))?;
+        return Ok(result(
+            DeleteItemOutcome::Quarantined,
+            format!("已移入隔离区，{} 天后永久删除: {}", days, entry.original),
+        ));
+    }
+
+    if to_trash.unwrap_or(true) {
+        move_to_trash(path_buf).map_err(|e| e.to_string())?;
+        return Ok(result(
+            DeleteItemOutcome::Trashed,
+            format!("已移到回收站: {}", path),
+        ));
     }
 
     // 执行删除
-    if path_buf.is_dir() {
+    if is_dir {
         fs::remove_dir_all(path_buf).map_err(|e| format!("删除目录失败: {}", e))?;
-        Ok(format!("已删除目录: {}", path))
+        Ok(result(
+            DeleteItemOutcome::Deleted,
+            format!("已删除目录: {}", path),
+        ))
     } else {
         fs::remove_file(path_buf).map_err(|e| format!("删除文件失败: {}", e))?;
-        Ok(format!("已删除文件: {}", path))
+        Ok(result(
+            DeleteItemOutcome::Deleted,
+            format!("已删除文件: {}", path),
+        ))
+    }
+}
+
+/// 隔离区中尚未到期的条目
+#[tauri::command]
+pub async fn list_quarantine(app: AppHandle) -> Result<Vec<QuarantineEntry>, String> {
+    Ok(open_quarantine(&app, DEFAULT_QUARANTINE_DAYS)?
+        .entries()
+        .to_vec())
+}
+
+/// 把隔离区中的条目移回原路径
+#[tauri::command]
+pub async fn restore_quarantined(app: AppHandle, id: u64) -> Result<QuarantineEntry, String> {
+    open_quarantine(&app, DEFAULT_QUARANTINE_DAYS)?
+        .restore(id)
+        .map_err(|e| format!("恢复失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forbidden_reason_windows_ignores_drive_letter_case() {
+        for path in [
+            r"C:\Windows\System32",
+            r"c:\windows\system32",
+            r"\\?\C:\PROGRAM FILES\app",
+            r"c:\Program Files (x86)",
+            r"D:\Windows",
+        ] {
+            assert!(forbidden_reason(path, true).is_some(), "{}", path);
+        }
+        for root in [r"C:\", r"c:\", r"\\?\D:\", "C:"] {
+            let reason = forbidden_reason(root, true).unwrap();
+            assert!(reason.contains("卷根"), "{}", root);
+        }
+        assert_eq!(forbidden_reason(r"C:\Users\u\Downloads\a.zip", false), None);
+        // 只按整段匹配，同名前缀的目录不受影响
+        assert_eq!(forbidden_reason(r"d:\WindowsBackup", true), None);
+        // 由系统管理的文件与受保护文件同样拒绝
+        assert!(forbidden_reason(r"C:\pagefile.sys", false).is_some());
+        assert!(forbidden_reason(r"C:\Users\u\.ssh\id_rsa", false).is_some());
+    }
+
+    #[test]
+    fn test_forbidden_reason_unix() {
+        assert!(forbidden_reason("/usr/lib", true).is_some());
+        assert!(forbidden_reason("/var/lib", true).is_some());
+        assert!(forbidden_reason("/private/var/lib", true).is_some());
+        assert_eq!(forbidden_reason("/private/tmp/x", false), None);
+        assert!(forbidden_reason("/", true).unwrap().contains("卷根"));
+        assert_eq!(forbidden_reason("/home", true), None);
+        assert_eq!(forbidden_reason("/home/u/usr", true), None);
+        assert_eq!(forbidden_reason("/usrdata/x", true), None);
     }
 }
@@ -0,0 +1,346 @@
+//! 运行环境能力探测：一次性回答「这里能做什么」——MFT 快速扫描、回收站、长路径、云存储目标，
+//! 前端据此置灰不可用的功能并给出可操作的提示。
+//!
+//! 各项子检查（`Probes`）与汇总逻辑（`aggregate`）分开，汇总逻辑可用构造的探测结果测试。
+
+use std::path::{Path, PathBuf};
+
+use futures::future;
+use serde::Serialize;
+
+use super::cloud_upload::{test_cloud_target, CloudError, TargetStatus, UploadConfig};
+use super::permission::check_admin_permission;
+
+/// 单项能力
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Capability {
+    pub available: bool,
+    /// 不可用（或受限）时给用户的提示
+    pub hint: Option<String>,
+}
+
+impl Capability {
+    fn yes() -> Self {
+        Self {
+            available: true,
+            hint: None,
+        }
+    }
+
+    fn no(hint: impl Into<String>) -> Self {
+        Self {
+            available: false,
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// 一个云存储目标的可用性
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CloudCapability {
+    pub name: String,
+    pub provider: String,
+    #[serde(flatten)]
+    pub capability: Capability,
+}
+
+/// 当前环境的能力汇总
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// MFT 快速扫描（Windows、NTFS 卷、管理员权限）
+    pub mft: Capability,
+    /// 删除时移到回收站
+    pub trash: Capability,
+    /// 列举与恢复回收站中的文件
+    pub recycle_bin: Capability,
+    /// 超过 260 字符的路径（仅 Windows 受限）
+    pub long_paths: Capability,
+    pub cloud: Vec<CloudCapability>,
+}
+
+/// 一个云存储目标的连接测试结果
+#[derive(Debug, Clone)]
+pub struct CloudProbe {
+    pub name: String,
+    pub provider: String,
+    pub result: Result<TargetStatus, CloudError>,
+}
+
+/// 各项子检查的原始结果
+#[derive(Debug, Clone)]
+pub struct Probes {
+    pub windows: bool,
+    pub elevated: bool,
+    /// 被检查卷的根路径，用于提示
+    pub volume: String,
+    /// 卷的文件系统名，无法识别时为 None
+    pub filesystem: Option<String>,
+    pub trash: bool,
+    pub recycle_bin: bool,
+    /// 系统是否启用了长路径；不受限的平台为 None
+    pub long_paths: Option<bool>,
+    pub cloud: Vec<CloudProbe>,
+}
+
+/// 探测 `path` 所在卷（默认系统盘）与 `configs` 中的云存储目标
+#[tauri::command]
+pub async fn capabilities(
+    path: Option<String>,
+    configs: Option<Vec<UploadConfig>>,
+) -> Result<Capabilities, String> {
+    let volume = path.unwrap_or_else(default_volume);
+    let cloud = future::join_all(configs.unwrap_or_default().into_iter().map(|config| async {
+        CloudProbe {
+            name: config.name.clone(),
+            provider: config.provider.clone(),
+            result: test_cloud_target(config).await,
+        }
+    }))
+    .await;
+    Ok(aggregate(Probes {
+        windows: cfg!(windows),
+        elevated: check_admin_permission(),
+        filesystem: volume_filesystem(&volume),
+        volume,
+        trash: trash_supported(),
+        recycle_bin: cfg!(any(windows, target_os = "linux")),
+        long_paths: long_paths_enabled(),
+        cloud,
+    }))
+}
+
+/// 把子检查结果汇总为能力与提示
+pub fn aggregate(probes: Probes) -> Capabilities {
+    let mft = if !probes.windows {
+        Capability::no("MFT 快速扫描仅支持 Windows，将使用普通遍历扫描")
+    } else {
+        match probes.filesystem.as_deref() {
+            Some(fs) if fs.eq_ignore_ascii_case("NTFS") => {
+                if probes.elevated {
+                    Capability::yes()
+                } else {
+                    Capability::no("以管理员身份运行以启用 MFT 快速扫描")
+                }
+            }
+            Some(fs) => Capability::no(format!(
+                "{} 的文件系统为 {}，MFT 快速扫描仅支持 NTFS",
+                probes.volume, fs
+            )),
+            None => Capability::no(format!(
+                "无法识别 {} 的文件系统，将使用普通遍历扫描",
+                probes.volume
+            )),
+        }
+    };
+    let trash = if probes.trash {
+        Capability::yes()
+    } else {
+        Capability::no("当前系统没有可用的回收站，删除将是永久的；可改用隔离区删除")
+    };
+    let recycle_bin = if probes.recycle_bin {
+        Capability::yes()
+    } else {
+        Capability::no("当前系统不支持从回收站恢复，需要撤销时请使用隔离区删除")
+    };
+    let long_paths = match probes.long_paths {
+        None | Some(true) => Capability::yes(),
+        Some(false) => Capability::no(
+            "未启用 Windows 长路径支持，超过 260 字符的路径可能无法删除或移动；\
+             可在注册表 LongPathsEnabled 或组策略中开启",
+        ),
+    };
+    let cloud = probes
+        .cloud
+        .into_iter()
+        .map(|probe| CloudCapability {
+            capability: match probe.result {
+                Ok(status) if status.writable => Capability::yes(),
+                Ok(status) => Capability::no(
+                    status
+                        .message
+                        .unwrap_or_else(|| format!("{} 不可写", status.target)),
+                ),
+                Err(e) => Capability::no(e.to_string()),
+            },
+            name: probe.name,
+            provider: probe.provider,
+        })
+        .collect();
+    Capabilities {
+        mft,
+        trash,
+        recycle_bin,
+        long_paths,
+        cloud,
+    }
+}
+
+fn default_volume() -> String {
+    if cfg!(windows) {
+        let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        format!("{}\\", drive.trim_end_matches('\\'))
+    } else {
+        "/".to_string()
+    }
+}
+
+#[cfg(windows)]
+fn volume_filesystem(path: &str) -> Option<String> {
+    ai_disk_scanner::get_volume_filesystem(path)
+}
+
+#[cfg(not(windows))]
+fn volume_filesystem(_path: &str) -> Option<String> {
+    None
+}
+
+/// Windows 与 macOS 总有回收站；Linux 需要 freedesktop 的数据目录（`~/.local/share`）
+fn trash_supported() -> bool {
+    if cfg!(any(windows, target_os = "macos")) {
+        return true;
+    }
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")));
+    data_home.is_some_and(|dir| dir.is_dir())
+}
+
+/// 读取注册表 `HKLM\SYSTEM\CurrentControlSet\Control\FileSystem\LongPathsEnabled`；
+/// 读取失败按未启用处理
+#[cfg(windows)]
+fn long_paths_enabled() -> Option<bool> {
+    use std::os::windows::process::CommandExt;
+    // CREATE_NO_WINDOW：不弹出控制台窗口
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SYSTEM\CurrentControlSet\Control\FileSystem",
+            "/v",
+            "LongPathsEnabled",
+        ])
+        .creation_flags(0x0800_0000)
+        .output();
+    let enabled = output
+        .ok()
+        .and_then(|o| parse_reg_dword(&String::from_utf8_lossy(&o.stdout)))
+        .is_some_and(|v| v != 0);
+    Some(enabled)
+}
+
+#[cfg(not(windows))]
+fn long_paths_enabled() -> Option<bool> {
+    None
+}
+
+/// 解析 `reg query` 输出中的 `REG_DWORD    0x1`
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_reg_dword(output: &str) -> Option<u32> {
+    let mut words = output.split_whitespace();
+    words.find(|w| *w == "REG_DWORD")?;
+    let value = words.next()?;
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probes() -> Probes {
+        Probes {
+            windows: true,
+            elevated: true,
+            volume: r"C:\".to_string(),
+            filesystem: Some("NTFS".to_string()),
+            trash: true,
+            recycle_bin: true,
+            long_paths: Some(true),
+            cloud: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_aggregate_capabilities_from_probes() {
+        let all = aggregate(probes());
+        assert!(all.mft.available && all.trash.available && all.long_paths.available);
+        assert_eq!(all.mft.hint, None);
+
+        // MFT：非管理员、非 NTFS、非 Windows 各有对应提示
+        let not_admin = aggregate(Probes {
+            elevated: false,
+            ..probes()
+        });
+        assert!(!not_admin.mft.available);
+        assert!(not_admin.mft.hint.unwrap().contains("管理员"));
+        let exfat = aggregate(Probes {
+            volume: r"E:\".to_string(),
+            filesystem: Some("exFAT".to_string()),
+            ..probes()
+        });
+        assert!(exfat.mft.hint.unwrap().contains(r"E:\ 的文件系统为 exFAT"));
+        let linux = aggregate(Probes {
+            windows: false,
+            filesystem: None,
+            long_paths: None,
+            trash: false,
+            recycle_bin: false,
+            ..probes()
+        });
+        assert!(!linux.mft.available && !linux.trash.available && !linux.recycle_bin.available);
+        // 长路径只在 Windows 上受限
+        assert!(linux.long_paths.available);
+        let short = aggregate(Probes {
+            long_paths: Some(false),
+            ..probes()
+        });
+        assert!(short.long_paths.hint.unwrap().contains("LongPathsEnabled"));
+
+        // 云存储：可写、只读、凭据失效
+        let status = |writable: bool| TargetStatus {
+            provider: "s3".to_string(),
+            name: "Cold".to_string(),
+            target: "bucket/backup".to_string(),
+            writable,
+            free_bytes: None,
+            message: (!writable).then(|| "AccessDenied".to_string()),
+        };
+        let cloud = aggregate(Probes {
+            cloud: vec![
+                CloudProbe {
+                    name: "Cold".to_string(),
+                    provider: "s3".to_string(),
+                    result: Ok(status(true)),
+                },
+                CloudProbe {
+                    name: "Archive".to_string(),
+                    provider: "s3".to_string(),
+                    result: Ok(status(false)),
+                },
+                CloudProbe {
+                    name: "Drive".to_string(),
+                    provider: "google_drive".to_string(),
+                    result: Err(CloudError::AuthExpired("invalid_grant".to_string())),
+                },
+            ],
+            ..probes()
+        })
+        .cloud;
+        assert_eq!(cloud.len(), 3);
+        assert_eq!(cloud[0].capability, Capability::yes());
+        assert_eq!(cloud[1].capability, Capability::no("AccessDenied"));
+        assert_eq!(cloud[2].name, "Drive");
+        assert!(cloud[2]
+            .capability
+            .hint
+            .as_ref()
+            .unwrap()
+            .contains("重新登录"));
+    }
+
+    #[test]
+    fn test_parse_reg_dword() {
+        let out = "\r\nHKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\FileSystem\r\n    \
+                   LongPathsEnabled    REG_DWORD    0x1\r\n\r\n";
+        assert_eq!(parse_reg_dword(out), Some(1));
+        assert_eq!(parse_reg_dword("ERROR: not found"), None);
+    }
+}
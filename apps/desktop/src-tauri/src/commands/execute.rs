@@ -1,7 +1,16 @@
 use ai_disk_domain::CleanupPlan;
+use ai_disk_executor::DryRunReport;
 
 #[tauri::command]
 pub async fn execute_plan(plan: CleanupPlan, dry_run: bool) -> Result<String, String> {
     let _ = (plan, dry_run);
     Ok("执行功能待实现".to_string())
 }
+
+/// 预演清理计划：逐项给出预计释放的空间与会失败的原因，不修改任何文件
+#[tauri::command]
+pub async fn dry_run_plan(plan: CleanupPlan) -> Result<DryRunReport, String> {
+    tauri::async_runtime::spawn_blocking(move || ai_disk_executor::dry_run_plan(&plan))
+        .await
+        .map_err(|e| e.to_string())
+}
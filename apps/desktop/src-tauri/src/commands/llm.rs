@@ -0,0 +1,14 @@
+use ai_disk_engine::llm::{KeyStatus, LlmClient};
+
+/// 保存 API Key 前校验其可用性与剩余额度
+#[tauri::command]
+pub async fn validate_llm_key(
+    api_url: String,
+    api_key: String,
+    model: String,
+) -> Result<KeyStatus, String> {
+    LlmClient::new(&api_url, &api_key, &model)
+        .validate()
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,7 +1,9 @@
 pub mod analyze;
 pub mod cloud_upload;
 pub mod delete;
+pub mod diagnostics;
 pub mod execute;
+pub mod llm;
 pub mod oauth;
 pub mod open_in_file_manager;
 pub mod permission;
@@ -679,6 +679,7 @@ pub async fn revoke_google_token(token: String) -> Result<(), String> {
         return Err(format!("撤销 token 失败: {}", error_text));
     }
 
+    super::cloud_upload::invalidate_folder_cache();
     Ok(())
 }
 
@@ -1,3 +1,19 @@
+//! 管理员权限：检测当前是否已提升权限，以及「以管理员身份重新扫描」的重启与续扫。
+//!
+//! 流程：前端收到带 `suggest_elevation` 的扫描完成事件后询问用户，同意则调用 `relaunch_elevated`
+//! 把扫描参数写入 `~/.disk-rookie/pending_rescan.json` 并以管理员身份启动新进程、退出当前进程；
+//! 新进程启动后调用 `take_pending_rescan` 取回参数（读取后即删除）并发起相同的扫描。
+
+use ai_disk_executor::{can_delete, can_write, PermissionStatus};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const PENDING_RESCAN_FILE: &str = "pending_rescan.json";
+/// 超过该时长（秒）未被取回的续扫请求视为过期，避免下次手动启动时意外开始扫描
+const PENDING_RESCAN_TTL_SECS: u64 = 5 * 60;
+
 /// 检测当前进程是否以管理员权限运行（Windows）
 #[tauri::command]
 pub fn check_admin_permission() -> bool {
@@ -11,3 +27,125 @@ pub fn check_admin_permission() -> bool {
         true
     }
 }
+
+/// 一个路径的删除与修改权限
+#[derive(Debug, Clone, Serialize)]
+pub struct PathPermission {
+    pub path: String,
+    pub delete: PermissionStatus,
+    pub write: PermissionStatus,
+}
+
+/// 批量查询路径权限，供界面把无法执行的动作置灰或提示以管理员身份运行
+#[tauri::command]
+pub async fn check_path_permissions(paths: Vec<String>) -> Vec<PathPermission> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let p = Path::new(&path);
+            PathPermission {
+                delete: can_delete(p),
+                write: can_write(p),
+                path,
+            }
+        })
+        .collect()
+}
+
+/// 需要在提升权限后的新进程中继续的扫描，字段与 `scan_path_command` 的参数一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingRescan {
+    pub path: String,
+    #[serde(default)]
+    pub shallow_dirs: Option<bool>,
+    #[serde(default)]
+    pub use_mft: Option<bool>,
+    #[serde(default)]
+    pub dirs_only: Option<bool>,
+    #[serde(default)]
+    pub estimate_progress: Option<bool>,
+    #[serde(default)]
+    pub peek_archives: Option<bool>,
+    #[serde(default)]
+    pub realistic_sizes: Option<bool>,
+    #[serde(default)]
+    pub retain_shallow_children: Option<bool>,
+    /// 写入时间（Unix 秒），由后端填写
+    #[serde(default)]
+    pub requested_at: u64,
+}
+
+/// 保存扫描参数并以管理员身份重新启动应用；用户在 UAC 提示中拒绝时返回错误，当前进程继续运行
+#[tauri::command]
+pub async fn relaunch_elevated(app: AppHandle, mut rescan: PendingRescan) -> Result<(), String> {
+    let file = pending_rescan_file(&app)?;
+    rescan.requested_at = unix_now();
+    let json = serde_json::to_string(&rescan).map_err(|e| format!("保存扫描参数失败: {}", e))?;
+    std::fs::write(&file, json).map_err(|e| format!("保存扫描参数失败: {}", e))?;
+    if let Err(e) = spawn_elevated() {
+        let _ = std::fs::remove_file(&file);
+        return Err(e);
+    }
+    app.exit(0);
+    Ok(())
+}
+
+/// 取回并删除待续的扫描；没有或已过期时为 None
+#[tauri::command]
+pub async fn take_pending_rescan(app: AppHandle) -> Result<Option<PendingRescan>, String> {
+    let file = pending_rescan_file(&app)?;
+    let text = match std::fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("读取扫描参数失败: {}", e)),
+    };
+    let _ = std::fs::remove_file(&file);
+    let rescan: PendingRescan =
+        serde_json::from_str(&text).map_err(|e| format!("扫描参数格式错误: {}", e))?;
+    let fresh = unix_now().saturating_sub(rescan.requested_at) <= PENDING_RESCAN_TTL_SECS;
+    Ok(fresh.then_some(rescan))
+}
+
+fn pending_rescan_file(app: &AppHandle) -> Result<PathBuf, String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("无法获取用户目录: {}", e))?;
+    let dir = home.join(".disk-rookie");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建存储目录失败: {}", e))?;
+    Ok(dir.join(PENDING_RESCAN_FILE))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 通过 PowerShell `Start-Process -Verb RunAs` 触发 UAC 提示启动自身
+#[cfg(windows)]
+fn spawn_elevated() -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    let exe = std::env::current_exe().map_err(|e| format!("无法获取程序路径: {}", e))?;
+    let command = format!(
+        "Start-Process -FilePath '{}' -Verb RunAs",
+        exe.display().to_string().replace('\'', "''")
+    );
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &command])
+        // CREATE_NO_WINDOW：不弹出控制台窗口
+        .creation_flags(0x0800_0000)
+        .status()
+        .map_err(|e| format!("无法以管理员身份启动: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("已取消以管理员身份运行".to_string())
+    }
+}
+
+#[cfg(not(windows))]
+fn spawn_elevated() -> Result<(), String> {
+    Err("仅 Windows 支持以管理员身份重新启动".to_string())
+}
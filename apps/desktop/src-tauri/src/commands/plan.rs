@@ -1,6 +1,116 @@
-use ai_disk_domain::CleanupPlan;
+use std::path::PathBuf;
+use std::sync::Mutex;
 
+use ai_disk_common::SharedConfig;
+use ai_disk_domain::{CleanupPlan, ScanResult};
+use ai_disk_engine::{JunkRules, KeepList};
+use tauri::State;
+
+/// 启动时加载的「已知垃圾」规则（内置 + ~/.disk-rookie/junk_rules.toml）
+pub struct JunkRulesState(pub JunkRules);
+
+/// 用户的保留列表（~/.disk-rookie/keep_list.toml），修改后立即写回文件
+pub struct KeepListState {
+    pub(super) list: Mutex<KeepList>,
+    file: PathBuf,
+}
+
+impl KeepListState {
+    pub fn new(list: KeepList, file: PathBuf) -> Self {
+        Self {
+            list: Mutex::new(list),
+            file,
+        }
+    }
+}
+
+/// `goal_bytes` 为用户想要释放的空间，计划预计释放远超该目标时标记为需要额外确认
+#[tauri::command]
+pub async fn get_cleanup_plan(
+    config: State<'_, SharedConfig>,
+    keep: State<'_, KeepListState>,
+    scan_result: String,
+    goal_bytes: Option<u64>,
+) -> Result<CleanupPlan, String> {
+    let mut plan = ai_disk_engine::plan_cleanup(&scan_result).await?;
+    ai_disk_engine::enforce_keep_list(&mut plan, &keep.list.lock().unwrap());
+    if let Some(goal) = goal_bytes {
+        ai_disk_engine::flag_overshoot(&mut plan, goal, config.current().overshoot_multiple());
+    }
+    Ok(plan)
+}
+
+/// 按已知垃圾规则生成清理计划（不调用 LLM）
+#[tauri::command]
+pub async fn get_rule_based_plan(
+    rules: State<'_, JunkRulesState>,
+    keep: State<'_, KeepListState>,
+    scan_result: ScanResult,
+) -> Result<CleanupPlan, String> {
+    let keep = keep.list.lock().unwrap();
+    Ok(ai_disk_engine::rule_based_plan(
+        &scan_result,
+        &rules.0,
+        &keep,
+    ))
+}
+
+/// 离线生成清理计划（不调用 LLM，无需 API Key），见 `ai_disk_engine::heuristic_plan`
+#[tauri::command]
+pub async fn get_heuristic_plan(
+    rules: State<'_, JunkRulesState>,
+    keep: State<'_, KeepListState>,
+    scan_result: ScanResult,
+) -> Result<CleanupPlan, String> {
+    let opts = ai_disk_engine::HeuristicOptions {
+        rules: rules.0.clone(),
+        keep: keep.list.lock().unwrap().clone(),
+        ..Default::default()
+    };
+    Ok(ai_disk_engine::heuristic_plan(&scan_result, &opts))
+}
+
+/// 执行前对照扫描结果校验计划，未通过的动作连同原因返回给界面展示
+#[tauri::command]
+pub async fn validate_plan(
+    plan: CleanupPlan,
+    scan_result: ScanResult,
+) -> Result<ai_disk_engine::ValidationReport, String> {
+    Ok(ai_disk_engine::validate_plan(&plan, &scan_result))
+}
+
+/// 当前保留列表中的路径/glob
+#[tauri::command]
+pub async fn list_keep_entries(keep: State<'_, KeepListState>) -> Result<Vec<String>, String> {
+    let list = keep.list.lock().unwrap();
+    Ok(list.entries().map(str::to_string).collect())
+}
+
+/// 标记保留：之后生成的计划不再包含该路径（或 glob 匹配的路径）
+#[tauri::command]
+pub async fn add_keep_entry(keep: State<'_, KeepListState>, entry: String) -> Result<bool, String> {
+    let mut list = keep.list.lock().unwrap();
+    let added = list
+        .add(&entry)
+        .map_err(|e| format!("无效的保留项: {}", e))?;
+    if added {
+        list.save(&keep.file)
+            .map_err(|e| format!("保存保留列表失败: {}", e))?;
+    }
+    Ok(added)
+}
+
+/// 取消保留
 #[tauri::command]
-pub async fn get_cleanup_plan(scan_result: String) -> Result<CleanupPlan, String> {
-    ai_disk_engine::plan_cleanup(&scan_result).await
+pub async fn remove_keep_entry(
+    keep: State<'_, KeepListState>,
+    entry: String,
+) -> Result<bool, String> {
+    let mut list = keep.list.lock().unwrap();
+    let removed = list.remove(&entry);
+    if removed {
+        list.save(&keep.file)
+            .map_err(|e| format!("保存保留列表失败: {}", e))?;
+    }
+    Ok(removed)
 }
@@ -1,27 +1,139 @@
 //! 扫描命令：当用户勾选「使用 MFT」且当前路径为 Windows 磁盘根（如 C:\）时，
-//! 后端通过 scan_path_with_progress(..., use_mft: true) 走 MFT 全量扫描（与普通扫描相同的树结构），
-//! 无需只取前 N 个文件，由 ai_disk_scanner 内部根据路径与 use_mft 决定是否调用 scan_volume_mft。
+//! 后端通过 scan(path, ScanOptions { use_mft: true, .. }) 走 MFT 全量扫描（与普通扫描相同的树结构），
+//! 无需只取前 N 个文件，由 ai_disk_scanner 内部根据路径与 use_mft 决定是否调用 scan_volume_mft，失败时自动回退。
 
-use ai_disk_domain::ScanResult;
-use ai_disk_scanner::scan_path_with_progress;
+use ai_disk_common::SharedConfig;
+use ai_disk_domain::{
+    FileNode, PhaseChange, ScanDone, ScanPhase, ScanProgress, ScanResult, ScanStrategy,
+    ScanSubtree, SCAN_DONE_EVENT, SCAN_PHASE_EVENT, SCAN_PROGRESS_EVENT, SCAN_SUBTREE_EVENT,
+};
+use ai_disk_scanner::{
+    is_system_volume_root, list_children, needs_elevation_for, scan, scan_strategy,
+    CoalescingProgress, ErrorHistory, RelayedProgress, ScanOptions,
+};
+use std::collections::HashMap;
 use std::io::Write;
-use tauri::{async_runtime, Emitter, Window};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{async_runtime, Emitter, State, Window};
+
+/// 普通遍历中拒绝访问的路径记录（位于应用数据目录），见 `ErrorHistory`
+const SCAN_ERRORS_FILE: &str = "scan_errors.json";
+/// `scan://subtree` 事件中子树保留的层数，够 Treemap 先行渲染，完整树随最终结果返回
+const SUBTREE_EVENT_DEPTH: usize = 3;
 
 fn stderr_flush() {
     let _ = std::io::stderr().flush();
 }
 
+/// 进行中的扫描的取消标记，按 scan_id 索引
+#[derive(Default)]
+pub struct ScanState {
+    cancel_flags: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl ScanState {
+    fn register(&self, scan_id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Ok(mut flags) = self.cancel_flags.lock() {
+            flags.insert(scan_id.to_string(), flag.clone());
+        }
+        flag
+    }
+
+    fn unregister(&self, scan_id: &str) {
+        if let Ok(mut flags) = self.cancel_flags.lock() {
+            flags.remove(scan_id);
+        }
+    }
+}
+
+/// 取消扫描：扫描尽快停止，`scan_path_command` 返回取消错误而不是部分结果
+#[tauri::command]
+pub async fn cancel_scan(state: State<'_, ScanState>, scan_id: String) -> Result<bool, String> {
+    let flags = state
+        .cancel_flags
+        .lock()
+        .map_err(|e| format!("获取扫描状态失败: {}", e))?;
+    match flags.get(&scan_id) {
+        Some(flag) => {
+            log::info!("取消扫描: {}", scan_id);
+            flag.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
 #[tauri::command]
 pub async fn scan_path_command(
     window: Window,
+    config: State<'_, SharedConfig>,
+    scan_state: State<'_, ScanState>,
     path: String,
+    scan_id: Option<String>,
     shallow_dirs: Option<bool>,
     use_mft: Option<bool>,
+    dirs_only: Option<bool>,
+    estimate_progress: Option<bool>,
+    peek_archives: Option<bool>,
+    realistic_sizes: Option<bool>,
+    retain_shallow_children: Option<bool>,
+    sort_by_size: Option<bool>,
 ) -> Result<ScanResult, String> {
     let path_trimmed = path.trim().to_string();
-    let use_shallow = shallow_dirs.unwrap_or(true);
+    // 未传入时取配置（config.toml 或 DISKROOKIE_SHALLOW_DIRS），默认开启
+    let use_shallow = shallow_dirs.unwrap_or_else(|| config.current().shallow_dirs());
     // 明确使用传入值：None 视为默认 true，Some(false) 必须为 false
     let use_mft = use_mft.unwrap_or(true);
+    let path_clone = path_trimmed.clone();
+    let window_progress = window.clone();
+    let window_subtree = window.clone();
+    // 扫描线程只写最新进度，由后台线程按顺序 emit；前端处理慢时中间进度被合并，内存不随扫描速度增长。
+    // 数量与百分比经同一通道送达，事件带递增编号
+    let relay = CoalescingProgress::spawn_events(move |event: &RelayedProgress| {
+        let mut progress = ScanProgress::new(event.count, &event.message).with_seq(event.seq);
+        progress.percent = event.percent;
+        let _ = window_progress.emit(SCAN_PROGRESS_EVENT, progress);
+    });
+    // 之前拒绝访问的路径在重试等待期内直接跳过
+    let history_file = config
+        .current()
+        .app_data_dir()
+        .map(|dir| dir.join(SCAN_ERRORS_FILE));
+    let history = history_file
+        .as_deref()
+        .map(|file| Arc::new(ErrorHistory::load(file)));
+    let opts = ScanOptions {
+        shallow_dirs: use_shallow,
+        use_mft,
+        // 保留被折叠目录的内容，之后切换「展开 node_modules」可即时生效
+        retain_shallow_children: retain_shallow_children.unwrap_or(false),
+        // 仅需文件夹大小的 Treemap 视图可只构建目录节点
+        dirs_only: dirs_only.unwrap_or(false),
+        // 普通遍历时在进度事件中附带百分比
+        estimate_progress: estimate_progress.unwrap_or(false),
+        // 读取 zip/tar 文件头，显示压缩包内容的条目数与解压后大小
+        peek_archives: peek_archives.unwrap_or(false),
+        // 系统卷默认按真实大小统计（硬链接去重、不跟随重解析点），避免 C:\Windows 被高估数倍
+        realistic_sizes: realistic_sizes
+            .unwrap_or_else(|| is_system_volume_root(std::path::Path::new(&path_trimmed))),
+        // 子节点按大小从大到小排好，前端无需再排序
+        sort_children_by_size: sort_by_size.unwrap_or(false),
+        progress: Some(relay.callback()),
+        on_percent: Some(Arc::new(relay.percent_callback())),
+        // 每个第一层子目录完成即发送，前端 Treemap 逐步填充
+        on_subtree: Some(Arc::new(Box::new(move |node: &FileNode| {
+            let subtree = ScanSubtree::new(node.clone_to_depth(SUBTREE_EVENT_DEPTH));
+            let _ = window_subtree.emit(SCAN_SUBTREE_EVENT, subtree);
+        }))),
+        // 跳过应用自身的数据目录（隔离区、暂存区），避免建议清理自己的撤销数据
+        exclude_dirs: config.current().scan_exclude_dirs(),
+        error_history: history.clone(),
+        // 传入 scan_id 时可通过 `cancel_scan` 取消
+        cancel: scan_id.as_deref().map(|id| scan_state.register(id)),
+        ..ScanOptions::default()
+    };
 
     let thread_count = std::thread::available_parallelism()
         .map(|p| p.get())
@@ -42,18 +154,36 @@ pub async fn scan_path_command(
     }
     stderr_flush();
 
-    let path_clone = path_trimmed.clone();
-    let window_progress = window.clone();
-    let progress = std::sync::Arc::new(Box::new(move |count: u64, path_str: &str| {
-        let _ = window_progress.emit("scan-progress", (count, path_str.to_string()));
-    }) as Box<dyn Fn(u64, &str) + Send + Sync>);
+    let phase = match scan_strategy(&path_trimmed, &opts) {
+        ScanStrategy::Mft => ScanPhase::Mft,
+        ScanStrategy::Walk => ScanPhase::Walk,
+    };
+    let _ = window.emit(SCAN_PHASE_EVENT, PhaseChange::new(phase, &path_trimmed));
+
     let window_emit = window.clone();
-    let (result, used_mft) = async_runtime::spawn_blocking(move || {
-        scan_path_with_progress(&path_clone, Some(&progress), use_shallow, use_mft)
+    let result = async_runtime::spawn_blocking(move || {
+        let scanned = scan(&path_clone, &opts);
+        relay.finish();
+        if let (Some(history), Some(file)) = (&history, &history_file) {
+            match history.save(file) {
+                Ok(()) => log::debug!("扫描错误记录已保存: {}", file.display()),
+                Err(e) => log::warn!("保存扫描错误记录失败: {}", e),
+            }
+        }
+        scanned
     })
-    .await
-    .map_err(|e| e.to_string())?
-    .map_err(|e| e.to_string())?;
+    .await;
+    if let Some(id) = &scan_id {
+        scan_state.unregister(id);
+    }
+    let result = result
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.diagnostic())?;
+    // MFT 失败时已自动回退到普通遍历，以结果中记录的策略为准
+    let used_mft = result
+        .meta
+        .as_ref()
+        .is_some_and(|m| m.strategy == ScanStrategy::Mft);
 
     if used_mft {
         let _ = writeln!(
@@ -73,6 +203,40 @@ pub async fn scan_path_command(
         );
     }
     stderr_flush();
-    let _ = window_emit.emit("scan-mft-status", (path_trimmed.clone(), used_mft));
+    let mut done = ScanDone::new(&path_trimmed, &result, used_mft);
+    // 拒绝访问较多时提示「以管理员身份重新扫描」，见 `permission::relaunch_elevated`
+    done.suggest_elevation =
+        !super::permission::check_admin_permission() && needs_elevation_for(&path_trimmed, &result);
+    let _ = window_emit.emit(SCAN_DONE_EVENT, done);
     Ok(result)
 }
+
+/// 切换 shallow 目录设置（如「展开 node_modules」）后的结果，无需重新扫描；
+/// 展开需要扫描时开启了 `retain_shallow_children`
+#[tauri::command]
+pub async fn rebuild_with_shallow(
+    mut scan_result: ScanResult,
+    shallow_dirs: bool,
+) -> Result<ScanResult, String> {
+    if scan_result.set_shallow(shallow_dirs) {
+        Ok(scan_result)
+    } else {
+        Err("扫描时未保留折叠目录的内容，请重新扫描".to_string())
+    }
+}
+
+/// 按需展开：只返回 `path` 的直接子项（子目录带递归大小），前端展开节点时调用
+#[tauri::command]
+pub async fn list_children_command(
+    path: String,
+    dirs_only: Option<bool>,
+) -> Result<Vec<FileNode>, String> {
+    let opts = ScanOptions {
+        dirs_only: dirs_only.unwrap_or(false),
+        ..ScanOptions::default()
+    };
+    async_runtime::spawn_blocking(move || list_children(path.trim(), &opts))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.diagnostic())
+}
@@ -1,6 +1,11 @@
 mod commands;
 
+use commands::cloud_upload::UploadState;
 use commands::oauth::OAuthState;
+use commands::plan::{JunkRulesState, KeepListState};
+use commands::scan::ScanState;
+use std::sync::Mutex;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -11,19 +16,109 @@ pub fn run() {
         .filter_module("winit", log::LevelFilter::Error)
         .init();
 
+    // 导出配置取自 config.toml（与下方配置监视读取同一文件），只在启动时读取
+    #[cfg(feature = "otel")]
+    let _otel_guard = {
+        let config = ai_disk_common::AppConfig::load().unwrap_or_else(|e| {
+            log::warn!("读取配置失败，OpenTelemetry 使用默认配置: {}", e);
+            ai_disk_common::AppConfig::default()
+        });
+        ai_disk_common::otel::init_otel(&config.otel)
+            .map_err(|e| log::warn!("OpenTelemetry 初始化失败: {}", e))
+            .ok()
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_notification::init())
         .manage(OAuthState::default())
+        .manage(UploadState::default())
+        .manage(ScanState::default())
+        .setup(|app| {
+            // 配置热加载：命令通过 State<SharedConfig> 读取最新配置，修改 config.toml 无需重启
+            let config_file = app
+                .path()
+                .home_dir()
+                .map_err(|e| e.to_string())?
+                .join(".disk-rookie")
+                .join("config.toml");
+            match ai_disk_common::ConfigWatcher::start(&config_file) {
+                Ok(watcher) => {
+                    // 遥测开关只在启动时读取
+                    ai_disk_common::init_telemetry(&watcher.shared().current());
+                    app.manage(watcher.shared());
+                    // 监视器随应用存活，drop 时停止监视
+                    app.manage(Mutex::new(watcher));
+                }
+                Err(e) => {
+                    log::warn!("配置文件监视启动失败，使用默认配置: {}", e);
+                    app.manage(ai_disk_common::SharedConfig::default());
+                }
+            }
+
+            let user_rules = app
+                .path()
+                .home_dir()
+                .ok()
+                .map(|home| home.join(".disk-rookie").join("junk_rules.toml"));
+            let rules =
+                ai_disk_engine::JunkRules::load(user_rules.as_deref()).unwrap_or_else(|e| {
+                    log::warn!("加载 junk_rules.toml 失败，使用内置规则: {}", e);
+                    ai_disk_engine::JunkRules::builtin()
+                });
+            app.manage(JunkRulesState(rules));
+
+            let keep_file = app
+                .path()
+                .home_dir()
+                .map_err(|e| e.to_string())?
+                .join(".disk-rookie")
+                .join("keep_list.toml");
+            let keep = ai_disk_engine::KeepList::load(&keep_file).unwrap_or_else(|e| {
+                log::warn!("加载 keep_list.toml 失败，使用空的保留列表: {}", e);
+                ai_disk_engine::KeepList::default()
+            });
+            app.manage(KeepListState::new(keep, keep_file));
+
+            // 启动时永久删除已过宽限期的隔离条目
+            let home = app.path().home_dir().map_err(|e| e.to_string())?;
+            match ai_disk_executor::Quarantine::open(&commands::delete::quarantine_policy(&home, 0))
+                .and_then(|mut q| q.purge_expired())
+            {
+                Ok(purged) if !purged.is_empty() => {
+                    log::info!("已清理 {} 个过期的隔离条目", purged.len())
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("清理隔离区失败: {}", e),
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::scan::scan_path_command,
+            commands::scan::cancel_scan,
+            commands::scan::list_children_command,
+            commands::scan::rebuild_with_shallow,
             commands::analyze::analyze_disk,
+            commands::analyze::analyze_and_report,
+            commands::llm::validate_llm_key,
             commands::plan::get_cleanup_plan,
+            commands::plan::get_rule_based_plan,
+            commands::plan::get_heuristic_plan,
+            commands::plan::validate_plan,
+            commands::plan::list_keep_entries,
+            commands::plan::add_keep_entry,
+            commands::plan::remove_keep_entry,
             commands::execute::execute_plan,
+            commands::execute::dry_run_plan,
             commands::permission::check_admin_permission,
+            commands::permission::check_path_permissions,
+            commands::permission::relaunch_elevated,
+            commands::permission::take_pending_rescan,
             commands::delete::delete_item,
+            commands::delete::list_quarantine,
+            commands::delete::restore_quarantined,
             commands::storage::read_storage_file,
             commands::storage::write_storage_file,
             commands::storage::delete_storage_file,
@@ -55,7 +150,13 @@ pub fn run() {
             commands::oauth::get_dropbox_quota,
             // Cloud upload commands
             commands::cloud_upload::upload_to_cloud,
+            commands::cloud_upload::upload_plan_to_cloud,
+            commands::cloud_upload::cancel_upload,
+            commands::cloud_upload::list_pending_uploads,
+            commands::cloud_upload::resume_upload,
+            commands::cloud_upload::test_cloud_target,
             commands::open_in_file_manager::open_in_file_manager,
+            commands::diagnostics::capabilities,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
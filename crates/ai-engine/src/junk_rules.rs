@@ -0,0 +1,169 @@
+//! 「已知垃圾」定义（临时目录、浏览器缓存、包管理器缓存、崩溃转储等），以数据形式描述，
+//! 无需重新编译即可更新：内置默认见 crate 根目录的 `junk_rules.toml`，用户文件可按 name 覆盖或追加。
+
+use std::path::Path;
+
+use ai_disk_common::DiskAnalyzerError;
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+/// 内置的默认规则
+pub const DEFAULT_JUNK_RULES: &str = include_str!("../junk_rules.toml");
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// 规则匹配后建议的清理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JunkAction {
+    /// 清空目录内容，保留目录本身
+    Empty,
+    Delete,
+    /// 移到回收站
+    Trash,
+}
+
+/// 一条垃圾定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JunkRule {
+    /// 规则名，用户文件中同名规则会覆盖内置规则
+    pub name: String,
+    pub category: String,
+    /// 路径 glob（`/` 分隔、不区分大小写）
+    pub paths: Vec<String>,
+    pub action: JunkAction,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JunkRulesFile {
+    #[serde(default)]
+    rules: Vec<JunkRule>,
+}
+
+/// 编译后的规则集
+#[derive(Debug, Clone)]
+pub struct JunkRules {
+    rules: Vec<(JunkRule, Vec<Pattern>)>,
+}
+
+impl JunkRules {
+    /// 解析 TOML 规则文本
+    pub fn from_toml_str(s: &str) -> Result<Self, DiskAnalyzerError> {
+        let file: JunkRulesFile = toml::from_str(s)
+            .map_err(|e| DiskAnalyzerError::Config(format!("junk rules: {}", e)))?;
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|rule| {
+                let patterns = rule
+                    .paths
+                    .iter()
+                    .map(|p| {
+                        Pattern::new(p).map_err(|e| {
+                            DiskAnalyzerError::Config(format!(
+                                "junk rule {}: invalid glob {:?}: {}",
+                                rule.name, p, e
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((rule, patterns))
+            })
+            .collect::<Result<Vec<_>, DiskAnalyzerError>>()?;
+        Ok(Self { rules })
+    }
+
+    /// 内置默认规则
+    pub fn builtin() -> Self {
+        Self::from_toml_str(DEFAULT_JUNK_RULES).expect("内置 junk_rules.toml 应可解析")
+    }
+
+    /// 加载内置规则，并在用户文件存在时合并其覆盖/追加的规则
+    pub fn load(user_file: Option<&Path>) -> Result<Self, DiskAnalyzerError> {
+        let mut rules = Self::builtin();
+        if let Some(path) = user_file.filter(|p| p.exists()) {
+            let text = std::fs::read_to_string(path)?;
+            rules.merge(Self::from_toml_str(&text)?);
+        }
+        Ok(rules)
+    }
+
+    /// 合并规则：同名规则被 `other` 中的版本替换，其余追加在后
+    pub fn merge(&mut self, other: JunkRules) {
+        for (rule, patterns) in other.rules {
+            match self.rules.iter_mut().find(|(r, _)| r.name == rule.name) {
+                Some(existing) => *existing = (rule, patterns),
+                None => self.rules.push((rule, patterns)),
+            }
+        }
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &JunkRule> {
+        self.rules.iter().map(|(r, _)| r)
+    }
+
+    /// 返回第一个匹配该路径的已启用规则
+    pub fn match_path(&self, path: &str) -> Option<&JunkRule> {
+        let path = path.replace('\\', "/");
+        let path = path.trim_end_matches('/');
+        self.rules
+            .iter()
+            .filter(|(rule, _)| rule.enabled)
+            .find(|(_, patterns)| patterns.iter().any(|p| p.matches_with(path, MATCH_OPTIONS)))
+            .map(|(rule, _)| rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_rules_and_override() {
+        let mut rules = JunkRules::builtin();
+        let hit = rules.match_path(r"C:\Users\u\AppData\Local\Temp").unwrap();
+        assert_eq!(
+            (hit.name.as_str(), hit.action),
+            ("system-temp", JunkAction::Empty)
+        );
+        assert!(rules
+            .match_path(r"C:\Users\u\AppData\Local\Temp\a.txt")
+            .is_none());
+
+        rules.merge(
+            JunkRules::from_toml_str(
+                r#"
+                [[rules]]
+                name = "system-temp"
+                category = "temp"
+                paths = ["**/AppData/Local/Temp"]
+                action = "trash"
+                enabled = false
+
+                [[rules]]
+                name = "gradle-cache"
+                category = "package-cache"
+                paths = ["**/.gradle/caches"]
+                action = "delete"
+                "#,
+            )
+            .unwrap(),
+        );
+        assert!(rules.match_path(r"C:\Users\u\AppData\Local\Temp").is_none());
+        assert_eq!(
+            rules.match_path("/home/u/.gradle/caches").unwrap().name,
+            "gradle-cache"
+        );
+        assert!(JunkRules::from_toml_str("[[rules]]\nname = \"x\"").is_err());
+    }
+}
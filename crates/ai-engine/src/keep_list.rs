@@ -0,0 +1,143 @@
+//! 「保留列表」：用户标记为保留（`Action::MarkKeep`）的路径或 glob，持久化为 TOML。
+//! 规划器生成计划时跳过这些路径及其子项，校验器从 AI 计划中剔除命中的动作，避免反复建议同一项。
+
+use std::path::Path;
+
+use ai_disk_common::path::{is_under, CaseSensitivity};
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::{Action, CleanupPlan};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeepListFile {
+    #[serde(default)]
+    keep: Vec<String>,
+}
+
+/// 保留列表。条目含 `*`、`?`、`[` 时按 glob 匹配（`/` 分隔、不区分大小写），
+/// 否则视为路径，其下所有子项一并保留
+#[derive(Debug, Clone, Default)]
+pub struct KeepList {
+    entries: Vec<(String, Option<Pattern>)>,
+}
+
+impl KeepList {
+    /// 读取保留列表文件，不存在时返回空列表
+    pub fn load(path: &Path) -> Result<Self, DiskAnalyzerError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)?;
+        let file: KeepListFile = toml::from_str(&text)
+            .map_err(|e| DiskAnalyzerError::Config(format!("keep list: {}", e)))?;
+        let mut list = Self::default();
+        for entry in file.keep {
+            list.add(&entry)?;
+        }
+        Ok(list)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), DiskAnalyzerError> {
+        let file = KeepListFile {
+            keep: self.entries().map(str::to_string).collect(),
+        };
+        let text = toml::to_string(&file)
+            .map_err(|e| DiskAnalyzerError::Config(format!("keep list: {}", e)))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// 添加条目，已存在时忽略；返回是否新增
+    pub fn add(&mut self, entry: &str) -> Result<bool, DiskAnalyzerError> {
+        let entry = entry.trim();
+        if entry.is_empty() || self.entries().any(|e| e == entry) {
+            return Ok(false);
+        }
+        let pattern = if entry.contains(['*', '?', '[']) {
+            let pattern = Pattern::new(&entry.replace('\\', "/")).map_err(|e| {
+                DiskAnalyzerError::Config(format!("keep list: invalid glob {:?}: {}", entry, e))
+            })?;
+            Some(pattern)
+        } else {
+            None
+        };
+        self.entries.push((entry.to_string(), pattern));
+        Ok(true)
+    }
+
+    /// 移除条目；返回是否存在
+    pub fn remove(&mut self, entry: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|(e, _)| e != entry.trim());
+        self.entries.len() != before
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(e, _)| e.as_str())
+    }
+
+    /// 把计划中的 `MarkKeep` 动作记入列表；返回新增条目数
+    pub fn record(&mut self, plan: &CleanupPlan) -> Result<usize, DiskAnalyzerError> {
+        let mut added = 0;
+        for action in &plan.actions {
+            if let Action::MarkKeep { path } = action {
+                added += usize::from(self.add(path)?);
+            }
+        }
+        Ok(added)
+    }
+
+    /// 路径是否被保留（命中 glob，或位于某个保留路径之下）
+    pub fn is_kept(&self, path: &str) -> bool {
+        let normalized = path.replace('\\', "/");
+        let normalized = normalized.trim_end_matches('/');
+        self.entries.iter().any(|(entry, pattern)| match pattern {
+            Some(p) => p.matches_with(normalized, MATCH_OPTIONS),
+            None => is_under(path, entry, CaseSensitivity::native()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_list_roundtrip_and_matching() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("keep_list.toml");
+        let mut list = KeepList::default();
+        let plan = CleanupPlan {
+            actions: vec![
+                Action::MarkKeep {
+                    path: "/home/u/Videos/wedding".to_string(),
+                },
+                Action::Delete {
+                    path: "/tmp/x".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(list.record(&plan).unwrap(), 1);
+        assert!(list.add("**/*.iso").unwrap());
+        assert!(!list.add("**/*.iso").unwrap());
+        list.save(&file).unwrap();
+
+        let mut loaded = KeepList::load(&file).unwrap();
+        assert!(loaded.is_kept("/home/u/Videos/wedding/clip.mp4"));
+        assert!(loaded.is_kept(r"D:\images\win11.ISO"));
+        assert!(!loaded.is_kept("/home/u/Videos/weddings"));
+        assert!(loaded.remove("**/*.iso"));
+        assert!(!loaded.is_kept("/data/a.iso"));
+    }
+}
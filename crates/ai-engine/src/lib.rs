@@ -1,8 +1,14 @@
+pub mod junk_rules;
+pub mod keep_list;
 pub mod llm;
 pub mod planner;
 pub mod prompt;
+pub mod report;
 pub mod validator;
 
+pub use junk_rules::*;
+pub use keep_list::*;
 pub use planner::*;
 pub use prompt::*;
+pub use report::*;
 pub use validator::*;
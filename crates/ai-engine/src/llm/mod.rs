@@ -1,2 +1,47 @@
+//! LLM 集成：OpenAI 兼容接口客户端与本地模型（预留）
 pub mod local;
 pub mod openai;
+
+pub use openai::LlmClient;
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// API Key 校验结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum KeyStatus {
+    /// Key 可用；提供商通过 `x-ratelimit-remaining-*` 响应头暴露剩余额度时一并返回
+    Valid {
+        remaining_requests: Option<u64>,
+        remaining_tokens: Option<u64>,
+    },
+    /// Key 有效但额度/余额已用尽
+    QuotaExhausted { message: String },
+}
+
+/// LLM 调用错误
+#[derive(Debug, thiserror::Error)]
+pub enum AiError {
+    #[error("API Key 无效: {0}")]
+    InvalidKey(String),
+    #[error("请求过于频繁: {0}")]
+    RateLimited(String),
+    #[error("API 错误 ({status}): {message}")]
+    Api { status: u16, message: String },
+    #[error("网络错误: {0}")]
+    Network(String),
+}
+
+/// LLM 请求共用的 HTTP 客户端：声明接受 gzip/br/deflate 压缩响应并自动解压，
+/// 大型树摘要的响应在慢速网络下明显更快
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .connect_timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_default()
+}
@@ -1 +1,235 @@
-//! OpenAI LLM 集成（预留）
+//! OpenAI 兼容接口客户端（`{base_url}/chat/completions`、`{base_url}/models`）
+
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::{RequestBuilder, StatusCode};
+
+use super::{AiError, KeyStatus};
+
+/// 请求体达到该大小才压缩，小请求压缩收益不抵开销
+const COMPRESS_MIN_BYTES: usize = 1024;
+
+/// OpenAI 兼容 LLM 客户端
+#[derive(Debug, Clone)]
+pub struct LlmClient {
+    client: reqwest::Client,
+    /// API 地址，如 `https://api.openai.com/v1`
+    base_url: String,
+    api_key: String,
+    model: String,
+    /// 以 `Content-Encoding: gzip` 发送较大的请求体，仅在接口支持时开启
+    compress_requests: bool,
+}
+
+impl LlmClient {
+    pub fn new(base_url: &str, api_key: &str, model: &str) -> Self {
+        Self {
+            client: super::http_client(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            compress_requests: false,
+        }
+    }
+
+    /// 对大请求体启用 gzip 压缩（如自建网关或支持压缩请求的提供商），减少上传大型提示词的流量
+    pub fn with_request_compression(mut self, enabled: bool) -> Self {
+        self.compress_requests = enabled;
+        self
+    }
+
+    /// 构造 JSON POST 请求；开启压缩且请求体足够大时改为发送 gzip 数据
+    fn post_json(&self, url: String, body: &serde_json::Value) -> Result<RequestBuilder, AiError> {
+        let request = self
+            .client
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        let bytes = serde_json::to_vec(body).map_err(|e| AiError::Network(e.to_string()))?;
+        if !self.compress_requests || bytes.len() < COMPRESS_MIN_BYTES {
+            return Ok(request.body(bytes));
+        }
+        let compressed = gzip(&bytes).map_err(|e| AiError::Network(e.to_string()))?;
+        Ok(request.header(CONTENT_ENCODING, "gzip").body(compressed))
+    }
+
+    /// 用一次 1 token 的补全请求确认 Key 可用（模型列表接口不消耗额度，无法发现余额不足），
+    /// 并从响应头读取剩余额度
+    pub async fn validate(&self) -> Result<KeyStatus, AiError> {
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": [{ "role": "user", "content": "ping" }],
+            "max_tokens": 1,
+        });
+        let response = self
+            .post_json(format!("{}/chat/completions", self.base_url), &body)?
+            .send()
+            .await
+            .map_err(|e| AiError::Network(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<u64>().ok())
+            };
+            return Ok(KeyStatus::Valid {
+                remaining_requests: header("x-ratelimit-remaining-requests"),
+                remaining_tokens: header("x-ratelimit-remaining-tokens"),
+            });
+        }
+
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        let error = &body["error"];
+        let message = error["message"]
+            .as_str()
+            .unwrap_or_else(|| status.canonical_reason().unwrap_or_default())
+            .to_string();
+        let quota_exhausted = [&error["code"], &error["type"]]
+            .iter()
+            .any(|v| v.as_str() == Some("insufficient_quota"));
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(AiError::InvalidKey(message)),
+            // 402：部分提供商（如余额不足）直接返回 Payment Required
+            StatusCode::PAYMENT_REQUIRED => Ok(KeyStatus::QuotaExhausted { message }),
+            StatusCode::TOO_MANY_REQUESTS if quota_exhausted => {
+                Ok(KeyStatus::QuotaExhausted { message })
+            }
+            StatusCode::TOO_MANY_REQUESTS => Err(AiError::RateLimited(message)),
+            _ => Err(AiError::Api {
+                status: status.as_u16(),
+                message,
+            }),
+        }
+    }
+}
+
+fn gzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn validate_with(
+        server: &mut mockito::ServerGuard,
+        status: usize,
+        body: &str,
+    ) -> Result<KeyStatus, AiError> {
+        let _mock = server
+            .mock("POST", "/v1/chat/completions")
+            .match_header("authorization", "Bearer sk-test")
+            .with_status(status)
+            .with_header("x-ratelimit-remaining-requests", "99")
+            .with_header("x-ratelimit-remaining-tokens", "40000")
+            .with_body(body)
+            .create_async()
+            .await;
+        LlmClient::new(&format!("{}/v1/", server.url()), "sk-test", "gpt-4o-mini")
+            .validate()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_validate_maps_responses() {
+        let mut server = mockito::Server::new_async().await;
+
+        let valid = validate_with(&mut server, 200, r#"{"choices":[]}"#).await;
+        assert_eq!(
+            valid.unwrap(),
+            KeyStatus::Valid {
+                remaining_requests: Some(99),
+                remaining_tokens: Some(40000),
+            }
+        );
+
+        let invalid = validate_with(
+            &mut server,
+            401,
+            r#"{"error":{"message":"Incorrect API key provided","code":"invalid_api_key"}}"#,
+        )
+        .await;
+        assert!(
+            matches!(invalid, Err(AiError::InvalidKey(ref m)) if m == "Incorrect API key provided"),
+            "{:?}",
+            invalid
+        );
+
+        let exhausted = validate_with(
+            &mut server,
+            429,
+            r#"{"error":{"message":"You exceeded your current quota","type":"insufficient_quota"}}"#,
+        )
+        .await;
+        assert_eq!(
+            exhausted.unwrap(),
+            KeyStatus::QuotaExhausted {
+                message: "You exceeded your current quota".to_string()
+            }
+        );
+
+        let limited = validate_with(
+            &mut server,
+            429,
+            r#"{"error":{"message":"Rate limit reached","code":"rate_limit_exceeded"}}"#,
+        )
+        .await;
+        assert!(
+            matches!(limited, Err(AiError::RateLimited(_))),
+            "{:?}",
+            limited
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_negotiates_and_compresses() {
+        let mut server = mockito::Server::new_async().await;
+        // 错误响应体经 gzip 压缩，客户端需自动解压才能读出错误信息
+        let body = gzip(br#"{"error":{"message":"Incorrect API key provided"}}"#).unwrap();
+        let _mock = server
+            .mock("POST", "/v1/chat/completions")
+            .match_header("accept-encoding", mockito::Matcher::Regex("gzip".into()))
+            .with_status(401)
+            .with_header("content-encoding", "gzip")
+            .with_body(body)
+            .create_async()
+            .await;
+        let client = LlmClient::new(&format!("{}/v1", server.url()), "sk-test", "gpt-4o-mini")
+            .with_request_compression(true);
+        let invalid = client.validate().await;
+        assert!(
+            matches!(invalid, Err(AiError::InvalidKey(ref m)) if m == "Incorrect API key provided"),
+            "{:?}",
+            invalid
+        );
+
+        // 大请求体压缩后可还原，小请求体保持原样
+        let prompt = serde_json::json!({ "content": "x".repeat(4096) });
+        let request = client
+            .post_json(server.url(), &prompt)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[CONTENT_ENCODING], "gzip");
+        let sent = request.body().and_then(|b| b.as_bytes()).unwrap();
+        let mut decoded = String::new();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(sent), &mut decoded)
+            .unwrap();
+        assert_eq!(decoded, prompt.to_string());
+        let small = client
+            .post_json(server.url(), &serde_json::json!({}))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(small.headers().get(CONTENT_ENCODING).is_none());
+    }
+}
@@ -1,9 +1,549 @@
-use ai_disk_domain::CleanupPlan;
+use ai_disk_common::path::CaseSensitivity;
+use ai_disk_common::telemetry;
+use ai_disk_domain::{
+    explain, is_user_document_path, Action, CleanupPlan, FileNode, RiskAging, RiskLevel, ScanResult,
+};
+
+use crate::junk_rules::{JunkAction, JunkRules};
+use crate::keep_list::KeepList;
 
 /// AI 规划器（预留）
 pub async fn plan_cleanup(_scan_result: &str) -> Result<CleanupPlan, String> {
-    Ok(CleanupPlan {
-        actions: vec![],
-        estimated_space: 0,
-    })
+    let _span = telemetry::llm_span("plan_cleanup").entered();
+    Ok(CleanupPlan::default())
+}
+
+/// 基于「已知垃圾」规则生成清理计划（不调用 LLM）：命中规则的节点按规则生成动作，
+/// 其子树不再继续匹配；保留列表中的路径及其子项、由系统管理的文件不参与匹配
+pub fn rule_based_plan(
+    scan_result: &ScanResult,
+    rules: &JunkRules,
+    keep: &KeepList,
+) -> CleanupPlan {
+    let mut plan = CleanupPlan::default();
+    collect_junk(&scan_result.root, rules, keep, &mut plan);
+    plan
+}
+
+fn collect_junk(node: &FileNode, rules: &JunkRules, keep: &KeepList, plan: &mut CleanupPlan) {
+    if keep.is_kept(&node.path) || node.is_system_managed() {
+        return;
+    }
+    if let Some(rule) = rules.match_path(&node.path) {
+        let path = node.path.clone();
+        let action = match rule.action {
+            JunkAction::Empty if node.is_dir => Some(Action::Empty { path }),
+            JunkAction::Empty => None,
+            JunkAction::Delete => Some(Action::Delete { path }),
+            JunkAction::Trash => Some(Action::Trash { path }),
+        };
+        if let Some(action) = action {
+            plan.actions.push(action);
+            plan.estimated_space += node.size;
+            plan.sizes.insert(node.path.clone(), node.size);
+            return;
+        }
+    }
+    for child in &node.children {
+        collect_junk(child, rules, keep, plan);
+    }
+}
+
+/// `heuristic_plan` 的参数
+#[derive(Debug, Clone)]
+pub struct HeuristicOptions {
+    /// 大文件阈值（字节）
+    pub large_file_bytes: u64,
+    /// 大文件超过该天数未修改才建议清理
+    pub min_age_days: u64,
+    /// 计算文件年龄的当前时间（Unix 秒）
+    pub now: u64,
+    /// 识别缓存、临时目录等已知垃圾的规则
+    pub rules: JunkRules,
+    pub keep: KeepList,
+}
+
+impl Default for HeuristicOptions {
+    fn default() -> Self {
+        Self {
+            large_file_bytes: RiskAging::default().large_file_bytes,
+            min_age_days: 180,
+            now: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            rules: JunkRules::builtin(),
+            keep: KeepList::default(),
+        }
+    }
+}
+
+/// 离线生成清理计划（不调用 LLM，无需 API Key）：`rule_based_plan` 命中的已知垃圾中，
+/// 要清空的目录（如 `Temp`）逐项建议删除其内容，其余按规则的动作；再把久未修改的大文件建议移到回收站。
+/// 受保护路径、用户文档目录、保留列表中的路径（及包含它们的目录）不会出现在计划中。
+/// 动作按 `explain` 的风险分组，低风险在前，同组内大的在前
+pub fn heuristic_plan(scan_result: &ScanResult, opts: &HeuristicOptions) -> CleanupPlan {
+    let junk = rule_based_plan(scan_result, &opts.rules, &opts.keep);
+    let mut found = Vec::new();
+    for action in &junk.actions {
+        let Some(node) = find_node(&scan_result.root, action.target_path()) else {
+            continue;
+        };
+        match action {
+            Action::Empty { .. } => {
+                for child in node.children.iter().filter(|c| !c.is_aggregate()) {
+                    let path = child.path.clone();
+                    found.push((child, Action::Delete { path }));
+                }
+            }
+            action => found.push((node, action.clone())),
+        }
+    }
+    collect_large_old(&scan_result.root, opts, &junk, &mut found);
+
+    let mut found: Vec<(RiskLevel, &FileNode, Action)> = found
+        .into_iter()
+        .filter(|(node, _)| node.size > 0 && !is_blocked(node, &opts.keep))
+        .map(|(node, action)| (explain(node).level, node, action))
+        .collect();
+    found.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| b.1.size.cmp(&a.1.size))
+            .then_with(|| a.1.path.cmp(&b.1.path))
+    });
+
+    let mut plan = CleanupPlan::default();
+    for (_, node, action) in found {
+        plan.actions.push(action);
+        plan.estimated_space += node.size;
+        plan.sizes.insert(node.path.clone(), node.size);
+    }
+    plan
+}
+
+/// 已知垃圾之外、久未修改且风险不高的大文件，建议移到回收站
+fn collect_large_old<'a>(
+    node: &'a FileNode,
+    opts: &HeuristicOptions,
+    junk: &CleanupPlan,
+    out: &mut Vec<(&'a FileNode, Action)>,
+) {
+    if node.is_system_managed()
+        || node.is_aggregate()
+        || node.is_link
+        || junk
+            .actions
+            .iter()
+            .any(|a| is_within(&node.path, a.target_path()))
+    {
+        return;
+    }
+    if !node.is_dir {
+        let cutoff = opts.now.saturating_sub(opts.min_age_days * 86_400);
+        let old = node.modified.is_some_and(|m| m < cutoff);
+        if node.size >= opts.large_file_bytes && old && explain(node).level < RiskLevel::High {
+            let path = node.path.clone();
+            out.push((node, Action::Trash { path }));
+        }
+        return;
+    }
+    for child in &node.children {
+        collect_large_old(child, opts, junk, out);
+    }
+}
+
+/// 节点或其子树中是否有受保护、位于用户文档目录或在保留列表中的路径
+fn is_blocked(node: &FileNode, keep: &KeepList) -> bool {
+    keep.is_kept(&node.path)
+        || explain(node).is_protected()
+        || is_user_document_path(&node.path)
+        || node.children.iter().any(|c| is_blocked(c, keep))
+}
+
+/// 在树中按路径查找节点
+fn find_node<'a>(node: &'a FileNode, path: &str) -> Option<&'a FileNode> {
+    if !is_within(path, &node.path) {
+        return None;
+    }
+    if node.path.len() == path.len() {
+        return Some(node);
+    }
+    node.children.iter().find_map(|c| find_node(c, path))
+}
+
+/// 按目标空间生成计划：在风险不超过 `max_risk` 的项中，先选风险低的、同风险先选大的，
+/// 直到释放空间达到 `target_bytes`，再按选入的逆序剔除多余项。受保护或保留的路径（及包含它们的目录）
+/// 不会入选；无法达成时 `shortfall` 记录差额
+pub fn plan_to_free(
+    scan: &ScanResult,
+    target_bytes: u64,
+    max_risk: RiskLevel,
+    keep: &KeepList,
+) -> CleanupPlan {
+    let mut candidates = Vec::new();
+    collect_candidates(&scan.root, max_risk, keep, &mut candidates);
+    candidates.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| b.1.size.cmp(&a.1.size))
+            .then_with(|| a.1.path.cmp(&b.1.path))
+    });
+
+    let mut chosen: Vec<&FileNode> = Vec::new();
+    let mut freed: u64 = 0;
+    for (_, node) in candidates {
+        if freed >= target_bytes {
+            break;
+        }
+        if node.size == 0
+            || chosen
+                .iter()
+                .any(|c| is_within(&node.path, &c.path) || is_within(&c.path, &node.path))
+        {
+            continue;
+        }
+        freed += node.size;
+        chosen.push(node);
+    }
+    // 按选入的逆序（风险最高的先，同一风险内从小到大）剔除去掉后仍能达标的项
+    if freed >= target_bytes {
+        for i in (0..chosen.len()).rev() {
+            if freed - chosen[i].size >= target_bytes {
+                freed -= chosen[i].size;
+                chosen.remove(i);
+            }
+        }
+    }
+
+    let mut plan = CleanupPlan::default();
+    for node in chosen {
+        plan.actions.push(Action::Delete {
+            path: node.path.clone(),
+        });
+        plan.sizes.insert(node.path.clone(), node.size);
+    }
+    plan.estimated_space = freed;
+    plan.shortfall = (freed < target_bytes).then(|| target_bytes - freed);
+    plan
+}
+
+/// 收集可选项（含目录及其子项），返回子树中是否有受保护或保留的路径
+fn collect_candidates<'a>(
+    node: &'a FileNode,
+    max_risk: RiskLevel,
+    keep: &KeepList,
+    out: &mut Vec<(RiskLevel, &'a FileNode)>,
+) -> bool {
+    if keep.is_kept(&node.path) {
+        return true;
+    }
+    let risk = explain(node);
+    let mut protected = risk.is_protected();
+    for child in &node.children {
+        protected |= collect_candidates(child, max_risk, keep, out);
+    }
+    if !protected && risk.level <= max_risk {
+        out.push((risk.level, node));
+    }
+    protected
+}
+
+/// `path` 是否等于 `dir` 或位于其下（按本机文件系统的大小写规则）
+fn is_within(path: &str, dir: &str) -> bool {
+    ai_disk_common::path::is_under(path, dir, CaseSensitivity::native())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size,
+            is_dir: !children.is_empty() || !path.contains('.'),
+            children,
+            ..Default::default()
+        }
+    }
+
+    fn scan(root: FileNode) -> ScanResult {
+        ScanResult {
+            total_size: root.size,
+            root,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_to_free_meets_target_or_reports_shortfall() {
+        let scan = scan(node(
+            "/",
+            6_700,
+            vec![
+                node(
+                    "/tmp",
+                    600,
+                    vec![
+                        node("/tmp/a.bin", 400, vec![]),
+                        node("/tmp/b.bin", 150, vec![]),
+                        node("/tmp/c.bin", 50, vec![]),
+                    ],
+                ),
+                node(
+                    "/home/u/Documents",
+                    1_100,
+                    vec![
+                        node("/home/u/Documents/notes.txt", 100, vec![]),
+                        node("/home/u/Documents/vault.kdbx", 1_000, vec![]),
+                    ],
+                ),
+                node("/usr/lib/huge.so", 5_000, vec![]),
+            ],
+        ));
+        let summary = |plan: &CleanupPlan| -> Vec<String> {
+            plan.actions
+                .iter()
+                .map(|a| a.target_path().to_string())
+                .collect()
+        };
+
+        // 低风险：大项优先，达标即停
+        let plan = plan_to_free(&scan, 420, RiskLevel::Low, &KeepList::default());
+        assert_eq!(summary(&plan), vec!["/tmp/a.bin", "/tmp/b.bin"]);
+        assert_eq!((plan.estimated_space, plan.shortfall), (550, None));
+
+        // 风险更高的大项入选后，剔除已不需要的低风险小项
+        let plan = plan_to_free(&scan, 650, RiskLevel::Medium, &KeepList::default());
+        assert_eq!(
+            summary(&plan),
+            vec!["/tmp/a.bin", "/tmp/b.bin", "/home/u/Documents/notes.txt"]
+        );
+        assert_eq!(plan.estimated_space, 650);
+
+        // 低风险不够时报告差额
+        let plan = plan_to_free(&scan, 1_000, RiskLevel::Low, &KeepList::default());
+        assert_eq!(plan.estimated_space, 600);
+        assert_eq!(plan.shortfall, Some(400));
+
+        // 放宽风险后仍不会选中受保护文件、系统目录及包含它们的目录
+        let plan = plan_to_free(&scan, 10_000, RiskLevel::High, &KeepList::default());
+        assert_eq!(
+            summary(&plan),
+            vec![
+                "/tmp/a.bin",
+                "/tmp/b.bin",
+                "/tmp/c.bin",
+                "/home/u/Documents/notes.txt"
+            ]
+        );
+        assert_eq!(plan.shortfall, Some(10_000 - 700));
+    }
+
+    #[test]
+    fn test_rule_based_plan_from_rules_file() {
+        let rules = JunkRules::from_toml_str(
+            r#"
+            [[rules]]
+            name = "tmp"
+            category = "temp"
+            paths = ["**/AppData/Local/Temp"]
+            action = "empty"
+
+            [[rules]]
+            name = "pip"
+            category = "package-cache"
+            paths = ["**/.cache/pip"]
+            action = "delete"
+
+            [[rules]]
+            name = "dumps"
+            category = "crash-dump"
+            paths = ["**/*.dmp"]
+            action = "trash"
+            "#,
+        )
+        .unwrap();
+        let root = node(
+            "C:/Users/u",
+            1_000,
+            vec![
+                node(
+                    "C:/Users/u/AppData/Local/Temp",
+                    300,
+                    vec![node("C:/Users/u/AppData/Local/Temp/x.dmp", 100, vec![])],
+                ),
+                node("C:/Users/u/.cache/pip", 200, vec![]),
+                node("C:/Users/u/app.dmp", 50, vec![]),
+                node("C:/Users/u/notes.txt", 10, vec![]),
+            ],
+        );
+        let scan = ScanResult {
+            root,
+            file_count: 4,
+            total_size: 1_000,
+            ..Default::default()
+        };
+
+        let plan = rule_based_plan(&scan, &rules, &KeepList::default());
+        let summary: Vec<String> = plan.actions.iter().map(|a| format!("{:?}", a)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                r#"Empty { path: "C:/Users/u/AppData/Local/Temp" }"#,
+                r#"Delete { path: "C:/Users/u/.cache/pip" }"#,
+                r#"Trash { path: "C:/Users/u/app.dmp" }"#,
+            ]
+        );
+        assert_eq!(plan.estimated_space, 550);
+    }
+
+    #[test]
+    fn test_system_managed_files_are_never_proposed() {
+        let mut pagefile = node(r"C:\pagefile.sys", 16 << 30, vec![]);
+        pagefile.system_managed = true;
+        let scan = scan(node(
+            r"C:\",
+            32 << 30,
+            vec![
+                pagefile,
+                // 未经扫描标记时按路径识别
+                node(r"C:\hiberfil.sys", 12 << 30, vec![]),
+                node(r"C:\old.iso", 4 << 30, vec![]),
+            ],
+        ));
+        let plan = plan_to_free(&scan, 30 << 30, RiskLevel::High, &KeepList::default());
+        let paths: Vec<&str> = plan.actions.iter().map(|a| a.target_path()).collect();
+        assert_eq!(paths, [r"C:\old.iso"]);
+        assert_eq!(plan.shortfall, Some(26 << 30));
+
+        let rules = JunkRules::from_toml_str(
+            "[[rules]]\nname = \"sys\"\ncategory = \"t\"\npaths = [\"**/*.sys\"]\naction = \"delete\"",
+        )
+        .unwrap();
+        assert!(rule_based_plan(&scan, &rules, &KeepList::default())
+            .actions
+            .is_empty());
+
+        // 仍出现在大小视图与提示词摘要中
+        let summary = crate::summarize_tree(&scan, crate::ContextSize::Small);
+        assert!(summary.contains("- pagefile.sys 16.00 GiB [系统管理，不可清理]"));
+    }
+
+    #[test]
+    fn test_kept_paths_are_excluded_from_new_plans() {
+        let scan = scan(node(
+            "/",
+            700,
+            vec![node(
+                "/tmp",
+                700,
+                vec![
+                    node("/tmp/a.bin", 400, vec![]),
+                    node("/tmp/b.bin", 300, vec![]),
+                ],
+            )],
+        ));
+        let mut keep = KeepList::default();
+        keep.add("/tmp/a.bin").unwrap();
+
+        let plan = plan_to_free(&scan, 10_000, RiskLevel::High, &keep);
+        let paths: Vec<&str> = plan.actions.iter().map(|a| a.target_path()).collect();
+        // 包含保留项的目录也不能整体入选
+        assert_eq!(paths, ["/tmp/b.bin"]);
+
+        let rules = JunkRules::from_toml_str(
+            "[[rules]]\nname = \"bin\"\ncategory = \"t\"\npaths = [\"**/*.bin\"]\naction = \"delete\"",
+        )
+        .unwrap();
+        let mut ai_plan = rule_based_plan(&scan, &rules, &KeepList::default());
+        assert_eq!(ai_plan.actions.len(), 2);
+        let removed = crate::enforce_keep_list(&mut ai_plan, &keep);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(ai_plan.estimated_space, 300);
+        assert_eq!(
+            rule_based_plan(&scan, &rules, &keep).actions.len(),
+            ai_plan.actions.len()
+        );
+    }
+
+    #[test]
+    fn test_heuristic_plan_without_llm() {
+        const DAY: u64 = 86_400;
+        let now = 1_700_000_000;
+        let file = |path: &str, size: u64, age_days: u64| FileNode {
+            modified: Some(now - age_days * DAY),
+            ..node(path, size, vec![])
+        };
+        let temp = r"C:\Users\u\AppData\Local\Temp";
+        let scan = scan(node(
+            r"C:\",
+            0,
+            vec![
+                node(
+                    r"C:\Windows\System32",
+                    0,
+                    vec![file(r"C:\Windows\System32\big.dll", 5_000, 900)],
+                ),
+                node(
+                    r"C:\Users\u",
+                    0,
+                    vec![
+                        node(
+                            temp,
+                            300,
+                            vec![
+                                file(r"C:\Users\u\AppData\Local\Temp\foo.tmp", 200, 1),
+                                file(r"C:\Users\u\AppData\Local\Temp\id_rsa", 100, 1),
+                            ],
+                        ),
+                        node(
+                            r"C:\Users\u\Documents",
+                            0,
+                            vec![
+                                file(r"C:\Users\u\Documents\thesis.docx", 9_000, 900),
+                                file(r"C:\Users\u\Documents\crash.dmp", 70, 1),
+                            ],
+                        ),
+                        node(
+                            r"C:\Users\u\proj",
+                            0,
+                            vec![file(r"C:\Users\u\proj\crash.dmp", 50, 1)],
+                        ),
+                        file(r"C:\Users\u\old.iso", 4_000, 400),
+                        file(r"C:\Users\u\new.iso", 4_000, 10),
+                    ],
+                ),
+            ],
+        ));
+        let mut opts = HeuristicOptions {
+            large_file_bytes: 1_000,
+            now,
+            ..HeuristicOptions::default()
+        };
+
+        let plan = heuristic_plan(&scan, &opts);
+        let summary: Vec<String> = plan.actions.iter().map(|a| format!("{:?}", a)).collect();
+        // Temp 目录的内容逐项删除；受保护文件、系统目录与文档目录中的项不会出现
+        assert_eq!(
+            summary,
+            [
+                r#"Trash { path: "C:\\Users\\u\\old.iso" }"#,
+                r#"Delete { path: "C:\\Users\\u\\AppData\\Local\\Temp\\foo.tmp" }"#,
+                r#"Trash { path: "C:\\Users\\u\\proj\\crash.dmp" }"#,
+            ]
+        );
+        assert_eq!(plan.estimated_space, 4_000 + 200 + 50);
+        assert!(plan.actions.iter().all(|a| {
+            let risk = explain(find_node(&scan.root, a.target_path()).unwrap());
+            !risk.is_protected() && !is_user_document_path(a.target_path())
+        }));
+
+        // 保留列表中的路径不建议
+        opts.keep.add(temp).unwrap();
+        let plan = heuristic_plan(&scan, &opts);
+        assert!(plan
+            .actions
+            .iter()
+            .all(|a| !a.target_path().contains("Temp")));
+    }
 }
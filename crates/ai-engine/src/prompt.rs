@@ -1,4 +1,244 @@
+//! 扫描结果摘要：按模型上下文大小选择目录树的深度与宽度，生成可直接放进提示词的稳定文本。
+//! 卷空间与主要扩展名总是包含在内，目录树从最详细的层级开始逐级收缩，直到估算的 token 数不超预算。
+
+use std::collections::HashMap;
+
+use ai_disk_common::format::format_bytes;
+use ai_disk_domain::{FileNode, ScanResult};
+
+/// 粗略估算：平均每 token 约 4 个字符
+const CHARS_PER_TOKEN: usize = 4;
+/// 摘要最多占用上下文的比例（其余留给系统提示与模型回复）
+const SUMMARY_SHARE: usize = 2;
+/// 摘要中列出的扩展名数量
+const TOP_EXTENSIONS: usize = 10;
+/// 目录树的（深度, 每层项数）候选，从详细到简略依次尝试
+const TREE_LEVELS: &[(usize, usize)] = &[
+    (8, 50),
+    (6, 30),
+    (5, 20),
+    (4, 15),
+    (3, 10),
+    (3, 5),
+    (2, 5),
+    (2, 3),
+    (1, 3),
+    (1, 1),
+];
+
+/// 常见模型上下文大小
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextSize {
+    /// 8k
+    Small,
+    /// 32k
+    Medium,
+    /// 128k
+    Large,
+}
+
+impl ContextSize {
+    /// 模型上下文的 token 数
+    pub fn context_tokens(self) -> usize {
+        match self {
+            ContextSize::Small => 8_000,
+            ContextSize::Medium => 32_000,
+            ContextSize::Large => 128_000,
+        }
+    }
+
+    /// 摘要可用的 token 预算
+    pub fn token_budget(self) -> usize {
+        self.context_tokens() / SUMMARY_SHARE
+    }
+}
+
+/// 估算文本的 token 数
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// 生成适合 `size` 的扫描摘要；同一输入总是得到同一输出
+pub fn summarize_tree(scan: &ScanResult, size: ContextSize) -> String {
+    let header = summary_header(scan);
+    let budget = size.token_budget();
+    let mut summary = String::new();
+    for &(depth, breadth) in TREE_LEVELS {
+        summary = format!("{}{}", header, tree_section(&scan.root, depth, breadth));
+        if estimate_tokens(&summary) <= budget {
+            break;
+        }
+    }
+    summary
+}
+
 /// AI 提示词模板（预留）
 pub fn build_analysis_prompt(_data: &str) -> String {
     "".to_string()
 }
+
+fn summary_header(scan: &ScanResult) -> String {
+    let mut out = String::from("# 扫描摘要\n");
+    out.push_str(&format!("根目录: {}\n", scan.root.path));
+    out.push_str(&format!(
+        "扫描大小: {}，文件数: {}\n",
+        format_bytes(scan.total_size),
+        scan.file_count
+    ));
+    match (scan.volume_free_bytes, scan.volume_total_bytes) {
+        (Some(free), Some(total)) => out.push_str(&format!(
+            "卷可用空间: {} / {}\n",
+            format_bytes(free),
+            format_bytes(total)
+        )),
+        (Some(free), None) => out.push_str(&format!("卷可用空间: {}\n", format_bytes(free))),
+        _ => out.push_str("卷可用空间: 未知\n"),
+    }
+    out.push_str("\n## 主要扩展名\n");
+    for (ext, bytes, count) in top_extensions(&scan.root) {
+        out.push_str(&format!("{} {} ({} 个)\n", ext, format_bytes(bytes), count));
+    }
+    out
+}
+
+/// 按总大小降序的前 `TOP_EXTENSIONS` 个扩展名：(扩展名, 字节数, 文件数)
+pub(crate) fn top_extensions(root: &FileNode) -> Vec<(String, u64, u64)> {
+    let mut by_ext: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.is_dir {
+            stack.extend(&node.children);
+            continue;
+        }
+        let ext = node
+            .display_name()
+            .rsplit_once('.')
+            .filter(|(stem, _)| !stem.is_empty())
+            .map(|(_, ext)| format!(".{}", ext.to_ascii_lowercase()))
+            .unwrap_or_else(|| "(无扩展名)".to_string());
+        let entry = by_ext.entry(ext).or_default();
+        entry.0 += node.size;
+        entry.1 += 1;
+    }
+    let mut exts: Vec<(String, u64, u64)> = by_ext
+        .into_iter()
+        .map(|(ext, (bytes, count))| (ext, bytes, count))
+        .collect();
+    exts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    exts.truncate(TOP_EXTENSIONS);
+    exts
+}
+
+fn tree_section(root: &FileNode, depth: usize, breadth: usize) -> String {
+    let mut out = format!("\n## 目录树（深度 {}，每层最多 {} 项）\n", depth, breadth);
+    push_node(&mut out, root, 0, depth, breadth);
+    out
+}
+
+fn push_node(out: &mut String, node: &FileNode, level: usize, depth: usize, breadth: usize) {
+    let kind = if node.is_dir { "/" } else { "" };
+    // 页面文件等由系统管理，提醒模型不要建议清理
+    let note = if node.is_system_managed() {
+        " [系统管理，不可清理]"
+    } else {
+        ""
+    };
+    out.push_str(&format!(
+        "{}- {}{} {}{}\n",
+        "  ".repeat(level),
+        node.display_name(),
+        kind,
+        format_bytes(node.size),
+        note
+    ));
+    if level >= depth || node.children.is_empty() {
+        return;
+    }
+    let mut children: Vec<&FileNode> = node.children.iter().collect();
+    children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    for child in children.iter().take(breadth) {
+        push_node(out, child, level + 1, depth, breadth);
+    }
+    if children.len() > breadth {
+        let rest = &children[breadth..];
+        out.push_str(&format!(
+            "{}- …其余 {} 项 {}\n",
+            "  ".repeat(level + 1),
+            rest.len(),
+            format_bytes(rest.iter().map(|c| c.size).sum())
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(path: String, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            name: ai_disk_common::path::file_name(&path).to_string(),
+            path,
+            size: children.iter().map(|c| c.size).sum(),
+            is_dir: true,
+            children,
+            ..Default::default()
+        }
+    }
+
+    fn big_scan() -> ScanResult {
+        let projects = (0..40)
+            .map(|p| {
+                let modules = (0..30)
+                    .map(|m| {
+                        let files = (0..20)
+                            .map(|f| {
+                                let path = format!(
+                                    "/data/p{}/m{}/file{}.{}",
+                                    p,
+                                    m,
+                                    f,
+                                    ["log", "bin", "mp4"][f % 3]
+                                );
+                                FileNode {
+                                    name: ai_disk_common::path::file_name(&path).to_string(),
+                                    path,
+                                    size: (p * 1_000 + m * 50 + f) as u64 * 1024,
+                                    ..Default::default()
+                                }
+                            })
+                            .collect();
+                        dir(format!("/data/p{}/m{}", p, m), files)
+                    })
+                    .collect();
+                dir(format!("/data/p{}", p), modules)
+            })
+            .collect();
+        let root = dir("/data".to_string(), projects);
+        ScanResult {
+            file_count: 40 * 30 * 20,
+            total_size: root.size,
+            root,
+            volume_total_bytes: Some(500 << 30),
+            volume_free_bytes: Some(12 << 30),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_small_context_summary_is_smaller_but_complete() {
+        let scan = big_scan();
+        let small = summarize_tree(&scan, ContextSize::Small);
+        let large = summarize_tree(&scan, ContextSize::Large);
+
+        assert_eq!(small, summarize_tree(&scan, ContextSize::Small));
+        assert!(small.len() < large.len());
+        assert!(estimate_tokens(&small) <= ContextSize::Small.token_budget());
+        assert!(estimate_tokens(&large) <= ContextSize::Large.token_budget());
+        for summary in [&small, &large] {
+            assert!(summary.contains("卷可用空间: 12.00 GiB / 500.00 GiB"));
+            assert!(summary.contains("## 主要扩展名\n.bin "));
+            assert!(summary.contains("\n- data/ "));
+            assert!(summary.contains("\n  - p39/ "));
+        }
+    }
+}
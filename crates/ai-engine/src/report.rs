@@ -0,0 +1,329 @@
+//! 「分析并报告」：扫描结果 + 规则清理计划生成一份可直接贴进工单的报告（Markdown 或自包含 HTML），
+//! 包含概要、最大的文件、顶层目录、主要扩展名与可回收项。
+//!
+//! 报告只由输入数据决定（不含生成时间等），同一输入总是得到同一输出。
+
+use ai_disk_common::format::format_bytes;
+use ai_disk_domain::{Action, CleanupPlan, FileNode, ScanResult};
+use serde::{Deserialize, Serialize};
+
+use crate::junk_rules::JunkRules;
+use crate::keep_list::KeepList;
+use crate::planner::rule_based_plan;
+use crate::prompt::top_extensions;
+
+/// 报告中「最大的文件」「顶层目录」「可回收项」各列出的条数
+const REPORT_ROWS: usize = 20;
+
+/// 报告格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+/// 一张表格，Markdown 与 HTML 共用同一份数据
+struct Table {
+    title: &'static str,
+    intro: Option<String>,
+    headers: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+/// 按规则生成清理计划并渲染报告
+pub fn analyze_and_report(
+    scan: &ScanResult,
+    rules: &JunkRules,
+    keep: &KeepList,
+    format: ReportFormat,
+) -> String {
+    let plan = rule_based_plan(scan, rules, keep);
+    render_report(scan, &plan, format)
+}
+
+/// 用给定的清理计划渲染报告
+pub fn render_report(scan: &ScanResult, plan: &CleanupPlan, format: ReportFormat) -> String {
+    let tables = report_tables(scan, plan);
+    match format {
+        ReportFormat::Markdown => render_markdown(&tables),
+        ReportFormat::Html => render_html(&tables),
+    }
+}
+
+fn report_tables(scan: &ScanResult, plan: &CleanupPlan) -> Vec<Table> {
+    let mut overview = vec![
+        vec!["扫描路径".to_string(), scan.root.path.clone()],
+        vec!["总大小".to_string(), format_bytes(scan.total_size)],
+        vec!["文件数".to_string(), scan.file_count.to_string()],
+    ];
+    if let Some(free) = scan.volume_free_bytes {
+        let volume = match scan.volume_total_bytes {
+            Some(total) => format!("{} / {}", format_bytes(free), format_bytes(total)),
+            None => format_bytes(free),
+        };
+        overview.push(vec!["卷可用空间".to_string(), volume]);
+    }
+    overview.push(vec![
+        "可回收（估计）".to_string(),
+        format_bytes(plan.estimated_space),
+    ]);
+
+    let files = top_files(scan)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (path, size))| vec![(i + 1).to_string(), path, format_bytes(size)])
+        .collect();
+
+    let mut dirs: Vec<&FileNode> = scan.root.children.iter().filter(|c| c.is_dir).collect();
+    dirs.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    let dirs = dirs
+        .into_iter()
+        .take(REPORT_ROWS)
+        .map(|d| {
+            vec![
+                d.path.clone(),
+                format_bytes(d.size),
+                percent(d.size, scan.total_size),
+            ]
+        })
+        .collect();
+
+    let extensions = top_extensions(&scan.root)
+        .into_iter()
+        .map(|(ext, bytes, count)| vec![ext, format_bytes(bytes), count.to_string()])
+        .collect();
+
+    let mut actions: Vec<(&Action, u64)> = plan
+        .actions
+        .iter()
+        .map(|a| (a, plan.sizes.get(a.target_path()).copied().unwrap_or(0)))
+        .collect();
+    actions.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| a.0.target_path().cmp(b.0.target_path()))
+    });
+    let reclaimable = actions
+        .into_iter()
+        .take(REPORT_ROWS)
+        .map(|(action, size)| {
+            vec![
+                action_label(action).to_string(),
+                action.target_path().to_string(),
+                format_bytes(size),
+            ]
+        })
+        .collect();
+
+    vec![
+        Table {
+            title: "概要",
+            intro: None,
+            headers: &["项目", "值"],
+            rows: overview,
+        },
+        Table {
+            title: "最大的文件",
+            intro: None,
+            headers: &["#", "路径", "大小"],
+            rows: files,
+        },
+        Table {
+            title: "顶层目录",
+            intro: None,
+            headers: &["目录", "大小", "占比"],
+            rows: dirs,
+        },
+        Table {
+            title: "主要扩展名",
+            intro: None,
+            headers: &["扩展名", "大小", "文件数"],
+            rows: extensions,
+        },
+        Table {
+            title: "可回收项",
+            intro: Some(plan.summary_text()),
+            headers: &["操作", "路径", "大小"],
+            rows: reclaimable,
+        },
+    ]
+}
+
+/// 最大的 `REPORT_ROWS` 个文件：优先用扫描结果中的前 N 大文件（MFT 扫描），否则遍历目录树
+fn top_files(scan: &ScanResult) -> Vec<(String, u64)> {
+    let mut files: Vec<(String, u64)> = match &scan.top_files {
+        Some(top) => top.iter().map(|f| (f.path.clone(), f.size)).collect(),
+        None => {
+            let mut files = Vec::new();
+            let mut stack = vec![&scan.root];
+            while let Some(node) = stack.pop() {
+                if node.is_dir {
+                    stack.extend(&node.children);
+                } else if !node.is_aggregate() {
+                    files.push((node.path.clone(), node.size));
+                }
+            }
+            files
+        }
+    };
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    files.truncate(REPORT_ROWS);
+    files
+}
+
+fn percent(part: u64, total: u64) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", part as f64 * 100.0 / total as f64)
+}
+
+fn action_label(action: &Action) -> &'static str {
+    match action {
+        Action::Delete { .. } => "删除",
+        Action::Move { .. } => "移动",
+        Action::Trash { .. } => "移到回收站",
+        Action::Empty { .. } => "清空",
+        Action::MarkKeep { .. } => "保留",
+    }
+}
+
+fn render_markdown(tables: &[Table]) -> String {
+    let mut out = String::from("# 磁盘分析报告\n");
+    for table in tables {
+        out.push_str(&format!("\n## {}\n\n", table.title));
+        if let Some(intro) = &table.intro {
+            out.push_str(&format!("{}\n\n", markdown_cell(intro)));
+        }
+        if table.rows.is_empty() {
+            out.push_str("（无）\n");
+            continue;
+        }
+        out.push_str(&format!("| {} |\n", table.headers.join(" | ")));
+        out.push_str(&format!("|{}\n", "---|".repeat(table.headers.len())));
+        for row in &table.rows {
+            let cells: Vec<String> = row.iter().map(|c| markdown_cell(c)).collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+    }
+    out
+}
+
+fn render_html(tables: &[Table]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>磁盘分析报告</title>\n<style>\
+         body{font-family:sans-serif;margin:2em}\
+         table{border-collapse:collapse;margin-bottom:1em}\
+         th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+         th{background:#f4f4f4}\
+         </style>\n</head>\n<body>\n<h1>磁盘分析报告</h1>\n",
+    );
+    for table in tables {
+        out.push_str(&format!("<h2>{}</h2>\n", table.title));
+        if let Some(intro) = &table.intro {
+            out.push_str(&format!("<p>{}</p>\n", html_escape(intro)));
+        }
+        if table.rows.is_empty() {
+            out.push_str("<p>（无）</p>\n");
+            continue;
+        }
+        out.push_str("<table>\n<tr>");
+        for header in table.headers {
+            out.push_str(&format!("<th>{}</th>", html_escape(header)));
+        }
+        out.push_str("</tr>\n");
+        for row in &table.rows {
+            out.push_str("<tr>");
+            for cell in row {
+                out.push_str(&format!("<td>{}</td>", html_escape(cell)));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// 表格单元格中的 `|` 与换行会破坏 Markdown 表格
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size: size + children.iter().map(|c| c.size).sum::<u64>(),
+            is_dir: !children.is_empty(),
+            children,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_report_contains_key_figures() {
+        let temp = node(
+            "/home/u/AppData/Local/Temp",
+            0,
+            vec![node(
+                "/home/u/AppData/Local/Temp/setup.log",
+                3 << 20,
+                vec![],
+            )],
+        );
+        let root = node(
+            "/home/u",
+            0,
+            vec![
+                node(
+                    "/home/u/Videos",
+                    0,
+                    vec![node("/home/u/Videos/trip|2024.mp4", 2 << 30, vec![])],
+                ),
+                node("/home/u/AppData/Local", 0, vec![temp]),
+                node("/home/u/notes.txt", 1_000, vec![]),
+            ],
+        );
+        let scan = ScanResult {
+            total_size: root.size,
+            file_count: 3,
+            root,
+            volume_total_bytes: Some(500 << 30),
+            volume_free_bytes: Some(12 << 30),
+            ..Default::default()
+        };
+        let rules = JunkRules::builtin();
+        let keep = KeepList::default();
+
+        let md = analyze_and_report(&scan, &rules, &keep, ReportFormat::Markdown);
+        assert_eq!(
+            md,
+            analyze_and_report(&scan, &rules, &keep, ReportFormat::Markdown)
+        );
+        assert!(md.contains(&format!("| 总大小 | {} |", format_bytes(scan.total_size))));
+        assert!(md.contains("| 卷可用空间 | 12.00 GiB / 500.00 GiB |"));
+        assert!(md.contains("| 可回收（估计） | 3.00 MiB |"));
+        assert!(md.contains("| 1 | /home/u/Videos/trip\\|2024.mp4 | 2.00 GiB |"));
+        assert!(md.contains("| 清空 | /home/u/AppData/Local/Temp | 3.00 MiB |"));
+        assert!(md.contains("| .mp4 | 2.00 GiB | 1 |"));
+
+        let html = analyze_and_report(&scan, &rules, &keep, ReportFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>可回收（估计）</td><td>3.00 MiB</td>"));
+        assert!(html.contains("<td>/home/u/Videos/trip|2024.mp4</td>"));
+    }
+}
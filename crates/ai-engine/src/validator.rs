@@ -1,6 +1,307 @@
-use ai_disk_domain::Action;
+use ai_disk_common::path::{components, is_under, CaseSensitivity};
+use ai_disk_domain::{
+    is_system_managed_file, is_system_path, Action, CleanupPlan, FileNode, ScanResult,
+};
+use serde::{Deserialize, Serialize};
 
-/// 动作校验器（预留）
-pub fn validate_action(_action: &Action) -> Result<(), String> {
-    Ok(())
+use crate::keep_list::KeepList;
+
+/// 单个动作的静态校验（无需扫描结果）：清理或移动系统目录、由系统管理的文件一律拒绝，
+/// 移动时也检查目的地；保留（`MarkKeep`）总是通过。错误为展示给用户的说明
+pub fn validate_action(action: &Action) -> Result<(), String> {
+    let forbidden = match action {
+        Action::MarkKeep { .. } => None,
+        Action::Move { from, to } => [from.as_str(), to.as_str()]
+            .into_iter()
+            .find(|p| is_forbidden(p)),
+        _ => Some(action.target_path()).filter(|p| is_forbidden(p)),
+    };
+    match forbidden {
+        Some(p) => Err(format!(
+            "{} 为系统目录或由系统管理的文件，不可清理或移动",
+            p
+        )),
+        None => Ok(()),
+    }
+}
+
+/// 动作被拒绝的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// 目标（或移动的目的地）为系统目录或由系统管理的文件
+    ForbiddenPath,
+    /// 目标不在扫描根目录下
+    OutsideScanRoot,
+    /// 扫描结果中没有该路径（如 LLM 编造的路径）
+    NotFound,
+}
+
+/// 未通过校验的动作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedAction {
+    pub action: Action,
+    pub reason: RejectReason,
+    /// 展示给用户的说明
+    pub message: String,
+}
+
+/// `validate_plan` 的结果：`valid` 与 `rejected` 各自保持计划中的顺序
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub valid: Vec<Action>,
+    pub rejected: Vec<RejectedAction>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+/// 执行前对照扫描结果校验计划（尤其是 LLM 生成的）：目标须位于扫描根目录下且存在于扫描树中，
+/// 清理或移动系统目录、由系统管理的文件一律拒绝（见 `validate_action`）。
+/// 未完整展开的目录（shallow 目录、达到最大深度或子项被截断的目录）下的路径无法核实，视为存在；
+/// 保留（`MarkKeep`）不检查
+pub fn validate_plan(plan: &CleanupPlan, result: &ScanResult) -> ValidationReport {
+    let case = CaseSensitivity::native();
+    let mut report = ValidationReport::default();
+    for action in &plan.actions {
+        if matches!(action, Action::MarkKeep { .. }) {
+            report.valid.push(action.clone());
+            continue;
+        }
+        let path = action.target_path();
+        let rejection = if let Err(message) = validate_action(action) {
+            Some((RejectReason::ForbiddenPath, message))
+        } else if !is_under(path, &result.root.path, case) {
+            Some((
+                RejectReason::OutsideScanRoot,
+                format!("{} 不在扫描目录 {} 下", path, result.root.path),
+            ))
+        } else if !exists_in_tree(&result.root, path, case) {
+            Some((RejectReason::NotFound, format!("扫描结果中不存在 {}", path)))
+        } else {
+            None
+        };
+        match rejection {
+            Some((reason, message)) => report.rejected.push(RejectedAction {
+                action: action.clone(),
+                reason,
+                message,
+            }),
+            None => report.valid.push(action.clone()),
+        }
+    }
+    report
+}
+
+fn is_forbidden(path: &str) -> bool {
+    is_system_path(path) || is_system_managed_file(path)
+}
+
+/// `path`（已知位于 `node` 下）是否可能存在：是 `node` 子树中的节点，或途经的目录未完整展开而无法核实——
+/// 没有子节点的目录（shallow 目录、达到最大深度的目录）、子项被截断或并入汇总节点的目录、
+/// 只扫描目录模式下不含文件节点的目录
+fn exists_in_tree(node: &FileNode, path: &str, case: CaseSensitivity) -> bool {
+    if components(path).len() == components(&node.path).len() {
+        return true;
+    }
+    if node.is_dir && node.children.is_empty() {
+        return true;
+    }
+    match node
+        .children
+        .iter()
+        .filter(|c| !c.is_aggregate())
+        .find(|c| is_under(path, &c.path, case))
+    {
+        Some(child) => exists_in_tree(child, path, case),
+        None => {
+            node.has_more
+                || node.file_count.is_some()
+                || node.children.iter().any(FileNode::is_aggregate)
+        }
+    }
+}
+
+/// 过量清理保护：用户只要求释放 `goal_bytes`，计划（尤其是 AI 生成的）却预计释放超过
+/// `max_multiple` 倍时，在计划上记录目标并要求额外确认。返回是否触发；目标为 0 时不检查
+pub fn flag_overshoot(plan: &mut CleanupPlan, goal_bytes: u64, max_multiple: f64) -> bool {
+    let overshoot =
+        goal_bytes > 0 && plan.estimated_space as f64 > goal_bytes as f64 * max_multiple;
+    plan.overshoot_goal = overshoot.then_some(goal_bytes);
+    overshoot
+}
+
+/// 从计划（如 AI 生成的计划）中剔除作用于保留路径的动作及其预计空间，返回被剔除的动作
+pub fn enforce_keep_list(plan: &mut CleanupPlan, keep: &KeepList) -> Vec<Action> {
+    let (removed, actions): (Vec<Action>, Vec<Action>) = std::mem::take(&mut plan.actions)
+        .into_iter()
+        .partition(|a| !matches!(a, Action::MarkKeep { .. }) && keep.is_kept(a.target_path()));
+    plan.actions = actions;
+    for action in &removed {
+        if let Some(size) = plan.sizes.remove(action.target_path()) {
+            plan.estimated_space = plan.estimated_space.saturating_sub(size);
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_common::DEFAULT_OVERSHOOT_MULTIPLE;
+
+    fn plan(size: u64) -> CleanupPlan {
+        let path = "/home/u/Videos".to_string();
+        CleanupPlan {
+            actions: vec![Action::Delete { path: path.clone() }],
+            estimated_space: size,
+            sizes: [(path, size)].into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_overshoot_flags_runaway_plan() {
+        let goal = 10 << 30;
+        let mut runaway = plan(300 << 30);
+        assert!(flag_overshoot(
+            &mut runaway,
+            goal,
+            DEFAULT_OVERSHOOT_MULTIPLE
+        ));
+        assert!(runaway.requires_extra_confirmation());
+        assert!(runaway
+            .summary_text()
+            .ends_with("目标仅需释放 10.00 GiB，本计划远超目标，请逐项确认后再执行。"));
+
+        let mut proportionate = plan(12 << 30);
+        assert!(!flag_overshoot(
+            &mut proportionate,
+            goal,
+            DEFAULT_OVERSHOOT_MULTIPLE
+        ));
+        assert!(!proportionate.requires_extra_confirmation());
+        assert!(!proportionate.summary_text().contains("远超目标"));
+        // 倍数可配置
+        assert!(flag_overshoot(&mut proportionate, goal, 1.1));
+    }
+
+    #[test]
+    fn test_validate_plan_rejects_missing_outside_and_system_paths() {
+        let node = |path: &str, size: u64, children: Vec<FileNode>| FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size,
+            is_dir: !children.is_empty() || !path.contains('.'),
+            children,
+            ..Default::default()
+        };
+        let root = node(
+            r"C:\",
+            0,
+            vec![
+                node(
+                    r"C:\Windows",
+                    0,
+                    vec![node(r"C:\Windows\win.ini", 1, vec![])],
+                ),
+                node(
+                    r"C:\Users\u",
+                    0,
+                    vec![
+                        node(r"C:\Users\u\old.iso", 700, vec![]),
+                        // 未展开的 shallow 目录
+                        node(r"C:\Users\u\node_modules", 300, vec![]),
+                        // 子项被截断的目录：未列出的项无法核实
+                        FileNode {
+                            has_more: true,
+                            children_count: Some(40),
+                            ..node(
+                                r"C:\Users\u\Downloads",
+                                900,
+                                vec![node(r"C:\Users\u\Downloads\a.zip", 500, vec![])],
+                            )
+                        },
+                    ],
+                ),
+            ],
+        );
+        let result = ScanResult {
+            file_count: 3,
+            total_size: 1_001,
+            root,
+            ..Default::default()
+        };
+        let path = |p: &str| p.to_string();
+        let plan = CleanupPlan {
+            actions: vec![
+                Action::Delete {
+                    path: path(r"C:\Users\u\old.iso"),
+                },
+                Action::Delete {
+                    path: path(r"C:\Users\u\missing.iso"),
+                },
+                Action::Empty {
+                    path: path(r"C:\Windows"),
+                },
+                Action::Trash {
+                    path: path(r"D:\old.iso"),
+                },
+                Action::Move {
+                    from: path(r"C:\Users\u\old.iso"),
+                    to: path(r"C:\Windows\old.iso"),
+                },
+                Action::Delete {
+                    path: path(r"C:\Users\u\node_modules\left-pad"),
+                },
+                Action::Delete {
+                    path: path(r"C:\Users\u\Downloads\b.zip"),
+                },
+                Action::Delete {
+                    path: path(r"C:\Users\u\Downloads\a.zip\inner"),
+                },
+                Action::MarkKeep {
+                    path: path(r"C:\Users\u\anything"),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let report = validate_plan(&plan, &result);
+        assert!(!report.is_valid());
+        let valid: Vec<&str> = report.valid.iter().map(|a| a.target_path()).collect();
+        assert_eq!(
+            valid,
+            [
+                r"C:\Users\u\old.iso",
+                r"C:\Users\u\node_modules\left-pad",
+                r"C:\Users\u\Downloads\b.zip",
+                r"C:\Users\u\anything"
+            ]
+        );
+        let rejected: Vec<(&str, RejectReason)> = report
+            .rejected
+            .iter()
+            .map(|r| (r.action.target_path(), r.reason))
+            .collect();
+        assert_eq!(
+            rejected,
+            [
+                (r"C:\Users\u\missing.iso", RejectReason::NotFound),
+                (r"C:\Windows", RejectReason::ForbiddenPath),
+                (r"D:\old.iso", RejectReason::OutsideScanRoot),
+                (r"C:\Users\u\old.iso", RejectReason::ForbiddenPath),
+                (r"C:\Users\u\Downloads\a.zip\inner", RejectReason::NotFound),
+            ]
+        );
+        assert!(report.rejected[3].message.contains(r"C:\Windows\old.iso"));
+        assert_eq!(
+            validate_action(&plan.actions[4]),
+            Err(report.rejected[3].message.clone())
+        );
+        assert!(validate_action(&plan.actions[0]).is_ok());
+    }
 }
@@ -1,6 +1,386 @@
-/// 应用配置
-#[derive(Debug, Clone, Default)]
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CloudRouting, DiskAnalyzerError};
+
+/// 应用数据目录名（位于用户主目录下），隔离区、暂存区、保留列表等都在其中
+pub const APP_DIR_NAME: &str = ".disk-rookie";
+
+/// 配置文件名，位于应用数据目录下
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 覆盖配置项的环境变量前缀，如 `DISKROOKIE_SCAN_DEPTH=3`；`DISKROOKIE_CONFIG` 指定配置文件路径
+pub const CONFIG_ENV_PREFIX: &str = "DISKROOKIE_";
+
+/// 清理计划预计释放空间与用户目标之比的默认上限
+pub const DEFAULT_OVERSHOOT_MULTIPLE: f64 = 5.0;
+
+/// 应用配置，可从 TOML 文件读取（缺省字段取默认值）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     pub scan_depth: Option<usize>,
     pub dry_run: bool,
+    /// OpenTelemetry 导出配置（仅在启用 `otel` feature 时生效）
+    pub otel: OtelConfig,
+    /// 敏感目录（如「文档」「桌面」「图片」）：非系统保护，但执行器在其中删除/移动前需要二次确认。
+    /// 为空时使用 `default_sensitive_dirs()`
+    pub sensitive_dirs: Vec<String>,
+    /// 批量上传时按扩展名/类别选择云存储目标
+    pub cloud_routing: CloudRouting,
+    /// 应用数据目录，为空时使用 `~/.disk-rookie`
+    pub data_dir: Option<String>,
+    /// 扫描时额外跳过的目录；应用数据目录总是跳过
+    pub scan_excludes: Vec<String>,
+    /// 清理计划预计释放的空间超过用户目标的多少倍时要求额外确认，为空时取 `DEFAULT_OVERSHOOT_MULTIPLE`
+    pub overshoot_multiple: Option<f64>,
+    /// 是否上报遥测事件（扫描耗时、错误数等），为空时开启；设为 false 时安装 `NoopSink`
+    pub telemetry_enabled: Option<bool>,
+    /// 扫描时是否把 node_modules 等目录折叠为一项，为空时开启
+    pub shallow_dirs: Option<bool>,
+    /// 生成清理计划所用的 LLM
+    pub llm: LlmConfig,
+}
+
+impl AppConfig {
+    /// 读取 TOML 配置文件并校验，文件不存在时返回默认配置
+    pub fn load_from(path: &Path) -> Result<Self, DiskAnalyzerError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)?;
+        let config: Self = toml::from_str(&text)
+            .map_err(|e| DiskAnalyzerError::Config(format!("{}: {}", path.display(), e)))?;
+        config.validate().map_err(|problems| {
+            DiskAnalyzerError::Config(format!("{}: {}", path.display(), problems))
+        })?;
+        Ok(config)
+    }
+
+    /// 读取配置文件（`DISKROOKIE_CONFIG` 指定的路径，否则为 `config_file_path()`），
+    /// 不存在时取默认配置，再应用 `DISKROOKIE_*` 环境变量覆盖
+    pub fn load() -> Result<Self, DiskAnalyzerError> {
+        let path = std::env::var_os(format!("{}CONFIG", CONFIG_ENV_PREFIX))
+            .map(PathBuf::from)
+            .or_else(config_file_path)
+            .ok_or_else(|| DiskAnalyzerError::Config("无法确定用户主目录".to_string()))?;
+        Self::load_with_env(&path)
+    }
+
+    /// 同 `load_from`，之后应用当前进程的 `DISKROOKIE_*` 环境变量覆盖并重新校验
+    pub fn load_with_env(path: &Path) -> Result<Self, DiskAnalyzerError> {
+        let mut config = Self::load_from(path)?;
+        config.apply_env_overrides(
+            std::env::vars_os()
+                .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?))),
+        )?;
+        config
+            .validate()
+            .map_err(|problems| DiskAnalyzerError::Config(format!("环境变量: {}", problems)))?;
+        Ok(config)
+    }
+
+    /// 按 `DISKROOKIE_<字段>` 覆盖配置项，如 `DISKROOKIE_SCAN_DEPTH`、`DISKROOKIE_LLM_API_KEY`；
+    /// 其他前缀的变量忽略，前缀相同但无法识别的变量或无法解析的值返回错误
+    pub fn apply_env_overrides(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<(), DiskAnalyzerError> {
+        for (key, value) in vars {
+            let Some(field) = key.strip_prefix(CONFIG_ENV_PREFIX) else {
+                continue;
+            };
+            let invalid = || DiskAnalyzerError::Config(format!("{}: 无效的值 {:?}", key, value));
+            let text = || Some(value.clone()).filter(|v| !v.is_empty());
+            match field {
+                "CONFIG" => {}
+                "SCAN_DEPTH" => {
+                    self.scan_depth = match text() {
+                        Some(v) => Some(v.parse().map_err(|_| invalid())?),
+                        None => None,
+                    }
+                }
+                "DRY_RUN" => self.dry_run = parse_bool(&value).ok_or_else(invalid)?,
+                "SHALLOW_DIRS" => self.shallow_dirs = Some(parse_bool(&value).ok_or_else(invalid)?),
+                "TELEMETRY_ENABLED" => {
+                    self.telemetry_enabled = Some(parse_bool(&value).ok_or_else(invalid)?);
+                }
+                "OVERSHOOT_MULTIPLE" => {
+                    self.overshoot_multiple = Some(value.parse().map_err(|_| invalid())?);
+                }
+                "DATA_DIR" => self.data_dir = text(),
+                "LLM_PROVIDER" => self.llm.provider = text(),
+                "LLM_API_KEY" => self.llm.api_key = text(),
+                "LLM_MODEL" => self.llm.model = text(),
+                _ => return Err(DiskAnalyzerError::Config(format!("未知的环境变量 {}", key))),
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查取值范围、枚举名称等，一次列出全部问题（以 `; ` 分隔），
+    /// 避免错误配置在运行时才表现为难以理解的行为
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+        if self.scan_depth == Some(0) {
+            problems.push("scan_depth must be at least 1".to_string());
+        }
+        let ratio = self.otel.sampling_ratio;
+        if !(0.0..=1.0).contains(&ratio) {
+            problems.push(format!(
+                "otel.sampling_ratio must be between 0.0 and 1.0, got {}",
+                ratio
+            ));
+        }
+        if let Some(multiple) = self.overshoot_multiple {
+            if !(multiple.is_finite() && multiple >= 1.0) {
+                problems.push(format!(
+                    "overshoot_multiple must be a number of at least 1.0, got {}",
+                    multiple
+                ));
+            }
+        }
+        if self
+            .data_dir
+            .as_deref()
+            .is_some_and(|d| d.trim().is_empty())
+        {
+            problems.push("data_dir is empty; remove it to use the default".to_string());
+        }
+        if self.scan_excludes.iter().any(|d| d.trim().is_empty()) {
+            problems.push("scan_excludes contains an empty entry".to_string());
+        }
+        for (i, rule) in self.cloud_routing.rules.iter().enumerate() {
+            problems.extend(rule.problems(&format!("cloud_routing.rules[{}]", i)));
+        }
+        if self
+            .cloud_routing
+            .default_target
+            .as_deref()
+            .is_some_and(|t| t.trim().is_empty())
+        {
+            problems.push("cloud_routing.default_target is empty".to_string());
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("; "))
+        }
+    }
+
+    /// 实际生效的应用数据目录；无法确定用户主目录且未配置时为 None
+    pub fn app_data_dir(&self) -> Option<PathBuf> {
+        match &self.data_dir {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => home_dir().map(|home| home.join(APP_DIR_NAME)),
+        }
+    }
+
+    /// 扫描时跳过的目录：应用数据目录（含隔离区与暂存区，避免建议清理自身的撤销数据）与 `scan_excludes`
+    pub fn scan_exclude_dirs(&self) -> Vec<PathBuf> {
+        self.app_data_dir()
+            .into_iter()
+            .chain(self.scan_excludes.iter().map(PathBuf::from))
+            .collect()
+    }
+
+    pub fn shallow_dirs(&self) -> bool {
+        self.shallow_dirs.unwrap_or(true)
+    }
+
+    pub fn telemetry_enabled(&self) -> bool {
+        self.telemetry_enabled.unwrap_or(true)
+    }
+
+    /// 实际生效的过量清理倍数上限
+    pub fn overshoot_multiple(&self) -> f64 {
+        self.overshoot_multiple
+            .unwrap_or(DEFAULT_OVERSHOOT_MULTIPLE)
+    }
+
+    /// 实际生效的敏感目录列表
+    pub fn sensitive_roots(&self) -> Vec<String> {
+        if self.sensitive_dirs.is_empty() {
+            default_sensitive_dirs()
+        } else {
+            self.sensitive_dirs.clone()
+        }
+    }
+}
+
+/// 当前用户主目录下的 Documents、Desktop、Pictures
+pub fn default_sensitive_dirs() -> Vec<String> {
+    let Some(home) = home_dir() else {
+        return Vec::new();
+    };
+    ["Documents", "Desktop", "Pictures"]
+        .iter()
+        .map(|dir| home.join(dir).to_string_lossy().into_owned())
+        .collect()
+}
+
+/// 默认的配置文件路径 `~/.disk-rookie/config.toml`；无法确定用户主目录时为 None
+pub fn config_file_path() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// `1`/`true`/`yes`/`on` 与 `0`/`false`/`no`/`off`，不区分大小写
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from)
+}
+
+/// LLM 服务配置，均可为空（离线时使用规则生成计划）
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    /// 服务提供方，如 `openai`、`local`
+    pub provider: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+}
+
+impl std::fmt::Debug for LlmConfig {
+    /// 不输出 API Key
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LlmConfig")
+            .field("provider", &self.provider)
+            .field("api_key", &self.api_key.as_ref().map(|_| "***"))
+            .field("model", &self.model)
+            .finish()
+    }
+}
+
+/// OTLP 链路追踪导出配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtelConfig {
+    /// OTLP/HTTP traces 端点，如 Jaeger/Tempo collector 的 `http://host:4318/v1/traces`
+    pub endpoint: String,
+    /// 上报的 service.name
+    pub service_name: String,
+    /// 采样率（0.0 ~ 1.0），1.0 表示全部采样
+    pub sampling_ratio: f64,
+}
+
+impl Default for OtelConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:4318/v1/traces".to_string(),
+            service_name: "disk-rookie".to_string(),
+            sampling_ratio: 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(toml_text: &str) -> String {
+        let config: AppConfig = toml::from_str(toml_text).unwrap();
+        config.validate().unwrap_err()
+    }
+
+    #[test]
+    fn test_validate_lists_every_problem() {
+        assert_eq!(AppConfig::default().validate(), Ok(()));
+        assert_eq!(
+            problems("[otel]\nsampling_ratio = 1.5\n"),
+            "otel.sampling_ratio must be between 0.0 and 1.0, got 1.5"
+        );
+        assert_eq!(
+            problems("scan_depth = 0\novershoot_multiple = -2.0\n"),
+            "scan_depth must be at least 1; \
+             overshoot_multiple must be a number of at least 1.0, got -2"
+        );
+        assert_eq!(
+            problems(
+                r#"
+                [[cloud_routing.rules]]
+                category = "movies"
+                target = "Cold S3"
+
+                [[cloud_routing.rules]]
+                target = ""
+                "#
+            ),
+            "unknown cloud_routing.rules[0].category 'movies'; \
+             expected one of video, image, audio, document, archive; \
+             cloud_routing.rules[1].target is empty; \
+             cloud_routing.rules[1] matches no file; set extensions or category"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.toml");
+        std::fs::write(&file, "scan_excludes = [\"\"]\n").unwrap();
+        let err = AppConfig::load_from(&file).unwrap_err().to_string();
+        assert!(err.ends_with("config.toml: scan_excludes contains an empty entry"));
+    }
+
+    #[test]
+    fn test_load_from_file_then_env_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.toml");
+        std::fs::write(
+            &file,
+            "scan_depth = 4\nshallow_dirs = false\n\n[llm]\nprovider = \"openai\"\napi_key = \"sk-file\"\n",
+        )
+        .unwrap();
+        let mut config = AppConfig::load_from(&file).unwrap();
+        assert_eq!(
+            (
+                config.scan_depth,
+                config.shallow_dirs(),
+                config.telemetry_enabled()
+            ),
+            (Some(4), false, true)
+        );
+
+        let vars = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        config
+            .apply_env_overrides(vars(&[
+                ("DISKROOKIE_SCAN_DEPTH", "2"),
+                ("DISKROOKIE_TELEMETRY_ENABLED", "off"),
+                ("DISKROOKIE_LLM_API_KEY", "sk-env"),
+                ("PATH", "/usr/bin"),
+            ]))
+            .unwrap();
+        assert_eq!(config.scan_depth, Some(2));
+        assert!(!config.telemetry_enabled());
+        assert_eq!(config.llm.provider.as_deref(), Some("openai"));
+        assert_eq!(config.llm.api_key.as_deref(), Some("sk-env"));
+        assert!(!format!("{:?}", config).contains("sk-env"));
+
+        let err = config
+            .apply_env_overrides(vars(&[("DISKROOKIE_SCAN_DEPHT", "2")]))
+            .unwrap_err();
+        assert!(err.to_string().contains("DISKROOKIE_SCAN_DEPHT"));
+        assert!(config
+            .apply_env_overrides(vars(&[("DISKROOKIE_SHALLOW_DIRS", "maybe")]))
+            .is_err());
+
+        // 通过进程环境变量指定文件并覆盖字段（只用本测试独有的字段，避免影响并行的测试）
+        std::env::set_var("DISKROOKIE_CONFIG", &file);
+        std::env::set_var("DISKROOKIE_LLM_MODEL", "gpt-test");
+        let loaded = AppConfig::load();
+        std::env::remove_var("DISKROOKIE_CONFIG");
+        std::env::remove_var("DISKROOKIE_LLM_MODEL");
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.scan_depth, Some(4));
+        assert_eq!(loaded.llm.model.as_deref(), Some("gpt-test"));
+    }
 }
@@ -0,0 +1,183 @@
+//! 配置热加载：监视配置文件，变化后重新读取并发布到 `SharedConfig`，
+//! 扫描器、规划器、上传等模块持有 `SharedConfig` 的克隆，每次使用时读取最新配置，无需重启或重建。
+//! 重新读取失败（如 TOML 语法错误）时保留上一份配置并记录警告。
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{AppConfig, DiskAnalyzerError};
+
+/// 事件去抖窗口：编辑器保存时常产生多次写入/重命名事件，静默这么久后才重新读取
+const DEBOUNCE: Duration = Duration::from_millis(200);
+/// 工作线程检查停止标记的间隔
+const POLL: Duration = Duration::from_millis(50);
+
+/// 可在线程间共享、随时读取最新值的配置句柄（克隆开销为一次 `Arc` 计数）
+#[derive(Debug, Clone)]
+pub struct SharedConfig(Arc<ArcSwap<AppConfig>>);
+
+impl SharedConfig {
+    pub fn new(config: AppConfig) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(config)))
+    }
+
+    /// 当前配置的快照；持有期间即使配置更新也保持不变
+    pub fn current(&self) -> Arc<AppConfig> {
+        self.0.load_full()
+    }
+
+    /// 发布新配置，之后的 `current()` 都返回它
+    pub fn replace(&self, config: AppConfig) {
+        self.0.store(Arc::new(config));
+    }
+}
+
+impl Default for SharedConfig {
+    fn default() -> Self {
+        Self::new(AppConfig::default())
+    }
+}
+
+/// 配置文件监视句柄，drop 时停止监视
+pub struct ConfigWatcher {
+    shared: SharedConfig,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    /// 读取 `path` 作为初始配置（不存在时为默认配置，并应用环境变量覆盖）并开始监视其所在目录
+    pub fn start(path: &Path) -> Result<Self, DiskAnalyzerError> {
+        let shared = SharedConfig::new(AppConfig::load_with_env(path)?);
+        // 编辑器常以「写临时文件再重命名」的方式保存，监视目录而非文件本身
+        let dir = path
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        let file_name = path.file_name().map(|n| n.to_os_string());
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name)
+                {
+                    let _ = tx.send(());
+                }
+            }
+        })
+        .map_err(|e| DiskAnalyzerError::Config(format!("监视配置文件失败: {}", e)))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| DiskAnalyzerError::Config(format!("监视配置文件失败: {}", e)))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let shared = shared.clone();
+            let stop = stop.clone();
+            let path = path.to_path_buf();
+            std::thread::spawn(move || reload_loop(&path, &shared, &stop, &rx))
+        };
+
+        Ok(Self {
+            shared,
+            stop,
+            worker: Some(worker),
+            _watcher: watcher,
+        })
+    }
+
+    /// 供各模块持有的配置句柄
+    pub fn shared(&self) -> SharedConfig {
+        self.shared.clone()
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn reload_loop(path: &Path, shared: &SharedConfig, stop: &AtomicBool, rx: &mpsc::Receiver<()>) {
+    let mut pending = false;
+    let mut quiet = Duration::ZERO;
+    while !stop.load(Ordering::Relaxed) {
+        match rx.recv_timeout(POLL) {
+            Ok(()) => {
+                pending = true;
+                quiet = Duration::ZERO;
+            }
+            Err(RecvTimeoutError::Timeout) => quiet += POLL,
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if pending && quiet >= DEBOUNCE {
+            pending = false;
+            match AppConfig::load_with_env(path) {
+                Ok(config) => shared.replace(config),
+                Err(e) => tracing::warn!("配置重新加载失败，继续使用上一份配置: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// 等待 `cond` 成立，最多 5 秒
+    fn wait_until(cond: impl Fn() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if cond() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        cond()
+    }
+
+    #[test]
+    fn test_config_file_changes_are_published() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.toml");
+        std::fs::write(&file, "scan_depth = 3\n").unwrap();
+        let watcher = ConfigWatcher::start(&file).unwrap();
+        // 模拟某个模块启动时拿到的句柄，之后不再重建
+        let scanner_config = watcher.shared();
+        assert_eq!(scanner_config.current().scan_depth, Some(3));
+
+        std::fs::write(&file, "scan_depth = 5\ndry_run = true\n").unwrap();
+        assert!(wait_until(|| scanner_config.current().scan_depth == Some(5)));
+        assert!(scanner_config.current().dry_run);
+
+        // 无效配置不生效，保留上一份
+        std::fs::write(&file, "scan_depth = \"deep\"\n").unwrap();
+        std::thread::sleep(DEBOUNCE * 3);
+        assert_eq!(scanner_config.current().scan_depth, Some(5));
+
+        std::fs::write(
+            &file,
+            "scan_depth = 7\n[otel]\nservice_name = \"rookie-test\"\n",
+        )
+        .unwrap();
+        assert!(wait_until(|| scanner_config.current().scan_depth == Some(7)));
+        assert_eq!(scanner_config.current().otel.service_name, "rookie-test");
+    }
+}
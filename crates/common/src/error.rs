@@ -1,3 +1,5 @@
+use std::fmt;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -13,4 +15,168 @@ pub enum DiskAnalyzerError {
 
     #[error("Configuration error: {0}")]
     Config(String),
+
+    #[error("Scan cancelled")]
+    Cancelled,
+
+    /// 需要管理员权限（如打开 NTFS 卷读取 $MFT），调用方可据此提示以管理员身份重新运行
+    #[error("Needs elevation: {0}")]
+    NeedsElevation(String),
+
+    /// 卷不是 NTFS，无法使用 MFT 扫描
+    #[error("Volume is not NTFS: {0}")]
+    VolumeNotNtfs(String),
+
+    /// 读取或解析 $MFT 失败
+    #[error("MFT read failed: {0}")]
+    MftReadFailed(String),
+
+    /// 附带发生位置（操作、阶段、路径）的错误，便于用户反馈时定位
+    #[error("{source} [{context}]")]
+    WithContext {
+        context: ErrorContext,
+        #[source]
+        source: Box<DiskAnalyzerError>,
+    },
+}
+
+/// 错误发生时的上下文：操作、阶段与路径，均可选
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub operation: Option<String>,
+    pub phase: Option<String>,
+    pub path: Option<String>,
+}
+
+impl ErrorContext {
+    pub fn new(operation: impl Into<String>) -> Self {
+        Self {
+            operation: Some(operation.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn phase(mut self, phase: impl Into<String>) -> Self {
+        self.phase = Some(phase.into());
+        self
+    }
+
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    /// 如 `scan: during tree build at path C:\X`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(phase) = &self.phase {
+            parts.push(format!("during {}", phase));
+        }
+        if let Some(path) = &self.path {
+            parts.push(format!("at path {}", path));
+        }
+        match &self.operation {
+            Some(op) if parts.is_empty() => write!(f, "{}", op),
+            Some(op) => write!(f, "{}: {}", op, parts.join(" ")),
+            None => write!(f, "{}", parts.join(" ")),
+        }
+    }
+}
+
+impl DiskAnalyzerError {
+    /// 错误类别的稳定名称（不含路径等细节），用于遥测统计；带上下文时取内层错误的类别
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DiskAnalyzerError::Io(_) => "io",
+            DiskAnalyzerError::PermissionDenied(_) => "permission_denied",
+            DiskAnalyzerError::InvalidPath(_) => "invalid_path",
+            DiskAnalyzerError::Config(_) => "config",
+            DiskAnalyzerError::Cancelled => "cancelled",
+            DiskAnalyzerError::NeedsElevation(_) => "needs_elevation",
+            DiskAnalyzerError::VolumeNotNtfs(_) => "volume_not_ntfs",
+            DiskAnalyzerError::MftReadFailed(_) => "mft_read_failed",
+            DiskAnalyzerError::WithContext { source, .. } => source.kind(),
+        }
+    }
+
+    /// 附加上下文，可链式多次调用（外层上下文在后）
+    pub fn context(self, context: ErrorContext) -> Self {
+        DiskAnalyzerError::WithContext {
+            context,
+            source: Box::new(self),
+        }
+    }
+
+    /// 去掉所有上下文后的原始错误
+    pub fn root_cause(&self) -> &DiskAnalyzerError {
+        match self {
+            DiskAnalyzerError::WithContext { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+
+    /// 由内到外的全部上下文
+    pub fn contexts(&self) -> Vec<&ErrorContext> {
+        match self {
+            DiskAnalyzerError::WithContext { context, source } => {
+                let mut all = source.contexts();
+                all.push(context);
+                all
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// 多行诊断文本，供用户复制到问题反馈中
+    pub fn diagnostic(&self) -> String {
+        let mut out = format!("error: {}", self.root_cause());
+        for context in self.contexts() {
+            out.push_str(&format!("\n  {}", context));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_is_attached_and_displayed() {
+        let err = DiskAnalyzerError::Io(std::io::Error::other("bad sector"))
+            .context(ErrorContext::new("scan").phase("tree build").path(r"C:\X"))
+            .context(ErrorContext::new("scan_path_command"));
+
+        assert_eq!(
+            err.to_string(),
+            r"IO error: bad sector [scan: during tree build at path C:\X] [scan_path_command]"
+        );
+        assert!(matches!(err.root_cause(), DiskAnalyzerError::Io(_)));
+        assert_eq!(err.contexts()[0].path.as_deref(), Some(r"C:\X"));
+        assert_eq!(
+            err.diagnostic(),
+            "error: IO error: bad sector\n  scan: during tree build at path C:\\X\n  scan_path_command"
+        );
+    }
+
+    #[test]
+    fn test_mft_variants_kind_and_display() {
+        let err = DiskAnalyzerError::NeedsElevation(r"\\.\C:".to_string())
+            .context(ErrorContext::new("scan").phase("read mft"));
+        assert_eq!(err.kind(), "needs_elevation");
+        assert!(matches!(
+            err.root_cause(),
+            DiskAnalyzerError::NeedsElevation(_)
+        ));
+        assert_eq!(
+            DiskAnalyzerError::VolumeNotNtfs(r"E:\ (exFAT)".to_string()).to_string(),
+            r"Volume is not NTFS: E:\ (exFAT)"
+        );
+        assert_eq!(
+            DiskAnalyzerError::MftReadFailed("bad record".to_string()).kind(),
+            "mft_read_failed"
+        );
+    }
 }
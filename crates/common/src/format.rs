@@ -0,0 +1,14 @@
+//! 面向用户的数值格式化
+
+/// 格式化字节为可读字符串（1024 进制，保留两位小数）
+pub fn format_bytes(n: u64) -> String {
+    if n >= 1024 * 1024 * 1024 {
+        format!("{:.2} GiB", n as f64 / (1024f64.powi(3)))
+    } else if n >= 1024 * 1024 {
+        format!("{:.2} MiB", n as f64 / (1024f64.powi(2)))
+    } else if n >= 1024 {
+        format!("{:.2} KiB", n as f64 / 1024f64)
+    } else {
+        format!("{} B", n)
+    }
+}
@@ -1,7 +1,15 @@
 pub mod config;
+pub mod config_watch;
 pub mod error;
+pub mod format;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod path;
+pub mod routing;
 pub mod telemetry;
 
 pub use config::*;
+pub use config_watch::{ConfigWatcher, SharedConfig};
 pub use error::*;
+pub use routing::{category_names, file_category, file_extension, CloudRouting, RoutingRule};
 pub use telemetry::*;
@@ -0,0 +1,96 @@
+//! OpenTelemetry 桥接（`otel` feature）：把各 crate 通过 `tracing` 产生的 span
+//! （扫描阶段、LLM 调用、清理执行，见 `telemetry` 模块）经 OTLP/HTTP 导出到 Jaeger/Tempo 等后端。
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider, SpanExporter};
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+use crate::{DiskAnalyzerError, OtelConfig};
+
+/// 持有 TracerProvider；drop 时刷新并关闭导出器，应在进程退出前保持存活
+pub struct OtelGuard {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        let _ = self.provider.shutdown();
+    }
+}
+
+/// 按配置（service.name、采样率）构建 TracerProvider，exporter 可为 OTLP 或测试用的内存 exporter
+pub fn build_provider<E: SpanExporter + 'static>(
+    config: &OtelConfig,
+    exporter: E,
+) -> SdkTracerProvider {
+    let ratio = config.sampling_ratio.clamp(0.0, 1.0);
+    SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build()
+}
+
+/// 构建把 `tracing` span 转发到给定 provider 的 subscriber
+pub fn subscriber(provider: &SdkTracerProvider) -> impl tracing::Subscriber + Send + Sync {
+    let tracer = provider.tracer("disk-rookie");
+    Registry::default().with(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// 初始化 OTLP 导出并安装为全局 subscriber；返回的 guard 需保持到进程退出
+pub fn init_otel(config: &OtelConfig) -> Result<OtelGuard, DiskAnalyzerError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(config.endpoint.clone())
+        .build()
+        .map_err(|e| DiskAnalyzerError::Config(format!("OTLP exporter: {}", e)))?;
+    let provider = build_provider(config, exporter);
+    tracing::subscriber::set_global_default(subscriber(&provider))
+        .map_err(|e| DiskAnalyzerError::Config(format!("tracing subscriber: {}", e)))?;
+    Ok(OtelGuard { provider })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{execute_span, mft_scan_span};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SpanData};
+
+    fn attr(span: &SpanData, key: &str) -> Option<String> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.as_str().into_owned())
+    }
+
+    #[test]
+    fn test_spans_exported_with_attributes() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = build_provider(&OtelConfig::default(), exporter.clone());
+        tracing::subscriber::with_default(subscriber(&provider), || {
+            let span = mft_scan_span("C:");
+            span.record("record_count", 1_234u64);
+            drop(span);
+            let span = execute_span("delete", r"C:\Temp\big.iso");
+            span.record("bytes_freed", 4_096u64);
+        });
+        provider.force_flush().unwrap();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let mft = spans.iter().find(|s| s.name == "scan.mft").unwrap();
+        assert_eq!(attr(mft, "drive").as_deref(), Some("C:"));
+        assert_eq!(attr(mft, "record_count").as_deref(), Some("1234"));
+        let exec = spans.iter().find(|s| s.name == "execute").unwrap();
+        assert_eq!(attr(exec, "action").as_deref(), Some("delete"));
+        assert_eq!(attr(exec, "bytes_freed").as_deref(), Some("4096"));
+    }
+}
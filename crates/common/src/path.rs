@@ -0,0 +1,269 @@
+//! 路径分段：同时识别 `/` 与 `\` 分隔符，正确处理卷根（`C:`、`\\?\C:`）、UNC 根（`\\server\share`）
+//! 与尾部分隔符，供各扫描器构建节点名称使用，不依赖当前平台的 `std::path` 语义。
+//! 路径比较按文件系统的大小写规则进行：Linux 上 `Foo` 与 `foo` 是两个文件，Windows/macOS（默认 APFS）上是同一个。
+
+use std::path::{Path, PathBuf};
+
+/// 文件名比较是否区分大小写
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    Sensitive,
+    Insensitive,
+}
+
+impl CaseSensitivity {
+    /// 当前平台常见文件系统的默认规则：Windows 与 macOS 不区分，其余区分
+    pub const fn native() -> Self {
+        if cfg!(any(windows, target_os = "macos")) {
+            CaseSensitivity::Insensitive
+        } else {
+            CaseSensitivity::Sensitive
+        }
+    }
+
+    /// 实测 `dir` 所在文件系统：创建一个小写名的探测文件，再看大写名能否访问到它
+    pub fn detect(dir: &Path) -> std::io::Result<Self> {
+        let probe = dir.join(format!(".case-probe-{}", std::process::id()));
+        std::fs::write(&probe, b"")?;
+        let upper = dir.join(format!(".CASE-PROBE-{}", std::process::id()));
+        let insensitive = upper.exists();
+        std::fs::remove_file(&probe)?;
+        Ok(if insensitive {
+            CaseSensitivity::Insensitive
+        } else {
+            CaseSensitivity::Sensitive
+        })
+    }
+}
+
+/// 按给定规则比较两个名称
+pub fn names_equal(a: &str, b: &str, case: CaseSensitivity) -> bool {
+    match case {
+        CaseSensitivity::Sensitive => a == b,
+        CaseSensitivity::Insensitive => a.to_lowercase() == b.to_lowercase(),
+    }
+}
+
+/// `path` 是否等于 `dir` 或位于其下（按路径分段比较，`/` 与 `\` 等价）
+pub fn is_under(path: &str, dir: &str, case: CaseSensitivity) -> bool {
+    let path = components(path);
+    let dir = components(dir);
+    dir.len() <= path.len() && dir.iter().zip(&path).all(|(d, p)| names_equal(d, p, case))
+}
+
+fn is_sep(b: u8) -> bool {
+    b == b'/' || b == b'\\'
+}
+
+/// 从 `start` 起跳过 `n` 个非空分段，返回结束位置（不含其后的分隔符）
+fn skip_segments(path: &str, start: usize, n: usize) -> usize {
+    let bytes = path.as_bytes();
+    let mut i = start;
+    for _ in 0..n {
+        while i < bytes.len() && is_sep(bytes[i]) {
+            i += 1;
+        }
+        while i < bytes.len() && !is_sep(bytes[i]) {
+            i += 1;
+        }
+    }
+    i
+}
+
+/// 拆出路径的根：盘符 `C:`、设备路径 `\\?\C:` / `\\?\Volume{..}`、UNC `\\server\share`
+/// （含 `\\?\UNC\server\share`）。Unix 根 `/` 不作为分段。
+fn split_root(path: &str) -> (Option<&str>, &str) {
+    let bytes = path.as_bytes();
+    let end = if bytes.len() >= 4
+        && is_sep(bytes[0])
+        && is_sep(bytes[1])
+        && (bytes[2] == b'?' || bytes[2] == b'.')
+        && is_sep(bytes[3])
+    {
+        let is_unc =
+            bytes.len() >= 8 && bytes[4..7].eq_ignore_ascii_case(b"UNC") && is_sep(bytes[7]);
+        skip_segments(path, 4, if is_unc { 3 } else { 1 })
+    } else if bytes.len() >= 3 && is_sep(bytes[0]) && is_sep(bytes[1]) && !is_sep(bytes[2]) {
+        skip_segments(path, 2, 2)
+    } else if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        2
+    } else {
+        return (None, path);
+    };
+    (Some(&path[..end]), &path[end..])
+}
+
+/// 路径的各个分段（根作为第一段，忽略空段与尾部分隔符），如：
+/// `C:\Users\u\` -> `["C:", "Users", "u"]`，`/home/u` -> `["home", "u"]`，
+/// `\\server\share\a` -> `["\\server\share", "a"]`
+pub fn components(path: &str) -> Vec<&str> {
+    let (root, rest) = split_root(path);
+    root.into_iter()
+        .chain(rest.split(['/', '\\']).filter(|s| !s.is_empty()))
+        .collect()
+}
+
+/// 路径的根（盘符、设备路径或 UNC 根），Unix 路径返回 None
+pub fn root(path: &str) -> Option<&str> {
+    split_root(path).0
+}
+
+/// 路径的最后一段，用作节点显示名；卷根/UNC 根返回根本身，`/` 等无分段的路径原样返回
+pub fn file_name(path: &str) -> &str {
+    components(path).last().copied().unwrap_or(path)
+}
+
+/// 含未配对代理项的 UTF-16 路径（NTFS 允许）转成字符串时会被替换为 U+FFFD，替换后的路径无法再用于操作文件。
+/// `original` 不是合法 UTF-16 时，返回与显示路径 `display`（由 `original` 有损转换并规范化前缀、
+/// 分隔符得到）对应的原始 UTF-16 路径；合法时返回 None。
+/// 规范化只改动前缀与分隔符，因此按 UTF-16 单元从末尾对齐即可把替换字符还原
+pub fn restore_unpaired_surrogates(display: &str, original: &[u16]) -> Option<Vec<u16>> {
+    if String::from_utf16(original).is_ok() {
+        return None;
+    }
+    let is_sep = |u: u16| u == u16::from(b'\\') || u == u16::from(b'/');
+    let end = original
+        .iter()
+        .rposition(|&u| !is_sep(u))
+        .map_or(0, |i| i + 1);
+    let mut wide: Vec<u16> = display.encode_utf16().collect();
+    for (w, &o) in wide.iter_mut().rev().zip(original[..end].iter().rev()) {
+        if *w == 0xFFFD && (0xD800..=0xDFFF).contains(&o) {
+            *w = o;
+        }
+    }
+    Some(wide)
+}
+
+/// 操作文件时使用的路径：有原始 UTF-16 路径（见 `restore_unpaired_surrogates`）时在 Windows 上按原样还原，
+/// 否则使用显示路径
+pub fn fs_path(display: &str, raw: Option<&[u16]>) -> PathBuf {
+    #[cfg(windows)]
+    if let Some(raw) = raw {
+        use std::os::windows::ffi::OsStringExt;
+        return PathBuf::from(std::ffi::OsString::from_wide(raw));
+    }
+    #[cfg(not(windows))]
+    let _ = raw;
+    PathBuf::from(display)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix_paths() {
+        assert_eq!(components("/home/u/a.txt"), ["home", "u", "a.txt"]);
+        assert_eq!(file_name("/home/u/a.txt"), "a.txt");
+        assert_eq!(file_name("/home/u/"), "u");
+        assert_eq!(file_name("relative/dir"), "dir");
+        assert_eq!(file_name("/"), "/");
+    }
+
+    #[test]
+    fn test_windows_paths() {
+        assert_eq!(components(r"C:\Users\u\"), ["C:", "Users", "u"]);
+        assert_eq!(file_name(r"C:\Users\u\a.txt"), "a.txt");
+        assert_eq!(file_name(r"C:\Users\u\\"), "u");
+        assert_eq!(file_name(r"C:\"), "C:");
+        assert_eq!(file_name("C:/Program Files/x"), "x");
+        assert_eq!(components(r"\\?\C:\Windows"), [r"\\?\C:", "Windows"]);
+        assert_eq!(
+            file_name(r"\\?\Volume{0b1c2d3e-4f50-6172-8394-a5b6c7d8e9f0}\"),
+            r"\\?\Volume{0b1c2d3e-4f50-6172-8394-a5b6c7d8e9f0}"
+        );
+    }
+
+    #[test]
+    fn test_is_under_respects_case_sensitivity() {
+        use CaseSensitivity::*;
+        assert!(is_under("/home/u/Foo/a.txt", "/home/u/Foo", Sensitive));
+        assert!(!is_under("/home/u/foo/a.txt", "/home/u/Foo", Sensitive));
+        assert!(is_under("/home/u/foo/a.txt", "/home/u/Foo/", Insensitive));
+        assert!(!is_under("/home/u/Foobar", "/home/u/Foo", Insensitive));
+        assert!(is_under(r"C:\Users\U\x", "c:/users/u", Insensitive));
+        assert!(!names_equal("Foo", "foo", Sensitive));
+        assert!(names_equal("Foo", "foo", Insensitive));
+    }
+
+    #[test]
+    fn test_detect_case_sensitive_temp_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let case = CaseSensitivity::detect(dir.path()).unwrap();
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        if case == CaseSensitivity::Insensitive {
+            return;
+        }
+        // 区分大小写的文件系统上 Foo 与 foo 是两个独立文件
+        std::fs::write(dir.path().join("Foo"), b"1").unwrap();
+        std::fs::write(dir.path().join("foo"), b"22").unwrap();
+        let names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names.len(), 2);
+        let foo = dir.path().join("foo").to_string_lossy().to_string();
+        let upper = dir.path().join("Foo").to_string_lossy().to_string();
+        assert!(!is_under(&foo, &upper, case));
+    }
+
+    #[test]
+    fn test_unc_paths() {
+        assert_eq!(
+            components(r"\\server\share\dir\f.txt"),
+            [r"\\server\share", "dir", "f.txt"]
+        );
+        assert_eq!(file_name(r"\\server\share\"), r"\\server\share");
+        assert_eq!(file_name(r"\\server\share\dir\"), "dir");
+        assert_eq!(
+            components(r"\\?\UNC\server\share\dir"),
+            [r"\\?\UNC\server\share", "dir"]
+        );
+    }
+
+    #[test]
+    fn test_restore_unpaired_surrogates_after_normalization() {
+        // \\?\C:\dl\a<D800>b.txt\ 有损转换并规范化为 C:\dl\a\u{FFFD}b.txt
+        let mut original: Vec<u16> = r"\\?\C:\dl\a".encode_utf16().collect();
+        original.push(0xD800);
+        original.extend(r"b.txt\".encode_utf16());
+        let lossy = String::from_utf16_lossy(&original);
+        assert_eq!(lossy, "\\\\?\\C:\\dl\\a\u{FFFD}b.txt\\");
+        let display = "C:\\dl\\a\u{FFFD}b.txt";
+
+        let raw = restore_unpaired_surrogates(display, &original).unwrap();
+        let mut expected: Vec<u16> = r"C:\dl\a".encode_utf16().collect();
+        expected.push(0xD800);
+        expected.extend("b.txt".encode_utf16());
+        assert_eq!(raw, expected);
+        // 合法的文件名（包括本身含 U+FFFD 的）不保留原始路径
+        let valid: Vec<u16> = "C:\\dl\\\u{FFFD}.txt".encode_utf16().collect();
+        assert_eq!(
+            restore_unpaired_surrogates("C:\\dl\\\u{FFFD}.txt", &valid),
+            None
+        );
+        #[cfg(not(windows))]
+        assert_eq!(fs_path(display, Some(&raw)), PathBuf::from(display));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_fs_path_reaches_file_with_unpaired_surrogate() {
+        use std::os::windows::ffi::{OsStrExt, OsStringExt};
+        let dir = tempfile::tempdir().unwrap();
+        let mut name: Vec<u16> = "a".encode_utf16().collect();
+        name.push(0xDC00);
+        let file = dir.path().join(std::ffi::OsString::from_wide(&name));
+        std::fs::write(&file, b"x").unwrap();
+        let original: Vec<u16> = file.as_os_str().encode_wide().collect();
+        let display = file.to_string_lossy().into_owned();
+        assert!(!Path::new(&display).exists());
+
+        let raw = restore_unpaired_surrogates(&display, &original).unwrap();
+        let real = fs_path(&display, Some(&raw));
+        assert_eq!(real, file);
+        std::fs::remove_file(&real).unwrap();
+        assert!(!file.exists());
+    }
+}
@@ -0,0 +1,194 @@
+//! 云存储目标路由：按扩展名或文件类别把文件分派到不同的云存储目标（如视频传到便宜的 S3 桶、
+//! 文档传到 Google Drive），未命中任何规则时使用默认目标。
+//!
+//! ```toml
+//! [cloud_routing]
+//! default_target = "Drive"
+//!
+//! [[cloud_routing.rules]]
+//! category = "video"
+//! target = "Cold S3"
+//!
+//! [[cloud_routing.rules]]
+//! extensions = ["psd", ".kra"]
+//! target = "Drive"
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::path;
+
+/// 内置文件类别及其扩展名（小写、不带点）
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "video",
+        &[
+            "mp4", "mkv", "mov", "avi", "wmv", "flv", "webm", "m4v", "mpg", "mpeg", "ts",
+        ],
+    ),
+    (
+        "image",
+        &[
+            "jpg", "jpeg", "png", "gif", "bmp", "webp", "heic", "tif", "tiff", "raw", "cr2", "nef",
+            "arw", "dng",
+        ],
+    ),
+    (
+        "audio",
+        &["mp3", "flac", "wav", "aac", "ogg", "m4a", "wma", "opus"],
+    ),
+    (
+        "document",
+        &[
+            "doc", "docx", "xls", "xlsx", "ppt", "pptx", "pdf", "txt", "md", "odt", "ods", "odp",
+            "rtf", "csv", "epub",
+        ],
+    ),
+    (
+        "archive",
+        &["zip", "rar", "7z", "tar", "gz", "bz2", "xz", "zst", "iso"],
+    ),
+];
+
+/// 内置类别名称，按 `file_category` 的匹配顺序
+pub fn category_names() -> impl Iterator<Item = &'static str> {
+    CATEGORIES.iter().map(|(category, _)| *category)
+}
+
+/// 路径所属的内置类别（`video`、`image`、`audio`、`document`、`archive`），按扩展名判断
+pub fn file_category(file: &str) -> Option<&'static str> {
+    let ext = file_extension(file)?;
+    CATEGORIES
+        .iter()
+        .find(|(_, exts)| exts.contains(&ext.as_str()))
+        .map(|(category, _)| *category)
+}
+
+/// 小写、不带点的扩展名；隐藏文件（如 `.bashrc`）没有扩展名
+pub fn file_extension(file: &str) -> Option<String> {
+    let name = path::file_name(file);
+    let (stem, ext) = name.rsplit_once('.')?;
+    (!stem.is_empty() && !ext.is_empty()).then(|| ext.to_ascii_lowercase())
+}
+
+/// 一条路由规则：扩展名或类别命中其一即分派到 `target`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingRule {
+    /// 扩展名，大小写与前导点均可忽略
+    pub extensions: Vec<String>,
+    /// 内置类别，见 `file_category`
+    pub category: Option<String>,
+    /// 云存储目标的名称（与上传配置中的 `name` 对应）
+    pub target: String,
+}
+
+impl RoutingRule {
+    /// 规则中的配置问题，`at` 为规则在配置中的位置（如 `cloud_routing.rules[0]`）
+    pub(crate) fn problems(&self, at: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.target.trim().is_empty() {
+            problems.push(format!("{}.target is empty", at));
+        }
+        if self.extensions.is_empty() && self.category.is_none() {
+            problems.push(format!(
+                "{} matches no file; set extensions or category",
+                at
+            ));
+        }
+        if self
+            .extensions
+            .iter()
+            .any(|e| e.trim_start_matches('.').is_empty())
+        {
+            problems.push(format!("{}.extensions contains an empty entry", at));
+        }
+        if let Some(category) = &self.category {
+            if !category_names().any(|c| c.eq_ignore_ascii_case(category)) {
+                problems.push(format!(
+                    "unknown {}.category '{}'; expected one of {}",
+                    at,
+                    category,
+                    category_names().collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+        problems
+    }
+
+    fn matches(&self, ext: Option<&str>, category: Option<&str>) -> bool {
+        let by_ext = ext.is_some_and(|ext| {
+            self.extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(ext))
+        });
+        let by_category = category.is_some_and(|c| {
+            self.category
+                .as_deref()
+                .is_some_and(|rule| rule.eq_ignore_ascii_case(c))
+        });
+        by_ext || by_category
+    }
+}
+
+/// 云存储目标路由配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudRouting {
+    /// 按顺序匹配，先命中者生效
+    pub rules: Vec<RoutingRule>,
+    /// 未命中任何规则时的目标
+    pub default_target: Option<String>,
+}
+
+impl CloudRouting {
+    /// 是否配置了任何路由
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.default_target.is_none()
+    }
+
+    /// 文件应上传到的目标名称；未配置路由或未命中且无默认目标时为 None
+    pub fn route(&self, file: &str) -> Option<&str> {
+        let ext = file_extension(file);
+        let category = file_category(file);
+        self.rules
+            .iter()
+            .find(|r| r.matches(ext.as_deref(), category))
+            .map(|r| r.target.as_str())
+            .or(self.default_target.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_by_category_extension_and_default() {
+        let routing: CloudRouting = toml::from_str(
+            r#"
+            default_target = "Drive"
+
+            [[rules]]
+            extensions = [".PSD"]
+            target = "Design"
+
+            [[rules]]
+            category = "video"
+            target = "Cold S3"
+
+            [[rules]]
+            category = "document"
+            target = "Drive Docs"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(routing.route(r"D:\Videos\trip.MP4"), Some("Cold S3"));
+        assert_eq!(routing.route("/home/u/report.docx"), Some("Drive Docs"));
+        assert_eq!(routing.route("/home/u/cover.psd"), Some("Design"));
+        assert_eq!(routing.route("/home/u/backup.bin"), Some("Drive"));
+        assert_eq!(routing.route("/home/u/.mp4"), Some("Drive"));
+        assert_eq!(CloudRouting::default().route("/a/b.mp4"), None);
+    }
+}
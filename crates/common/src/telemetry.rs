@@ -1,4 +1,152 @@
-/// 遥测与日志（预留）
-pub fn init_telemetry() {
-    // TODO: 初始化日志和遥测
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde::Serialize;
+use tracing::field::Empty;
+use tracing::Span;
+
+use crate::AppConfig;
+
+/// 上报给遥测接收端的事件。只含统计数据，不含路径等用户信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    /// 一次扫描完成；`error_count` 为因权限被拒与被跳过的路径数
+    ScanCompleted {
+        strategy: &'static str,
+        duration_ms: u64,
+        file_count: u64,
+        error_count: u64,
+    },
+    /// 扫描失败（用户取消不计），`error` 为 `DiskAnalyzerError::kind`
+    ScanFailed {
+        duration_ms: u64,
+        error: &'static str,
+    },
+}
+
+/// 遥测事件的去向，由嵌入方实现（如转发到自己的统计服务）
+pub trait TelemetrySink: Send + Sync {
+    fn record_event(&self, event: TelemetryEvent);
+}
+
+/// 丢弃所有事件，`telemetry_enabled = false` 时使用
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopSink;
+
+impl TelemetrySink for NoopSink {
+    fn record_event(&self, _event: TelemetryEvent) {}
+}
+
+/// 把事件写入日志（`tracing` 的 `telemetry` target），默认的接收端
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogSink;
+
+impl TelemetrySink for LogSink {
+    fn record_event(&self, event: TelemetryEvent) {
+        tracing::info!(target: "telemetry", ?event);
+    }
+}
+
+fn global_sink() -> &'static RwLock<Arc<dyn TelemetrySink>> {
+    static SINK: OnceLock<RwLock<Arc<dyn TelemetrySink>>> = OnceLock::new();
+    SINK.get_or_init(|| RwLock::new(Arc::new(LogSink)))
+}
+
+/// 替换全局接收端，之后的事件都交给 `sink`
+pub fn set_sink(sink: Arc<dyn TelemetrySink>) {
+    *global_sink().write().unwrap_or_else(|e| e.into_inner()) = sink;
+}
+
+/// 把事件交给当前的全局接收端
+pub fn record_event(event: TelemetryEvent) {
+    let sink = global_sink()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    sink.record_event(event);
+}
+
+/// 按配置安装接收端：`telemetry_enabled = false` 时安装 `NoopSink`，否则为 `LogSink`
+pub fn init_telemetry(config: &AppConfig) {
+    if config.telemetry_enabled() {
+        set_sink(Arc::new(LogSink));
+    } else {
+        set_sink(Arc::new(NoopSink));
+    }
+}
+
+// 以下 span 构造函数集中定义各 crate 共用的 span 名称与属性，便于导出端（OTLP）统一检索。
+// 未安装 tracing subscriber 时（未启用 `otel` feature）这些 span 为禁用状态，几乎无开销。
+
+/// 一次完整扫描；结束时记录 `strategy`（"mft" / "walk"）、`file_count`、`total_size`
+pub fn scan_span(root: &str) -> Span {
+    tracing::info_span!(
+        "scan",
+        root = root,
+        strategy = Empty,
+        file_count = Empty,
+        total_size = Empty
+    )
+}
+
+/// MFT 全量扫描；枚举完成后记录 `record_count`
+pub fn mft_scan_span(drive: &str) -> Span {
+    tracing::info_span!("scan.mft", drive = drive, record_count = Empty)
+}
+
+/// 一次 LLM 调用（如生成清理计划）
+pub fn llm_span(operation: &str) -> Span {
+    tracing::info_span!("llm", operation = operation)
+}
+
+/// 执行单个清理动作；完成后记录 `bytes_freed`
+pub fn execute_span(action: &str, path: &str) -> Span {
+    tracing::info_span!("execute", action = action, path = path, bytes_freed = Empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct CaptureSink(Mutex<Vec<TelemetryEvent>>);
+
+    impl TelemetrySink for CaptureSink {
+        fn record_event(&self, event: TelemetryEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    fn failed(duration_ms: u64) -> TelemetryEvent {
+        TelemetryEvent::ScanFailed {
+            duration_ms,
+            error: "io",
+        }
+    }
+
+    #[test]
+    fn test_global_sink_is_swappable_and_opt_out_records_nothing() {
+        let capture = Arc::new(CaptureSink::default());
+        set_sink(capture.clone());
+        record_event(failed(1));
+        assert_eq!(*capture.0.lock().unwrap(), [failed(1)]);
+
+        // 关闭遥测后换成 NoopSink，之前的接收端不再收到事件
+        let config: AppConfig = toml::from_str("telemetry_enabled = false").unwrap();
+        init_telemetry(&config);
+        record_event(failed(2));
+        assert_eq!(capture.0.lock().unwrap().len(), 1);
+
+        init_telemetry(&AppConfig::default());
+        set_sink(capture.clone());
+        let completed = TelemetryEvent::ScanCompleted {
+            strategy: "walk",
+            duration_ms: 12,
+            file_count: 3,
+            error_count: 1,
+        };
+        record_event(completed.clone());
+        assert_eq!(*capture.0.lock().unwrap(), [failed(1), completed]);
+    }
 }
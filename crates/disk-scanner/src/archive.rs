@@ -0,0 +1,317 @@
+//! 压缩包内容摘要：扫描时（`ScanOptions::peek_archives`）只读取 zip 的中央目录或 tar 的文件头，
+//! 得出条目数与解压后大小，不解压任何数据。读取量与条目数都有上限，格式异常或超限时返回 None。
+//! 7z 的文件头通常经 LZMA 压缩，需要解压才能读取，因此不在支持范围内；tar.gz 等整体压缩的格式同理。
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use ai_disk_domain::ArchiveInfo;
+
+/// 最多统计的条目数，超出视为无法摘要
+const MAX_ENTRIES: u64 = 200_000;
+/// zip 中央目录最多读取的字节数
+const MAX_CENTRAL_DIR_BYTES: u64 = 64 * 1024 * 1024;
+/// tar 的 pax 扩展头最多读取的字节数
+const MAX_PAX_BYTES: u64 = 64 * 1024;
+
+const ZIP_EOCD_SIG: u32 = 0x0605_4b50;
+const ZIP64_LOCATOR_SIG: u32 = 0x0706_4b50;
+const ZIP64_EOCD_SIG: u32 = 0x0606_4b50;
+const ZIP_CENTRAL_SIG: u32 = 0x0201_4b50;
+const ZIP_EOCD_LEN: usize = 22;
+const ZIP_CENTRAL_LEN: usize = 46;
+const TAR_BLOCK: u64 = 512;
+
+/// 按扩展名（不区分大小写）识别 zip/tar 并读取内容摘要；其他格式或解析失败时返回 None
+pub fn peek_archive(path: &Path) -> Option<ArchiveInfo> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mut file = File::open(path).ok()?;
+    match ext.as_str() {
+        "zip" => peek_zip(&mut file),
+        "tar" => peek_tar(&mut file),
+        _ => None,
+    }
+}
+
+fn u16_at(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u16::from_le_bytes(buf.get(at..at + 2)?.try_into().ok()?).into())
+}
+
+fn u32_at(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?).into())
+}
+
+fn u64_at(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> Option<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+/// 从文件尾部找到中央目录结尾记录（必要时跟随 zip64 定位记录），再逐条读取中央目录
+fn peek_zip<R: Read + Seek>(reader: &mut R) -> Option<ArchiveInfo> {
+    let file_len = reader.seek(SeekFrom::End(0)).ok()?;
+    // 结尾记录之后最多跟 65535 字节注释
+    let tail_len = file_len.min((ZIP_EOCD_LEN + u16::MAX as usize) as u64);
+    let tail = read_at(reader, file_len - tail_len, tail_len as usize)?;
+    let eocd = (0..=tail.len().checked_sub(ZIP_EOCD_LEN)?)
+        .rev()
+        .find(|&i| u32_at(&tail, i) == Some(ZIP_EOCD_SIG.into()))?;
+    let mut entries = u16_at(&tail, eocd + 10)?;
+    let mut cd_size = u32_at(&tail, eocd + 12)?;
+    let mut cd_offset = u32_at(&tail, eocd + 16)?;
+
+    let eocd_pos = file_len - tail_len + eocd as u64;
+    if entries == 0xFFFF || cd_size == 0xFFFF_FFFF || cd_offset == 0xFFFF_FFFF {
+        let locator = read_at(reader, eocd_pos.checked_sub(20)?, 20)?;
+        if u32_at(&locator, 0)? != u64::from(ZIP64_LOCATOR_SIG) {
+            return None;
+        }
+        let record = read_at(reader, u64_at(&locator, 8)?, 56)?;
+        if u32_at(&record, 0)? != u64::from(ZIP64_EOCD_SIG) {
+            return None;
+        }
+        entries = u64_at(&record, 32)?;
+        cd_size = u64_at(&record, 40)?;
+        cd_offset = u64_at(&record, 48)?;
+    }
+    if entries > MAX_ENTRIES
+        || cd_size > MAX_CENTRAL_DIR_BYTES
+        || cd_offset.checked_add(cd_size)? > file_len
+    {
+        return None;
+    }
+
+    let cd = read_at(reader, cd_offset, cd_size as usize)?;
+    let mut pos = 0usize;
+    let mut uncompressed_size = 0u64;
+    for _ in 0..entries {
+        if u32_at(&cd, pos)? != u64::from(ZIP_CENTRAL_SIG) {
+            return None;
+        }
+        let name_len = u16_at(&cd, pos + 28)? as usize;
+        let extra_len = u16_at(&cd, pos + 30)? as usize;
+        let comment_len = u16_at(&cd, pos + 32)? as usize;
+        let mut size = u32_at(&cd, pos + 24)?;
+        if size == 0xFFFF_FFFF {
+            let extra_start = pos + ZIP_CENTRAL_LEN + name_len;
+            size = zip64_uncompressed(cd.get(extra_start..extra_start + extra_len)?)?;
+        }
+        uncompressed_size = uncompressed_size.saturating_add(size);
+        pos += ZIP_CENTRAL_LEN + name_len + extra_len + comment_len;
+    }
+    Some(ArchiveInfo {
+        entries,
+        uncompressed_size,
+    })
+}
+
+/// zip64 扩展字段（id 0x0001）中的解压后大小，位于该字段的第一个值
+fn zip64_uncompressed(extra: &[u8]) -> Option<u64> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let id = u16_at(extra, pos)?;
+        let len = u16_at(extra, pos + 2)? as usize;
+        if id == 0x0001 {
+            return u64_at(extra, pos + 4);
+        }
+        pos += 4 + len;
+    }
+    None
+}
+
+/// 解析 tar 头中的数字字段：八进制文本，或 GNU 的 base-256（首字节最高位为 1）
+fn tar_number(field: &[u8]) -> Option<u64> {
+    if field.first().is_some_and(|b| b & 0x80 != 0) {
+        return field[1..]
+            .iter()
+            .try_fold(u64::from(field[0] & 0x7f), |acc, &b| {
+                acc.checked_mul(256).map(|v| v | u64::from(b))
+            });
+    }
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// 文件头校验和：校验和字段本身按空格计算
+fn tar_checksum_ok(header: &[u8]) -> bool {
+    let Some(expected) = tar_number(&header[148..156]) else {
+        return false;
+    };
+    let sum: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (148..156).contains(&i) {
+                32
+            } else {
+                u64::from(b)
+            }
+        })
+        .sum();
+    sum == expected
+}
+
+/// pax 扩展头中的 `size=` 记录（用于超过 8 GiB 的文件）
+fn pax_size(data: &[u8]) -> Option<u64> {
+    let text = String::from_utf8_lossy(data);
+    text.lines().find_map(|line| {
+        let (_, record) = line.split_once(' ')?;
+        record.strip_prefix("size=")?.parse().ok()
+    })
+}
+
+/// 依次读取 512 字节的文件头并跳过数据块；GNU 长文件名与 pax 扩展头不计为条目
+fn peek_tar<R: Read + Seek>(reader: &mut R) -> Option<ArchiveInfo> {
+    let mut header = [0u8; TAR_BLOCK as usize];
+    let mut entries = 0u64;
+    let mut uncompressed_size = 0u64;
+    let mut next_size: Option<u64> = None;
+    reader.seek(SeekFrom::Start(0)).ok()?;
+    loop {
+        if reader.read_exact(&mut header).is_err() {
+            // 缺少结尾的全零块也按已读到的内容返回，但至少要有一个条目
+            break;
+        }
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if !tar_checksum_ok(&header) {
+            return None;
+        }
+        let header_size = tar_number(&header[124..136])?;
+        let size = next_size.take().unwrap_or(header_size);
+        let padded = size.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+        match header[156] {
+            b'x' if size <= MAX_PAX_BYTES => {
+                let mut data = vec![0u8; padded as usize];
+                reader.read_exact(&mut data).ok()?;
+                next_size = pax_size(&data[..size as usize]);
+                continue;
+            }
+            b'x' | b'g' | b'L' | b'K' => {}
+            kind => {
+                entries += 1;
+                if entries > MAX_ENTRIES {
+                    return None;
+                }
+                if matches!(kind, b'0' | b'\0' | b'7') {
+                    uncompressed_size = uncompressed_size.saturating_add(size);
+                }
+            }
+        }
+        reader.seek(SeekFrom::Current(padded as i64)).ok()?;
+    }
+    (entries > 0).then_some(ArchiveInfo {
+        entries,
+        uncompressed_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 以 store 方式（不压缩）写出 zip：本地文件头 + 数据，随后是中央目录与结尾记录
+    fn stored_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in files {
+            let offset = out.len() as u32;
+            let size = data.len() as u32;
+            out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            out.extend_from_slice(&0u32.to_le_bytes()); // crc 不参与解析
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            central.extend_from_slice(&ZIP_CENTRAL_SIG.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            central.extend_from_slice(&0u32.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&ZIP_EOCD_SIG.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    fn tar_header(name: &str, size: u64, kind: u8) -> [u8; 512] {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+        header
+    }
+
+    #[test]
+    fn test_peek_zip_and_tar_report_entries_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip = dir.path().join("Backup.ZIP");
+        std::fs::write(
+            &zip,
+            stored_zip(&[
+                ("docs/", b""),
+                ("docs/a.txt", &[b'a'; 1000]),
+                ("b.bin", &[7; 2345]),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            peek_archive(&zip),
+            Some(ArchiveInfo {
+                entries: 3,
+                uncompressed_size: 3345,
+            })
+        );
+
+        let mut tar = Vec::new();
+        tar.extend_from_slice(&tar_header("logs/", 0, b'5'));
+        tar.extend_from_slice(&tar_header("logs/app.log", 700, b'0'));
+        tar.extend_from_slice(&[b'x'; 1024]);
+        tar.extend_from_slice(&[0; 1024]);
+        let tar_path = dir.path().join("logs.tar");
+        std::fs::write(&tar_path, &tar).unwrap();
+        assert_eq!(
+            peek_archive(&tar_path),
+            Some(ArchiveInfo {
+                entries: 2,
+                uncompressed_size: 700,
+            })
+        );
+
+        // 扩展名对但内容不是压缩包时跳过
+        let broken = dir.path().join("broken.zip");
+        std::fs::write(&broken, b"not a zip at all").unwrap();
+        assert_eq!(peek_archive(&broken), None);
+    }
+}
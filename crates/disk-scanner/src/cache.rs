@@ -0,0 +1,332 @@
+//! 扫描结果缓存：按卷序列号在磁盘上保存上次的 `ScanResult` 及扫描开始时 USN 日志的位置。
+//! 再次扫描同一卷时，若日志没有新记录（卷上没有任何变化），直接返回缓存的结果，免去重新读取整个 $MFT。
+//!
+//! 目前只要日志有新记录、日志不可用或序列号不符，一律完整重新扫描；按日志记录增量更新缓存的树留待后续实现。
+//! 缓存目录位于被扫描的卷上时，保存缓存本身就会产生日志记录，缓存永远无法命中，此时不读写缓存。
+
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::ScanResult;
+use serde::{Deserialize, Serialize};
+
+use crate::options::ScanOptions;
+use crate::scanner::{normalize_path, scan};
+use crate::volume::is_windows_volume_root;
+
+/// 缓存文件格式版本，不兼容时丢弃旧缓存
+const CACHE_VERSION: u32 = 1;
+
+/// USN 日志中的位置；日志被删除重建后 `journal_id` 改变，此前的位置失效
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalPosition {
+    pub journal_id: u64,
+    /// 下一条日志记录的 USN
+    pub next_usn: i64,
+}
+
+/// 卷的标识与变更位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeState {
+    pub serial: u64,
+    /// USN 日志未启用或无法查询（如未以管理员身份运行）时为 None
+    pub journal: Option<JournalPosition>,
+}
+
+/// 检查缓存的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    /// 卷自上次扫描后没有变化，使用缓存的结果
+    Hit,
+    /// 没有缓存，或缓存文件已损坏、版本不符
+    Missing,
+    /// 缓存属于另一个卷（如卷已重新格式化）
+    SerialMismatch,
+    /// 缓存的扫描根或影响树内容的选项（shallow 目录、过滤条件）与本次不同
+    OptionsChanged,
+    /// 无法获取卷序列号或 USN 日志位置
+    JournalUnavailable,
+    /// 日志有新记录或已重建
+    VolumeChanged,
+    /// 缓存目录位于被扫描的卷上，不使用缓存（见模块说明）
+    CacheOnScannedVolume,
+}
+
+/// 一个卷的缓存文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCache {
+    version: u32,
+    /// 扫描根（规范化后）
+    pub root: String,
+    /// 扫描开始时卷的状态
+    pub volume: VolumeState,
+    pub result: ScanResult,
+}
+
+impl ScanCache {
+    pub fn new(root: impl Into<String>, volume: VolumeState, result: ScanResult) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            root: root.into(),
+            volume,
+            result,
+        }
+    }
+
+    /// 读取缓存；文件不存在、损坏或版本不符时返回 None
+    pub fn load(path: &Path) -> Option<Self> {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ScanCache>(&bytes).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+    }
+
+    /// 先写临时文件再改名，避免中途退出留下半个文件
+    pub fn save(&self, path: &Path) -> Result<(), DiskAnalyzerError> {
+        let tmp = path.with_extension("tmp");
+        let json =
+            serde_json::to_vec(self).map_err(|e| DiskAnalyzerError::Config(e.to_string()))?;
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// 缓存能否代替以 `options` 重新扫描 `root`，`current` 为卷的当前状态
+    pub fn check(&self, root: &str, options: &ScanOptions, current: &VolumeState) -> CacheStatus {
+        if self.volume.serial != current.serial {
+            return CacheStatus::SerialMismatch;
+        }
+        let same_options = self.result.meta.as_ref().is_some_and(|m| {
+            m.shallow_dirs == options.shallow_dirs && m.filters_summary == options.filters_summary()
+        });
+        if self.root != root || !same_options {
+            return CacheStatus::OptionsChanged;
+        }
+        match (self.volume.journal, current.journal) {
+            (Some(cached), Some(now)) if cached == now => CacheStatus::Hit,
+            (Some(_), Some(_)) => CacheStatus::VolumeChanged,
+            _ => CacheStatus::JournalUnavailable,
+        }
+    }
+}
+
+/// `dir` 下序列号为 `serial` 的卷的缓存文件
+pub fn cache_file_path(dir: &Path, serial: u64) -> PathBuf {
+    dir.join(format!("scan_cache_{:016x}.json", serial))
+}
+
+/// `dir`（可以尚不存在，按其最近的已存在上级判断）是否位于 `volume_root`（已规范化）所在的卷上
+fn is_on_volume(dir: &Path, volume_root: &Path) -> bool {
+    dir.ancestors()
+        .find_map(|p| std::fs::canonicalize(p).ok())
+        .is_some_and(|p| p.starts_with(volume_root))
+}
+
+/// 卷根（如 `C:\`）的当前状态；非 Windows 或无法获取序列号时为 None
+pub fn volume_state(volume_root: &Path) -> Option<VolumeState> {
+    #[cfg(windows)]
+    {
+        crate::volume::VolumeRoot::parse(volume_root)
+            .and_then(|root| crate::mft_scan::query_volume_state(&root))
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = volume_root;
+        None
+    }
+}
+
+/// 带缓存的扫描：`path` 为 Windows 卷根且卷自上次扫描后没有变化时直接返回 `cache_dir` 中缓存的结果，
+/// 否则完整扫描；日志可用时把新结果写入缓存（写入失败只打印日志）。其他路径，
+/// 以及 `cache_dir` 位于被扫描的卷上时，总是完整扫描且不缓存
+pub fn scan_with_cache(
+    path: &str,
+    options: &ScanOptions,
+    cache_dir: &Path,
+) -> Result<(ScanResult, CacheStatus), DiskAnalyzerError> {
+    let canonical = std::fs::canonicalize(normalize_path(path))
+        .ok()
+        .filter(|p| is_windows_volume_root(p));
+    let Some((canonical, state)) = canonical.and_then(|p| volume_state(&p).map(|state| (p, state)))
+    else {
+        return scan(path, options).map(|result| (result, CacheStatus::JournalUnavailable));
+    };
+    if is_on_volume(cache_dir, &canonical) {
+        eprintln!(
+            "[scan] cache dir {} is on the scanned volume, scan cache disabled",
+            cache_dir.display()
+        );
+        return scan(path, options).map(|result| (result, CacheStatus::CacheOnScannedVolume));
+    }
+    let root = canonical.display().to_string();
+    let file = cache_file_path(cache_dir, state.serial);
+    let status = match ScanCache::load(&file) {
+        Some(cache) => match cache.check(&root, options, &state) {
+            CacheStatus::Hit => {
+                eprintln!("[scan] volume unchanged, using cached result: {}", root);
+                return Ok((cache.result, CacheStatus::Hit));
+            }
+            status => status,
+        },
+        None => CacheStatus::Missing,
+    };
+
+    let result = scan(path, options)?;
+    if state.journal.is_some() {
+        let saved = std::fs::create_dir_all(cache_dir)
+            .map_err(DiskAnalyzerError::from)
+            .and_then(|()| ScanCache::new(root, state, result.clone()).save(&file));
+        if let Err(e) = saved {
+            eprintln!("[scan] failed to save scan cache: {}", e);
+        }
+    }
+    Ok((result, status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::{FileNode, ScanStrategy};
+
+    const ROOT: &str = r"C:\";
+
+    fn state(serial: u64, next_usn: i64) -> VolumeState {
+        VolumeState {
+            serial,
+            journal: Some(JournalPosition {
+                journal_id: 7,
+                next_usn,
+            }),
+        }
+    }
+
+    fn cached(volume: VolumeState, options: &ScanOptions) -> ScanCache {
+        let root = FileNode {
+            path: ROOT.to_string(),
+            name: ROOT.to_string(),
+            size: 42,
+            is_dir: true,
+            children: vec![FileNode {
+                path: r"C:\a.bin".to_string(),
+                name: "a.bin".to_string(),
+                size: 42,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let result = ScanResult {
+            root,
+            scan_time_ms: 1_500,
+            file_count: 1,
+            total_size: 42,
+            volume_total_bytes: Some(1 << 30),
+            volume_free_bytes: Some(1 << 29),
+            system_reserved_bytes: Some(4_096),
+            meta: Some(options.scan_meta(ScanStrategy::Mft, ROOT, 1_700_000_000)),
+            ..Default::default()
+        };
+        ScanCache::new(ROOT, volume, result)
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ScanOptions::default();
+        let file = cache_file_path(dir.path(), 0xABCD_1234);
+        assert!(file.ends_with("scan_cache_00000000abcd1234.json"));
+        assert!(ScanCache::load(&file).is_none());
+
+        let cache = cached(state(0xABCD_1234, 100), &options);
+        cache.save(&file).unwrap();
+        let loaded = ScanCache::load(&file).unwrap();
+        assert_eq!(loaded.root, ROOT);
+        assert_eq!(loaded.volume, cache.volume);
+        assert_eq!(loaded.result.total_size, 42);
+        assert_eq!(loaded.result.system_reserved_bytes, Some(4_096));
+        assert_eq!(loaded.result.root.children[0].path, r"C:\a.bin");
+        assert_eq!(
+            loaded.check(ROOT, &options, &state(0xABCD_1234, 100)),
+            CacheStatus::Hit
+        );
+
+        // 版本不符或损坏的缓存视为不存在
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+        json["version"] = (CACHE_VERSION + 1).into();
+        std::fs::write(&file, serde_json::to_vec(&json).unwrap()).unwrap();
+        assert!(ScanCache::load(&file).is_none());
+        std::fs::write(&file, b"{\"version\":").unwrap();
+        assert!(ScanCache::load(&file).is_none());
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let options = ScanOptions::default();
+        let cache = cached(state(1, 100), &options);
+
+        assert_eq!(
+            cache.check(ROOT, &options, &state(2, 100)),
+            CacheStatus::SerialMismatch
+        );
+        assert_eq!(
+            cache.check(ROOT, &options, &state(1, 160)),
+            CacheStatus::VolumeChanged
+        );
+        let rebuilt = VolumeState {
+            journal: Some(JournalPosition {
+                journal_id: 8,
+                next_usn: 100,
+            }),
+            ..state(1, 100)
+        };
+        assert_eq!(
+            cache.check(ROOT, &options, &rebuilt),
+            CacheStatus::VolumeChanged
+        );
+        let no_journal = VolumeState {
+            journal: None,
+            ..state(1, 100)
+        };
+        assert_eq!(
+            cache.check(ROOT, &options, &no_journal),
+            CacheStatus::JournalUnavailable
+        );
+        assert_eq!(
+            cache.check(r"D:\", &options, &state(1, 100)),
+            CacheStatus::OptionsChanged
+        );
+        let expanded = ScanOptions::builder().shallow_dirs(false).build();
+        assert_eq!(
+            cache.check(ROOT, &expanded, &state(1, 100)),
+            CacheStatus::OptionsChanged
+        );
+    }
+
+    #[test]
+    fn test_cache_dir_on_scanned_volume_is_detected() {
+        let volume = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(volume.path()).unwrap();
+        // 尚未创建的缓存目录按已存在的上级判断
+        assert!(is_on_volume(
+            &volume.path().join("data").join("cache"),
+            &root
+        ));
+        assert!(!is_on_volume(other.path(), &root));
+    }
+
+    #[test]
+    fn test_non_volume_path_is_scanned_without_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"hello").unwrap();
+        let cache_dir = dir.path().join("cache");
+        let options = ScanOptions::builder().use_mft(false).build();
+
+        let (result, status) =
+            scan_with_cache(&dir.path().to_string_lossy(), &options, &cache_dir).unwrap();
+        assert_eq!(status, CacheStatus::JournalUnavailable);
+        assert_eq!((result.file_count, result.total_size), (1, 5));
+        assert!(!cache_dir.exists());
+    }
+}
@@ -0,0 +1,653 @@
+//! 重复文件检测任务：先按大小分组，只对大小相同的候选计算 BLAKE3 哈希（比 SHA-256 快数倍，
+//! 碰撞概率同样可忽略）。
+//! 计算出的哈希写入内容哈希缓存（JSON 检查点），任务可随时取消，重新运行时复用缓存中
+//! 大小与修改时间都未变的哈希，只计算剩余文件。
+//!
+//! 开启 `quick_prefilter` 时分两步：先只哈希每个候选的首尾各 64 KB（连同大小）找出「可能重复」，
+//! 再只对可能重复的文件计算完整哈希确认。大型媒体库中大小相同但内容不同的文件很多，
+//! 这一步能省去绝大部分读取；结果仍以完整哈希为准。
+//!
+//! 对已有的扫描结果可直接用 `find_duplicates`，不写检查点。
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, UNIX_EPOCH};
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::FileNode;
+use serde::{Deserialize, Serialize};
+
+/// 检查点格式版本，不兼容时丢弃旧缓存（版本 2 起哈希算法由 SHA-256 改为 BLAKE3）
+const CACHE_VERSION: u32 = 2;
+/// 每新算多少个文件写一次检查点
+const CHECKPOINT_EVERY: u64 = 64;
+/// 读取文件的块大小，取消标记按块检查
+const CHUNK: usize = 64 * 1024;
+/// 哈希单个大文件时每读取多少字节报告一次进度
+const FILE_PROGRESS_BYTES: u64 = 16 * 1024 * 1024;
+/// 快速预筛时首尾各读取的字节数
+const PARTIAL_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedHash {
+    size: u64,
+    mtime_ns: u64,
+    hash: String,
+}
+
+/// 内容哈希缓存，按路径记录哈希及计算时的大小和修改时间
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HashCache {
+    version: u32,
+    entries: HashMap<String, CachedHash>,
+}
+
+impl HashCache {
+    /// 读取检查点；文件不存在、损坏或版本不符时返回空缓存
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<HashCache>(&bytes).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// 先写临时文件再改名，避免中途退出留下半个检查点
+    pub fn save(&self, path: &Path) -> Result<(), DiskAnalyzerError> {
+        let tmp = path.with_extension("tmp");
+        let json = serde_json::to_vec(&Self {
+            version: CACHE_VERSION,
+            entries: self.entries.clone(),
+        })
+        .map_err(|e| DiskAnalyzerError::Config(e.to_string()))?;
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 文件未变化时返回缓存的哈希
+    fn get(&self, path: &str, size: u64, mtime_ns: u64) -> Option<&str> {
+        self.entries
+            .get(path)
+            .filter(|c| c.size == size && c.mtime_ns == mtime_ns)
+            .map(|c| c.hash.as_str())
+    }
+
+    fn insert(&mut self, path: String, size: u64, mtime_ns: u64, hash: String) {
+        self.entries.insert(
+            path,
+            CachedHash {
+                size,
+                mtime_ns,
+                hash,
+            },
+        );
+    }
+}
+
+/// 一组内容相同的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    /// 单个文件的大小
+    pub size: u64,
+    /// 按路径排序
+    pub paths: Vec<String>,
+    /// 只比较了首尾内容（`DedupJob::probable_duplicates`），未经完整哈希确认，不能据此删除
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub probable: bool,
+}
+
+impl DuplicateGroup {
+    /// 只保留一份时可释放的字节数
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.paths.len() as u64).saturating_sub(1)
+    }
+}
+
+/// 任务进度，可在其他线程轮询
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DedupStatus {
+    /// 需要哈希的候选文件数（大小有重复的文件）
+    pub files_total: u64,
+    pub bytes_total: u64,
+    /// 本次运行新计算哈希的文件数
+    pub files_hashed: u64,
+    pub bytes_hashed: u64,
+    /// 从缓存复用哈希的文件数
+    pub files_cached: u64,
+    pub bytes_cached: u64,
+    /// 按本次哈希速度估算的剩余秒数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eta_secs: Option<u64>,
+    /// 正在哈希的文件及其已读取的字节数，大文件哈希期间也能看到进度
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_path: Option<String>,
+    #[serde(default)]
+    pub current_bytes: u64,
+}
+
+impl DedupStatus {
+    fn bytes_done(&self) -> u64 {
+        self.bytes_hashed + self.bytes_cached
+    }
+}
+
+pub type DedupProgressCb = Box<dyn Fn(&DedupStatus) + Send + Sync>;
+
+/// `find_duplicates` 的选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupOptions {
+    /// 小于该字节数的文件不参与比较
+    pub min_size: u64,
+    /// 见 `DedupJob::quick_prefilter`
+    pub quick_prefilter: bool,
+}
+
+/// 在扫描结果中查找内容相同的文件：按大小分组后只对大小相同的文件计算哈希，
+/// 按可释放空间降序返回
+pub fn find_duplicates(root: &FileNode, opts: DedupOptions) -> Vec<DuplicateGroup> {
+    let mut files = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.is_dir {
+            stack.extend(&node.children);
+        } else if !node.is_aggregate() && node.size >= opts.min_size {
+            files.push(PathBuf::from(&node.path));
+        }
+    }
+    // 没有检查点也没有取消句柄，任务不会出错
+    DedupJob::in_memory(files)
+        .quick_prefilter(opts.quick_prefilter)
+        .run()
+        .unwrap_or_default()
+}
+
+/// 可取消、可续跑的去重任务
+pub struct DedupJob {
+    files: Vec<PathBuf>,
+    /// None 时不读写哈希缓存
+    checkpoint: Option<PathBuf>,
+    cancel: Arc<AtomicBool>,
+    status: Mutex<DedupStatus>,
+    on_progress: Option<DedupProgressCb>,
+    quick_prefilter: bool,
+}
+
+impl DedupJob {
+    /// `files` 为待比较的文件（通常来自扫描结果），`checkpoint` 为哈希缓存文件
+    pub fn new(files: Vec<PathBuf>, checkpoint: impl Into<PathBuf>) -> Self {
+        Self {
+            files,
+            checkpoint: Some(checkpoint.into()),
+            ..Self::in_memory(Vec::new())
+        }
+    }
+
+    /// 不使用哈希缓存的任务，每次运行都重新计算
+    pub fn in_memory(files: Vec<PathBuf>) -> Self {
+        Self {
+            files,
+            checkpoint: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            status: Mutex::new(DedupStatus::default()),
+            on_progress: None,
+            quick_prefilter: false,
+        }
+    }
+
+    /// 先按首尾内容预筛，只对可能重复的文件计算完整哈希
+    pub fn quick_prefilter(mut self, enabled: bool) -> Self {
+        self.quick_prefilter = enabled;
+        self
+    }
+
+    /// 每处理完一个文件回调一次；哈希大文件期间每读取 16 MB 也回调一次
+    pub fn on_progress(mut self, cb: DedupProgressCb) -> Self {
+        self.on_progress = Some(cb);
+        self
+    }
+
+    /// 置位后任务在当前文件块结束时停止，已算出的哈希会写入检查点
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    pub fn status(&self) -> DedupStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// 只比较大小与首尾内容得出的「可能重复」分组（`probable` 为 true），不读取完整文件
+    pub fn probable_duplicates(&self) -> Result<Vec<DuplicateGroup>, DiskAnalyzerError> {
+        let candidates = same_size_candidates(&self.files);
+        let groups = self
+            .partial_groups(&candidates)?
+            .into_iter()
+            .map(|((size, hash), paths)| DuplicateGroup {
+                hash,
+                size,
+                paths: paths
+                    .iter()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect(),
+                probable: true,
+            })
+            .collect();
+        Ok(sorted_groups(groups))
+    }
+
+    /// 运行任务；取消时返回 `DiskAnalyzerError::Cancelled`，之后可用同一检查点重新运行
+    pub fn run(&self) -> Result<Vec<DuplicateGroup>, DiskAnalyzerError> {
+        let mut candidates = same_size_candidates(&self.files);
+        if self.quick_prefilter {
+            let groups = self.partial_groups(&candidates)?;
+            let probable: HashSet<&Path> =
+                groups.values().flatten().map(PathBuf::as_path).collect();
+            candidates.retain(|(path, _, _)| probable.contains(path.as_path()));
+        }
+        let mut cache = self
+            .checkpoint
+            .as_deref()
+            .map(HashCache::load)
+            .unwrap_or_default();
+        {
+            let mut status = self.status.lock().unwrap();
+            *status = DedupStatus {
+                files_total: candidates.len() as u64,
+                bytes_total: candidates.iter().map(|c| c.1).sum(),
+                ..DedupStatus::default()
+            };
+        }
+
+        let started = Instant::now();
+        let mut since_checkpoint = 0;
+        let mut by_hash: BTreeMap<(u64, String), Vec<String>> = BTreeMap::new();
+        for (path, size, mtime_ns) in candidates {
+            let key = path.to_string_lossy().into_owned();
+            let hash = match cache.get(&key, size, mtime_ns) {
+                Some(hash) => {
+                    self.update(|s| {
+                        s.files_cached += 1;
+                        s.bytes_cached += size;
+                    });
+                    hash.to_string()
+                }
+                None => {
+                    let hash = match hash_file(&path, &self.cancel, |read| {
+                        self.update(|s| {
+                            s.current_path = Some(key.clone());
+                            s.current_bytes = read;
+                        });
+                    }) {
+                        Ok(Some(hash)) => hash,
+                        Ok(None) => {
+                            self.save_checkpoint(&cache)?;
+                            return Err(DiskAnalyzerError::Cancelled);
+                        }
+                        // 哈希期间消失或无法读取的文件不参与比较
+                        Err(_) => continue,
+                    };
+                    cache.insert(key.clone(), size, mtime_ns, hash.clone());
+                    since_checkpoint += 1;
+                    if since_checkpoint >= CHECKPOINT_EVERY {
+                        self.save_checkpoint(&cache)?;
+                        since_checkpoint = 0;
+                    }
+                    let elapsed = started.elapsed().as_secs_f64();
+                    self.update(|s| {
+                        s.current_path = None;
+                        s.current_bytes = 0;
+                        s.files_hashed += 1;
+                        s.bytes_hashed += size;
+                        s.eta_secs = (elapsed > 0.0).then(|| {
+                            let rate = s.bytes_hashed as f64 / elapsed;
+                            ((s.bytes_total - s.bytes_done()) as f64 / rate).ceil() as u64
+                        });
+                    });
+                    hash
+                }
+            };
+            by_hash.entry((size, hash)).or_default().push(key);
+            if self.cancel.load(Ordering::Relaxed) {
+                self.save_checkpoint(&cache)?;
+                return Err(DiskAnalyzerError::Cancelled);
+            }
+        }
+        self.save_checkpoint(&cache)?;
+
+        let groups = by_hash
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|((size, hash), mut paths)| {
+                paths.sort();
+                DuplicateGroup {
+                    hash,
+                    size,
+                    paths,
+                    probable: false,
+                }
+            })
+            .collect();
+        Ok(sorted_groups(groups))
+    }
+
+    /// 按 (大小, 首尾哈希) 分组，只保留多于一个文件的组；读取失败的文件不参与比较
+    fn partial_groups(
+        &self,
+        candidates: &[(PathBuf, u64, u64)],
+    ) -> Result<BTreeMap<(u64, String), Vec<PathBuf>>, DiskAnalyzerError> {
+        let mut by_partial: BTreeMap<(u64, String), Vec<PathBuf>> = BTreeMap::new();
+        for (path, size, _) in candidates {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(DiskAnalyzerError::Cancelled);
+            }
+            if let Ok(hash) = partial_hash(path, *size) {
+                by_partial
+                    .entry((*size, hash))
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+        by_partial.retain(|_, paths| paths.len() > 1);
+        Ok(by_partial)
+    }
+
+    fn save_checkpoint(&self, cache: &HashCache) -> Result<(), DiskAnalyzerError> {
+        match &self.checkpoint {
+            Some(path) => cache.save(path),
+            None => Ok(()),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut DedupStatus)) {
+        let snapshot = {
+            let mut status = self.status.lock().unwrap();
+            f(&mut status);
+            status.clone()
+        };
+        if let Some(cb) = &self.on_progress {
+            cb(&snapshot);
+        }
+    }
+}
+
+/// 按可释放空间降序，相同时按路径
+fn sorted_groups(mut groups: Vec<DuplicateGroup>) -> Vec<DuplicateGroup> {
+    groups.sort_by(|a, b| {
+        b.wasted_bytes()
+            .cmp(&a.wasted_bytes())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    groups
+}
+
+/// 大小至少与另一个文件相同的非空文件：(路径, 大小, 修改时间纳秒)，按路径排序
+fn same_size_candidates(files: &[PathBuf]) -> Vec<(PathBuf, u64, u64)> {
+    let mut by_size: HashMap<u64, Vec<(PathBuf, u64)>> = HashMap::new();
+    for path in files {
+        let Ok(meta) = std::fs::metadata(path) else {
+            continue;
+        };
+        if !meta.is_file() || meta.len() == 0 {
+            continue;
+        }
+        let mtime_ns = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos() as u64);
+        by_size
+            .entry(meta.len())
+            .or_default()
+            .push((path.clone(), mtime_ns));
+    }
+    let mut candidates: Vec<(PathBuf, u64, u64)> = by_size
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .flat_map(|(size, files)| {
+            files
+                .into_iter()
+                .map(move |(path, mtime_ns)| (path, size, mtime_ns))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// 按块计算文件的 BLAKE3 哈希，每块之前检查取消标记，被取消时返回 `Ok(None)`；
+/// 每读取 `FILE_PROGRESS_BYTES` 字节以已读字节数调用 `on_read`
+fn hash_file(
+    path: &Path,
+    cancel: &AtomicBool,
+    mut on_read: impl FnMut(u64),
+) -> std::io::Result<Option<String>> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; CHUNK];
+    let mut read = 0u64;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        let before = read;
+        read += n as u64;
+        if read / FILE_PROGRESS_BYTES > before / FILE_PROGRESS_BYTES {
+            on_read(read);
+        }
+    }
+    Ok(Some(hasher.finalize().to_hex().to_string()))
+}
+
+/// 大小与首尾各 `PARTIAL_BYTES` 字节的 BLAKE3 哈希；不超过两段长度的文件即为完整内容的哈希
+fn partial_hash(path: &Path, size: u64) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&size.to_le_bytes());
+    let mut buf = Vec::with_capacity(PARTIAL_BYTES as usize);
+    if size <= 2 * PARTIAL_BYTES {
+        file.read_to_end(&mut buf)?;
+        hasher.update(&buf);
+    } else {
+        (&mut file).take(PARTIAL_BYTES).read_to_end(&mut buf)?;
+        hasher.update(&buf);
+        buf.clear();
+        file.seek(SeekFrom::End(-(PARTIAL_BYTES as i64)))?;
+        file.take(PARTIAL_BYTES).read_to_end(&mut buf)?;
+        hasher.update(&buf);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_and_resume_reuses_cached_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut files = Vec::new();
+        for (name, content) in [
+            ("a1", "alpha"),
+            ("a2", "alpha"),
+            ("a3", "alpha"),
+            ("b1", "bravo"),
+            ("b2", "bravo"),
+            ("c1", "charl"),
+            ("unique", "no size twin"),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            files.push(path);
+        }
+        let checkpoint = dir.path().join("hashes.json");
+
+        let expected = DedupJob::new(files.clone(), dir.path().join("fresh.json"))
+            .run()
+            .unwrap();
+        assert_eq!(expected.len(), 2);
+        assert_eq!(expected[0].paths.len(), 3);
+        assert_eq!(expected[0].wasted_bytes(), 10);
+
+        // 第 2 个文件哈希完成后取消
+        let job = DedupJob::new(files.clone(), &checkpoint);
+        let cancel = job.cancel_handle();
+        let job = job.on_progress(Box::new(move |s| {
+            if s.files_hashed == 2 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        }));
+        assert!(matches!(job.run(), Err(DiskAnalyzerError::Cancelled)));
+        assert_eq!(job.status().files_hashed, 2);
+        assert_eq!(HashCache::load(&checkpoint).len(), 2);
+
+        let resumed = DedupJob::new(files, &checkpoint);
+        assert_eq!(resumed.run().unwrap(), expected);
+        let status = resumed.status();
+        assert_eq!((status.files_cached, status.files_hashed), (2, 4));
+        assert_eq!(status.files_total, 6);
+        assert_eq!(
+            status.bytes_cached + status.bytes_hashed,
+            status.bytes_total
+        );
+    }
+
+    #[test]
+    fn test_cancel_stops_midway_through_large_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let size = 8 * FILE_PROGRESS_BYTES;
+        let files: Vec<PathBuf> = ["big1", "big2"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                // 稀疏文件，无需真正写入数据
+                File::create(&path).unwrap().set_len(size).unwrap();
+                path
+            })
+            .collect();
+        let checkpoint = dir.path().join("hashes.json");
+
+        let job = DedupJob::new(files, &checkpoint);
+        let cancel = job.cancel_handle();
+        let job = job.on_progress(Box::new(move |s| {
+            if s.current_bytes >= 2 * FILE_PROGRESS_BYTES {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        }));
+        assert!(matches!(job.run(), Err(DiskAnalyzerError::Cancelled)));
+        let status = job.status();
+        assert_eq!(status.files_hashed, 0);
+        assert_eq!(status.current_bytes, 2 * FILE_PROGRESS_BYTES);
+        assert!(status.current_path.unwrap().ends_with("big1"));
+        assert!(HashCache::load(&checkpoint).is_empty());
+    }
+
+    #[test]
+    fn test_quick_prefilter_rejects_files_differing_only_in_middle() {
+        let dir = tempfile::tempdir().unwrap();
+        let len = 4 * PARTIAL_BYTES as usize;
+        let base: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let mut middle = base.clone();
+        middle[len / 2] ^= 0xFF;
+        let mut head = base.clone();
+        head[0] ^= 0xFF;
+        let mut files = Vec::new();
+        for (name, content) in [
+            ("a.mkv", &base),
+            ("b.mkv", &base),
+            ("middle.mkv", &middle),
+            ("head.mkv", &head),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            files.push(path);
+        }
+        let name = |p: &String| {
+            Path::new(p)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        let job = DedupJob::new(files, dir.path().join("hashes.json")).quick_prefilter(true);
+        let probable = job.probable_duplicates().unwrap();
+        assert_eq!(probable.len(), 1);
+        assert!(probable[0].probable);
+        let names: Vec<String> = probable[0].paths.iter().map(name).collect();
+        assert_eq!(names, ["a.mkv", "b.mkv", "middle.mkv"]);
+
+        // 完整哈希确认后排除只有中间不同的文件；首部不同的文件不会被完整读取
+        let confirmed = job.run().unwrap();
+        assert_eq!(confirmed.len(), 1);
+        assert!(!confirmed[0].probable);
+        let names: Vec<String> = confirmed[0].paths.iter().map(name).collect();
+        assert_eq!(names, ["a.mkv", "b.mkv"]);
+        let status = job.status();
+        assert_eq!(status.files_total, 3);
+        assert_eq!(status.bytes_hashed, 3 * len as u64);
+    }
+
+    #[test]
+    fn test_find_duplicates_in_scanned_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            FileNode {
+                path: path.to_string_lossy().into_owned(),
+                name: name.to_string(),
+                size: content.len() as u64,
+                ..Default::default()
+            }
+        };
+        let root = FileNode {
+            path: dir.path().to_string_lossy().into_owned(),
+            is_dir: true,
+            children: vec![
+                file("copy1", "same content"),
+                file("copy2", "same content"),
+                file("copy3", "same content"),
+                // 大小相同、内容不同
+                file("decoy", "other conten"),
+                file("tiny1", "ab"),
+                file("tiny2", "ab"),
+            ],
+            ..Default::default()
+        };
+
+        let groups = find_duplicates(
+            &root,
+            DedupOptions {
+                min_size: 4,
+                ..Default::default()
+            },
+        );
+        assert_eq!(groups.len(), 1);
+        let names: Vec<&str> = groups[0]
+            .paths
+            .iter()
+            .map(|p| ai_disk_common::path::file_name(p))
+            .collect();
+        assert_eq!(names, ["copy1", "copy2", "copy3"]);
+        assert_eq!(groups[0].wasted_bytes(), 24);
+        assert_eq!(find_duplicates(&root, DedupOptions::default()).len(), 2);
+    }
+}
@@ -0,0 +1,82 @@
+//! 「以管理员身份重新扫描」建议：普通遍历在系统卷上遇到较多拒绝访问的目录时，提升权限通常能看到
+//! 更多内容（卷根还可改用 MFT 扫描）。
+//!
+//! 应用侧的重启流程：扫描结束后若当前未提升权限且 `needs_elevation_for` 为 true，提示用户；用户同意后
+//! 把本次扫描参数写入存储目录，以管理员身份启动自身并退出。新进程启动时读取并删除该文件，
+//! 用相同参数重新发起扫描（桌面端见 `commands::permission::relaunch_elevated`）。
+
+use std::path::Path;
+
+use ai_disk_domain::{ScanResult, ScanStrategy};
+
+use crate::volume::is_system_volume_root;
+
+/// 系统卷上达到该数量的拒绝访问目录即建议提升权限
+pub const MIN_DENIED_ON_SYSTEM_VOLUME: u64 = 5;
+/// 其他卷上的阈值：数据盘上零星的拒绝访问多半是其他用户的目录，提升权限意义不大
+pub const MIN_DENIED_ELSEWHERE: u64 = 100;
+
+/// 以管理员身份重新扫描 `path` 是否可能有帮助，依据 `result` 中记录的拒绝访问目录数。
+/// 调用方应先确认当前进程未以管理员身份运行
+pub fn needs_elevation_for(path: &str, result: &ScanResult) -> bool {
+    let on_system_volume = Path::new(path).ancestors().any(is_system_volume_root);
+    should_elevate(on_system_volume, result)
+}
+
+fn should_elevate(on_system_volume: bool, result: &ScanResult) -> bool {
+    // MFT 扫描已读取整个卷，提升权限不会带来更多内容
+    if result
+        .meta
+        .as_ref()
+        .is_some_and(|m| m.strategy == ScanStrategy::Mft)
+    {
+        return false;
+    }
+    // 因之前拒绝访问而跳过的路径同样计入
+    let denied = result.denied_dirs.unwrap_or(0) + result.skipped_paths.unwrap_or(0);
+    if on_system_volume {
+        denied >= MIN_DENIED_ON_SYSTEM_VOLUME
+    } else {
+        denied >= MIN_DENIED_ELSEWHERE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::ScanMeta;
+
+    fn result(strategy: ScanStrategy, denied_dirs: Option<u64>) -> ScanResult {
+        ScanResult {
+            root: Default::default(),
+            file_count: 10_000,
+            denied_dirs,
+            meta: Some(ScanMeta {
+                strategy,
+                shallow_dirs: true,
+                filters_summary: String::new(),
+                root: r"C:\".to_string(),
+                timestamp: 0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_elevation_heuristic_from_denied_counts() {
+        let walk = |denied| result(ScanStrategy::Walk, Some(denied));
+        // 系统卷：少量拒绝访问即建议
+        assert!(!should_elevate(true, &walk(0)));
+        assert!(!should_elevate(
+            true,
+            &walk(MIN_DENIED_ON_SYSTEM_VOLUME - 1)
+        ));
+        assert!(should_elevate(true, &walk(MIN_DENIED_ON_SYSTEM_VOLUME)));
+        // 数据盘：需要大量拒绝访问
+        assert!(!should_elevate(false, &walk(MIN_DENIED_ON_SYSTEM_VOLUME)));
+        assert!(should_elevate(false, &walk(MIN_DENIED_ELSEWHERE)));
+        // MFT 扫描或没有统计时不建议
+        assert!(!should_elevate(true, &result(ScanStrategy::Mft, Some(500))));
+        assert!(!should_elevate(true, &result(ScanStrategy::Walk, None)));
+    }
+}
@@ -0,0 +1,123 @@
+//! 出错路径记录：有些机器上总有几个路径（其他用户的目录、受保护的系统目录）每次扫描都拒绝访问，
+//! 反复尝试只会拖慢扫描、刷屏日志。普通遍历把拒绝访问的路径记入此处（JSON 文件跨次保存），
+//! 之后的扫描在重试等待期内直接跳过它们，只计入 `ScanResult::skipped_paths`；
+//! 等待期过后再试一次，成功即从记录中移除。设置 `ScanOptions::retry_errored_paths` 可强制全部重试。
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use ai_disk_common::DiskAnalyzerError;
+use serde::{Deserialize, Serialize};
+
+use crate::scanner::unix_now;
+
+/// 记录文件格式版本，不兼容时丢弃旧记录
+const HISTORY_VERSION: u32 = 1;
+/// 默认重试等待期：一周
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 7 * 24 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct ErrorEntry {
+    /// 最近一次出错的时间（Unix 秒）
+    last_error: u64,
+    /// 连续出错的次数
+    failures: u32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    entries: HashMap<String, ErrorEntry>,
+}
+
+/// 出错路径记录，扫描期间可被多个线程同时查询与更新
+#[derive(Debug)]
+pub struct ErrorHistory {
+    entries: RwLock<HashMap<String, ErrorEntry>>,
+    retry_after_secs: u64,
+}
+
+impl Default for ErrorHistory {
+    fn default() -> Self {
+        Self {
+            entries: RwLock::default(),
+            retry_after_secs: DEFAULT_RETRY_AFTER_SECS,
+        }
+    }
+}
+
+impl ErrorHistory {
+    /// 读取记录；文件不存在、损坏或版本不符时返回空记录
+    pub fn load(path: &Path) -> Self {
+        let entries = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<HistoryFile>(&bytes).ok())
+            .filter(|file| file.version == HISTORY_VERSION)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        Self {
+            entries: RwLock::new(entries),
+            ..Self::default()
+        }
+    }
+
+    /// 先写临时文件再改名，避免中途退出留下半个文件
+    pub fn save(&self, path: &Path) -> Result<(), DiskAnalyzerError> {
+        let tmp = path.with_extension("tmp");
+        let json = serde_json::to_vec(&HistoryFile {
+            version: HISTORY_VERSION,
+            entries: self.entries.read().unwrap().clone(),
+        })
+        .map_err(|e| DiskAnalyzerError::Config(e.to_string()))?;
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// 出错后多久（秒）再重试
+    pub fn retry_after(mut self, secs: u64) -> Self {
+        self.retry_after_secs = secs;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().unwrap().is_empty()
+    }
+
+    /// 路径之前出错且仍在重试等待期内
+    pub fn is_waiting(&self, path: &Path) -> bool {
+        let now = unix_now();
+        self.entries
+            .read()
+            .unwrap()
+            .get(path.to_string_lossy().as_ref())
+            .is_some_and(|e| now < e.last_error.saturating_add(self.retry_after_secs))
+    }
+
+    /// 记录一次出错
+    pub fn record(&self, path: &Path) {
+        let now = unix_now();
+        let mut entries = self.entries.write().unwrap();
+        let entry = entries
+            .entry(path.to_string_lossy().into_owned())
+            .or_insert(ErrorEntry {
+                last_error: now,
+                failures: 0,
+            });
+        entry.last_error = now;
+        entry.failures += 1;
+    }
+
+    /// 路径已能正常读取，移除其记录
+    pub fn forget(&self, path: &Path) {
+        let key = path.to_string_lossy();
+        if self.entries.read().unwrap().contains_key(key.as_ref()) {
+            self.entries.write().unwrap().remove(key.as_ref());
+        }
+    }
+}
@@ -1,6 +1,150 @@
-/// 扫描过滤器（预留）
-#[derive(Default)]
+//! 扫描过滤器：按 glob 排除路径、限制展开深度。通过 `ScanOptionsBuilder::filters` 应用到扫描选项。
+//! 另有前 N 大文件的筛选条件 `TopFilesFilter`（按扩展名、修改时间与大小）。
+//!
+//! 排除模式匹配**完整路径**（分隔符统一为 `/`，大小写规则与当前平台一致），不只是文件名：
+//! `*/target/*` 排除任意 `target` 目录下的全部内容，`*/node_modules` 排除该目录本身，
+//! `*.log` 排除所有 `.log` 文件。`*` 可跨越多级目录。
+
+use std::path::Path;
+
+use ai_disk_common::path::CaseSensitivity;
+use ai_disk_common::{file_extension, DiskAnalyzerError};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+/// 扫描过滤器
+#[derive(Debug, Clone, Default)]
 pub struct ScanFilters {
+    /// 排除的路径模式，见模块文档
     pub exclude_patterns: Vec<String>,
+    /// 普通遍历展开的最大目录深度，见 `ScanOptions::max_depth`
     pub max_depth: Option<usize>,
 }
+
+/// 前 N 大文件的筛选条件（如「最大的 100 个视频」「一年未修改的大文件」），各条件同时满足才计入
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TopFilesFilter {
+    /// 只保留这些扩展名（不区分大小写，前导点可有可无）；没有扩展名的文件不匹配
+    pub extensions: Option<Vec<String>>,
+    /// 只保留修改时间早于该时间（Unix 秒）的文件；修改时间未知的文件不匹配
+    pub modified_before: Option<u64>,
+    /// 只保留不小于该字节数的文件
+    pub min_size: Option<u64>,
+}
+
+impl TopFilesFilter {
+    /// 文件是否满足全部条件；`modified` 为 Unix 秒
+    pub fn matches(&self, path: &str, size: u64, modified: Option<u64>) -> bool {
+        if self.min_size.is_some_and(|min| size < min) {
+            return false;
+        }
+        if let Some(before) = self.modified_before {
+            if modified.is_none_or(|m| m >= before) {
+                return false;
+            }
+        }
+        match &self.extensions {
+            None => true,
+            Some(extensions) => file_extension(path).is_some_and(|ext| {
+                extensions
+                    .iter()
+                    .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+            }),
+        }
+    }
+}
+
+/// 编译后的排除模式
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExcludePatterns(Vec<Pattern>);
+
+impl ExcludePatterns {
+    pub(crate) fn compile(patterns: &[String]) -> Result<Self, DiskAnalyzerError> {
+        patterns
+            .iter()
+            .map(|p| {
+                Pattern::new(&p.replace('\\', "/")).map_err(|e| {
+                    DiskAnalyzerError::Config(format!("invalid exclude pattern {:?}: {}", p, e))
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// 完整路径是否命中任一模式
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        if self.0.is_empty() {
+            return false;
+        }
+        let options = MatchOptions {
+            case_sensitive: CaseSensitivity::native() == CaseSensitivity::Sensitive,
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+        let path = path.replace('\\', "/");
+        self.0.iter().any(|p| p.matches_with(&path, options))
+    }
+
+    pub(crate) fn matches_path(&self, path: &Path) -> bool {
+        self.matches(&path.to_string_lossy())
+    }
+
+    /// 路径本身或其任一上级目录命中：不经逐级遍历的扫描（MFT）用它排除被排除目录的后代
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn matches_with_ancestors(&self, path: &str) -> bool {
+        !self.0.is_empty()
+            && (self.matches(path)
+                || path
+                    .match_indices(['/', '\\'])
+                    .any(|(i, _)| i > 0 && self.matches(&path[..i])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mft_tree::TopFiles;
+
+    #[test]
+    fn test_top_files_filter_by_extension_and_age() {
+        const YEAR_AGO: u64 = 1_700_000_000;
+        let records = [
+            (r"C:\Videos\trip.MP4", 9_000, Some(YEAR_AGO - 10)),
+            (r"C:\Videos\new.mkv", 8_000, Some(YEAR_AGO + 10)),
+            (r"C:\logs\huge.txt", 50_000, Some(YEAR_AGO - 10)),
+            (r"C:\bin\mp4", 40_000, Some(YEAR_AGO - 10)),
+            (r"C:\Videos\clip.mkv", 100, None),
+        ];
+        let top = |filter: &TopFilesFilter| -> Vec<String> {
+            let mut top = TopFiles::new(10);
+            for (path, size, modified) in records {
+                if filter.matches(path, size, modified) {
+                    top.push(path.to_string(), size, modified);
+                }
+            }
+            top.finish().into_iter().map(|f| f.path).collect()
+        };
+
+        let videos = TopFilesFilter {
+            extensions: Some(vec!["mp4".to_string(), ".MKV".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            top(&videos),
+            [
+                r"C:\Videos\trip.MP4",
+                r"C:\Videos\new.mkv",
+                r"C:\Videos\clip.mkv"
+            ]
+        );
+
+        let old_videos = TopFilesFilter {
+            modified_before: Some(YEAR_AGO),
+            min_size: Some(1_000),
+            ..videos
+        };
+        assert_eq!(top(&old_videos), [r"C:\Videos\trip.MP4"]);
+        assert_eq!(top(&TopFilesFilter::default()).len(), records.len());
+    }
+}
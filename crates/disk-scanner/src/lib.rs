@@ -1,15 +1,53 @@
+pub mod archive;
+pub mod cache;
+pub mod dedup;
+pub mod elevation;
+pub mod error_history;
 pub mod filters;
+mod links;
 pub mod node;
+pub mod options;
+pub mod progress;
 pub mod scanner;
+pub mod volume;
+pub mod watch;
 
+#[cfg(feature = "mem-harness")]
+pub mod mft_harness;
 #[cfg(windows)]
 pub mod mft_scan;
+#[cfg(any(windows, test))]
+#[cfg_attr(not(windows), allow(dead_code))]
+mod mft_stream;
+#[cfg(any(windows, test, feature = "mem-harness"))]
+#[cfg_attr(not(windows), allow(dead_code))]
+mod mft_tree;
 
 pub use ai_disk_domain::ScanResult;
+pub use archive::peek_archive;
+pub use cache::{scan_with_cache, CacheStatus, ScanCache, VolumeState};
+pub use dedup::{find_duplicates, DedupJob, DedupOptions, DedupStatus, DuplicateGroup, HashCache};
+pub use elevation::needs_elevation_for;
+pub use error_history::{ErrorHistory, DEFAULT_RETRY_AFTER_SECS};
 pub use filters::*;
 pub use node::*;
-pub use scanner::{scan_path, scan_path_with_progress, scan_will_use_mft};
+pub use options::{
+    MftBudget, ScanOptions, ScanOptionsBuilder, SymlinkPolicy, TreeBuildOptions,
+    DEFAULT_MAX_CHILDREN_PER_DIR, DEFAULT_MAX_DEPTH,
+};
+pub use progress::{CoalescingProgress, RelayedProgress};
+pub use scanner::{
+    list_children, scan, scan_path, scan_paths, scan_strategy, scan_will_use_mft, PercentCb,
+    ProgressCb, ProgressCbArc, SubtreeCb,
+};
+#[allow(deprecated)]
+pub use scanner::{scan_path_with_options, scan_path_with_percent, scan_path_with_progress};
+pub use volume::{is_system_volume_root, is_windows_volume_root, VolumeRoot};
+pub use watch::{watch_path, ChangeKind, TreeChange, WatchHandle};
 
 pub use ai_disk_domain::TopFileEntry;
 #[cfg(windows)]
-pub use mft_scan::{get_volume_space_bytes, scan_volume_mft_top_files, TOP_FILES_DEFAULT_N};
+pub use mft_scan::{
+    get_volume_filesystem, get_volume_space_bytes, scan_volume_mft_by_extension,
+    scan_volume_mft_streaming, scan_volume_mft_top_files, TOP_FILES_DEFAULT_N,
+};
@@ -0,0 +1,146 @@
+//! 「真实大小」统计（`ScanOptions::realistic_sizes`）：同一文件的多个硬链接只计一次，
+//! 重解析点（符号链接、目录联接）不展开。系统卷上 WinSxS 与 System32 等目录大量互为硬链接，
+//! 朴素相加会把 `C:\Windows` 算大数倍。
+//! 另含按 `ScanOptions::symlinks` 展开链接时的目标记录。
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// 文件在本机上的唯一标识：(卷号, 文件号)
+pub(crate) type FileId = (u64, u64);
+
+/// 遍历中共享的硬链接去重状态
+#[derive(Debug, Default)]
+pub(crate) struct LinkDedup {
+    seen: Mutex<HashSet<FileId>>,
+    shared_bytes: AtomicU64,
+    reparse_points: AtomicU64,
+}
+
+impl LinkDedup {
+    /// 文件应计入的字节数：同一 `id` 再次出现时为 0，并计入 `shared_bytes`；
+    /// 没有 `id`（单链接文件）时原样计入
+    pub fn count(&self, id: Option<FileId>, size: u64) -> u64 {
+        let Some(id) = id else {
+            return size;
+        };
+        if self.seen.lock().unwrap().insert(id) {
+            size
+        } else {
+            self.shared_bytes.fetch_add(size, Ordering::Relaxed);
+            0
+        }
+    }
+
+    /// 记录一个未展开的重解析点
+    pub fn skip_reparse(&self) {
+        self.reparse_points.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 因重复硬链接而未计入的字节数，朴素总大小 = 去重后总大小 + 该值
+    pub fn shared_bytes(&self) -> u64 {
+        self.shared_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn reparse_points(&self) -> u64 {
+        self.reparse_points.load(Ordering::Relaxed)
+    }
+}
+
+/// 遍历中已被符号链接或目录联接展开过的目标（规范化路径），每个目标只展开一次
+#[derive(Debug)]
+pub(crate) struct FollowedTargets {
+    /// 扫描根的真实路径
+    root: PathBuf,
+    seen: Mutex<HashSet<PathBuf>>,
+}
+
+impl FollowedTargets {
+    pub fn new(root: PathBuf) -> Self {
+        FollowedTargets {
+            root,
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 目标位于扫描根内，其内容已按真实路径统计
+    pub fn is_inside_root(&self, target: &Path) -> bool {
+        target.starts_with(&self.root)
+    }
+
+    /// 首次遇到 `target` 时记录并返回 true
+    pub fn first_visit(&self, target: &Path) -> bool {
+        self.seen.lock().unwrap().insert(target.to_path_buf())
+    }
+}
+
+/// 有多个硬链接的文件的标识；单链接文件无需去重，返回 None
+#[cfg(unix)]
+pub(crate) fn file_id(_path: &Path, meta: &std::fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    (meta.nlink() > 1).then(|| (meta.dev(), meta.ino()))
+}
+
+/// 有多个硬链接的文件的标识；单链接文件或无法打开时返回 None
+#[cfg(windows)]
+#[allow(unsafe_code)]
+pub(crate) fn file_id(path: &Path, _meta: &std::fs::Metadata) -> Option<FileId> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+    };
+    // 只查询属性，不需要读权限
+    let file = std::fs::OpenOptions::new().access_mode(0).open(path).ok()?;
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    let ok = unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) };
+    if ok == 0 || info.nNumberOfLinks < 2 {
+        return None;
+    }
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    Some((u64::from(info.dwVolumeSerialNumber), index))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn file_id(_path: &Path, _meta: &std::fs::Metadata) -> Option<FileId> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hardlinked_system_files_are_counted_once() {
+        // WinSxS 中的组件与 System32 中的同一文件互为硬链接
+        let records: [(&str, Option<FileId>, u64); 6] = [
+            (
+                r"C:\Windows\WinSxS\amd64_a\kernel32.dll",
+                Some((7, 100)),
+                800,
+            ),
+            (r"C:\Windows\System32\kernel32.dll", Some((7, 100)), 800),
+            (
+                r"C:\Windows\WinSxS\amd64_b\ntdll.dll",
+                Some((7, 200)),
+                2_000,
+            ),
+            (r"C:\Windows\System32\ntdll.dll", Some((7, 200)), 2_000),
+            (r"C:\Windows\SysWOW64\ntdll.dll", Some((7, 200)), 2_000),
+            (r"C:\Windows\notepad.exe", None, 300),
+        ];
+        let dedup = LinkDedup::default();
+        let naive: u64 = records.iter().map(|r| r.2).sum();
+        let adjusted: u64 = records
+            .iter()
+            .map(|&(_, id, size)| dedup.count(id, size))
+            .sum();
+
+        assert_eq!(naive, 7_900);
+        assert_eq!(adjusted, 800 + 2_000 + 300);
+        assert_eq!(dedup.shared_bytes(), 800 + 2 * 2_000);
+        assert_eq!(adjusted + dedup.shared_bytes(), naive);
+    }
+}
@@ -0,0 +1,88 @@
+//! MFT 内存测试工具（`mem-harness` feature）：不依赖 NTFS 卷，向 MFT 汇总喂入合成记录流，
+//! 便于在测试中用计数分配器断言峰值内存不随记录数无界增长。见 tests/mft_memory_budget.rs
+//! 与 tests/mft_streaming_memory.rs
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::mft_tree::{compute_recursive_sizes, MftAggregate};
+use crate::options::MftBudget;
+
+/// 记录当前与峰值分配字节数的分配器，需在测试 crate 中注册才生效：
+/// `#[global_allocator] static GLOBAL: CountingAlloc = CountingAlloc;`
+pub struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+#[allow(unsafe_code)]
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+/// 执行 `f`，返回其结果与期间相对起点的峰值分配字节数（未注册 `CountingAlloc` 时为 0）
+pub fn measure_peak<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    let out = f();
+    (out, PEAK.load(Ordering::Relaxed).saturating_sub(base))
+}
+
+/// 一次汇总的结果摘要
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateReport {
+    /// 保存下来的记录数（目录 + 预算内的文件）
+    pub records_kept: usize,
+    /// 超出预算、只计入父目录的文件数
+    pub folded_files: u64,
+    /// 是否进入降级模式
+    pub degraded: bool,
+    /// 卷根递归大小
+    pub root_size: u64,
+}
+
+/// 合成卷 `C:` 上的记录流：先产出 `dirs` 个目录，再把 `files` 个文件轮流放入各目录，
+/// 第 i 个文件大小为 `i % 4096 + 1`
+pub fn synthetic_records(files: u64, dirs: u64) -> impl Iterator<Item = (String, u64, bool)> {
+    let dirs = dirs.max(1);
+    let dir_records = (0..dirs).map(|d| (format!(r"C:\d{}", d), 0, true));
+    let file_records =
+        (0..files).map(move |i| (format!(r"C:\d{}\f{}.bin", i % dirs, i), i % 4096 + 1, false));
+    dir_records.chain(file_records)
+}
+
+/// 按预算汇总记录流并计算递归大小（与 MFT 扫描的前两个阶段相同）
+pub fn aggregate(
+    records: impl IntoIterator<Item = (String, u64, bool)>,
+    budget: &MftBudget,
+) -> AggregateReport {
+    let mut agg = MftAggregate::with_budget("C:", budget);
+    for (path, size, is_dir) in records {
+        agg.push(path, size, is_dir, None);
+    }
+    let sizes = compute_recursive_sizes(
+        &agg.records,
+        &agg.child_index,
+        &agg.direct_sizes,
+        "C:",
+        r"C:\",
+    );
+    AggregateReport {
+        records_kept: agg.records.len(),
+        folded_files: agg.folded_counts.values().sum(),
+        degraded: agg.is_degraded(),
+        root_size: sizes.get("C:").copied().unwrap_or(0),
+    }
+}
@@ -1,32 +1,43 @@
 //! Windows NTFS volume scan via MFT (Everything-style): use ntfs-reader to open
-//! volume `\\.\X:`, read $MFT into memory, and enumerate files with path cache.
+//! volume `\\.\X:` (or `\\?\Volume{GUID}` for folder-mounted drives without a letter),
+//! read $MFT into memory, and enumerate files with path cache.
 //! Requires admin (elevated) privileges.
 //!
 //! **当前限制**：ntfs-reader 的 `Mft::new(volume)` 会一次性将整个 $MFT 读入内存，因此
-//! “volume opened” 与 “MFT loaded” 之间会有较长等待；真正的边读边处理需自实现分块读 $MFT
-//! 或改用支持流式读取的库。
+//! “volume opened” 与 “MFT loaded” 之间会有较长等待。只要前 N 大文件时可改用
+//! `scan_volume_mft_streaming`，它自行分块读取 $MFT、边读边处理（见 `mft_stream`）。
 //!
-//! **阶段耗时**：设置环境变量 `MFT_TIMING=1` 后扫描会打印三阶段耗时（获取 MFT / 枚举 / 建树）
-//! 及可并行化建议。参见 tests/scan_timing.rs 中的运行示例。
+//! **阶段耗时**：各阶段耗时（读取 MFT / 解析记录 / 汇总 / 建树 / 整理）总是记录在 `ScanResult::timing` 中；
+//! 设置环境变量 `MFT_TIMING=1` 后另打印到 stderr。参见 tests/scan_timing.rs 中的运行示例。
 //!
-//! **仅要前 N 大文件**：使用 `scan_volume_mft_top_files(path, n, progress)`，只做枚举 + 最小堆，
-//! 不建树，默认 N=100 时显著省时省内存。
+//! **仅要前 N 大文件**：使用 `scan_volume_mft_top_files(path, n, filter, progress)`，只做枚举 + 堆，
+//! 不建树，默认 N=100 时显著省时省内存；`TopFilesFilter` 可限定扩展名、修改时间与大小。
 
-use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap};
-use std::path::Path;
+use std::collections::HashMap;
+use std::os::windows::ffi::OsStrExt;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
 
-use ai_disk_common::DiskAnalyzerError;
-use ai_disk_domain::{FileNode, ScanResult, TopFileEntry};
+use ai_disk_common::path::{is_under, restore_unpaired_surrogates, CaseSensitivity};
+use ai_disk_common::{telemetry, DiskAnalyzerError};
+use ai_disk_domain::{
+    FileNode, FolderGroup, ScanResult, ScanStrategy, ScanTiming, TimingPhase, TopFileEntry,
+};
 use ntfs_reader::errors::NtfsReaderError;
 use ntfs_reader::file_info::{FileInfo, HashMapCache};
 use ntfs_reader::mft::Mft;
 use ntfs_reader::volume::Volume;
-use rayon::prelude::*;
 
-use crate::scanner::{normalize_path, ProgressCb, ProgressCbArc, SHALLOW_DIR_NAMES};
+use crate::cache::{JournalPosition, VolumeState};
+use crate::filters::TopFilesFilter;
+use crate::mft_stream::{open_mft, stream_top_files, StreamingMftReader};
+use crate::mft_tree::{
+    build_tree_from_mft_records, compute_recursive_modified, compute_recursive_sizes,
+    is_system_metafile, CappedCache, ExtensionGroups, MftAggregate, MftRecord, TopFiles,
+};
+use crate::options::ScanOptions;
+use crate::scanner::{normalize_path, unix_now, PhaseClock, ProgressCb, ProgressCbArc};
+pub use crate::volume::is_windows_volume_root;
+use crate::volume::VolumeRoot;
 
 /// 通过 Windows API GetDiskFreeSpaceExW 获取卷总容量与剩余空间（字节）。
 /// 仅 Windows 有效；path 为卷上任意路径（如 "C:\" 或 "C:\Users"）。
@@ -54,88 +65,139 @@ pub fn get_volume_space_bytes(path: &str) -> Option<(u64, u64)> {
     }
 }
 
-/// Resolve drive letter from volume root path (e.g. `F:\` or `\\?\F:\` -> `"F"`).
-fn drive_letter_from_volume_root(volume_root: &Path) -> Option<String> {
-    let s = volume_root.to_string_lossy();
-    let s = s.trim_end_matches('\\');
-    let drive = if s.len() == 2 && s.as_bytes()[1] == b':' {
-        &s[..1]
-    } else if s.len() >= 4 && s.starts_with("\\\\?\\") {
-        let rest = &s[4..];
-        if rest.len() == 2 && rest.as_bytes()[1] == b':' {
-            &rest[..1]
-        } else {
-            return None;
-        }
-    } else {
+/// 通过 GetVolumePathNameW + GetVolumeInformationW 获取路径所在卷的文件系统名（如 `"NTFS"`、`"exFAT"`）。
+/// 仅 Windows 有效；MFT 扫描只支持 NTFS 卷。
+#[allow(unsafe_code)]
+pub fn get_volume_filesystem(path: &str) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW};
+    let wide: Vec<u16> = std::path::Path::new(path)
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let mut root = [0u16; 261];
+    let ok = unsafe { GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) };
+    if ok == 0 {
         return None;
+    }
+    let mut fs_name = [0u16; 64];
+    let ok = unsafe {
+        GetVolumeInformationW(
+            root.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            fs_name.as_mut_ptr(),
+            fs_name.len() as u32,
+        )
     };
-    if !drive.as_bytes()[0].is_ascii_alphabetic() {
+    if ok == 0 {
         return None;
     }
-    Some(drive.to_uppercase())
+    let len = fs_name
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(fs_name.len());
+    Some(String::from_utf16_lossy(&fs_name[..len]))
 }
 
-fn to_disk_analyzer_error(e: NtfsReaderError) -> DiskAnalyzerError {
-    let msg = match &e {
-        NtfsReaderError::ElevationError => {
-            "NTFS volume access requires elevated (admin) privileges".to_string()
-        }
-        NtfsReaderError::IOError(io) => format!("MFT read I/O error: {}", io),
-        _ => format!("MFT error: {}", e),
+/// 卷序列号与 USN 日志的当前位置，供扫描缓存判断卷是否有变化（见 `cache` 模块）。
+/// 无法获取序列号时为 None；日志未启用或无权打开卷（需管理员权限）时 `journal` 为 None
+#[allow(unsafe_code)]
+pub(crate) fn query_volume_state(volume_root: &VolumeRoot) -> Option<VolumeState> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::GetVolumeInformationW;
+    use windows_sys::Win32::System::Ioctl::{FSCTL_QUERY_USN_JOURNAL, USN_JOURNAL_DATA_V0};
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+    let root: Vec<u16> = std::ffi::OsStr::new(&volume_root.root_path())
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let mut serial = 0u32;
+    let ok = unsafe {
+        GetVolumeInformationW(
+            root.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            &mut serial,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0,
+        )
     };
-    DiskAnalyzerError::Io(std::io::Error::new(std::io::ErrorKind::Other, msg))
+    if ok == 0 {
+        return None;
+    }
+    let journal = std::fs::File::open(volume_root.device_path())
+        .ok()
+        .and_then(|volume| {
+            let mut data: USN_JOURNAL_DATA_V0 = unsafe { std::mem::zeroed() };
+            let mut returned = 0u32;
+            let ok = unsafe {
+                DeviceIoControl(
+                    volume.as_raw_handle() as _,
+                    FSCTL_QUERY_USN_JOURNAL,
+                    std::ptr::null(),
+                    0,
+                    &mut data as *mut USN_JOURNAL_DATA_V0 as *mut _,
+                    std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
+                    &mut returned,
+                    std::ptr::null_mut(),
+                )
+            };
+            (ok != 0).then_some(JournalPosition {
+                journal_id: data.UsnJournalID,
+                next_usn: data.NextUsn,
+            })
+        });
+    Some(VolumeState {
+        serial: u64::from(serial),
+        journal,
+    })
 }
 
-/// Normalize path from ntfs-reader (e.g. `\\.\F:\dir\file` 或 `C:\dir\file`) to `F:\dir\file`，
-/// 保证盘符后必有反斜杠以便正确做父路径切分（如 `C:\Windows` 的 parent 为 `C:\`）。
-fn normalize_ntfs_path(path_str: &str, drive: &str) -> String {
-    let path_str = path_str.trim_end_matches('\\').replace('/', "\\");
-    let prefix = format!(r"\\.\{}:", drive);
-    let rest = if path_str.as_str().starts_with(&prefix) {
-        path_str[prefix.len()..].trim_start_matches('\\')
-    } else if path_str.starts_with("\\\\?\\") && path_str.len() >= 6 {
-        let rest = &path_str[4..];
-        if rest.starts_with(&format!("{}:", drive)) {
-            rest[2..].trim_start_matches('\\')
-        } else {
-            return path_str;
+/// 权限不足映射为 `NeedsElevation`（调用方据此提示以管理员身份运行），其余读取失败为 `MftReadFailed`
+fn to_disk_analyzer_error(e: NtfsReaderError) -> DiskAnalyzerError {
+    match e {
+        NtfsReaderError::ElevationError => DiskAnalyzerError::NeedsElevation(
+            "NTFS volume access requires elevated (admin) privileges".to_string(),
+        ),
+        NtfsReaderError::IOError(io) if io.kind() == std::io::ErrorKind::PermissionDenied => {
+            DiskAnalyzerError::NeedsElevation(format!("cannot open NTFS volume: {}", io))
         }
-    } else if path_str.len() >= 2 && path_str.as_bytes()[1] == b':' {
-        let c = path_str.chars().next().unwrap();
-        if drive
-            .chars()
-            .next()
-            .map(|d| c.eq_ignore_ascii_case(&d))
-            .unwrap_or(false)
-        {
-            let after = path_str[2..].trim_start_matches('\\');
-            return if after.is_empty() {
-                format!(r"{}:\", drive)
-            } else {
-                format!(r"{}:\{}", drive, after)
-            };
+        NtfsReaderError::IOError(io) => {
+            DiskAnalyzerError::MftReadFailed(format!("I/O error: {}", io))
         }
-        return path_str;
-    } else {
-        return path_str;
-    };
-    if rest.is_empty() {
-        format!(r"{}:\", drive)
-    } else {
-        format!(r"{}:\{}", drive, rest)
+        e => DiskAnalyzerError::MftReadFailed(e.to_string()),
     }
 }
 
-const MAX_DEPTH: usize = 10;
-const MAX_CHILDREN_PER_DIR: usize = 500;
+/// 文件系统已知且不是 NTFS 时返回 `VolumeNotNtfs`；无法获取文件系统名时照常尝试打开
+fn require_ntfs(volume: &str, filesystem: Option<&str>) -> Result<(), DiskAnalyzerError> {
+    match filesystem {
+        Some(fs) if !fs.eq_ignore_ascii_case("NTFS") => Err(DiskAnalyzerError::VolumeNotNtfs(
+            format!("{} ({})", volume, fs),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 确认卷为 NTFS 后打开，供 ntfs-reader 读取 $MFT
+fn open_ntfs_volume(volume_root: &VolumeRoot) -> Result<Volume, DiskAnalyzerError> {
+    let root = volume_root.root_path();
+    require_ntfs(&root, get_volume_filesystem(&root).as_deref())?;
+    Volume::new(volume_root.device_path().as_str()).map_err(to_disk_analyzer_error)
+}
+
 /// 返回给前端的树与 Treemap 一致：只保留 6 层、每层最多 250 子节点，减小 payload 与解析时间
 const MAX_DEPTH_RETURN: usize = 6;
 const MAX_CHILDREN_PER_DIR_RETURN: usize = 250;
 /// 进度回调间隔（增大以略减 IPC 次数）
 const PROGRESS_EVERY: u64 = 10_000;
-/// build_tree 阶段每构建多少节点上报一次进度
-const BUILD_TREE_PROGRESS_EVERY: u64 = 10_000;
 /// 供前端摘要与 AI 分析的前 N 大文件数量
 const TOP_FILES_FOR_RESULT: usize = 500;
 
@@ -162,32 +224,17 @@ fn path_under_volume_ascii(path: &str, vol_trim: &str) -> bool {
         .all(|(a, b)| a.eq_ignore_ascii_case(b))
 }
 
-/// Whether path is a Windows volume root (e.g. `C:\`, `D:\`).
-pub fn is_windows_volume_root(path: &Path) -> bool {
-    let s = path.to_string_lossy();
-    let s = s.trim_end_matches('\\');
-    if s.len() == 2 {
-        let b = s.as_bytes();
-        return b[0].is_ascii_alphabetic() && b[1] == b':';
-    }
-    if s.len() >= 4 && s.starts_with("\\\\?\\") {
-        let rest = &s[4..];
-        return rest.len() == 2
-            && rest.as_bytes()[0].is_ascii_alphabetic()
-            && rest.as_bytes()[1] == b':';
-    }
-    false
-}
-
 /// 「前 N 大文件」功能的默认 N（如 100）。
 pub const TOP_FILES_DEFAULT_N: usize = 100;
 
 /// 仅获取卷上按文件大小最大的前 N 个**文件**（不含目录）。
-/// 优化：枚举时用最小堆维护前 N，**不构建整棵树**，省去阶段 3，内存仅 O(N)。
+/// 优化：枚举时用堆维护前 N，**不构建整棵树**，省去阶段 3，内存仅 O(N)。
 /// 若只需“最大的 100 个文件”场景，比完整 `scan_volume_mft` 快且省内存。
+/// 结果按 `top_file_order` 排列，同大小的文件顺序也固定。只有满足 `filter` 的文件参与排名。
 pub fn scan_volume_mft_top_files(
     path: &str,
     n: usize,
+    filter: &TopFilesFilter,
     progress: Option<&ProgressCb>,
 ) -> Result<Vec<TopFileEntry>, DiskAnalyzerError> {
     let path_buf = normalize_path(path);
@@ -199,23 +246,14 @@ pub fn scan_volume_mft_top_files(
     }
     let path_buf = std::fs::canonicalize(&path_buf)
         .map_err(|e| DiskAnalyzerError::InvalidPath(format!("cannot resolve path: {}", e)))?;
-    if !is_windows_volume_root(&path_buf) {
-        return Err(DiskAnalyzerError::InvalidPath(
-            "not a volume root".to_string(),
-        ));
-    }
+    let volume_root = VolumeRoot::parse(&path_buf)
+        .ok_or_else(|| DiskAnalyzerError::InvalidPath("not a volume root".to_string()))?;
 
-    let drive = drive_letter_from_volume_root(&path_buf).ok_or_else(|| {
-        DiskAnalyzerError::InvalidPath("cannot get drive letter from volume root".to_string())
-    })?;
-
-    let volume_path = format!(r"\\.\{}:", drive);
-    let volume = Volume::new(volume_path.as_str()).map_err(to_disk_analyzer_error)?;
+    let volume = open_ntfs_volume(&volume_root)?;
     let mft = Mft::new(volume).map_err(to_disk_analyzer_error)?;
 
-    let vol_trim_for_filter = format!("{}:", drive);
-    let cap = n.saturating_add(1).min(1_000_000);
-    let mut heap: BinaryHeap<Reverse<(u64, String, Option<u64>)>> = BinaryHeap::with_capacity(cap);
+    let vol_trim_for_filter = volume_root.path_prefix();
+    let mut top = TopFiles::new(n);
     let mut cache = HashMapCache::default();
     let counter = AtomicU64::new(0);
 
@@ -225,8 +263,10 @@ pub fn scan_volume_mft_top_files(
             return;
         }
         let path_str = info.path.to_string_lossy();
-        let full_path = normalize_ntfs_path(&path_str, &drive);
-        if !path_under_volume_ascii(&full_path, &vol_trim_for_filter) {
+        let full_path = volume_root.normalize_path(&path_str);
+        if !path_under_volume_ascii(&full_path, &vol_trim_for_filter)
+            || is_system_metafile(&full_path, &vol_trim_for_filter)
+        {
             return;
         }
         let modified = info.modified.and_then(|t| {
@@ -243,10 +283,8 @@ pub fn scan_volume_mft_top_files(
                 cb(c, &full_path);
             }
         }
-        let size = info.size;
-        heap.push(Reverse((size, full_path, modified)));
-        while heap.len() > n {
-            heap.pop();
+        if filter.matches(&full_path, info.size, modified) {
+            top.push(full_path, info.size, modified);
         }
     });
 
@@ -254,82 +292,128 @@ pub fn scan_volume_mft_top_files(
         cb(counter.load(Ordering::Relaxed), path);
     }
 
-    let mut list: Vec<_> = heap
-        .into_iter()
-        .map(|Reverse((size, path, modified))| TopFileEntry {
-            path,
-            size,
-            modified,
-        })
-        .collect();
-    list.sort_by(|a, b| b.size.cmp(&a.size));
-    Ok(list)
+    Ok(top.finish())
 }
 
-/// Single MFT-derived record for tree building.
-struct MftRecord {
-    full_path: String,
-    size: u64,
-    is_dir: bool,
-    modified: Option<u64>,
+/// 与 `scan_volume_mft_top_files` 结果相同，但不经 ntfs-reader 一次性加载 $MFT：直接读卷，
+/// 每读入一块（`MFT_CHUNK_BYTES`）就解析并汇总其中的记录、上报进度，内存只需一张名称表。
+/// 极度碎片化的 $MFT（数据运行溢出到扩展记录）返回错误，此时应改用 `scan_volume_mft_top_files`。
+pub fn scan_volume_mft_streaming(
+    path: &str,
+    n: usize,
+    filter: &TopFilesFilter,
+    progress: Option<&ProgressCb>,
+) -> Result<Vec<TopFileEntry>, DiskAnalyzerError> {
+    let path_buf = normalize_path(path);
+    if !path_buf.exists() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "path does not exist: {}",
+            path
+        )));
+    }
+    let path_buf = std::fs::canonicalize(&path_buf)
+        .map_err(|e| DiskAnalyzerError::InvalidPath(format!("cannot resolve path: {}", e)))?;
+    let volume_root = VolumeRoot::parse(&path_buf)
+        .ok_or_else(|| DiskAnalyzerError::InvalidPath("not a volume root".to_string()))?;
+
+    let root = volume_root.root_path();
+    require_ntfs(&root, get_volume_filesystem(&root).as_deref())?;
+    let volume = std::fs::File::open(volume_root.device_path()).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            DiskAnalyzerError::NeedsElevation(
+                "NTFS volume access requires elevated (admin) privileges".to_string(),
+            )
+        } else {
+            e.into()
+        }
+    })?;
+    let (source, geometry) = open_mft(volume)?;
+    let total_records = source.mft_bytes() / geometry.record_size as u64;
+    let reader = StreamingMftReader::new(source, geometry.record_size).on_chunk(|records| {
+        if let Some(cb) = progress {
+            cb(
+                records,
+                &format!("[scan:mft] {} / {} MFT records", records, total_records),
+            );
+        }
+    });
+    let top = stream_top_files(reader, &volume_root.path_prefix(), n, filter)?;
+
+    if let Some(cb) = progress {
+        cb(total_records, path);
+    }
+    Ok(top)
 }
 
-/// 从直接大小与子索引一次性汇总递归大小（避免枚举时每文件 O(深度) 的祖先更新）
-fn compute_recursive_sizes(
-    records: &[MftRecord],
-    child_index: &HashMap<String, Vec<usize>>,
-    direct_sizes: &HashMap<String, u64>,
-    volume_root_trim: &str,
-    volume_root_key: &str,
-) -> HashMap<String, u64> {
-    let mut paths: Vec<String> = records
-        .iter()
-        .map(|r| r.full_path.trim_end_matches('\\').to_string())
-        .collect();
-    if !paths
-        .iter()
-        .any(|p| p.eq_ignore_ascii_case(volume_root_trim))
-    {
-        paths.push(volume_root_trim.to_string());
+/// 枚举卷上所有扩展名属于 `extensions` 的**文件**（如 RAW 照片、视频），按所在目录分组、
+/// 组与组内均按大小降序。与前 N 大文件不同，返回全部命中项；不建树，内存 O(命中数)。
+pub fn scan_volume_mft_by_extension(
+    path: &str,
+    extensions: &[&str],
+    progress: Option<&ProgressCb>,
+) -> Result<Vec<FolderGroup>, DiskAnalyzerError> {
+    let path_buf = normalize_path(path);
+    if !path_buf.exists() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "path does not exist: {}",
+            path
+        )));
     }
-    paths.sort();
-    paths.dedup();
-    paths.sort_by_cached_key(|p| std::cmp::Reverse(p.matches('\\').count()));
-    let mut recursive_sizes: HashMap<String, u64> = HashMap::new();
-    for path in paths {
-        let direct = direct_sizes.get(&path).copied().unwrap_or(0);
-        let child_sum: u64 = {
-            let key = if path.eq_ignore_ascii_case(volume_root_trim) {
-                volume_root_key
-            } else {
-                &path
-            };
-            child_index
-                .get(key)
-                .map(|indices| {
-                    indices
-                        .iter()
-                        .map(|&i| {
-                            let c = records[i].full_path.trim_end_matches('\\').to_string();
-                            recursive_sizes.get(&c).copied().unwrap_or(0)
-                        })
-                        .sum()
-                })
-                .unwrap_or(0)
-        };
-        recursive_sizes.insert(path, direct.saturating_add(child_sum));
+    let path_buf = std::fs::canonicalize(&path_buf)
+        .map_err(|e| DiskAnalyzerError::InvalidPath(format!("cannot resolve path: {}", e)))?;
+    let volume_root = VolumeRoot::parse(&path_buf)
+        .ok_or_else(|| DiskAnalyzerError::InvalidPath("not a volume root".to_string()))?;
+
+    let volume = open_ntfs_volume(&volume_root)?;
+    let mft = Mft::new(volume).map_err(to_disk_analyzer_error)?;
+
+    let vol_trim_for_filter = volume_root.path_prefix();
+    let mut groups = ExtensionGroups::new(extensions);
+    let mut cache = HashMapCache::default();
+    let counter = AtomicU64::new(0);
+
+    mft.iterate_files(|file| {
+        let info = FileInfo::with_cache(&mft, file, &mut cache);
+        if info.is_directory {
+            return;
+        }
+        let path_str = info.path.to_string_lossy();
+        let full_path = volume_root.normalize_path(&path_str);
+        if !path_under_volume_ascii(&full_path, &vol_trim_for_filter)
+            || is_system_metafile(&full_path, &vol_trim_for_filter)
+        {
+            return;
+        }
+        let c = counter.fetch_add(1, Ordering::Relaxed);
+        if c > 0 && c % PROGRESS_EVERY == 0 {
+            if let Some(ref cb) = progress {
+                cb(c, &full_path);
+            }
+        }
+        let modified = info
+            .modified
+            .map(|t| t.unix_timestamp())
+            .filter(|&s| s > 0)
+            .map(|s| s as u64);
+        groups.push(&full_path, info.size, modified);
+    });
+
+    if let Some(ref cb) = progress {
+        cb(counter.load(Ordering::Relaxed), path);
     }
-    recursive_sizes
+    Ok(groups.finish())
 }
 
 /// Scan volume root via MFT using ntfs-reader (Everything-style). Opens `\\.\X:`,
 /// reads $MFT into memory, iterates files with path cache, then builds tree.
+/// `opts.dirs_only` 时仍用全部文件大小汇总，但只为目录构建 `FileNode`。
 pub fn scan_volume_mft(
     path: &str,
     progress: Option<ProgressCbArc>,
-    shallow_dirs: bool,
+    opts: &ScanOptions,
 ) -> Result<ScanResult, DiskAnalyzerError> {
-    let start = Instant::now();
+    let mut clock = PhaseClock::start();
+    let started_at = unix_now();
     let path_buf = normalize_path(path);
     if !path_buf.exists() {
         return Err(DiskAnalyzerError::InvalidPath(format!(
@@ -339,57 +423,56 @@ pub fn scan_volume_mft(
     }
     let path_buf = std::fs::canonicalize(&path_buf)
         .map_err(|e| DiskAnalyzerError::InvalidPath(format!("cannot resolve path: {}", e)))?;
-    if !is_windows_volume_root(&path_buf) {
-        return Err(DiskAnalyzerError::InvalidPath(
-            "not a volume root".to_string(),
-        ));
-    }
+    let volume_root = VolumeRoot::parse(&path_buf)
+        .ok_or_else(|| DiskAnalyzerError::InvalidPath("not a volume root".to_string()))?;
 
-    let volume_root_str = path_buf
-        .to_string_lossy()
-        .trim_end_matches('\\')
-        .to_string();
-    let volume_root_str = if volume_root_str.ends_with(':') {
-        format!("{}\\", volume_root_str)
-    } else {
-        volume_root_str
-    };
-
-    let drive = drive_letter_from_volume_root(&path_buf).ok_or_else(|| {
-        DiskAnalyzerError::InvalidPath("cannot get drive letter from volume root".to_string())
-    })?;
+    let volume_root_str = volume_root.root_path();
+    let span = telemetry::mft_scan_span(&volume_root.path_prefix());
+    let _enter = span.enter();
 
     eprintln!(
-        "[scan:mft] starting MFT full scan for volume {} (drive {})",
+        "[scan:mft] starting MFT full scan for volume {} (device {})",
         path_buf.display(),
-        drive
+        volume_root.device_path()
     );
     if let Some(ref cb) = progress {
         cb(0, "[scan:mft] opening volume...");
     }
-    let volume_path = format!(r"\\.\{}:", drive);
-    let volume_root_trim = format!("{}:", drive);
-    let volume_root_key = format!(r"{}:\", drive);
+    let volume_root_trim = volume_root.path_prefix();
+    let volume_root_key = volume_root.root_path();
     // 使用上游 ntfs-reader API：Mft::new 一次性加载 $MFT，再 iterate_files 枚举。
-    let volume = Volume::new(volume_path.as_str()).map_err(to_disk_analyzer_error)?;
+    let volume = open_ntfs_volume(&volume_root)?;
     eprintln!("[scan:mft] volume opened: {} bytes", volume.volume_size);
     let mft = Mft::new(volume).map_err(to_disk_analyzer_error)?;
     eprintln!(
         "[scan:mft] MFT loaded into memory, max_records={}",
         mft.max_record
     );
-    let vol_trim_for_filter = format!("{}:", drive);
-    let mut records: Vec<MftRecord> = Vec::with_capacity(2_000_000);
-    let mut child_index: HashMap<String, Vec<usize>> = HashMap::new();
-    let mut direct_sizes: HashMap<String, u64> = HashMap::new();
-    let mut cache = HashMapCache::default();
+    clock.lap(TimingPhase::ReadMft);
+    if opts.is_cancelled() {
+        return Err(DiskAnalyzerError::Cancelled);
+    }
+    let vol_trim_for_filter = volume_root.path_prefix();
+    let excludes: Vec<String> = opts
+        .resolved_excludes()
+        .iter()
+        .map(|dir| volume_root.normalize_path(&dir.to_string_lossy()))
+        .collect();
+    let patterns = opts.compiled_exclude_patterns()?;
+    let budget = opts.mft_budget;
+    let mut agg = MftAggregate::with_budget(&volume_root_trim, &budget);
+    let mut cache: CappedCache<HashMapCache> = CappedCache::new(budget.max_path_cache);
     let counter = AtomicU64::new(0);
     let filtered_count = AtomicU64::new(0);
     let filtered_file_size = AtomicU64::new(0); // 仅非目录，用于 total_size
     mft.iterate_files(|file| {
-        let info = FileInfo::with_cache(&mft, file, &mut cache);
+        // iterate_files 无法中途退出：取消后跳过剩余记录，枚举结束后返回 Cancelled
+        if opts.is_cancelled() {
+            return;
+        }
+        let info = FileInfo::with_cache(&mft, file, cache.next());
         let path_str = info.path.to_string_lossy();
-        let full_path = normalize_ntfs_path(&path_str, &drive);
+        let full_path = volume_root.normalize_path(&path_str);
         if !path_under_volume_ascii(&full_path, &vol_trim_for_filter) {
             filtered_count.fetch_add(1, Ordering::Relaxed);
             if !info.is_directory {
@@ -397,6 +480,21 @@ pub fn scan_volume_mft(
             }
             return;
         }
+        // 应用数据目录等排除项：不进入树、不计入大小
+        if excludes
+            .iter()
+            .any(|dir| is_under(&full_path, dir, CaseSensitivity::Insensitive))
+            || patterns.matches_with_ancestors(&full_path)
+        {
+            return;
+        }
+        // 文件名含未配对代理项时有损路径无法用于删除，另存原始 UTF-16 路径
+        let raw_path = if info.path.to_str().is_none() {
+            let wide: Vec<u16> = info.path.as_os_str().encode_wide().collect();
+            restore_unpaired_surrogates(&full_path, &wide)
+        } else {
+            None
+        };
         let modified = info.modified.and_then(|t| {
             let s = t.unix_timestamp();
             if s > 0 {
@@ -411,27 +509,33 @@ pub fn scan_volume_mft(
                 cb(c, &full_path);
             }
         }
-        records.push(MftRecord {
-            full_path: full_path.clone(),
-            size: info.size,
-            is_dir: info.is_directory,
-            modified,
-        });
-        let idx = records.len() - 1;
-        let path_trim = full_path.trim_end_matches('\\');
-        if !path_trim.eq_ignore_ascii_case(&volume_root_trim) {
-            if let Some(i) = full_path.rfind('\\') {
-                let parent = full_path[..i].to_string();
-                child_index.entry(parent).or_default().push(idx);
-            }
-        }
-        let s = info.size;
-        direct_sizes
-            .entry(path_trim.to_string())
-            .and_modify(|v| *v = v.saturating_add(s))
-            .or_insert(s);
+        agg.push_with_raw(full_path, raw_path, info.size, info.is_directory, modified);
     });
+    drop(cache);
+    if opts.is_cancelled() {
+        return Err(DiskAnalyzerError::Cancelled);
+    }
+    clock.lap(TimingPhase::Enumerate);
+    // 所有用户文件（非目录）的 size 之和；path 过滤的与系统元文件不计入（避免重复/膨胀）
+    let sum_all_file_sizes = agg.sum_file_sizes();
+    let degraded = agg.is_degraded();
+    let MftAggregate {
+        records,
+        child_index,
+        direct_sizes,
+        system_reserved_bytes,
+        folded_counts,
+        folded_bytes,
+        ..
+    } = agg;
+    if degraded {
+        eprintln!(
+            "[scan:mft] 文件记录超出预算 {}：{} 字节的文件只计入所在目录",
+            budget.max_file_records, folded_bytes
+        );
+    }
     let n_records = counter.load(Ordering::Relaxed);
+    span.record("record_count", n_records);
     let n_filtered = filtered_count.load(Ordering::Relaxed);
     let size_filtered = filtered_file_size.load(Ordering::Relaxed);
     if n_filtered > 0 || size_filtered > 0 {
@@ -452,10 +556,35 @@ pub fn scan_volume_mft(
         &volume_root_key,
     );
 
-    // 所有文件（非目录）的 size 之和；path 过滤的不计入（避免重复/膨胀）
-    let sum_all_file_sizes: u64 = records.iter().filter(|r| !r.is_dir).map(|r| r.size).sum();
-    let t_after_mft_read = Instant::now();
-    let t_after_iterate = t_after_mft_read;
+    // 只扫描目录模式：与递归大小同法汇总每个目录下的文件数
+    let recursive_file_counts = opts.dirs_only.then(|| {
+        let mut direct_counts: HashMap<String, u64> = records
+            .iter()
+            .filter(|r| !r.is_dir)
+            .map(|r| (r.full_path.trim_end_matches('\\').to_string(), 1))
+            .collect();
+        // 降级模式下未保存记录的文件计入父目录自身
+        for (parent, count) in &folded_counts {
+            *direct_counts.entry(parent.clone()).or_insert(0) += count;
+        }
+        compute_recursive_sizes(
+            &records,
+            &child_index,
+            &direct_counts,
+            &volume_root_trim,
+            &volume_root_key,
+        )
+    });
+
+    let dir_modified = opts.dir_modified_from_descendants.then(|| {
+        compute_recursive_modified(&records, &child_index, &volume_root_trim, &volume_root_key)
+    });
+
+    eprintln!(
+        "[scan:mft] system metafiles + root dir: {} bytes (reported separately)",
+        system_reserved_bytes
+    );
+    clock.lap(TimingPhase::Index);
 
     // 与标准模式一致：根节点 name/path 与 scan_path_with_progress -> build_tree 一致
     let root_name = path_buf
@@ -473,208 +602,99 @@ pub fn scan_volume_mft(
         &volume_root_key,
         &root_name,
         &root_path_str,
-        shallow_dirs,
+        opts.shallow_dirs,
+        opts.tree_build_options(),
+        recursive_file_counts.as_ref(),
+        dir_modified.as_ref(),
         progress.as_ref(),
         n_records,
     )?;
-    let t_after_build_tree = Instant::now();
-    let scan_time_ms = start.elapsed().as_millis() as u64;
+    clock.lap(TimingPhase::BuildTree);
     // total_size 使用所有文件 size 之和，与树结构无关，最准确
     let total_size = sum_all_file_sizes;
     eprintln!(
         "[scan:mft] build_tree done: file_count={}, total_size={}, elapsed_ms={}",
-        file_count, total_size, scan_time_ms
+        file_count,
+        total_size,
+        clock.elapsed_ms()
     );
 
-    if std::env::var("MFT_TIMING").is_ok() {
-        let get_mft_ms = t_after_mft_read.duration_since(start).as_millis();
-        let iterate_ms = t_after_iterate.duration_since(t_after_mft_read).as_millis();
-        let build_tree_ms = t_after_build_tree
-            .duration_since(t_after_iterate)
-            .as_millis();
-        let total_ms = scan_time_ms as u128;
-        eprintln!("[MFT_TIMING] ---------- MFT scan phase timing (ms) ----------");
-        eprintln!(
-            "[MFT_TIMING] 1. get MFT content (Volume + Mft::new): {:>8} ms  ({:>5.1}%)",
-            get_mft_ms,
-            100.0 * get_mft_ms as f64 / total_ms as f64
-        );
-        eprintln!(
-            "[MFT_TIMING] 2. iterate_files + collect records:    {:>8} ms  ({:>5.1}%)",
-            iterate_ms,
-            100.0 * iterate_ms as f64 / total_ms as f64
-        );
-        eprintln!(
-            "[MFT_TIMING] 3. build_tree (parallel):              {:>8} ms  ({:>5.1}%)",
-            build_tree_ms,
-            100.0 * build_tree_ms as f64 / total_ms as f64
-        );
-        eprintln!(
-            "[MFT_TIMING] total:                                {:>8} ms  records={}",
-            total_ms,
-            records.len()
-        );
-        eprintln!("[MFT_TIMING] ---------- parallelization notes ----------");
-        eprintln!("[MFT_TIMING] - phase 1: disk I/O, not parallelizable.");
-        eprintln!("[MFT_TIMING] - phase 2: ntfs-reader is single-threaded.");
-        eprintln!("[MFT_TIMING] - phase 3: already parallel (chunked map/index + par_iter).");
-    }
-
-    let (volume_total_bytes, volume_free_bytes) =
-        match get_volume_space_bytes(&format!(r"{}:\", drive)) {
-            Some((t, f)) => (Some(t), Some(f)),
-            None => (None, None),
-        };
+    let (volume_total_bytes, volume_free_bytes) = match get_volume_space_bytes(&volume_root_key) {
+        Some((t, f)) => (Some(t), Some(f)),
+        None => (None, None),
+    };
 
     let root_pruned = prune_tree_for_display(root, 0);
     let top_files = Some(build_top_files_from_records(&records, TOP_FILES_FOR_RESULT));
+    clock.lap(TimingPhase::Finish);
+    let scan_time_ms = clock.elapsed_ms();
+    let timing = ScanTiming {
+        cache_resets: cache.resets(),
+        degraded,
+        ..clock.into_timing(records.len() as u64)
+    };
+    if std::env::var("MFT_TIMING").is_ok() {
+        print_timing(&timing, scan_time_ms);
+    }
 
     Ok(ScanResult {
         root: root_pruned,
         scan_time_ms,
         file_count,
         total_size,
-        scan_warning: None,
+        scan_warning: degraded.then(|| {
+            format!(
+                "文件数超过内存预算（{} 条），超出部分的文件只计入所在目录大小，不单独显示",
+                budget.max_file_records
+            )
+        }),
         volume_total_bytes,
         volume_free_bytes,
         top_files,
+        system_reserved_bytes: Some(system_reserved_bytes),
+        naive_total_size: None,
+        denied_dirs: None,
+        skipped_paths: None,
+        denied_paths: None,
+        meta: Some(opts.scan_meta(ScanStrategy::Mft, &root_path_str, started_at)),
+        timing: Some(timing),
     })
 }
 
-/// 从 records + index( indices ) 取根节点信息，再构建子树；建树过程中用 display_count 上报进度，避免前端数字回跳。
-fn build_tree_from_mft_records(
-    records: &[MftRecord],
-    child_index: &HashMap<String, Vec<usize>>,
-    recursive_sizes: &HashMap<String, u64>,
-    volume_root_trim: &str,
-    volume_root_key: &str,
-    root_name: &str,
-    root_path_str: &str,
-    shallow_dirs: bool,
-    progress: Option<&ProgressCbArc>,
-    display_count: u64,
-) -> Result<(FileNode, u64, u64), DiskAnalyzerError> {
-    let root_record = records.iter().find(|r| {
-        r.full_path
-            .trim_end_matches('\\')
-            .eq_ignore_ascii_case(volume_root_trim)
-    });
-    let (root_size, root_modified) = root_record
-        .map(|r| (r.size, r.modified))
-        .unwrap_or((0u64, None));
-
-    let direct_indices: Vec<usize> = child_index
-        .get(volume_root_key)
-        .or_else(|| child_index.get(volume_root_trim))
-        .or_else(|| {
-            child_index
-                .keys()
-                .find(|k| {
-                    k.eq_ignore_ascii_case(volume_root_key)
-                        || k.eq_ignore_ascii_case(volume_root_trim)
-                })
-                .and_then(|k| child_index.get(k))
-        })
-        .cloned()
-        .unwrap_or_default();
-
-    let nodes_built = AtomicU64::new(0);
-    let last_reported = AtomicU64::new(0);
-
-    let child_nodes: Vec<FileNode> = direct_indices
-        .par_iter()
-        .map(|&idx| {
-            let rec = &records[idx];
-            let name = rec
-                .full_path
-                .rsplit('\\')
-                .next()
-                .unwrap_or(rec.full_path.as_str());
-            let is_shallow = shallow_dirs
-                && rec.is_dir
-                && SHALLOW_DIR_NAMES
-                    .iter()
-                    .any(|&s| s.eq_ignore_ascii_case(name));
-            let path = rec.full_path.as_str();
-            if is_shallow {
-                let size = recursive_sizes
-                    .get(path.trim_end_matches('\\'))
-                    .copied()
-                    .unwrap_or(rec.size);
-                FileNode {
-                    path: path.to_string(),
-                    name: name.to_string(),
-                    size,
-                    is_dir: true,
-                    modified: rec.modified,
-                    children: vec![],
-                }
-            } else {
-                let (node, _cnt) = build_subtree_from_indices(
-                    records,
-                    child_index,
-                    recursive_sizes,
-                    path,
-                    name,
-                    1,
-                    shallow_dirs,
-                    &nodes_built,
-                    &last_reported,
-                    progress,
-                    display_count,
-                );
-                node
-            }
-        })
-        .collect();
-
-    let mut total_size = root_size;
-    let mut file_count = 1u64;
-    for c in &child_nodes {
-        total_size += c.size;
-        file_count += count_nodes(c);
-    }
-
-    // total_size 必须基于 recursive_sizes 中卷根的递归总大小，而非建树求和。
-    // 建树时因 MAX_CHILDREN_PER_DIR 截断，求和会漏掉大量子项；recursive_sizes 基于全部 records 计算，准确。
-    let total_size = recursive_sizes
-        .get(volume_root_trim)
-        .or_else(|| recursive_sizes.get(volume_root_key.trim_end_matches('\\')))
-        .copied()
-        .unwrap_or(total_size);
-
-    let root = FileNode {
-        path: root_path_str.to_string(),
-        name: root_name.to_string(),
-        size: total_size,
-        is_dir: true,
-        modified: root_modified,
-        children: child_nodes,
-    };
-    Ok((root, file_count, total_size))
-}
-
-fn count_nodes(n: &FileNode) -> u64 {
-    if n.children.is_empty() {
-        return 1;
+/// `MFT_TIMING=1` 时打印各阶段耗时
+fn print_timing(timing: &ScanTiming, total_ms: u64) {
+    eprintln!("[MFT_TIMING] ---------- MFT scan phase timing (ms) ----------");
+    for p in &timing.phases {
+        eprintln!(
+            "[MFT_TIMING] {:<16} {:>8} ms  ({:>5.1}%)  {}",
+            format!("{:?}", p.phase),
+            p.ms,
+            100.0 * p.ms as f64 / total_ms.max(1) as f64,
+            p.phase.bound()
+        );
     }
-    1 + n.children.iter().map(count_nodes).sum::<u64>()
+    eprintln!(
+        "[MFT_TIMING] total:           {:>8} ms  records={}  cache_resets={}",
+        total_ms, timing.records, timing.cache_resets
+    );
 }
 
-/// 剪枝树以匹配前端 Treemap（深度 6、每层最多 250 子节点，按 size 取 top），减小 payload 与解析时间
+/// 剪枝树以匹配前端 Treemap（深度 6、每层最多 250 子节点，按 size 取 top），减小 payload 与解析时间；
+/// 被剪掉子节点的目录与建树时被截断的一样标记 `has_more`
 fn prune_tree_for_display(root: FileNode, depth: usize) -> FileNode {
+    let count = root.children_count.unwrap_or(root.children.len() as u64);
     if depth >= MAX_DEPTH_RETURN {
+        let has_more = root.has_more || !root.children.is_empty();
         return FileNode {
-            path: root.path,
-            name: root.name,
-            size: root.size,
-            is_dir: root.is_dir,
-            modified: root.modified,
             children: vec![],
+            children_count: has_more.then_some(count),
+            has_more,
+            ..root
         };
     }
     let mut children = root.children;
-    if children.len() > MAX_CHILDREN_PER_DIR_RETURN {
+    let pruned = children.len() > MAX_CHILDREN_PER_DIR_RETURN;
+    if pruned {
         children.sort_by(|a, b| b.size.cmp(&a.size));
         children.truncate(MAX_CHILDREN_PER_DIR_RETURN);
     }
@@ -682,151 +702,55 @@ fn prune_tree_for_display(root: FileNode, depth: usize) -> FileNode {
         .into_iter()
         .map(|c| prune_tree_for_display(c, depth + 1))
         .collect();
+    let has_more = root.has_more || pruned;
     FileNode {
-        path: root.path,
-        name: root.name,
-        size: root.size,
-        is_dir: root.is_dir,
-        modified: root.modified,
         children,
+        children_count: has_more.then_some(count),
+        has_more,
+        ..root
     }
 }
 
-/// 从 records 中取前 N 大文件（仅文件，不含目录），供前端摘要与 AI 分析
+/// 从 records 中取前 N 大文件（仅文件，不含目录），供前端摘要与 AI 分析；顺序见 `top_file_order`
 fn build_top_files_from_records(records: &[MftRecord], n: usize) -> Vec<TopFileEntry> {
-    let mut files: Vec<(&MftRecord, u64)> = records
-        .iter()
-        .filter(|r| !r.is_dir)
-        .map(|r| (r, r.size))
-        .collect();
-    files.sort_by(|a, b| b.1.cmp(&a.1));
-    files
-        .into_iter()
-        .take(n)
-        .map(|(r, _)| TopFileEntry {
-            path: r.full_path.clone(),
-            size: r.size,
-            modified: r.modified,
-        })
-        .collect()
+    let mut top = TopFiles::new(n);
+    for r in records.iter().filter(|r| !r.is_dir) {
+        top.push(r.full_path.clone(), r.size, r.modified);
+    }
+    top.finish()
 }
 
-/// 使用 indices 版 index 建子树，并周期性上报进度（用 display_count 保持前端数字不变），避免前端长时间无响应。
-fn build_subtree_from_indices(
-    records: &[MftRecord],
-    index: &HashMap<String, Vec<usize>>,
-    recursive_sizes: &HashMap<String, u64>,
-    path_prefix: &str,
-    name: &str,
-    depth: usize,
-    shallow_dirs: bool,
-    nodes_built: &AtomicU64,
-    last_reported: &AtomicU64,
-    progress: Option<&ProgressCbArc>,
-    display_count: u64,
-) -> (FileNode, u64) {
-    let children_indices = index.get(path_prefix).map(|v| v.as_slice()).unwrap_or(&[]);
-    let mut size = 0u64;
-    let mut file_count = 0u64;
-    let modified: Option<u64> = None;
-
-    let mut children: Vec<FileNode> =
-        Vec::with_capacity(children_indices.len().min(MAX_CHILDREN_PER_DIR));
-    for &idx in children_indices {
-        let rec = &records[idx];
-        if rec.full_path.eq_ignore_ascii_case(path_prefix) {
-            continue;
-        }
-        let child_name = rec
-            .full_path
-            .rsplit('\\')
-            .next()
-            .unwrap_or(rec.full_path.as_str());
-        let child_path = rec.full_path.as_str();
-        let is_shallow = shallow_dirs
-            && rec.is_dir
-            && SHALLOW_DIR_NAMES
-                .iter()
-                .any(|&s| s.eq_ignore_ascii_case(child_name));
-        if is_shallow {
-            let child_size = recursive_sizes
-                .get(child_path.trim_end_matches('\\'))
-                .copied()
-                .unwrap_or(rec.size);
-            size += child_size;
-            file_count += 1;
-            children.push(FileNode {
-                path: child_path.to_string(),
-                name: child_name.to_string(),
-                size: child_size,
-                is_dir: true,
-                modified: rec.modified,
-                children: vec![],
-            });
-        } else if depth < MAX_DEPTH {
-            let (child_node, cnt) = build_subtree_from_indices(
-                records,
-                index,
-                recursive_sizes,
-                child_path,
-                child_name,
-                depth + 1,
-                shallow_dirs,
-                nodes_built,
-                last_reported,
-                progress,
-                display_count,
-            );
-            size += child_node.size;
-            file_count += cnt;
-            children.push(child_node);
-        } else {
-            size += rec.size;
-            file_count += 1;
-            children.push(FileNode {
-                path: child_path.to_string(),
-                name: child_name.to_string(),
-                size: rec.size,
-                is_dir: rec.is_dir,
-                modified: rec.modified,
-                children: vec![],
-            });
-        }
-        if children.len() >= MAX_CHILDREN_PER_DIR {
-            break;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ntfs_reader_errors_map_to_dedicated_variants() {
+        assert!(matches!(
+            to_disk_analyzer_error(NtfsReaderError::ElevationError),
+            DiskAnalyzerError::NeedsElevation(_)
+        ));
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            to_disk_analyzer_error(NtfsReaderError::IOError(denied)),
+            DiskAnalyzerError::NeedsElevation(_)
+        ));
+        let bad_sector = std::io::Error::other("bad sector");
+        match to_disk_analyzer_error(NtfsReaderError::IOError(bad_sector)) {
+            DiskAnalyzerError::MftReadFailed(msg) => assert!(msg.contains("bad sector")),
+            e => panic!("unexpected {:?}", e),
         }
     }
 
-    // 若因 MAX_CHILDREN_PER_DIR 截断，size 只包含前 500 个子项之和，会丢失大量数据。
-    // 使用 recursive_sizes 获取该目录的真实递归总大小，确保 total_size 正确。
-    let size = if children_indices.len() > children.len() {
-        recursive_sizes
-            .get(path_prefix.trim_end_matches('\\'))
-            .copied()
-            .unwrap_or(size)
-    } else {
-        size
-    };
-
-    let cur = nodes_built.fetch_add(1, Ordering::Relaxed) + 1;
-    if let Some(ref cb) = progress {
-        let last = last_reported.load(Ordering::Relaxed);
-        if cur.saturating_sub(last) >= BUILD_TREE_PROGRESS_EVERY
-            && last_reported
-                .compare_exchange(last, cur, Ordering::Relaxed, Ordering::Relaxed)
-                .is_ok()
-        {
-            cb(display_count, "[scan:mft] building tree...");
+    #[test]
+    fn test_require_ntfs() {
+        assert!(require_ntfs(r"C:\", Some("NTFS")).is_ok());
+        assert!(require_ntfs(r"C:\", None).is_ok());
+        match require_ntfs(r"E:\", Some("exFAT")) {
+            Err(e @ DiskAnalyzerError::VolumeNotNtfs(_)) => {
+                assert_eq!(e.to_string(), r"Volume is not NTFS: E:\ (exFAT)");
+            }
+            r => panic!("unexpected {:?}", r),
         }
     }
-
-    let node = FileNode {
-        path: path_prefix.to_string(),
-        name: name.to_string(),
-        size,
-        is_dir: true,
-        modified,
-        children,
-    };
-    (node, file_count + 1)
 }
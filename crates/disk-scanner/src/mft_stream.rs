@@ -0,0 +1,846 @@
+//! 分块流式读取 $MFT：ntfs-reader 的 `Mft::new` 会先把整个 $MFT 读入内存（数百万条记录时达数 GB、
+//! 耗时数十秒），这里改为直接读卷：从引导扇区找到 $MFT，按其数据运行每次读取 `MFT_CHUNK_BYTES`，
+//! 逐条解析 FILE 记录并立即产出 `MftEntry`，打开卷后即可开始汇总与上报进度。
+//!
+//! 记录只带父记录号与文件名，父目录可能排在子项之后，完整路径要等全部记录读完后由 `MftNameTable`
+//! 拼出；名称表每条记录只占几十字节，远小于原始的 1 KB 记录。
+//!
+//! 这里只做字节解析，与 ntfs-reader 和 Windows API 无关，便于在所有平台上用合成卷测试。
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::TopFileEntry;
+
+use crate::filters::TopFilesFilter;
+use crate::mft_tree::{is_system_metafile, TopFiles};
+
+/// 每次从卷上读取的 $MFT 字节数
+pub(crate) const MFT_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+/// 根目录的记录号
+const ROOT_RECORD: u64 = 5;
+/// 记录 0–15 为 NTFS 系统元文件
+const FIRST_USER_RECORD: usize = 16;
+/// 更新序列（fixup）按 512 字节分段，与扇区大小无关
+const FIXUP_STRIDE: usize = 512;
+/// 拼路径时向上查找的层数上限，防止损坏的父引用成环
+const MAX_PATH_DEPTH: usize = 1024;
+/// 文件引用的低 48 位为记录号，高 16 位为序列号
+const RECORD_NUMBER_MASK: u64 = 0xFFFF_FFFF_FFFF;
+/// FILETIME（1601 年起的 100 纳秒数）与 Unix 纪元相差的秒数
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
+const ATTR_STANDARD_INFORMATION: u32 = 0x10;
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_DATA: u32 = 0x80;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+const RECORD_IN_USE: u16 = 0x01;
+const RECORD_IS_DIRECTORY: u16 = 0x02;
+/// `$FILE_NAME` 的 DOS 命名空间（8.3 短名），记录中还有其他名称时不用
+const NAMESPACE_DOS: u8 = 2;
+
+fn invalid_data(msg: &str) -> DiskAnalyzerError {
+    DiskAnalyzerError::Io(io::Error::new(io::ErrorKind::InvalidData, msg.to_string()))
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// 引导扇区中定位 $MFT 所需的几何信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NtfsGeometry {
+    pub cluster_size: u64,
+    pub record_size: usize,
+    /// $MFT 起始位置（卷内字节偏移）
+    pub mft_offset: u64,
+}
+
+impl NtfsGeometry {
+    pub fn parse(boot: &[u8]) -> Result<Self, DiskAnalyzerError> {
+        if boot.len() < 512 || &boot[3..11] != b"NTFS    " {
+            return Err(invalid_data("not an NTFS boot sector"));
+        }
+        let bytes_per_sector = u64::from(u16_at(boot, 0x0B).unwrap_or(0));
+        // 大于 128 时表示 2 的 (256 - 值) 次方（64 KB 以上的簇）
+        let sectors_per_cluster = match boot[0x0D] {
+            v @ 0..=0x80 => Some(u64::from(v)),
+            v => 1u64.checked_shl(256 - u32::from(v)),
+        };
+        let cluster_size = sectors_per_cluster.and_then(|n| n.checked_mul(bytes_per_sector));
+        // 为负时记录大小为 2 的 (-值) 次方字节，否则为簇数
+        let record_size = match (boot[0x40] as i8, cluster_size) {
+            (v, _) if v < 0 => 1u64.checked_shl(u32::from(v.unsigned_abs())),
+            (v, Some(cluster_size)) => (v as u64).checked_mul(cluster_size),
+            (_, None) => None,
+        };
+        let (Some(cluster_size), Some(record_size)) = (cluster_size, record_size) else {
+            return Err(invalid_data("invalid NTFS cluster or record size"));
+        };
+        if cluster_size == 0 || !(FIXUP_STRIDE as u64..=65_536).contains(&record_size) {
+            return Err(invalid_data("invalid NTFS cluster or record size"));
+        }
+        Ok(Self {
+            cluster_size,
+            record_size: record_size as usize,
+            mft_offset: u64_at(boot, 0x30)
+                .unwrap_or(0)
+                .checked_mul(cluster_size)
+                .ok_or_else(|| invalid_data("invalid $MFT cluster number"))?,
+        })
+    }
+}
+
+/// 校验并还原更新序列：每个 512 字节段末尾两字节应等于更新序列号，换回数组中保存的原值。
+/// 不一致说明记录写入不完整，返回 false
+fn apply_fixups(record: &mut [u8]) -> bool {
+    let (Some(offset), Some(count)) = (u16_at(record, 4), u16_at(record, 6)) else {
+        return false;
+    };
+    let (offset, count) = (usize::from(offset), usize::from(count));
+    if count == 0 || offset + count * 2 > record.len() || (count - 1) * FIXUP_STRIDE > record.len()
+    {
+        return false;
+    }
+    let usn = [record[offset], record[offset + 1]];
+    for i in 1..count {
+        let end = i * FIXUP_STRIDE;
+        if record[end - 2..end] != usn {
+            return false;
+        }
+        let saved = offset + i * 2;
+        record[end - 2] = record[saved];
+        record[end - 1] = record[saved + 1];
+    }
+    true
+}
+
+/// 记录中的属性：(类型, 属性字节)
+fn attributes(record: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    let mut at = u16_at(record, 0x14).map_or(record.len(), usize::from);
+    std::iter::from_fn(move || {
+        let kind = u32_at(record, at)?;
+        if kind == ATTR_END {
+            return None;
+        }
+        let len = u32_at(record, at + 4)? as usize;
+        if len < 0x18 {
+            return None;
+        }
+        let attr = record.get(at..at + len)?;
+        at += len;
+        Some((kind, attr))
+    })
+}
+
+fn is_resident(attr: &[u8]) -> bool {
+    attr[8] == 0
+}
+
+fn is_unnamed(attr: &[u8]) -> bool {
+    attr[9] == 0
+}
+
+fn resident_value(attr: &[u8]) -> Option<&[u8]> {
+    let len = u32_at(attr, 0x10)? as usize;
+    let offset = usize::from(u16_at(attr, 0x14)?);
+    attr.get(offset..offset + len)
+}
+
+fn le_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |acc, &b| acc << 8 | u64::from(b))
+}
+
+fn le_int(bytes: &[u8]) -> i64 {
+    let value = le_uint(bytes);
+    let bits = bytes.len() * 8;
+    match bytes.last() {
+        Some(&last) if bits < 64 && last & 0x80 != 0 => (value | (!0u64 << bits)) as i64,
+        _ => value as i64,
+    }
+}
+
+/// 解码非常驻属性的数据运行：(起始簇号, 簇数)，稀疏运行的起始簇号为 None
+fn data_runs(attr: &[u8]) -> Option<Vec<(Option<u64>, u64)>> {
+    let mut at = usize::from(u16_at(attr, 0x20)?);
+    let mut lcn = 0i64;
+    let mut runs = Vec::new();
+    loop {
+        let header = *attr.get(at)?;
+        if header == 0 {
+            return Some(runs);
+        }
+        let len_size = usize::from(header & 0x0F);
+        let offset_size = usize::from(header >> 4);
+        if len_size == 0 || len_size > 8 || offset_size > 8 {
+            return None;
+        }
+        let clusters = le_uint(attr.get(at + 1..at + 1 + len_size)?);
+        if offset_size == 0 {
+            runs.push((None, clusters));
+        } else {
+            let start = at + 1 + len_size;
+            lcn = lcn.checked_add(le_int(attr.get(start..start + offset_size)?))?;
+            runs.push((Some(u64::try_from(lcn).ok()?), clusters));
+        }
+        at += 1 + len_size + offset_size;
+    }
+}
+
+/// 一条 FILE 记录中扫描需要的信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MftEntry {
+    pub record: u64,
+    /// 扩展记录所属的基本记录；属性过多时文件名或 `$DATA` 会溢出到扩展记录中
+    pub base: Option<u64>,
+    /// (父目录记录号, 文件名)
+    pub name: Option<(u64, String)>,
+    /// 未命名 `$DATA` 流的大小
+    pub size: Option<u64>,
+    pub is_dir: bool,
+    /// Unix 时间戳（秒），取自 `$STANDARD_INFORMATION`
+    pub modified: Option<u64>,
+}
+
+/// 解析一条记录（原地还原更新序列）；未使用、损坏或没有有用属性的记录返回 None
+fn parse_record(record: u64, bytes: &mut [u8]) -> Option<MftEntry> {
+    if !bytes.starts_with(b"FILE") || !apply_fixups(bytes) {
+        return None;
+    }
+    let flags = u16_at(bytes, 0x16)?;
+    if flags & RECORD_IN_USE == 0 {
+        return None;
+    }
+    let base = u64_at(bytes, 0x20)? & RECORD_NUMBER_MASK;
+    let mut entry = MftEntry {
+        record,
+        base: (base != 0).then_some(base),
+        name: None,
+        size: None,
+        is_dir: flags & RECORD_IS_DIRECTORY != 0,
+        modified: None,
+    };
+    let mut name_is_dos = false;
+    for (kind, attr) in attributes(bytes) {
+        match kind {
+            ATTR_STANDARD_INFORMATION if is_resident(attr) => {
+                entry.modified = resident_value(attr)
+                    .and_then(|v| u64_at(v, 0x08))
+                    .and_then(|ft| (ft / 10_000_000).checked_sub(FILETIME_UNIX_OFFSET_SECS))
+                    .filter(|&secs| secs > 0);
+            }
+            ATTR_FILE_NAME if is_resident(attr) => {
+                let Some(value) = resident_value(attr) else {
+                    continue;
+                };
+                let (Some(parent), Some(&len), Some(&namespace)) =
+                    (u64_at(value, 0), value.get(0x40), value.get(0x41))
+                else {
+                    continue;
+                };
+                if entry.name.is_some() && (namespace == NAMESPACE_DOS || !name_is_dos) {
+                    continue;
+                }
+                let Some(raw) = value.get(0x42..0x42 + usize::from(len) * 2) else {
+                    continue;
+                };
+                let units: Vec<u16> = raw
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                entry.name = Some((
+                    parent & RECORD_NUMBER_MASK,
+                    String::from_utf16_lossy(&units),
+                ));
+                name_is_dos = namespace == NAMESPACE_DOS;
+            }
+            ATTR_DATA if is_unnamed(attr) && entry.size.is_none() => {
+                entry.size = if is_resident(attr) {
+                    u32_at(attr, 0x10).map(u64::from)
+                } else if u64_at(attr, 0x10) == Some(0) {
+                    // 只有起始 VCN 为 0 的片段记录了流的真实大小
+                    u64_at(attr, 0x30)
+                } else {
+                    None
+                };
+            }
+            _ => {}
+        }
+    }
+    (entry.name.is_some() || entry.size.is_some()).then_some(entry)
+}
+
+/// 按数据运行把 $MFT 在卷上的各段拼成连续的字节流
+pub(crate) struct MftRunsReader<V> {
+    volume: V,
+    /// (卷内字节偏移, 字节数)；稀疏段的偏移为 None
+    extents: Vec<(Option<u64>, u64)>,
+    current: usize,
+    /// 当前段内已读的字节数
+    pos: u64,
+    /// 尚未读取的有效字节数，已分配但未使用的尾部不读
+    remaining: u64,
+    mft_bytes: u64,
+}
+
+impl<V> MftRunsReader<V> {
+    /// $MFT 的有效字节数
+    pub fn mft_bytes(&self) -> u64 {
+        self.mft_bytes
+    }
+}
+
+impl<V: Read + Seek> Read for MftRunsReader<V> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(&(offset, len)) = self.extents.get(self.current) {
+            if self.remaining == 0 || buf.is_empty() {
+                break;
+            }
+            if self.pos == len {
+                self.current += 1;
+                self.pos = 0;
+                continue;
+            }
+            let want = (buf.len() as u64).min(len - self.pos).min(self.remaining) as usize;
+            let n = match offset {
+                Some(offset) => {
+                    if self.pos == 0 {
+                        self.volume.seek(SeekFrom::Start(offset))?;
+                    }
+                    self.volume.read(&mut buf[..want])?
+                }
+                None => {
+                    buf[..want].fill(0);
+                    want
+                }
+            };
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.pos += n as u64;
+            self.remaining -= n as u64;
+            return Ok(n);
+        }
+        Ok(0)
+    }
+}
+
+/// 读取引导扇区与 $MFT 自身的记录（0 号），返回按数据运行读取 $MFT 的字节流
+pub(crate) fn open_mft<V: Read + Seek>(
+    mut volume: V,
+) -> Result<(MftRunsReader<V>, NtfsGeometry), DiskAnalyzerError> {
+    let mut boot = [0u8; 512];
+    volume.seek(SeekFrom::Start(0))?;
+    volume.read_exact(&mut boot)?;
+    let geometry = NtfsGeometry::parse(&boot)?;
+
+    let mut record = vec![0u8; geometry.record_size];
+    volume.seek(SeekFrom::Start(geometry.mft_offset))?;
+    volume.read_exact(&mut record)?;
+    if !record.starts_with(b"FILE") || !apply_fixups(&mut record) {
+        return Err(invalid_data("corrupt $MFT record"));
+    }
+    let data = attributes(&record)
+        .find(|(kind, attr)| *kind == ATTR_DATA && is_unnamed(attr) && !is_resident(attr))
+        .map(|(_, attr)| attr)
+        .ok_or_else(|| invalid_data("$MFT has no data attribute"))?;
+    let runs = data_runs(data).ok_or_else(|| invalid_data("corrupt $MFT data runs"))?;
+    let mft_bytes = u64_at(data, 0x30).unwrap_or(0);
+    let extents: Vec<(Option<u64>, u64)> = runs
+        .into_iter()
+        .map(|(lcn, clusters)| {
+            (
+                lcn.map(|lcn| lcn * geometry.cluster_size),
+                clusters * geometry.cluster_size,
+            )
+        })
+        .collect();
+    // 极度碎片化的 $MFT 把其余数据运行放在扩展记录中，这里读不到
+    if extents.iter().map(|e| e.1).sum::<u64>() < mft_bytes {
+        return Err(invalid_data(
+            "$MFT is too fragmented for streaming read; use the regular MFT scan",
+        ));
+    }
+    Ok((
+        MftRunsReader {
+            volume,
+            extents,
+            current: 0,
+            pos: 0,
+            remaining: mft_bytes,
+            mft_bytes,
+        },
+        geometry,
+    ))
+}
+
+/// 分块读取 $MFT、逐条产出 `MftEntry` 的迭代器，跳过未使用与损坏的记录；读取出错时产出一次 `Err` 后结束
+pub(crate) struct StreamingMftReader<'a, R> {
+    source: R,
+    record_size: usize,
+    chunk: Vec<u8>,
+    /// chunk 中的有效字节数与下一条待解析记录的偏移
+    filled: usize,
+    at: usize,
+    next_record: u64,
+    done: bool,
+    on_chunk: Option<Box<dyn FnMut(u64) + 'a>>,
+}
+
+impl<'a, R: Read> StreamingMftReader<'a, R> {
+    pub fn new(source: R, record_size: usize) -> Self {
+        Self::with_chunk_bytes(source, record_size, MFT_CHUNK_BYTES)
+    }
+
+    /// 每块读取 `chunk_bytes`（向下取整到记录大小的倍数，至少一条记录）
+    pub fn with_chunk_bytes(source: R, record_size: usize, chunk_bytes: usize) -> Self {
+        let records_per_chunk = (chunk_bytes / record_size).max(1);
+        Self {
+            source,
+            record_size,
+            chunk: vec![0u8; records_per_chunk * record_size],
+            filled: 0,
+            at: 0,
+            next_record: 0,
+            done: false,
+            on_chunk: None,
+        }
+    }
+
+    /// 每读入一块以截至该块的记录数回调一次
+    pub fn on_chunk(mut self, cb: impl FnMut(u64) + 'a) -> Self {
+        self.on_chunk = Some(Box::new(cb));
+        self
+    }
+
+    /// 读满一块（或读到末尾），不足一条记录的尾部丢弃；没有更多记录时返回 false
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < self.chunk.len() {
+            match self.source.read(&mut self.chunk[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.filled = filled - filled % self.record_size;
+        self.at = 0;
+        if self.filled > 0 {
+            let records = self.next_record + (self.filled / self.record_size) as u64;
+            if let Some(cb) = &mut self.on_chunk {
+                cb(records);
+            }
+        }
+        Ok(self.filled > 0)
+    }
+}
+
+impl<R: Read> Iterator for StreamingMftReader<'_, R> {
+    type Item = Result<MftEntry, DiskAnalyzerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if self.at >= self.filled {
+                match self.fill() {
+                    Ok(true) => {}
+                    Ok(false) => self.done = true,
+                    Err(e) => {
+                        self.done = true;
+                        return Some(Err(e.into()));
+                    }
+                }
+                continue;
+            }
+            let record = self.next_record;
+            let range = self.at..self.at + self.record_size;
+            self.at = range.end;
+            self.next_record += 1;
+            if let Some(entry) = parse_record(record, &mut self.chunk[range]) {
+                return Some(Ok(entry));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct NameEntry {
+    name: Option<(u64, Box<str>)>,
+    size: Option<u64>,
+    modified: Option<u64>,
+    is_dir: bool,
+    /// 已读到基本记录（扩展记录可能先于基本记录出现）
+    has_base: bool,
+}
+
+impl NameEntry {
+    fn is_complete(&self) -> bool {
+        self.has_base && self.size.is_some()
+    }
+}
+
+/// 按记录号保存父记录号、文件名与大小，全部记录读完后用于拼出完整路径
+#[derive(Default)]
+pub(crate) struct MftNameTable {
+    entries: Vec<Option<NameEntry>>,
+}
+
+impl MftNameTable {
+    /// 把记录并入其基本记录；文件因此首次凑齐基本记录与大小时返回其大小
+    pub fn push(&mut self, entry: MftEntry) -> Option<u64> {
+        let index = entry.base.unwrap_or(entry.record) as usize;
+        if index >= self.entries.len() {
+            self.entries.resize(index + 1, None);
+        }
+        let slot = self.entries[index].get_or_insert_with(NameEntry::default);
+        let was_complete = slot.is_complete();
+        if entry.base.is_none() {
+            slot.has_base = true;
+            slot.is_dir = entry.is_dir;
+            slot.modified = entry.modified;
+        }
+        if slot.name.is_none() {
+            slot.name = entry.name.map(|(parent, name)| (parent, name.into()));
+        }
+        if slot.size.is_none() {
+            slot.size = entry.size;
+        }
+        (!was_complete && slot.is_complete() && !slot.is_dir)
+            .then_some(slot.size)
+            .flatten()
+    }
+
+    /// 记录的完整路径：`volume_prefix` 加各级名称；父链断开（父记录未使用或缺失）时为 None
+    pub fn path(&self, record: u64, volume_prefix: &str) -> Option<String> {
+        let mut names = Vec::new();
+        let mut current = record;
+        while current != ROOT_RECORD {
+            if names.len() >= MAX_PATH_DEPTH {
+                return None;
+            }
+            let (parent, name) = self
+                .entries
+                .get(current as usize)?
+                .as_ref()?
+                .name
+                .as_ref()?;
+            names.push(&**name);
+            current = *parent;
+        }
+        let mut path = volume_prefix.to_string();
+        for name in names.iter().rev() {
+            path.push('\\');
+            path.push_str(name);
+        }
+        Some(path)
+    }
+
+    /// 基本记录已读到、满足 `filter` 的文件（扩展名按文件名判断）
+    fn file_matches(&self, record: usize, filter: &TopFilesFilter) -> bool {
+        let Some(Some(slot)) = self.entries.get(record) else {
+            return false;
+        };
+        match (&slot.name, slot.size) {
+            (Some((_, name)), Some(size)) if slot.has_base && !slot.is_dir => {
+                filter.matches(name, size, slot.modified)
+            }
+            _ => false,
+        }
+    }
+
+    /// 大小不小于 `min_size`、满足 `filter` 的用户文件中按 `top_file_order` 排前 `n` 的项
+    fn top_files(
+        &self,
+        volume_prefix: &str,
+        n: usize,
+        min_size: u64,
+        filter: &TopFilesFilter,
+    ) -> Vec<TopFileEntry> {
+        let mut top = TopFiles::new(n);
+        for (record, slot) in self.entries.iter().enumerate().skip(FIRST_USER_RECORD) {
+            let Some(slot) = slot else {
+                continue;
+            };
+            let Some(size) = slot.size.filter(|&s| s >= min_size) else {
+                continue;
+            };
+            if !self.file_matches(record, filter) {
+                continue;
+            }
+            let Some(path) = self.path(record as u64, volume_prefix) else {
+                continue;
+            };
+            if !is_system_metafile(&path, volume_prefix) {
+                top.push(path, size, slot.modified);
+            }
+        }
+        top.finish()
+    }
+}
+
+/// 边读边维护前 N 大文件：读取期间堆中只放 (大小, 记录号)，读完后只为不小于第 N 大的文件拼路径，
+/// 同大小的文件按 `top_file_order` 取舍，结果与 `TopFiles` 一致。只有满足 `filter` 的文件参与排名
+pub(crate) fn stream_top_files<R: Read>(
+    reader: StreamingMftReader<'_, R>,
+    volume_prefix: &str,
+    n: usize,
+    filter: &TopFilesFilter,
+) -> Result<Vec<TopFileEntry>, DiskAnalyzerError> {
+    let mut table = MftNameTable::default();
+    let mut heap: BinaryHeap<Reverse<u64>> = BinaryHeap::new();
+    for entry in reader {
+        let entry = entry?;
+        let index = entry.base.unwrap_or(entry.record) as usize;
+        if let Some(size) = table.push(entry) {
+            if index >= FIRST_USER_RECORD && n > 0 && table.file_matches(index, filter) {
+                heap.push(Reverse(size));
+                if heap.len() > n {
+                    heap.pop();
+                }
+            }
+        }
+    }
+    let threshold = match heap.peek() {
+        Some(Reverse(size)) if heap.len() == n => *size,
+        _ => 0,
+    };
+    let top = table.top_files(volume_prefix, n, threshold, filter);
+    // 入堆的文件中有路径无法解析或位于 $Extend 下的，名额不足时放宽到全部文件
+    if top.len() < n && threshold > 0 {
+        return Ok(table.top_files(volume_prefix, n, 0, filter));
+    }
+    Ok(top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const CLUSTER: usize = 4096;
+    const RECORD: usize = 1024;
+
+    fn resident(kind: u32, value: &[u8]) -> Vec<u8> {
+        let len = (0x18 + value.len() + 7) & !7;
+        let mut attr = vec![0u8; len];
+        attr[0..4].copy_from_slice(&kind.to_le_bytes());
+        attr[4..8].copy_from_slice(&(len as u32).to_le_bytes());
+        attr[0x10..0x14].copy_from_slice(&(value.len() as u32).to_le_bytes());
+        attr[0x14..0x16].copy_from_slice(&0x18u16.to_le_bytes());
+        attr[0x18..0x18 + value.len()].copy_from_slice(value);
+        attr
+    }
+
+    fn file_name(parent: u64, name: &str, namespace: u8) -> Vec<u8> {
+        let units: Vec<u16> = name.encode_utf16().collect();
+        let mut value = vec![0u8; 0x42 + units.len() * 2];
+        // 高 16 位为序列号
+        value[0..8].copy_from_slice(&(parent | 7 << 48).to_le_bytes());
+        value[0x40] = units.len() as u8;
+        value[0x41] = namespace;
+        for (i, unit) in units.iter().enumerate() {
+            value[0x42 + i * 2..0x44 + i * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        resident(ATTR_FILE_NAME, &value)
+    }
+
+    fn standard_info(unix_secs: u64) -> Vec<u8> {
+        let mut value = vec![0u8; 0x30];
+        let filetime = (unix_secs + FILETIME_UNIX_OFFSET_SECS) * 10_000_000;
+        value[0x08..0x10].copy_from_slice(&filetime.to_le_bytes());
+        resident(ATTR_STANDARD_INFORMATION, &value)
+    }
+
+    fn non_resident_data(size: u64, runs: &[u8]) -> Vec<u8> {
+        let len = (0x40 + runs.len() + 1 + 7) & !7;
+        let mut attr = vec![0u8; len];
+        attr[0..4].copy_from_slice(&ATTR_DATA.to_le_bytes());
+        attr[4..8].copy_from_slice(&(len as u32).to_le_bytes());
+        attr[8] = 1;
+        attr[0x20..0x22].copy_from_slice(&0x40u16.to_le_bytes());
+        attr[0x30..0x38].copy_from_slice(&size.to_le_bytes());
+        attr[0x40..0x40 + runs.len()].copy_from_slice(runs);
+        attr
+    }
+
+    fn record(flags: u16, base: u64, attrs: &[Vec<u8>]) -> Vec<u8> {
+        let mut r = vec![0u8; RECORD];
+        r[..4].copy_from_slice(b"FILE");
+        r[4..6].copy_from_slice(&0x30u16.to_le_bytes());
+        r[6..8].copy_from_slice(&3u16.to_le_bytes());
+        r[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+        r[0x16..0x18].copy_from_slice(&flags.to_le_bytes());
+        r[0x20..0x28].copy_from_slice(&base.to_le_bytes());
+        let mut at = 0x38;
+        for attr in attrs {
+            r[at..at + attr.len()].copy_from_slice(attr);
+            at += attr.len();
+        }
+        r[at..at + 4].copy_from_slice(&ATTR_END.to_le_bytes());
+        // 更新序列号写入每段末尾，原值存入更新序列数组
+        let usn = [0x2A, 0x00];
+        r[0x30..0x32].copy_from_slice(&usn);
+        for i in 1..3 {
+            let end = i * FIXUP_STRIDE;
+            let saved = [r[end - 2], r[end - 1]];
+            r[0x30 + i * 2..0x32 + i * 2].copy_from_slice(&saved);
+            r[end - 2..end].copy_from_slice(&usn);
+        }
+        r
+    }
+
+    /// 25 条记录的卷：$MFT 分两段，0–7 号记录在 4 号簇，其余在 20 号簇起
+    fn synthetic_volume() -> Vec<u8> {
+        const FILE: u16 = RECORD_IN_USE;
+        const DIR: u16 = RECORD_IN_USE | RECORD_IS_DIRECTORY;
+        let elsewhere = [0x11, 0x01, 0x30];
+        let mut records = vec![vec![0u8; RECORD]; 25];
+        records[0] = record(
+            FILE,
+            0,
+            &[
+                file_name(5, "$MFT", 3),
+                non_resident_data(25 * RECORD as u64, &[0x11, 0x02, 0x04, 0x11, 0x05, 0x10]),
+            ],
+        );
+        records[5] = record(DIR, 0, &[file_name(5, ".", 3)]);
+        records[11] = record(DIR, 0, &[file_name(5, "$Extend", 3)]);
+        records[16] = record(DIR, 0, &[file_name(5, "Videos", 1)]);
+        records[17] = record(
+            FILE,
+            0,
+            &[
+                standard_info(1_700_000_000),
+                file_name(16, "a.mp4", 1),
+                non_resident_data(5_000, &elsewhere),
+            ],
+        );
+        records[18] = record(
+            FILE,
+            0,
+            &[file_name(5, "b.txt", 1), resident(ATTR_DATA, &[7; 10])],
+        );
+        records[19] = record(
+            FILE,
+            0,
+            &[
+                file_name(5, "c.bin", 1),
+                non_resident_data(5_000, &elsewhere),
+            ],
+        );
+        // 已删除的记录
+        records[20] = record(
+            0,
+            0,
+            &[
+                file_name(5, "deleted.iso", 1),
+                non_resident_data(9_999, &elsewhere),
+            ],
+        );
+        // 先出现 DOS 短名；常驻数据跨过第一段末尾，依赖更新序列还原
+        records[21] = record(
+            FILE,
+            0,
+            &[
+                file_name(5, "LONGFI~1.DAT", 2),
+                file_name(5, "long file name.dat", 1),
+                resident(ATTR_DATA, &[1; 600]),
+            ],
+        );
+        // $DATA 溢出到扩展记录，且扩展记录排在基本记录之前
+        records[22] = record(FILE, 23, &[non_resident_data(7_000, &elsewhere)]);
+        records[23] = record(FILE, 0, &[file_name(16, "huge.vhd", 1)]);
+        records[24] = record(
+            FILE,
+            0,
+            &[
+                file_name(11, "$UsnJrnl", 1),
+                non_resident_data(99_999, &elsewhere),
+            ],
+        );
+
+        let mut volume = vec![0u8; 25 * CLUSTER];
+        volume[3..11].copy_from_slice(b"NTFS    ");
+        volume[0x0B..0x0D].copy_from_slice(&512u16.to_le_bytes());
+        volume[0x0D] = 8;
+        volume[0x30..0x38].copy_from_slice(&4u64.to_le_bytes());
+        volume[0x40] = 0xF6; // -10：记录大小 1024
+        for (i, r) in records.iter().enumerate() {
+            let at = if i < 8 {
+                4 * CLUSTER + i * RECORD
+            } else {
+                20 * CLUSTER + (i - 8) * RECORD
+            };
+            volume[at..at + RECORD].copy_from_slice(r);
+        }
+        volume
+    }
+
+    fn top(n: usize, chunks: &mut Vec<u64>) -> Vec<TopFileEntry> {
+        let (source, geometry) = open_mft(Cursor::new(synthetic_volume())).unwrap();
+        assert_eq!(geometry.record_size, RECORD);
+        assert_eq!(source.mft_bytes(), 25 * RECORD as u64);
+        let reader = StreamingMftReader::with_chunk_bytes(source, geometry.record_size, CLUSTER)
+            .on_chunk(|records| chunks.push(records));
+        stream_top_files(reader, "C:", n, &TopFilesFilter::default()).unwrap()
+    }
+
+    #[test]
+    fn test_streaming_reader_ranks_top_files_across_chunks() {
+        let mut chunks = Vec::new();
+        let all = top(10, &mut chunks);
+        assert_eq!(chunks, [4, 8, 12, 16, 20, 24, 25]);
+        let listed: Vec<(&str, u64)> = all.iter().map(|f| (f.path.as_str(), f.size)).collect();
+        assert_eq!(
+            listed,
+            [
+                (r"C:\Videos\huge.vhd", 7_000),
+                (r"C:\Videos\a.mp4", 5_000),
+                (r"C:\c.bin", 5_000),
+                (r"C:\long file name.dat", 600),
+                (r"C:\b.txt", 10),
+            ]
+        );
+        assert_eq!(all[1].modified, Some(1_700_000_000));
+
+        // 同大小的文件在名额边界上按路径取舍
+        let two: Vec<String> = top(2, &mut Vec::new())
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        assert_eq!(two, [r"C:\Videos\huge.vhd", r"C:\Videos\a.mp4"]);
+    }
+
+    #[test]
+    fn test_geometry_rejects_out_of_range_shifts() {
+        let mut boot = synthetic_volume()[..512].to_vec();
+        let geometry = NtfsGeometry::parse(&boot).unwrap();
+        assert_eq!(
+            (geometry.cluster_size, geometry.mft_offset),
+            (CLUSTER as u64, 4 * CLUSTER as u64)
+        );
+        // 移位超出 u64 的簇大小、记录大小均报错而非溢出
+        boot[0x0D] = 0x81;
+        assert!(NtfsGeometry::parse(&boot).is_err());
+        boot[0x0D] = 8;
+        boot[0x40] = 0x80;
+        assert!(NtfsGeometry::parse(&boot).is_err());
+        boot[0x40] = 0xF6;
+        boot[0x30..0x38].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(NtfsGeometry::parse(&boot).is_err());
+    }
+}
@@ -0,0 +1,1125 @@
+//! MFT 记录汇总与建树：与 ntfs-reader 无关的纯数据处理（父目录索引、直接/递归大小、系统元文件统计、
+//! 按 `TreeBuildOptions` 截断的文件树），便于在所有平台上用合成记录测试。
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::atomic::{self, AtomicU64};
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::{
+    is_shallow_dir_name, is_system_managed_file, FileNode, FolderGroup, TopFileEntry,
+};
+use rayon::prelude::*;
+
+use crate::options::{MftBudget, TreeBuildOptions};
+use crate::scanner::ProgressCbArc;
+
+/// 记录数组的初始容量上限，避免小卷或小预算时一次性预分配过多
+const INITIAL_RECORD_CAPACITY: usize = 2_000_000;
+
+/// NTFS 系统元文件（MFT 记录 0–15，均位于卷根；`$Extend` 为目录，其下也都是元文件）。
+/// `$MFT`/`$LogFile` 等是文件系统开销，不属于用户可见数据，计入 `system_reserved_bytes` 而非用户总大小
+const NTFS_METAFILES: &[&str] = &[
+    "$MFT", "$MFTMirr", "$LogFile", "$Volume", "$AttrDef", "$Bitmap", "$Boot", "$BadClus",
+    "$Secure", "$UpCase", "$Extend",
+];
+
+/// 逻辑大小不反映实际占用的元文件：`$BadClus` 的 `$Bad` 流是与卷同大小的稀疏流，
+/// 实际只占坏簇，既不进入树也不计入 `system_reserved_bytes`
+const SPARSE_METAFILES: &[&str] = &["$BadClus"];
+
+/// 卷根下路径（已去掉尾部反斜杠）的第一段，不在卷根下时为 None
+fn first_segment<'a>(path_trim: &'a str, volume_root_trim: &str) -> Option<&'a str> {
+    let rest = path_trim
+        .get(..volume_root_trim.len())
+        .filter(|p| p.eq_ignore_ascii_case(volume_root_trim))
+        .and_then(|_| path_trim[volume_root_trim.len()..].strip_prefix('\\'))?;
+    rest.split('\\').next()
+}
+
+/// 路径（已去掉尾部反斜杠）是否为卷根下的 NTFS 系统元文件或 `$Extend` 中的项
+pub(crate) fn is_system_metafile(path_trim: &str, volume_root_trim: &str) -> bool {
+    first_segment(path_trim, volume_root_trim)
+        .is_some_and(|first| NTFS_METAFILES.iter().any(|m| m.eq_ignore_ascii_case(first)))
+}
+
+/// 路径是否为 `SPARSE_METAFILES` 中的元文件
+fn is_sparse_metafile(path_trim: &str, volume_root_trim: &str) -> bool {
+    first_segment(path_trim, volume_root_trim).is_some_and(|first| {
+        SPARSE_METAFILES
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(first))
+    })
+}
+
+/// Single MFT-derived record for tree building.
+pub(crate) struct MftRecord {
+    pub full_path: String,
+    /// 原始 UTF-16 路径，仅当文件名含未配对代理项时保存（见 `FileNode::raw_path`）
+    pub raw_path: Option<Vec<u16>>,
+    pub size: u64,
+    pub is_dir: bool,
+    pub modified: Option<u64>,
+}
+
+/// 枚举 MFT 时逐条收集的记录及索引
+pub(crate) struct MftAggregate {
+    pub records: Vec<MftRecord>,
+    /// 父路径 -> 子记录下标；卷根的子项以 `volume_root_trim`（如 `C:`）为键
+    pub child_index: HashMap<String, Vec<usize>>,
+    /// 路径（去尾部反斜杠）-> 记录自身大小
+    pub direct_sizes: HashMap<String, u64>,
+    /// 系统元文件与卷根目录自身属性占用的字节数
+    pub system_reserved_bytes: u64,
+    /// 超出预算后未保存记录的文件数，按父目录（去尾部反斜杠）统计
+    pub folded_counts: HashMap<String, u64>,
+    /// 超出预算后未保存记录的文件大小之和
+    pub folded_bytes: u64,
+    volume_root_trim: String,
+    max_file_records: usize,
+    file_records: usize,
+}
+
+impl MftAggregate {
+    pub fn new(volume_root_trim: &str) -> Self {
+        Self::with_budget(volume_root_trim, &MftBudget::default())
+    }
+
+    pub fn with_budget(volume_root_trim: &str, budget: &MftBudget) -> Self {
+        Self {
+            records: Vec::with_capacity(budget.max_file_records.min(INITIAL_RECORD_CAPACITY)),
+            child_index: HashMap::new(),
+            direct_sizes: HashMap::new(),
+            system_reserved_bytes: 0,
+            folded_counts: HashMap::new(),
+            folded_bytes: 0,
+            volume_root_trim: volume_root_trim.to_string(),
+            max_file_records: budget.max_file_records,
+            file_records: 0,
+        }
+    }
+
+    /// 是否已超出预算进入降级模式
+    pub fn is_degraded(&self) -> bool {
+        self.folded_bytes > 0 || !self.folded_counts.is_empty()
+    }
+
+    /// 加入一条已规范化路径的记录。系统元文件不进入树，只累加到 `system_reserved_bytes`；
+    /// 卷根目录记录保留（提供修改时间），但其自身大小同样计入系统占用
+    pub fn push(&mut self, full_path: String, size: u64, is_dir: bool, modified: Option<u64>) {
+        self.push_with_raw(full_path, None, size, is_dir, modified);
+    }
+
+    /// 同 `push`，附带原始 UTF-16 路径
+    pub fn push_with_raw(
+        &mut self,
+        full_path: String,
+        raw_path: Option<Vec<u16>>,
+        size: u64,
+        is_dir: bool,
+        modified: Option<u64>,
+    ) {
+        let path_trim = full_path.trim_end_matches('\\');
+        if is_system_metafile(path_trim, &self.volume_root_trim) {
+            if !is_sparse_metafile(path_trim, &self.volume_root_trim) {
+                self.system_reserved_bytes = self.system_reserved_bytes.saturating_add(size);
+            }
+            return;
+        }
+        let is_root = path_trim.eq_ignore_ascii_case(&self.volume_root_trim);
+        let size = if is_root {
+            self.system_reserved_bytes = self.system_reserved_bytes.saturating_add(size);
+            0
+        } else {
+            size
+        };
+        if !is_dir && self.file_records >= self.max_file_records {
+            self.fold(&full_path, size);
+            return;
+        }
+        if !is_dir {
+            self.file_records += 1;
+        }
+        let idx = self.records.len();
+        if !is_root {
+            if let Some(i) = full_path.rfind('\\') {
+                let parent = full_path[..i].to_string();
+                self.child_index.entry(parent).or_default().push(idx);
+            }
+        }
+        self.direct_sizes
+            .entry(path_trim.to_string())
+            .and_modify(|v| *v = v.saturating_add(size))
+            .or_insert(size);
+        self.records.push(MftRecord {
+            full_path,
+            raw_path,
+            size,
+            is_dir,
+            modified,
+        });
+    }
+
+    /// 降级模式：文件大小计入父目录的直接大小，只保留父目录下的文件计数
+    fn fold(&mut self, full_path: &str, size: u64) {
+        let parent = full_path
+            .rsplit_once('\\')
+            .map(|(parent, _)| parent)
+            .unwrap_or(&self.volume_root_trim);
+        if let Some(v) = self.direct_sizes.get_mut(parent) {
+            *v = v.saturating_add(size);
+        } else {
+            self.direct_sizes.insert(parent.to_string(), size);
+        }
+        if let Some(c) = self.folded_counts.get_mut(parent) {
+            *c += 1;
+        } else {
+            self.folded_counts.insert(parent.to_string(), 1);
+        }
+        self.folded_bytes = self.folded_bytes.saturating_add(size);
+    }
+
+    /// 所有用户文件（非目录）的 size 之和，含降级模式下未保存记录的文件
+    pub fn sum_file_sizes(&self) -> u64 {
+        self.records
+            .iter()
+            .filter(|r| !r.is_dir)
+            .map(|r| r.size)
+            .sum::<u64>()
+            .saturating_add(self.folded_bytes)
+    }
+}
+
+/// 按条目数设上限的路径缓存：累计 `max_entries` 次查询后清空重建，避免超大卷上无界增长
+pub(crate) struct CappedCache<C> {
+    cache: C,
+    entries: usize,
+    max_entries: usize,
+    resets: u64,
+}
+
+impl<C: Default> CappedCache<C> {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            cache: C::default(),
+            entries: 0,
+            max_entries,
+            resets: 0,
+        }
+    }
+
+    /// 每条记录取一次缓存；已达上限时先清空
+    pub fn next(&mut self) -> &mut C {
+        if self.entries >= self.max_entries {
+            self.cache = C::default();
+            self.entries = 0;
+            self.resets += 1;
+        }
+        self.entries += 1;
+        &mut self.cache
+    }
+
+    /// 已清空重建的次数
+    pub fn resets(&self) -> u64 {
+        self.resets
+    }
+}
+
+/// 从直接大小与子索引一次性汇总递归大小（避免枚举时每文件 O(深度) 的祖先更新）
+pub(crate) fn compute_recursive_sizes(
+    records: &[MftRecord],
+    child_index: &HashMap<String, Vec<usize>>,
+    direct_sizes: &HashMap<String, u64>,
+    volume_root_trim: &str,
+    volume_root_key: &str,
+) -> HashMap<String, u64> {
+    fold_bottom_up(
+        records,
+        child_index,
+        direct_sizes,
+        volume_root_trim,
+        volume_root_key,
+        u64::saturating_add,
+    )
+}
+
+/// 每个路径自身及所有后代记录中最新的修改时间（Unix 秒）；没有任何时间的路径为 0
+pub(crate) fn compute_recursive_modified(
+    records: &[MftRecord],
+    child_index: &HashMap<String, Vec<usize>>,
+    volume_root_trim: &str,
+    volume_root_key: &str,
+) -> HashMap<String, u64> {
+    let mut own: HashMap<String, u64> = HashMap::new();
+    for r in records {
+        if let Some(t) = r.modified {
+            let v = own
+                .entry(r.full_path.trim_end_matches('\\').to_string())
+                .or_insert(0);
+            *v = (*v).max(t);
+        }
+    }
+    fold_bottom_up(
+        records,
+        child_index,
+        &own,
+        volume_root_trim,
+        volume_root_key,
+        u64::max,
+    )
+}
+
+/// 按深度从深到浅，把每个路径自身的值与其子项的汇总值用 `combine` 合并
+fn fold_bottom_up(
+    records: &[MftRecord],
+    child_index: &HashMap<String, Vec<usize>>,
+    own: &HashMap<String, u64>,
+    volume_root_trim: &str,
+    volume_root_key: &str,
+    combine: fn(u64, u64) -> u64,
+) -> HashMap<String, u64> {
+    let mut paths: Vec<String> = records
+        .iter()
+        .map(|r| r.full_path.trim_end_matches('\\').to_string())
+        .collect();
+    if !paths
+        .iter()
+        .any(|p| p.eq_ignore_ascii_case(volume_root_trim))
+    {
+        paths.push(volume_root_trim.to_string());
+    }
+    paths.sort();
+    paths.dedup();
+    paths.sort_by_cached_key(|p| Reverse(p.matches('\\').count()));
+    let mut folded: HashMap<String, u64> = HashMap::new();
+    for path in paths {
+        let direct = own.get(&path).copied().unwrap_or(0);
+        // 卷根的子项可能以 `C:\` 或 `C:` 为键，两者都查
+        let children = if path.eq_ignore_ascii_case(volume_root_trim) {
+            child_index
+                .get(volume_root_key)
+                .or_else(|| child_index.get(volume_root_trim))
+        } else {
+            child_index.get(&path)
+        };
+        let value = children
+            .into_iter()
+            .flatten()
+            .map(|&i| {
+                let c = records[i].full_path.trim_end_matches('\\');
+                folded.get(c).copied().unwrap_or(0)
+            })
+            .fold(direct, combine);
+        folded.insert(path, value);
+    }
+    folded
+}
+
+/// 按扩展名收集文件并按所在目录分组，只保存命中的文件（内存 O(命中数)）
+pub(crate) struct ExtensionGroups {
+    /// 小写、不带点的扩展名
+    extensions: HashSet<String>,
+    groups: HashMap<String, FolderGroup>,
+}
+
+impl ExtensionGroups {
+    /// `extensions` 可带或不带前导点，不区分大小写（如 `"CR2"`、`".mp4"`）
+    pub fn new(extensions: &[&str]) -> Self {
+        Self {
+            extensions: extensions
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_ascii_lowercase())
+                .collect(),
+            groups: HashMap::new(),
+        }
+    }
+
+    /// 文件扩展名命中时加入其父目录的分组
+    pub fn push(&mut self, full_path: &str, size: u64, modified: Option<u64>) {
+        let name = ai_disk_common::path::file_name(full_path);
+        let matched = name
+            .rsplit_once('.')
+            .is_some_and(|(_, ext)| self.extensions.contains(&ext.to_ascii_lowercase()));
+        if !matched {
+            return;
+        }
+        let folder = full_path
+            .trim_end_matches(['\\', '/'])
+            .rsplit_once(['\\', '/'])
+            .map(|(parent, _)| parent)
+            .unwrap_or("");
+        let group = self
+            .groups
+            .entry(folder.to_string())
+            .or_insert_with(|| FolderGroup {
+                folder: folder.to_string(),
+                total_size: 0,
+                files: Vec::new(),
+            });
+        group.total_size = group.total_size.saturating_add(size);
+        group.files.push(TopFileEntry {
+            path: full_path.to_string(),
+            size,
+            modified,
+        });
+    }
+
+    /// 分组按总大小降序，组内文件按 `top_file_order`
+    pub fn finish(self) -> Vec<FolderGroup> {
+        let mut groups: Vec<FolderGroup> = self.groups.into_values().collect();
+        for group in &mut groups {
+            group.files.sort_by(top_file_order);
+        }
+        groups.sort_by(|a, b| {
+            b.total_size
+                .cmp(&a.total_size)
+                .then_with(|| a.folder.cmp(&b.folder))
+        });
+        groups
+    }
+}
+
+/// 大文件列表的顺序：大小降序，同大小按路径升序，再按修改时间升序（无修改时间的在前），
+/// 相同输入总得到相同顺序
+pub(crate) fn top_file_order(a: &TopFileEntry, b: &TopFileEntry) -> Ordering {
+    b.size
+        .cmp(&a.size)
+        .then_with(|| a.path.cmp(&b.path))
+        .then_with(|| a.modified.cmp(&b.modified))
+}
+
+/// 排序键，越小越靠前，与 `top_file_order` 一致
+type TopFileKey = (Reverse<u64>, String, Option<u64>);
+
+/// 枚举时维护前 N 大文件：堆顶是当前排在最后的一项，超出 N 时将其淘汰，
+/// 因此同大小的文件在名额边界上也按 `top_file_order` 取舍，结果与枚举顺序无关
+pub(crate) struct TopFiles {
+    n: usize,
+    heap: BinaryHeap<TopFileKey>,
+}
+
+impl TopFiles {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            heap: BinaryHeap::with_capacity(n.saturating_add(1).min(1_000_000)),
+        }
+    }
+
+    pub fn push(&mut self, path: String, size: u64, modified: Option<u64>) {
+        self.heap.push((Reverse(size), path, modified));
+        while self.heap.len() > self.n {
+            self.heap.pop();
+        }
+    }
+
+    /// 按 `top_file_order` 排列的结果
+    pub fn finish(self) -> Vec<TopFileEntry> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(Reverse(size), path, modified)| TopFileEntry {
+                path,
+                size,
+                modified,
+            })
+            .collect()
+    }
+}
+
+/// build_tree 阶段每构建多少节点上报一次进度
+const BUILD_TREE_PROGRESS_EVERY: u64 = 10_000;
+
+/// 从 records + index( indices ) 取根节点信息，再构建子树；建树过程中用 display_count 上报进度，避免前端数字回跳。
+pub(crate) fn build_tree_from_mft_records(
+    records: &[MftRecord],
+    child_index: &HashMap<String, Vec<usize>>,
+    recursive_sizes: &HashMap<String, u64>,
+    volume_root_trim: &str,
+    volume_root_key: &str,
+    root_name: &str,
+    root_path_str: &str,
+    shallow_dirs: bool,
+    options: TreeBuildOptions,
+    file_counts: Option<&HashMap<String, u64>>,
+    dir_modified: Option<&HashMap<String, u64>>,
+    progress: Option<&ProgressCbArc>,
+    display_count: u64,
+) -> Result<(FileNode, u64, u64), DiskAnalyzerError> {
+    let root_record = records.iter().find(|r| {
+        r.full_path
+            .trim_end_matches('\\')
+            .eq_ignore_ascii_case(volume_root_trim)
+    });
+    let (root_size, root_modified) = root_record
+        .map(|r| (r.size, r.modified))
+        .unwrap_or((0u64, None));
+    let root_modified = dir_modified
+        .and_then(|m| newest_modified(m, volume_root_trim))
+        .or(root_modified);
+
+    let direct_indices: Vec<usize> = child_index
+        .get(volume_root_key)
+        .or_else(|| child_index.get(volume_root_trim))
+        .or_else(|| {
+            child_index
+                .keys()
+                .find(|k| {
+                    k.eq_ignore_ascii_case(volume_root_key)
+                        || k.eq_ignore_ascii_case(volume_root_trim)
+                })
+                .and_then(|k| child_index.get(k))
+        })
+        .cloned()
+        .unwrap_or_default();
+
+    let nodes_built = AtomicU64::new(0);
+    let last_reported = AtomicU64::new(0);
+
+    // file_counts 为 Some 即只扫描目录模式：跳过文件记录
+    let dirs_only = file_counts.is_some();
+    let child_nodes: Vec<FileNode> = direct_indices
+        .par_iter()
+        .filter(|&&idx| !dirs_only || records[idx].is_dir)
+        .map(|&idx| {
+            let rec = &records[idx];
+            let name = ai_disk_common::path::file_name(&rec.full_path);
+            let is_shallow = shallow_dirs && rec.is_dir && is_shallow_dir_name(name);
+            let path = rec.full_path.as_str();
+            if is_shallow {
+                let size = recursive_sizes
+                    .get(path.trim_end_matches('\\'))
+                    .copied()
+                    .unwrap_or(rec.size);
+                FileNode {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    size,
+                    is_dir: true,
+                    modified: dir_modified
+                        .and_then(|m| newest_modified(m, path))
+                        .or(rec.modified),
+                    children: vec![],
+                    file_count: file_counts
+                        .and_then(|m| m.get(path.trim_end_matches('\\')).copied()),
+                    archive: None,
+                    system_managed: false,
+                    children_count: None,
+                    has_more: false,
+                    raw_path: rec.raw_path.clone(),
+                    collapsed_children: vec![],
+                    is_link: false,
+                }
+            } else if !rec.is_dir {
+                // 卷根下的文件（含 pagefile.sys 等系统管理文件）
+                FileNode {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    size: rec.size,
+                    is_dir: false,
+                    modified: rec.modified,
+                    system_managed: is_system_managed_file(path),
+                    children_count: None,
+                    has_more: false,
+                    raw_path: rec.raw_path.clone(),
+                    ..Default::default()
+                }
+            } else {
+                let (mut node, _cnt) = build_subtree_from_indices(
+                    records,
+                    child_index,
+                    recursive_sizes,
+                    path,
+                    name,
+                    1,
+                    shallow_dirs,
+                    options,
+                    file_counts,
+                    dir_modified,
+                    &nodes_built,
+                    &last_reported,
+                    progress,
+                    display_count,
+                );
+                node.raw_path = rec.raw_path.clone();
+                node
+            }
+        })
+        .collect();
+
+    let mut total_size = root_size;
+    let mut file_count = 1u64;
+    for c in &child_nodes {
+        total_size += c.size;
+        file_count += count_nodes(c);
+    }
+
+    // total_size 必须基于 recursive_sizes 中卷根的递归总大小，而非建树求和。
+    // 建树时因 max_children_per_dir 截断，求和会漏掉大量子项；recursive_sizes 基于全部 records 计算，准确。
+    let total_size = recursive_sizes
+        .get(volume_root_trim)
+        .or_else(|| recursive_sizes.get(volume_root_key.trim_end_matches('\\')))
+        .copied()
+        .unwrap_or(total_size);
+
+    // 只扫描目录模式下树中没有文件节点，file_count 取卷根的递归文件数
+    let root_file_count = file_counts.and_then(|m| {
+        m.get(volume_root_trim)
+            .or_else(|| m.get(volume_root_key.trim_end_matches('\\')))
+            .copied()
+    });
+    let file_count = root_file_count.unwrap_or(file_count);
+
+    let root = FileNode {
+        path: root_path_str.to_string(),
+        name: root_name.to_string(),
+        size: total_size,
+        is_dir: true,
+        modified: root_modified,
+        children: child_nodes,
+        file_count: root_file_count,
+        archive: None,
+        system_managed: false,
+        children_count: None,
+        has_more: false,
+        raw_path: None,
+        collapsed_children: vec![],
+        is_link: false,
+    };
+    Ok((root, file_count, total_size))
+}
+
+/// `compute_recursive_modified` 结果中该路径的时间，0 表示自身与后代都没有时间
+fn newest_modified(dir_modified: &HashMap<String, u64>, path: &str) -> Option<u64> {
+    dir_modified
+        .get(path.trim_end_matches('\\'))
+        .copied()
+        .filter(|&t| t > 0)
+}
+
+fn count_nodes(n: &FileNode) -> u64 {
+    if n.children.is_empty() {
+        return 1;
+    }
+    1 + n.children.iter().map(count_nodes).sum::<u64>()
+}
+
+/// 使用 indices 版 index 建子树，并周期性上报进度（用 display_count 保持前端数字不变），避免前端长时间无响应。
+fn build_subtree_from_indices(
+    records: &[MftRecord],
+    index: &HashMap<String, Vec<usize>>,
+    recursive_sizes: &HashMap<String, u64>,
+    path_prefix: &str,
+    name: &str,
+    depth: usize,
+    shallow_dirs: bool,
+    options: TreeBuildOptions,
+    file_counts: Option<&HashMap<String, u64>>,
+    dir_modified: Option<&HashMap<String, u64>>,
+    nodes_built: &AtomicU64,
+    last_reported: &AtomicU64,
+    progress: Option<&ProgressCbArc>,
+    display_count: u64,
+) -> (FileNode, u64) {
+    let children_indices = index.get(path_prefix).map(|v| v.as_slice()).unwrap_or(&[]);
+    let mut size = 0u64;
+    let mut file_count = 0u64;
+    // 未开启 `dir_modified_from_descendants` 时目录不带修改时间
+    let modified = dir_modified.and_then(|m| newest_modified(m, path_prefix));
+
+    let mut children: Vec<FileNode> =
+        Vec::with_capacity(children_indices.len().min(options.max_children_per_dir));
+    let dirs_only = file_counts.is_some();
+    let count_of = |p: &str| file_counts.and_then(|m| m.get(p.trim_end_matches('\\')).copied());
+    let modified_of = |rec: &MftRecord| {
+        rec.is_dir
+            .then(|| dir_modified.and_then(|m| newest_modified(m, &rec.full_path)))
+            .flatten()
+            .or(rec.modified)
+    };
+    // 自身记录与只扫描目录模式下的文件不算子项
+    let is_child_of = |parent: &str, rec: &MftRecord| {
+        !rec.full_path.eq_ignore_ascii_case(parent) && (!dirs_only || rec.is_dir)
+    };
+    let eligible = |rec: &MftRecord| is_child_of(path_prefix, rec);
+    let mut truncated = false;
+    for (pos, &idx) in children_indices.iter().enumerate() {
+        let rec = &records[idx];
+        if !eligible(rec) {
+            continue;
+        }
+        let child_name = ai_disk_common::path::file_name(&rec.full_path);
+        let child_path = rec.full_path.as_str();
+        let is_shallow = shallow_dirs && rec.is_dir && is_shallow_dir_name(child_name);
+        if is_shallow {
+            let child_size = recursive_sizes
+                .get(child_path.trim_end_matches('\\'))
+                .copied()
+                .unwrap_or(rec.size);
+            size += child_size;
+            file_count += 1;
+            children.push(FileNode {
+                path: child_path.to_string(),
+                name: child_name.to_string(),
+                size: child_size,
+                is_dir: true,
+                modified: modified_of(rec),
+                children: vec![],
+                file_count: count_of(child_path),
+                archive: None,
+                system_managed: false,
+                children_count: None,
+                has_more: false,
+                raw_path: rec.raw_path.clone(),
+                collapsed_children: vec![],
+                is_link: false,
+            });
+        } else if rec.is_dir && depth < options.max_depth {
+            let (mut child_node, cnt) = build_subtree_from_indices(
+                records,
+                index,
+                recursive_sizes,
+                child_path,
+                child_name,
+                depth + 1,
+                shallow_dirs,
+                options,
+                file_counts,
+                dir_modified,
+                nodes_built,
+                last_reported,
+                progress,
+                display_count,
+            );
+            child_node.raw_path = rec.raw_path.clone();
+            size += child_node.size;
+            file_count += cnt;
+            children.push(child_node);
+        } else {
+            // 文件，或超出深度的目录（取递归大小，标记 has_more）
+            let hidden_children = if rec.is_dir {
+                index.get(child_path).map_or(0, |v| {
+                    v.iter()
+                        .filter(|&&i| is_child_of(child_path, &records[i]))
+                        .count()
+                })
+            } else {
+                0
+            };
+            let child_size = if rec.is_dir {
+                recursive_sizes
+                    .get(child_path.trim_end_matches('\\'))
+                    .copied()
+                    .unwrap_or(rec.size)
+            } else {
+                rec.size
+            };
+            size += child_size;
+            file_count += 1;
+            children.push(FileNode {
+                path: child_path.to_string(),
+                name: child_name.to_string(),
+                size: child_size,
+                is_dir: rec.is_dir,
+                modified: modified_of(rec),
+                children: vec![],
+                file_count: count_of(child_path),
+                archive: None,
+                system_managed: !rec.is_dir && is_system_managed_file(child_path),
+                children_count: (hidden_children > 0).then_some(hidden_children as u64),
+                has_more: hidden_children > 0,
+                raw_path: rec.raw_path.clone(),
+                collapsed_children: vec![],
+                is_link: false,
+            });
+        }
+        if children.len() >= options.max_children_per_dir {
+            truncated = children_indices[pos + 1..]
+                .iter()
+                .any(|&i| eligible(&records[i]));
+            break;
+        }
+    }
+
+    // 若因 max_children_per_dir 截断，size 只包含前面部分子项之和，会丢失大量数据。
+    // 使用 recursive_sizes 获取该目录的真实递归总大小，确保 total_size 正确。
+    // 只扫描目录模式下文件子项被跳过，同样取递归总大小。
+    let size = if dirs_only || children_indices.len() > children.len() {
+        recursive_sizes
+            .get(path_prefix.trim_end_matches('\\'))
+            .copied()
+            .unwrap_or(size)
+    } else {
+        size
+    };
+
+    let cur = nodes_built.fetch_add(1, atomic::Ordering::Relaxed) + 1;
+    if let Some(cb) = progress {
+        let last = last_reported.load(atomic::Ordering::Relaxed);
+        if cur.saturating_sub(last) >= BUILD_TREE_PROGRESS_EVERY
+            && last_reported
+                .compare_exchange(
+                    last,
+                    cur,
+                    atomic::Ordering::Relaxed,
+                    atomic::Ordering::Relaxed,
+                )
+                .is_ok()
+        {
+            cb(display_count, "[scan:mft] building tree...");
+        }
+    }
+
+    let node = FileNode {
+        path: path_prefix.to_string(),
+        name: name.to_string(),
+        size,
+        is_dir: true,
+        modified,
+        children,
+        file_count: count_of(path_prefix),
+        archive: None,
+        system_managed: false,
+        children_count: truncated.then(|| {
+            children_indices
+                .iter()
+                .filter(|&&i| eligible(&records[i]))
+                .count() as u64
+        }),
+        has_more: truncated,
+        raw_path: None,
+        collapsed_children: vec![],
+        is_link: false,
+    };
+    (node, file_count + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::DEFAULT_MAX_DEPTH;
+
+    #[test]
+    fn test_system_metafiles_counted_separately() {
+        let mut agg = MftAggregate::new("C:");
+        // MFT 记录 0–15：元文件 + 卷根目录自身
+        for (path, size, is_dir) in [
+            (r"C:\$MFT", 400_000, false),
+            (r"C:\$MFTMirr", 4_096, false),
+            (r"C:\$LogFile", 64_000, false),
+            // 与卷同大小的稀疏流，不计入
+            (r"C:\$BadClus", 1_000_000_000, false),
+            (r"C:\$Extend", 0, true),
+            (r"C:\$Extend\$UsnJrnl", 32_000, false),
+            (r"C:\", 8_192, true),
+        ] {
+            agg.push(path.to_string(), size, is_dir, None);
+        }
+        for (path, size, is_dir) in [
+            (r"C:\Users", 0, true),
+            (r"C:\Users\u", 0, true),
+            (r"C:\Users\u\a.bin", 1_000, false),
+            (r"C:\Users\u\$notes.txt", 10, false),
+            (r"C:\pagefile.sys", 5_000, false),
+        ] {
+            agg.push(path.to_string(), size, is_dir, None);
+        }
+
+        assert_eq!(
+            agg.system_reserved_bytes,
+            400_000 + 4_096 + 64_000 + 32_000 + 8_192
+        );
+        assert_eq!(agg.sum_file_sizes(), 6_010);
+        assert_eq!(agg.records.len(), 6);
+        assert!(agg.records.iter().all(|r| !r.full_path.contains("$MFT")));
+
+        let sizes = compute_recursive_sizes(
+            &agg.records,
+            &agg.child_index,
+            &agg.direct_sizes,
+            "C:",
+            r"C:\",
+        );
+        assert_eq!(sizes["C:"], 6_010);
+        assert_eq!(sizes[r"C:\Users"], 1_010);
+    }
+
+    #[test]
+    fn test_budget_folds_files_into_parent() {
+        let budget = MftBudget {
+            max_file_records: 2,
+            ..MftBudget::default()
+        };
+        let mut agg = MftAggregate::with_budget("C:", &budget);
+        for (path, size, is_dir) in [
+            (r"C:\data", 0, true),
+            (r"C:\data\a.bin", 100, false),
+            (r"C:\data\b.bin", 200, false),
+            (r"C:\data\c.bin", 300, false),
+            (r"C:\data\sub", 0, true),
+            (r"C:\data\sub\d.bin", 400, false),
+            (r"C:\e.bin", 500, false),
+        ] {
+            agg.push(path.to_string(), size, is_dir, None);
+        }
+        assert!(agg.is_degraded());
+        // 目录记录始终保留，文件只保留前两条
+        assert_eq!(agg.records.len(), 4);
+        assert_eq!(agg.folded_counts[r"C:\data"], 1);
+        assert_eq!(agg.folded_counts[r"C:\data\sub"], 1);
+        assert_eq!(agg.folded_counts["C:"], 1);
+        assert_eq!(agg.sum_file_sizes(), 1_500);
+
+        let sizes = compute_recursive_sizes(
+            &agg.records,
+            &agg.child_index,
+            &agg.direct_sizes,
+            "C:",
+            r"C:\",
+        );
+        assert_eq!(sizes["C:"], 1_500);
+        assert_eq!(sizes[r"C:\data"], 1_000);
+        assert_eq!(sizes[r"C:\data\sub"], 400);
+    }
+
+    #[test]
+    fn test_tree_build_options_control_truncation() {
+        let mut agg = MftAggregate::new("C:");
+        let mut dir = "C:".to_string();
+        for level in 1..=15 {
+            dir = format!(r"{}\d{}", dir, level);
+            agg.push(dir.clone(), 0, true, None);
+        }
+        agg.push(format!(r"{}\leaf.bin", dir), 64, false, None);
+        for i in 0..4 {
+            agg.push(format!(r"C:\wide\f{}.bin", i), 10, false, None);
+        }
+        agg.push(r"C:\wide".to_string(), 0, true, None);
+        let sizes = compute_recursive_sizes(
+            &agg.records,
+            &agg.child_index,
+            &agg.direct_sizes,
+            "C:",
+            r"C:",
+        );
+        let build = |options| {
+            build_tree_from_mft_records(
+                &agg.records,
+                &agg.child_index,
+                &sizes,
+                "C:",
+                r"C:",
+                "C:",
+                r"C:",
+                false,
+                options,
+                None,
+                None,
+                None,
+                0,
+            )
+            .unwrap()
+            .0
+        };
+        let chain = |root: &FileNode| {
+            let mut levels = Vec::new();
+            let mut node = root.children.iter().find(|c| c.name == "d1");
+            while let Some(n) = node {
+                levels.push((n.has_more, n.children_count, n.size));
+                node = n.children.iter().find(|c| c.is_dir);
+            }
+            levels
+        };
+
+        let deep = build(TreeBuildOptions {
+            max_depth: 20,
+            max_children_per_dir: 2,
+        });
+        let levels = chain(&deep);
+        assert_eq!(levels.len(), 15);
+        assert!(levels.iter().all(|&l| l == (false, None, 64)));
+        let wide = deep.children.iter().find(|c| c.name == "wide").unwrap();
+        assert_eq!(wide.children.len(), 2);
+        assert_eq!((wide.has_more, wide.children_count), (true, Some(4)));
+        assert_eq!(wide.size, 40);
+
+        // 默认深度：更深一层的目录只计大小、不展开，标记 has_more
+        let shallow = build(TreeBuildOptions::default());
+        let levels = chain(&shallow);
+        assert_eq!(levels.len(), DEFAULT_MAX_DEPTH + 1);
+        assert_eq!(levels.last(), Some(&(true, Some(1), 64)));
+        assert!(!shallow
+            .children
+            .iter()
+            .any(|c| c.has_more && c.name == "wide"));
+
+        // 只扫描目录：截断点之后只剩文件时没有被丢弃的子项，不标记 has_more
+        let mut agg = MftAggregate::new("C:");
+        agg.push(r"C:\mixed".to_string(), 0, true, None);
+        agg.push(r"C:\mixed\sub".to_string(), 0, true, None);
+        agg.push(r"C:\mixed\sub\a.bin".to_string(), 5, false, None);
+        agg.push(r"C:\mixed\b.bin".to_string(), 7, false, None);
+        let sizes = compute_recursive_sizes(
+            &agg.records,
+            &agg.child_index,
+            &agg.direct_sizes,
+            "C:",
+            r"C:",
+        );
+        let file_counts = HashMap::new();
+        let root = build_tree_from_mft_records(
+            &agg.records,
+            &agg.child_index,
+            &sizes,
+            "C:",
+            r"C:",
+            "C:",
+            r"C:",
+            false,
+            TreeBuildOptions {
+                max_depth: 1,
+                max_children_per_dir: 1,
+            },
+            Some(&file_counts),
+            None,
+            None,
+            0,
+        )
+        .unwrap()
+        .0;
+        let mixed = &root.children[0];
+        assert_eq!(mixed.children.len(), 1);
+        assert_eq!((mixed.has_more, mixed.children_count), (false, None));
+        assert_eq!(mixed.size, 12);
+        // 超出深度的 sub 下只有文件，同样不标记
+        let sub = &mixed.children[0];
+        assert_eq!((sub.name.as_str(), sub.has_more), ("sub", false));
+    }
+
+    #[test]
+    fn test_dir_modified_is_newest_descendant() {
+        let mut agg = MftAggregate::new("C:");
+        for (path, is_dir, modified) in [
+            (r"C:\", true, Some(50)),
+            (r"C:\old", true, None),
+            (r"C:\old\a.txt", false, Some(100)),
+            (r"C:\old\deep", true, Some(120)),
+            (r"C:\old\deep\b.txt", false, Some(300)),
+            (r"C:\old\c.txt", false, None),
+            (r"C:\fresh", true, Some(900)),
+            (r"C:\fresh\d.txt", false, Some(400)),
+            (r"C:\empty", true, None),
+        ] {
+            agg.push(path.to_string(), 1, is_dir, modified);
+        }
+        let modified = compute_recursive_modified(&agg.records, &agg.child_index, "C:", r"C:\");
+        assert_eq!(modified[r"C:\old\deep"], 300);
+        assert_eq!(modified[r"C:\old"], 300);
+        // 目录自身记录的时间比后代新时取自身
+        assert_eq!(modified[r"C:\fresh"], 900);
+        assert_eq!(modified[r"C:\old\a.txt"], 100);
+        assert_eq!(modified[r"C:\empty"], 0);
+        assert_eq!(modified["C:"], 900);
+    }
+
+    #[test]
+    fn test_extension_groups_by_folder() {
+        let mut groups = ExtensionGroups::new(&["CR2", ".mp4"]);
+        for (path, size) in [
+            (r"D:\Photos\2024\a.cr2", 30),
+            (r"D:\Photos\2024\b.CR2", 50),
+            (r"D:\Photos\2024\b.jpg", 500),
+            (r"D:\Videos\trip.MP4", 70),
+            (r"D:\Videos\trip.mp4.txt", 1_000),
+            (r"D:\Videos\clips\c.mp4", 10),
+            (r"D:\notes", 5),
+        ] {
+            groups.push(path, size, None);
+        }
+        let groups = groups.finish();
+        let summary: Vec<(&str, u64, Vec<u64>)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.folder.as_str(),
+                    g.total_size,
+                    g.files.iter().map(|f| f.size).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (r"D:\Photos\2024", 80, vec![50, 30]),
+                (r"D:\Videos", 70, vec![70]),
+                (r"D:\Videos\clips", 10, vec![10]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_capped_cache_resets_at_limit() {
+        let mut cache: CappedCache<HashMap<String, u64>> = CappedCache::new(3);
+        for i in 0..3 {
+            cache.next().insert(format!("d{}", i), i);
+        }
+        assert_eq!(cache.next().len(), 0);
+        cache.next().insert("d3".to_string(), 3);
+        assert_eq!(cache.next().len(), 1);
+        assert_eq!(cache.resets(), 1);
+    }
+
+    #[test]
+    fn test_top_files_order_is_stable_for_equal_sizes() {
+        let files = [
+            (r"C:\b\movie.mkv", 700, Some(3)),
+            (r"C:\a\z.iso", 500, Some(9)),
+            (r"C:\a\y.iso", 500, None),
+            (r"C:\c\x.iso", 500, Some(1)),
+            (r"C:\a\y.iso", 500, Some(2)),
+            (r"C:\d\small.txt", 10, None),
+        ];
+        let run = |order: &[usize], n: usize| -> Vec<(String, u64, Option<u64>)> {
+            let mut top = TopFiles::new(n);
+            for &i in order {
+                let (path, size, modified) = files[i];
+                top.push(path.to_string(), size, modified);
+            }
+            top.finish()
+                .into_iter()
+                .map(|f| (f.path, f.size, f.modified))
+                .collect()
+        };
+
+        let expected: Vec<(String, u64, Option<u64>)> = [
+            (r"C:\b\movie.mkv", 700, Some(3)),
+            (r"C:\a\y.iso", 500, None),
+            (r"C:\a\y.iso", 500, Some(2)),
+            (r"C:\a\z.iso", 500, Some(9)),
+        ]
+        .into_iter()
+        .map(|(p, s, m)| (p.to_string(), s, m))
+        .collect();
+        // 不同的枚举顺序得到同样的结果，名额边界上同大小的 x.iso 按路径被淘汰
+        for order in [[0, 1, 2, 3, 4, 5], [5, 4, 3, 2, 1, 0], [3, 1, 5, 0, 4, 2]] {
+            assert_eq!(run(&order, 4), expected);
+        }
+
+        let mut entries: Vec<TopFileEntry> = files
+            .iter()
+            .rev()
+            .map(|&(path, size, modified)| TopFileEntry {
+                path: path.to_string(),
+                size,
+                modified,
+            })
+            .collect();
+        entries.sort_by(top_file_order);
+        let sorted: Vec<_> = entries
+            .into_iter()
+            .map(|f| (f.path, f.size, f.modified))
+            .take(4)
+            .collect();
+        assert_eq!(sorted, expected);
+    }
+}
@@ -0,0 +1,424 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use ai_disk_common::{AppConfig, DiskAnalyzerError};
+use ai_disk_domain::{ScanMeta, ScanStrategy};
+
+use crate::error_history::ErrorHistory;
+use crate::filters::{ExcludePatterns, ScanFilters};
+use crate::scanner::{PercentCb, ProgressCbArc, SubtreeCb};
+
+/// 默认展开的最大目录深度（根为 0）
+pub const DEFAULT_MAX_DEPTH: usize = 10;
+/// 每个目录默认最多保留的子节点数
+pub const DEFAULT_MAX_CHILDREN_PER_DIR: usize = 500;
+
+/// 建树的截断限制。超出限制的目录保留递归大小，并标记 `FileNode::has_more`
+/// （子节点被截断时另填 `children_count`），前端据此显示「…更多」并按需加载
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeBuildOptions {
+    /// 展开的最大目录深度（根为 0）
+    pub max_depth: usize,
+    /// 每个目录最多保留的子节点数
+    pub max_children_per_dir: usize,
+}
+
+impl Default for TreeBuildOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_children_per_dir: DEFAULT_MAX_CHILDREN_PER_DIR,
+        }
+    }
+}
+
+/// MFT 扫描的内存预算。超出 `max_file_records` 后进入降级模式：后续文件不再单独保存记录，
+/// 只把大小与数量累加到所在目录（目录记录始终保留），树中不显示这些文件但目录大小仍准确
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MftBudget {
+    /// 最多保存的文件记录数
+    pub max_file_records: usize,
+    /// 路径缓存最多累计的条目数，达到后清空重建
+    pub max_path_cache: usize,
+}
+
+impl Default for MftBudget {
+    fn default() -> Self {
+        Self {
+            max_file_records: 8_000_000,
+            max_path_cache: 1_000_000,
+        }
+    }
+}
+
+/// 普通遍历遇到符号链接与目录联接时的处理方式。无论哪种方式，链接都以 `FileNode::is_link` 标记；
+/// 指回自身或祖先目录的链接、以及已被其他链接展开过的目标都不会再展开
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// 不展开：链接保留为大小为 0 的节点
+    #[default]
+    Skip,
+    /// 展开位于扫描根之外的目标并计入大小，每个目标只计一次；指向根内的链接同 `Skip`
+    FollowOnce,
+    /// 展开目标以便浏览，但其中各层大小与文件数为 0，不计入总量（shallow 目录中同 `Skip`）
+    CountAsZero,
+}
+
+/// 扫描选项
+#[derive(Clone)]
+pub struct ScanOptions {
+    /// 对 node_modules/.git 等常见包管理器/缓存目录只计大小不递归
+    pub shallow_dirs: bool,
+    /// 路径为 Windows 卷根（如 C:\）时优先使用 MFT 加速扫描
+    pub use_mft: bool,
+    /// 开启 `shallow_dirs` 时仍完整扫描 shallow 目录，把其子节点保留在 `FileNode::collapsed_children` 中，
+    /// 之后切换 shallow 设置（`ScanResult::rebuild_with_shallow`）无需重新扫描；扫描更慢、结果更大
+    pub retain_shallow_children: bool,
+    /// 只扫描目录：树中只保留目录节点（带递归大小与文件数），不构建文件节点，
+    /// 用于只需文件夹大小的 Treemap 视图，显著减少内存与序列化开销。
+    /// 此时 `ScanResult::file_count` 为实际文件数（等于根节点的 `file_count`）：
+    /// 完整扫描时 shallow 目录与超出深度的目录各计 1，这里按其中的文件计
+    pub dirs_only: bool,
+    /// 普通遍历前先快速统计一遍目录数作为总量，遍历中按已处理目录数上报百分比
+    /// （单调递增、结束时为 100）；MFT 扫描不受影响
+    pub estimate_progress: bool,
+    /// 读取 zip/tar 的中央目录或文件头，在文件节点上记录条目数与解压后大小（`FileNode::archive`）；
+    /// 仅普通遍历生效
+    pub peek_archives: bool,
+    /// 展开的最大目录深度（根为 0，至少为 1）：达到该深度的目录只计递归大小、不含子节点
+    pub max_depth: usize,
+    /// 每个目录最多保留的子节点数（至少为 1）：超出的子项不出现在树中
+    /// （MFT 扫描仍计入目录大小，普通遍历不再读取）
+    pub max_children_per_dir: usize,
+    /// 目录的修改时间取其自身与所有后代中最新的一个（默认只取目录自身记录的时间，
+    /// MFT 扫描下多为空），用于判断「该文件夹两年未变」；需额外汇总一遍，默认关闭
+    pub dir_modified_from_descendants: bool,
+    /// 真实大小：同一文件的多个硬链接只计一次，符号链接与目录联接等重解析点不展开，
+    /// 朴素总大小另记在 `ScanResult::naive_total_size`；仅普通遍历生效（MFT 扫描每条文件记录只计一次），
+    /// 每个文件需额外查询一次文件标识，适合系统卷
+    pub realistic_sizes: bool,
+    /// 各层子节点按大小从大到小排列（同大小按名称），见 `FileNode::sort_children_by_size_desc`；
+    /// 默认按扫描顺序（普通遍历为目录在前、按名称）
+    pub sort_children_by_size: bool,
+    /// 符号链接与目录联接的处理方式，默认不展开；开启 `realistic_sizes` 时始终不展开
+    pub symlinks: SymlinkPolicy,
+    /// 普通遍历的线程数：各目录的子项并行处理，`None` 时使用 rayon 全局线程池（通常为 CPU 核数），
+    /// `Some(1)` 即单线程遍历；不影响扫描结果
+    pub scan_threads: Option<usize>,
+    /// 进度回调（`scan` 使用）
+    pub progress: Option<ProgressCbArc>,
+    /// 百分比回调，需同时开启 `estimate_progress`（`scan` 使用）
+    pub on_percent: Option<Arc<PercentCb>>,
+    /// 每个第一层子目录扫描完成时以其子树回调（`scan` 使用），可在整个扫描结束前逐步渲染；
+    /// 普通遍历中各子目录并行扫描，回调顺序不定。MFT 扫描在建树完成后一并回调
+    pub on_subtree: Option<Arc<SubtreeCb>>,
+    /// 置为 true 时尽快停止扫描并返回 `DiskAnalyzerError::Cancelled`
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// MFT 扫描的内存预算
+    pub mft_budget: MftBudget,
+    /// 跳过的目录（连同其内容不出现在结果中、不计入大小）；默认为应用数据目录，
+    /// 见 `AppConfig::scan_exclude_dirs`
+    pub exclude_dirs: Vec<PathBuf>,
+    /// 按 glob 排除的路径（匹配完整路径，见 `filters` 模块文档）；目录在枚举其子项之前判断，
+    /// 命中的目录不会被遍历
+    pub exclude_patterns: Vec<String>,
+    /// 之前扫描中出错的路径记录：普通遍历跳过仍在重试等待期内的路径，并记录新出错的路径
+    pub error_history: Option<Arc<ErrorHistory>>,
+    /// 忽略重试等待期，重新尝试所有之前出错的路径（仍会更新记录）
+    pub retry_errored_paths: bool,
+    /// 普通遍历时把因权限不足未能读取的目录路径收集到 `ScanResult::denied_paths`，供排查问题
+    pub collect_denied_paths: bool,
+}
+
+impl fmt::Debug for ScanOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanOptions")
+            .field("shallow_dirs", &self.shallow_dirs)
+            .field("use_mft", &self.use_mft)
+            .field("retain_shallow_children", &self.retain_shallow_children)
+            .field("dirs_only", &self.dirs_only)
+            .field("estimate_progress", &self.estimate_progress)
+            .field("peek_archives", &self.peek_archives)
+            .field("max_depth", &self.max_depth)
+            .field("max_children_per_dir", &self.max_children_per_dir)
+            .field(
+                "dir_modified_from_descendants",
+                &self.dir_modified_from_descendants,
+            )
+            .field("realistic_sizes", &self.realistic_sizes)
+            .field("sort_children_by_size", &self.sort_children_by_size)
+            .field("symlinks", &self.symlinks)
+            .field("scan_threads", &self.scan_threads)
+            .field("progress", &self.progress.is_some())
+            .field("on_percent", &self.on_percent.is_some())
+            .field("on_subtree", &self.on_subtree.is_some())
+            .field("cancel", &self.cancel)
+            .field("mft_budget", &self.mft_budget)
+            .field("exclude_dirs", &self.exclude_dirs)
+            .field("exclude_patterns", &self.exclude_patterns)
+            .field("error_history", &self.error_history.is_some())
+            .field("retry_errored_paths", &self.retry_errored_paths)
+            .field("collect_denied_paths", &self.collect_denied_paths)
+            .finish()
+    }
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            shallow_dirs: true,
+            use_mft: true,
+            retain_shallow_children: false,
+            dirs_only: false,
+            estimate_progress: false,
+            peek_archives: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_children_per_dir: DEFAULT_MAX_CHILDREN_PER_DIR,
+            dir_modified_from_descendants: false,
+            realistic_sizes: false,
+            sort_children_by_size: false,
+            symlinks: SymlinkPolicy::default(),
+            scan_threads: None,
+            progress: None,
+            on_percent: None,
+            on_subtree: None,
+            cancel: None,
+            mft_budget: MftBudget::default(),
+            exclude_dirs: AppConfig::default().scan_exclude_dirs(),
+            exclude_patterns: Vec::new(),
+            error_history: None,
+            retry_errored_paths: false,
+            collect_denied_paths: false,
+        }
+    }
+}
+
+impl ScanOptions {
+    /// 从默认值（与 `ScanOptions::default()` 相同）开始逐项设置，如
+    /// `ScanOptions::builder().shallow_dirs(true).max_depth(5).cancel(flag).build()`
+    pub fn builder() -> ScanOptionsBuilder {
+        ScanOptionsBuilder::default()
+    }
+
+    /// 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    /// 实际存在的排除目录，规范化为与扫描路径相同的形式
+    pub(crate) fn resolved_excludes(&self) -> Vec<PathBuf> {
+        self.exclude_dirs
+            .iter()
+            .filter_map(|dir| std::fs::canonicalize(dir).ok())
+            .collect()
+    }
+
+    /// 建树的截断限制
+    pub fn tree_build_options(&self) -> TreeBuildOptions {
+        TreeBuildOptions {
+            max_depth: self.max_depth,
+            max_children_per_dir: self.max_children_per_dir,
+        }
+    }
+
+    /// 编译 `exclude_patterns`，模式无效时返回 `DiskAnalyzerError::Config`
+    pub(crate) fn compiled_exclude_patterns(&self) -> Result<ExcludePatterns, DiskAnalyzerError> {
+        ExcludePatterns::compile(&self.exclude_patterns)
+    }
+
+    /// 影响树内容的选项摘要（不含 `shallow_dirs`，其单独记录在 `ScanMeta` 中）
+    pub fn filters_summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.dirs_only {
+            parts.push("dirs_only".to_string());
+        }
+        if self.peek_archives {
+            parts.push("peek_archives".to_string());
+        }
+        if self.max_depth != DEFAULT_MAX_DEPTH {
+            parts.push(format!("max_depth={}", self.max_depth));
+        }
+        if self.max_children_per_dir != DEFAULT_MAX_CHILDREN_PER_DIR {
+            parts.push(format!("max_children={}", self.max_children_per_dir));
+        }
+        if self.dir_modified_from_descendants {
+            parts.push("dir_modified_from_descendants".to_string());
+        }
+        if self.realistic_sizes {
+            parts.push("realistic_sizes".to_string());
+        }
+        match self.symlinks {
+            SymlinkPolicy::Skip => {}
+            SymlinkPolicy::FollowOnce => parts.push("symlinks=follow_once".to_string()),
+            SymlinkPolicy::CountAsZero => parts.push("symlinks=count_as_zero".to_string()),
+        }
+        if !self.exclude_patterns.is_empty() {
+            parts.push(format!("exclude={}", self.exclude_patterns.join(";")));
+        }
+        parts.join(",")
+    }
+
+    /// 记录本次扫描的策略与选项
+    pub fn scan_meta(&self, strategy: ScanStrategy, root: &str, timestamp: u64) -> ScanMeta {
+        ScanMeta {
+            strategy,
+            shallow_dirs: self.shallow_dirs,
+            filters_summary: self.filters_summary(),
+            root: root.to_string(),
+            timestamp,
+        }
+    }
+}
+
+/// `ScanOptions` 的构建器，各方法与同名字段对应
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptionsBuilder {
+    options: ScanOptions,
+}
+
+impl ScanOptionsBuilder {
+    pub fn shallow_dirs(mut self, enabled: bool) -> Self {
+        self.options.shallow_dirs = enabled;
+        self
+    }
+
+    pub fn use_mft(mut self, enabled: bool) -> Self {
+        self.options.use_mft = enabled;
+        self
+    }
+
+    pub fn retain_shallow_children(mut self, enabled: bool) -> Self {
+        self.options.retain_shallow_children = enabled;
+        self
+    }
+
+    pub fn dirs_only(mut self, enabled: bool) -> Self {
+        self.options.dirs_only = enabled;
+        self
+    }
+
+    pub fn estimate_progress(mut self, enabled: bool) -> Self {
+        self.options.estimate_progress = enabled;
+        self
+    }
+
+    pub fn peek_archives(mut self, enabled: bool) -> Self {
+        self.options.peek_archives = enabled;
+        self
+    }
+
+    /// 小于 1 时按 1 处理
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.options.max_depth = depth.max(1);
+        self
+    }
+
+    /// 小于 1 时按 1 处理
+    pub fn max_children_per_dir(mut self, count: usize) -> Self {
+        self.options.max_children_per_dir = count.max(1);
+        self
+    }
+
+    /// 同时设置 `max_depth` 与 `max_children_per_dir`
+    pub fn tree_build_options(self, tree: TreeBuildOptions) -> Self {
+        self.max_depth(tree.max_depth)
+            .max_children_per_dir(tree.max_children_per_dir)
+    }
+
+    pub fn dir_modified_from_descendants(mut self, enabled: bool) -> Self {
+        self.options.dir_modified_from_descendants = enabled;
+        self
+    }
+
+    pub fn realistic_sizes(mut self, enabled: bool) -> Self {
+        self.options.realistic_sizes = enabled;
+        self
+    }
+
+    pub fn sort_children_by_size(mut self, enabled: bool) -> Self {
+        self.options.sort_children_by_size = enabled;
+        self
+    }
+
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.options.symlinks = policy;
+        self
+    }
+
+    /// 小于 1 时按 1 处理
+    pub fn scan_threads(mut self, threads: usize) -> Self {
+        self.options.scan_threads = Some(threads.max(1));
+        self
+    }
+
+    pub fn progress(mut self, progress: ProgressCbArc) -> Self {
+        self.options.progress = Some(progress);
+        self
+    }
+
+    /// 同时开启 `estimate_progress`
+    pub fn on_percent(mut self, on_percent: PercentCb) -> Self {
+        self.options.on_percent = Some(Arc::new(on_percent));
+        self.options.estimate_progress = true;
+        self
+    }
+
+    pub fn on_subtree(mut self, on_subtree: SubtreeCb) -> Self {
+        self.options.on_subtree = Some(Arc::new(on_subtree));
+        self
+    }
+
+    pub fn cancel(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.options.cancel = Some(flag);
+        self
+    }
+
+    pub fn mft_budget(mut self, budget: MftBudget) -> Self {
+        self.options.mft_budget = budget;
+        self
+    }
+
+    pub fn exclude_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.options.exclude_dirs = dirs;
+        self
+    }
+
+    pub fn exclude_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.options.exclude_patterns = patterns;
+        self
+    }
+
+    /// 应用过滤器：追加其排除模式，设置了 `max_depth` 时覆盖最大深度
+    pub fn filters(mut self, filters: &ScanFilters) -> Self {
+        self.options
+            .exclude_patterns
+            .extend(filters.exclude_patterns.iter().cloned());
+        match filters.max_depth {
+            Some(depth) => self.max_depth(depth),
+            None => self,
+        }
+    }
+
+    pub fn error_history(mut self, history: Arc<ErrorHistory>) -> Self {
+        self.options.error_history = Some(history);
+        self
+    }
+
+    pub fn retry_errored_paths(mut self, enabled: bool) -> Self {
+        self.options.retry_errored_paths = enabled;
+        self
+    }
+
+    pub fn collect_denied_paths(mut self, enabled: bool) -> Self {
+        self.options.collect_denied_paths = enabled;
+        self
+    }
+
+    pub fn build(self) -> ScanOptions {
+        self.options
+    }
+}
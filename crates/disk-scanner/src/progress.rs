@@ -0,0 +1,203 @@
+//! 进度事件的有界合并通道：扫描线程只覆盖一个「最新进度」槽位，从不阻塞；后台线程把槽位中的
+//! 事件交给消费端（如向前端 emit）。消费端慢时中间进度被合并丢弃（进度可丢），内存占用恒定，
+//! 而 `finish` 保证最后一个事件一定送达。多个线程（如 MFT 枚举与后台加载线程、百分比估算）同时上报时，
+//! 事件在写入槽位时按全局顺序编号，消费端只在一个线程中按编号递增的顺序收到事件。
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use crate::scanner::{PercentCb, ProgressCbArc};
+
+/// 送达消费端的进度事件：数量、路径与百分比都取各自最近一次上报的值
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayedProgress {
+    /// 写入顺序编号，从 1 开始严格递增；被合并的事件也占用编号
+    pub seq: u64,
+    pub count: u64,
+    pub message: String,
+    pub percent: Option<u8>,
+}
+
+#[derive(Default)]
+struct Slot {
+    latest: RelayedProgress,
+    /// `latest` 尚未送达消费端
+    pending: bool,
+    closed: bool,
+    /// 被后续事件覆盖、未送达消费端的事件数
+    coalesced: u64,
+}
+
+#[derive(Default)]
+struct Shared {
+    slot: Mutex<Slot>,
+    ready: Condvar,
+}
+
+impl Shared {
+    /// 在锁内更新事件并分配编号，保证编号顺序与写入顺序一致
+    fn update(&self, apply: impl FnOnce(&mut RelayedProgress)) {
+        let mut slot = self.slot.lock().unwrap();
+        apply(&mut slot.latest);
+        slot.latest.seq += 1;
+        if std::mem::replace(&mut slot.pending, true) {
+            slot.coalesced += 1;
+        }
+        drop(slot);
+        self.ready.notify_one();
+    }
+}
+
+/// 合并进度通道，drop 时等同于 `finish`
+pub struct CoalescingProgress {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl CoalescingProgress {
+    /// 启动后台线程，依次把最新进度（数量、路径）交给 `sink`
+    pub fn spawn(sink: impl Fn(u64, &str) + Send + 'static) -> Self {
+        Self::spawn_events(move |event| sink(event.count, &event.message))
+    }
+
+    /// 同 `spawn`，`sink` 收到带编号与百分比的完整事件
+    pub fn spawn_events(sink: impl Fn(&RelayedProgress) + Send + 'static) -> Self {
+        let shared = Arc::new(Shared::default());
+        let worker = {
+            let shared = shared.clone();
+            std::thread::spawn(move || loop {
+                let next = {
+                    let mut slot = shared.slot.lock().unwrap();
+                    while !slot.pending && !slot.closed {
+                        slot = shared.ready.wait(slot).unwrap();
+                    }
+                    let pending = std::mem::take(&mut slot.pending);
+                    pending.then(|| slot.latest.clone())
+                };
+                match next {
+                    Some(event) => sink(&event),
+                    None => break,
+                }
+            })
+        };
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// 生产端回调，可直接传给各扫描入口；只替换槽位中的事件，不会阻塞扫描
+    pub fn callback(&self) -> ProgressCbArc {
+        let shared = self.shared.clone();
+        Arc::new(Box::new(move |count: u64, message: &str| {
+            shared.update(|event| {
+                event.count = count;
+                event.message.clear();
+                event.message.push_str(message);
+            });
+        }))
+    }
+
+    /// 百分比回调（`ScanOptions::on_percent`），与数量进度经同一槽位按顺序送达
+    pub fn percent_callback(&self) -> PercentCb {
+        let shared = self.shared.clone();
+        Box::new(move |percent: u8| shared.update(|event| event.percent = Some(percent)))
+    }
+
+    /// 被合并丢弃的中间事件数
+    pub fn coalesced(&self) -> u64 {
+        self.shared.slot.lock().unwrap().coalesced
+    }
+
+    /// 关闭通道并等待最后一个事件送达消费端
+    pub fn finish(mut self) {
+        self.close();
+    }
+
+    fn close(&mut self) {
+        self.shared.slot.lock().unwrap().closed = true;
+        self.shared.ready.notify_one();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for CoalescingProgress {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_slow_consumer_coalesces_and_gets_final_event() {
+        const EVENTS: u64 = 20_000;
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let relay = CoalescingProgress::spawn(move |count, message| {
+            std::thread::sleep(Duration::from_millis(2));
+            sink.lock().unwrap().push((count, message.to_string()));
+        });
+
+        let cb = relay.callback();
+        for i in 1..=EVENTS {
+            cb(i, if i == EVENTS { "done" } else { "scanning" });
+        }
+        let coalesced = relay.coalesced();
+        relay.finish();
+
+        let received = received.lock().unwrap();
+        // 槽位只保留一个待发事件：送达 + 合并丢弃 = 全部事件
+        assert_eq!(received.len() as u64 + coalesced, EVENTS);
+        assert!(received.len() < 1_000, "delivered {}", received.len());
+        assert!(received.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(received.last(), Some(&(EVENTS, "done".to_string())));
+    }
+
+    #[test]
+    fn test_concurrent_emitters_are_delivered_in_sequence() {
+        const THREADS: u64 = 4;
+        const PER_THREAD: u64 = 2_000;
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let relay = CoalescingProgress::spawn_events(move |event| {
+            sink.lock().unwrap().push(event.clone());
+        });
+
+        std::thread::scope(|scope| {
+            for t in 0..THREADS {
+                let cb = relay.callback();
+                scope.spawn(move || {
+                    for i in 0..PER_THREAD {
+                        let count = t * PER_THREAD + i;
+                        cb(count, &format!("t{}/{}", t, count));
+                    }
+                });
+            }
+            let on_percent = relay.percent_callback();
+            scope.spawn(move || (0..=100).for_each(on_percent));
+        });
+        let coalesced = relay.coalesced();
+        relay.finish();
+
+        let received = received.lock().unwrap();
+        let total = THREADS * PER_THREAD + 101;
+        assert_eq!(received.len() as u64 + coalesced, total);
+        assert!(received.windows(2).all(|w| w[0].seq < w[1].seq));
+        assert_eq!(received.last().unwrap().seq, total);
+        // 数量与路径总是同一次上报写入的一对
+        for event in received.iter().filter(|e| !e.message.is_empty()) {
+            let (_, count) = event.message.split_once('/').unwrap();
+            assert_eq!(count.parse::<u64>().unwrap(), event.count);
+        }
+        // 百分比来自单个线程，按顺序送达时不会回退
+        let percents: Vec<u8> = received.iter().filter_map(|e| e.percent).collect();
+        assert!(percents.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(received.last().unwrap().percent, Some(100));
+    }
+}
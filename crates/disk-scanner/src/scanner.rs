@@ -1,13 +1,21 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{Instant, UNIX_EPOCH};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use ai_disk_common::DiskAnalyzerError;
-use ai_disk_domain::{FileNode, ScanResult};
+use ai_disk_common::path::{components, is_under, CaseSensitivity};
+use ai_disk_common::{telemetry, DiskAnalyzerError, ErrorContext, TelemetryEvent};
+use ai_disk_domain::{
+    is_system_managed_file, FileNode, PhaseTiming, ScanResult, ScanStrategy, ScanTiming,
+    TimingPhase,
+};
 use rayon::prelude::*;
 
-const MAX_DEPTH: usize = 10;
-const MAX_CHILDREN_PER_DIR: usize = 500;
+use crate::archive::peek_archive;
+use crate::error_history::ErrorHistory;
+use crate::filters::ExcludePatterns;
+use crate::links::{file_id, FollowedTargets, LinkDedup};
+use crate::options::{ScanOptions, SymlinkPolicy};
 
 /// Windows: 文件或目录损坏且无法读取，遇到时跳过该路径继续扫描
 #[cfg(windows)]
@@ -24,78 +32,409 @@ fn is_corruption_io_error(_e: &std::io::Error) -> bool {
     false
 }
 
-/// 遇到这些目录名时只统计总大小，不递归子项（常见包管理器/缓存目录）
-pub(crate) const SHALLOW_DIR_NAMES: &[&str] = &[
-    "node_modules",
-    ".git",
-    ".github",
-    ".venv",
-    "venv",
-    "__pycache__",
-    "target",
-    "vendor",
-    ".npm",
-    ".yarn",
-    ".pnpm",
-    "bower_components",
-    "jspm_packages",
-];
-
-pub(crate) type ProgressCb = Box<dyn Fn(u64, &str) + Send + Sync>;
+/// 遍历中无法跳过的 I/O 错误，附带阶段与路径
+fn walk_error(e: std::io::Error, phase: &str, path: &Path) -> DiskAnalyzerError {
+    DiskAnalyzerError::Io(e).context(
+        ErrorContext::new("scan")
+            .phase(phase)
+            .path(path.display().to_string()),
+    )
+}
+
+/// 进度回调：(已处理数量, 当前路径)
+pub type ProgressCb = Box<dyn Fn(u64, &str) + Send + Sync>;
 
 /// 可共享的进度回调，用于 MFT 加载时在后台线程中上报进度。
-pub(crate) type ProgressCbArc = std::sync::Arc<ProgressCb>;
+pub type ProgressCbArc = std::sync::Arc<ProgressCb>;
+
+/// 第一层子目录扫描完成的回调，见 `ScanOptions::on_subtree`
+pub type SubtreeCb = Box<dyn Fn(&FileNode) + Send + Sync>;
+
+/// 百分比进度回调（0–100），见 `ScanOptions::estimate_progress`
+pub type PercentCb = Box<dyn Fn(u8) + Send + Sync>;
+
+/// 普通遍历的进度估算：以预扫描得到的目录数为分母，按已处理目录数上报百分比。
+/// 上报在锁内进行，保证回调看到的百分比严格递增；遍历结束前最多报到 99。
+struct WalkEstimate<'a> {
+    total: u64,
+    done: AtomicU64,
+    reported: Mutex<u8>,
+    on_percent: &'a PercentCb,
+}
+
+impl<'a> WalkEstimate<'a> {
+    fn new(total: u64, on_percent: &'a PercentCb) -> Self {
+        on_percent(0);
+        Self {
+            total: total.max(1),
+            done: AtomicU64::new(0),
+            reported: Mutex::new(0),
+            on_percent,
+        }
+    }
+
+    /// 一个目录处理完毕
+    fn advance(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let percent = (done.saturating_mul(100) / self.total).min(99) as u8;
+        self.report(percent);
+    }
+
+    fn finish(&self) {
+        self.report(100);
+    }
+
+    fn report(&self, percent: u8) {
+        let mut last = self.reported.lock().unwrap();
+        if percent > *last {
+            *last = percent;
+            (self.on_percent)(percent);
+        }
+    }
+}
+
+fn is_shallow_dir_name(name: &std::ffi::OsStr) -> bool {
+    ai_disk_domain::is_shallow_dir_name(&name.to_string_lossy())
+}
+
+/// 位于 `depth` 的目录是否只计大小不展开：shallow 目录，或已达到 `max_depth`
+fn is_collapsed_dir(name: &std::ffi::OsStr, depth: usize, opts: &ScanOptions) -> bool {
+    depth >= opts.max_depth || (opts.shallow_dirs && is_shallow_dir_name(name))
+}
+
+fn sub_dirs(path: &Path) -> Vec<std::fs::DirEntry> {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 预扫描：统计 `build_tree` 将会展开的目录数（只读目录项类型，不取元数据）
+fn count_walk_dirs(path: &Path, depth: usize, opts: &ScanOptions) -> u64 {
+    if depth >= opts.max_depth {
+        return 0;
+    }
+    1 + sub_dirs(path)
+        .par_iter()
+        .map(|entry| {
+            if is_collapsed_dir(&entry.file_name(), depth + 1, opts) {
+                count_all_dirs(&entry.path())
+            } else {
+                count_walk_dirs(&entry.path(), depth + 1, opts)
+            }
+        })
+        .sum::<u64>()
+}
 
-/// 仅统计目录总大小，不构建子树（用于 shallow 目录）
+/// 预扫描：统计 `dir_size_only` 将会访问的目录数
+fn count_all_dirs(path: &Path) -> u64 {
+    1 + sub_dirs(path)
+        .iter()
+        .map(|entry| count_all_dirs(&entry.path()))
+        .sum::<u64>()
+}
+
+/// 仅统计目录总大小与文件数，不构建子树（用于 shallow 目录）；`ancestors` 为 `path` 的真实路径链
 fn dir_size_only(
     path: &Path,
-    counter: &AtomicU64,
-    progress: Option<&ProgressCb>,
-) -> Result<u64, DiskAnalyzerError> {
+    walk: &Walk,
+    ancestors: &Ancestors,
+) -> Result<(u64, u64), DiskAnalyzerError> {
+    let Walk {
+        counter,
+        progress,
+        estimate,
+        links,
+        ..
+    } = *walk;
     let mut total: u64 = 0;
+    let mut files: u64 = 0;
+    // 本层的文件数，子目录的文件在各自的调用中计入进度
+    let mut direct: u64 = 0;
     let entries = match std::fs::read_dir(path) {
         Ok(e) => e,
         Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
-            return Ok(0);
+            walk.record_denied(path);
+            return Ok((0, 0));
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
             // 路径不存在（符号链接失效、文件被删除等），跳过
-            return Ok(0);
+            return Ok((0, 0));
         }
         Err(e) if is_corruption_io_error(&e) => {
-            return Ok(0); // 损坏，跳过该目录
+            return Ok((0, 0)); // 损坏，跳过该目录
         }
-        Err(e) => return Err(DiskAnalyzerError::Io(e)),
+        Err(e) => return Err(walk_error(e, "directory size", path)),
     };
     for entry in entries.filter_map(|e| e.ok()) {
         let path = entry.path();
+        if walk.is_excluded(&path) || walk.skip_errored(&path) {
+            continue;
+        }
+        // shallow 目录只计大小：`CountAsZero` 的链接在此不展开（也不占用目标的展开记录）
+        let chain = if entry.file_type().is_ok_and(|t| t.is_symlink()) {
+            let follow = match walk.symlinks {
+                SymlinkPolicy::CountAsZero if links.is_none() => None,
+                _ => walk.follow_link(&entry, ancestors),
+            };
+            match follow {
+                Some(chain) => chain,
+                None => continue,
+            }
+        } else {
+            ancestors.child(ancestors.real.join(entry.file_name()))
+        };
         if path.is_dir() {
-            if let Ok(size) = dir_size_only(&path, counter, progress) {
+            if let Ok((size, n)) = dir_size_only(&path, walk, &chain) {
                 total = total.saturating_add(size);
+                files += n;
             }
         } else {
-            total = total.saturating_add(entry.metadata().map(|m| m.len()).unwrap_or(0));
+            match entry.metadata() {
+                Ok(m) => total = total.saturating_add(counted_len(&path, &m, links)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(_) => {}
+            }
+            files += 1;
+            direct += 1;
         }
     }
-    counter.fetch_add(1, Ordering::Relaxed);
+    counter.fetch_add(direct, Ordering::Relaxed);
+    if let Some(est) = estimate {
+        est.advance();
+    }
     if let Some(ref cb) = progress {
         cb(
             counter.load(Ordering::Relaxed),
             path.display().to_string().as_str(),
         );
     }
-    Ok(total)
+    Ok((total, files))
+}
+
+/// 当前目录及其各级父目录的真实路径。跟随符号链接或目录联接时据此发现指回祖先的循环，
+/// 否则会一直递归到 `max_depth`（shallow 目录中则无限递归）。沿各分支向下传递，
+/// 循环判断不受并行遍历顺序影响
+struct Ancestors<'a> {
+    real: PathBuf,
+    parent: Option<&'a Ancestors<'a>>,
+}
+
+impl<'a> Ancestors<'a> {
+    /// 遍历根，`real` 应已规范化
+    fn root(real: PathBuf) -> Self {
+        Ancestors { real, parent: None }
+    }
+
+    /// 真实路径为 `real` 的子项
+    fn child(&'a self, real: PathBuf) -> Ancestors<'a> {
+        Ancestors {
+            real,
+            parent: Some(self),
+        }
+    }
+
+    /// `target` 是链中某一目录本身或其祖先：跟随指向它的链接会形成循环
+    fn loops_to(&self, target: &Path) -> bool {
+        let mut chain = Some(self);
+        while let Some(a) = chain {
+            if a.real.starts_with(target) {
+                return true;
+            }
+            chain = a.parent;
+        }
+        false
+    }
 }
 
+/// 普通遍历中各层共享的状态
+#[derive(Clone, Copy)]
+struct Walk<'a> {
+    counter: &'a AtomicU64,
+    progress: Option<&'a ProgressCb>,
+    /// 第一层子目录完成时回调
+    on_subtree: Option<&'a SubtreeCb>,
+    estimate: Option<&'a WalkEstimate<'a>>,
+    /// 开启 `realistic_sizes` 时的硬链接去重状态
+    links: Option<&'a LinkDedup>,
+    /// 因权限不足未能读取的目录数
+    denied: &'a AtomicU64,
+    /// 开启 `collect_denied_paths` 时收集这些目录的路径
+    denied_paths: Option<&'a Mutex<Vec<String>>>,
+    /// 跳过的目录，见 `ScanOptions::exclude_dirs`
+    excludes: &'a [PathBuf],
+    /// 跳过的路径模式，见 `ScanOptions::exclude_patterns`
+    patterns: &'a ExcludePatterns,
+    /// 之前出错的路径记录；`retry_errored_paths` 时为只记录不跳过
+    history: Option<&'a ErrorHistory>,
+    skip_errored: bool,
+    /// 因仍在重试等待期内而跳过的路径数
+    skipped: &'a AtomicU64,
+    /// 符号链接与目录联接的处理方式，见 `ScanOptions::symlinks`
+    symlinks: SymlinkPolicy,
+    /// 已被链接展开过的目标
+    followed: &'a FollowedTargets,
+}
+
+impl<'a> Walk<'a> {
+    /// 遍历初始状态，其余字段按需设置
+    fn new(
+        counter: &'a AtomicU64,
+        denied: &'a AtomicU64,
+        skipped: &'a AtomicU64,
+        excludes: &'a [PathBuf],
+        patterns: &'a ExcludePatterns,
+        followed: &'a FollowedTargets,
+        opts: &'a ScanOptions,
+    ) -> Self {
+        Walk {
+            counter,
+            progress: None,
+            on_subtree: opts.on_subtree.as_deref(),
+            estimate: None,
+            links: None,
+            denied,
+            denied_paths: None,
+            excludes,
+            patterns,
+            history: opts.error_history.as_deref(),
+            skip_errored: !opts.retry_errored_paths,
+            skipped,
+            symlinks: opts.symlinks,
+            followed,
+        }
+    }
+
+    /// 是否展开符号链接或目录联接 `entry`，展开时返回其目标的路径链。以下情况不展开：
+    /// 策略为 `Skip`（真实大小模式同样不展开）、目标无法解析、目标是链中某一目录或其祖先（循环）、
+    /// 目标已被其他链接展开过，以及 `FollowOnce` 下目标位于扫描根内（已按真实路径统计）
+    fn follow_link<'c>(
+        &self,
+        entry: &std::fs::DirEntry,
+        ancestors: &'c Ancestors<'c>,
+    ) -> Option<Ancestors<'c>> {
+        if let Some(links) = self.links {
+            links.skip_reparse();
+            return None;
+        }
+        if self.symlinks == SymlinkPolicy::Skip {
+            return None;
+        }
+        let target = std::fs::canonicalize(entry.path()).ok()?;
+        if ancestors.loops_to(&target)
+            || (self.symlinks == SymlinkPolicy::FollowOnce && self.followed.is_inside_root(&target))
+            || !self.followed.first_visit(&target)
+        {
+            return None;
+        }
+        Some(ancestors.child(target))
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        self.excludes.iter().any(|dir| dir == path) || self.patterns.matches_path(path)
+    }
+
+    /// 之前出错且仍在重试等待期内的路径直接跳过，计入 `skipped`
+    fn skip_errored(&self, path: &Path) -> bool {
+        let skip = self.skip_errored && self.history.is_some_and(|h| h.is_waiting(path));
+        if skip {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        skip
+    }
+
+    /// 因权限不足未能读取
+    fn record_denied(&self, path: &Path) {
+        self.denied.fetch_add(1, Ordering::Relaxed);
+        if let Some(paths) = self.denied_paths {
+            paths
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(path.display().to_string());
+        }
+        if let Some(history) = self.history {
+            history.record(path);
+        }
+    }
+}
+
+/// 在 `ScanOptions::scan_threads` 指定大小的线程池中执行遍历；未指定时使用 rayon 全局线程池
+fn in_walk_pool<T: Send>(
+    opts: &ScanOptions,
+    walk: impl FnOnce() -> T + Send,
+) -> Result<T, DiskAnalyzerError> {
+    match opts.scan_threads {
+        Some(threads) => rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .build()
+            .map(|pool| pool.install(walk))
+            .map_err(|e| DiskAnalyzerError::Io(std::io::Error::other(e))),
+        None => Ok(walk()),
+    }
+}
+
+/// 未展开的符号链接或目录联接：保留节点，大小为 0
+fn link_node(path: &Path, name: &str) -> FileNode {
+    FileNode {
+        path: path.display().to_string(),
+        name: name.to_string(),
+        is_link: true,
+        ..Default::default()
+    }
+}
+
+/// `CountAsZero` 展开的链接内容只供浏览：各层大小与文件数清零，不计入总量
+fn zero_sizes(node: &mut FileNode) {
+    node.size = 0;
+    node.file_count = node.file_count.map(|_| 0);
+    node.children.iter_mut().for_each(zero_sizes);
+}
+
+/// 文件计入的大小：真实大小模式下重复的硬链接计 0
+fn counted_len(path: &Path, metadata: &std::fs::Metadata, links: Option<&LinkDedup>) -> u64 {
+    match links {
+        Some(links) => links.count(file_id(path, metadata), metadata.len()),
+        None => metadata.len(),
+    }
+}
+
+/// 读取路径元数据；测试中可模拟「枚举后、读取元数据前文件被删除」
+fn stat_path(path: &Path) -> std::io::Result<std::fs::Metadata> {
+    #[cfg(test)]
+    if tests::VANISHED.lock().unwrap().iter().any(|p| p == path) {
+        return Err(std::io::ErrorKind::NotFound.into());
+    }
+    #[cfg(test)]
+    if tests::DENIED.lock().unwrap().iter().any(|p| p == path) {
+        return Err(std::io::ErrorKind::PermissionDenied.into());
+    }
+    std::fs::metadata(path)
+}
+
+/// 路径在枚举后消失时返回 `InvalidPath`，父目录据此跳过该子项。`ancestors` 为 `path` 的真实路径链
 fn build_tree(
     path: &Path,
     name: &str,
     depth: usize,
-    counter: &AtomicU64,
-    progress: Option<&ProgressCb>,
-    shallow_dirs: bool,
+    walk: &Walk,
+    ancestors: &Ancestors,
+    opts: &ScanOptions,
 ) -> Result<(FileNode, u64), DiskAnalyzerError> {
-    let metadata = match std::fs::metadata(path) {
+    if opts.is_cancelled() {
+        return Err(DiskAnalyzerError::Cancelled);
+    }
+    let Walk {
+        counter,
+        progress,
+        estimate,
+        links,
+        ..
+    } = *walk;
+    let metadata = match stat_path(path) {
         Ok(m) => m,
         Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
             return Err(DiskAnalyzerError::PermissionDenied(
@@ -103,7 +442,7 @@ fn build_tree(
             ));
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            return Err(DiskAnalyzerError::PermissionDenied(format!(
+            return Err(DiskAnalyzerError::InvalidPath(format!(
                 "{} [路径不存在]",
                 path.display()
             )));
@@ -117,19 +456,29 @@ fn build_tree(
                     is_dir: false,
                     modified: None,
                     children: vec![],
+                    ..Default::default()
                 },
                 0u64,
             ));
         }
-        Err(e) => return Err(DiskAnalyzerError::Io(e)),
+        Err(e) => return Err(walk_error(e, "tree build", path)),
     };
 
     let is_dir = metadata.is_dir();
-    let mut size = if is_dir { 0u64 } else { metadata.len() };
+    let mut size = if is_dir {
+        0u64
+    } else {
+        counted_len(path, &metadata, links)
+    };
     let mut file_count = if is_dir { 0u64 } else { 1u64 };
     let mut children = Vec::new();
+    // 已展开子项中最新的修改时间（含只扫描目录模式下不保留节点的文件）
+    let mut newest_child: Option<u64> = None;
+    // 子项数超出 max_children_per_dir 时截断，记录实际子项数
+    let mut truncated = false;
+    let mut entry_count = 0;
 
-    if is_dir && depth < MAX_DEPTH {
+    if is_dir && depth < opts.max_depth {
         let entries = match std::fs::read_dir(path) {
             Ok(iter) => iter,
             Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
@@ -138,7 +487,7 @@ fn build_tree(
                 ));
             }
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Err(DiskAnalyzerError::PermissionDenied(format!(
+                return Err(DiskAnalyzerError::InvalidPath(format!(
                     "{} [路径不存在]",
                     path.display()
                 )));
@@ -156,13 +505,17 @@ fn build_tree(
                             .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
                             .map(|d| d.as_secs()),
                         children: vec![],
+                        ..Default::default()
                     },
                     0u64,
                 ));
             }
-            Err(e) => return Err(DiskAnalyzerError::Io(e)),
+            Err(e) => return Err(walk_error(e, "tree build", path)),
         };
-        let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+        let mut entries: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| !walk.is_excluded(&e.path()))
+            .collect();
 
         entries.sort_by(|a, b| {
             let a_is_dir = a.path().is_dir();
@@ -177,108 +530,84 @@ fn build_tree(
             }
         });
 
-        let entries: Vec<_> = entries.into_iter().take(MAX_CHILDREN_PER_DIR).collect();
+        // 超出 max_children_per_dir 的子项不计入，目录标记 has_more
+        entry_count = entries.len();
+        truncated = entry_count > opts.max_children_per_dir;
+        let entries: Vec<_> = entries
+            .into_iter()
+            .take(opts.max_children_per_dir)
+            .collect();
 
-        // 并行处理子项；shallow_dirs 开启时，常见包管理器/缓存目录只计大小不递归
+        // 并行处理子项；shallow_dirs 开启时常见包管理器/缓存目录、以及达到最大深度的目录只计大小不递归
         let results: Vec<_> = entries
             .par_iter()
             .map(|entry| {
                 let child_path = entry.path();
                 let child_name = entry.file_name().to_string_lossy().to_string();
-                let is_shallow_dir = child_path.is_dir()
-                    && shallow_dirs
-                    && SHALLOW_DIR_NAMES
-                        .iter()
-                        .any(|&s| s.eq_ignore_ascii_case(&child_name));
-                let entry_modified = entry
-                    .metadata()
-                    .ok()
-                    .and_then(|m| m.modified().ok())
-                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs());
-                if is_shallow_dir {
-                    match dir_size_only(&child_path, counter, progress) {
-                        Ok(size) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: child_name.clone(),
-                                size,
-                                is_dir: true,
-                                modified: entry_modified,
-                                children: vec![],
-                            },
-                            1u64,
-                        )),
-                        Err(DiskAnalyzerError::PermissionDenied(_)) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [无权限]", child_name),
-                                size: 0,
-                                is_dir: true,
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [损坏]", child_name),
-                                size: 0,
-                                is_dir: true,
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(e) => Err(e),
-                    }
-                } else {
-                    match build_tree(
-                        &child_path,
-                        &child_name,
-                        depth + 1,
-                        counter,
-                        progress,
-                        shallow_dirs,
-                    ) {
-                        Ok((node, cnt)) => Ok((node, cnt)),
-                        Err(DiskAnalyzerError::PermissionDenied(_)) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [无权限]", child_name),
-                                size: 0,
-                                is_dir: child_path.is_dir(),
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [损坏]", child_name),
-                                size: 0,
-                                is_dir: child_path.is_dir(),
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(e) => Err(e),
+                if walk.skip_errored(&child_path) {
+                    return Ok((
+                        FileNode {
+                            path: child_path.display().to_string(),
+                            name: format!("{} [已跳过]", child_name),
+                            is_dir: entry.file_type().is_ok_and(|t| t.is_dir()),
+                            ..Default::default()
+                        },
+                        0u64,
+                    ));
+                }
+                if !entry.file_type().is_ok_and(|t| t.is_symlink()) {
+                    let chain = ancestors.child(ancestors.real.join(entry.file_name()));
+                    return build_child(entry, depth, walk, &chain, opts);
+                }
+                let Some(chain) = walk.follow_link(entry, ancestors) else {
+                    return Ok((link_node(&child_path, &child_name), 0u64));
+                };
+                let (mut node, cnt) = build_child(entry, depth, walk, &chain, opts)?;
+                node.is_link = true;
+                if walk.symlinks == SymlinkPolicy::CountAsZero {
+                    zero_sizes(&mut node);
+                    return Ok((node, 0u64));
+                }
+                Ok((node, cnt))
+            })
+            .inspect(|r| {
+                if let (0, Some(cb), Ok((node, _))) = (depth, walk.on_subtree, r) {
+                    if node.is_dir {
+                        cb(node);
                     }
                 }
             })
             .collect();
 
+        // 本层的文件数：子目录（含 shallow 目录）的文件已在遍历它们时计入进度，不重复累加
+        let mut direct = 0u64;
         for r in results {
-            let (node, cnt) = r?;
+            let (node, cnt) = match r {
+                Ok(v) => v,
+                // 枚举后被删除（如临时文件）：已不占空间，直接略过而不记为错误
+                Err(DiskAnalyzerError::InvalidPath(_)) => continue,
+                Err(e) => return Err(e),
+            };
             size += node.size;
             file_count += cnt;
-            children.push(node);
+            if !node.is_dir {
+                direct += cnt;
+            }
+            newest_child = newest_child.max(node.modified);
+            // 只扫描目录模式：文件只计入大小与数量，不保留节点
+            if node.is_dir || !opts.dirs_only {
+                children.push(node);
+            }
+        }
+        // 子目录在各自的 build_tree 中已排好，这里只排本层
+        if opts.sort_children_by_size {
+            children.sort_by(FileNode::cmp_size_desc);
         }
 
-        counter.fetch_add(file_count, Ordering::Relaxed);
+        counter.fetch_add(direct, Ordering::Relaxed);
+        if let Some(est) = estimate {
+            est.advance();
+        }
         if let Some(ref cb) = progress {
             let total_so_far = counter.load(Ordering::Relaxed);
             cb(total_so_far, path.display().to_string().as_str());
@@ -290,6 +619,11 @@ fn build_tree(
         .ok()
         .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
         .map(|d| d.as_secs());
+    let modified = if is_dir && opts.dir_modified_from_descendants {
+        modified.max(newest_child)
+    } else {
+        modified
+    };
     Ok((
         FileNode {
             path: path.display().to_string(),
@@ -298,11 +632,176 @@ fn build_tree(
             is_dir,
             modified,
             children,
+            file_count: (opts.dirs_only && is_dir).then_some(file_count),
+            archive: (opts.peek_archives && !is_dir)
+                .then(|| peek_archive(path))
+                .flatten(),
+            system_managed: !is_dir && is_system_managed_file(&path.to_string_lossy()),
+            children_count: truncated.then_some(entry_count as u64),
+            has_more: truncated,
+            raw_path: None,
+            collapsed_children: vec![],
+            is_link: false,
         },
         file_count,
     ))
 }
 
+/// 处理目录项 `entry`（位于 `depth + 1` 层）：shallow 目录与达到最大深度的目录只计大小，
+/// 其余递归建树。`chain` 为 `entry` 自身的真实路径链
+fn build_child(
+    entry: &std::fs::DirEntry,
+    depth: usize,
+    walk: &Walk,
+    chain: &Ancestors,
+    opts: &ScanOptions,
+) -> Result<(FileNode, u64), DiskAnalyzerError> {
+    let child_path = entry.path();
+    let child_name = entry.file_name().to_string_lossy().to_string();
+    let is_shallow_dir =
+        child_path.is_dir() && is_collapsed_dir(&entry.file_name(), depth + 1, opts);
+    let entry_modified = entry
+        .metadata()
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    if is_shallow_dir {
+        match dir_size_only(&child_path, walk, chain) {
+            // 只扫描目录模式下按实际文件数计入，否则 shallow 目录计为 1
+            Ok((size, files)) => Ok((
+                FileNode {
+                    path: child_path.display().to_string(),
+                    name: child_name.clone(),
+                    size,
+                    is_dir: true,
+                    modified: entry_modified,
+                    children: vec![],
+                    file_count: opts.dirs_only.then_some(files),
+                    archive: None,
+                    system_managed: false,
+                    children_count: None,
+                    has_more: false,
+                    raw_path: None,
+                    collapsed_children: vec![],
+                    is_link: false,
+                },
+                if opts.dirs_only { files } else { 1u64 },
+            )),
+            Err(DiskAnalyzerError::PermissionDenied(_)) => Ok((
+                FileNode {
+                    path: child_path.display().to_string(),
+                    name: format!("{} [无权限]", child_name),
+                    size: 0,
+                    is_dir: true,
+                    modified: None,
+                    children: vec![],
+                    ..Default::default()
+                },
+                0u64,
+            )),
+            Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok((
+                FileNode {
+                    path: child_path.display().to_string(),
+                    name: format!("{} [损坏]", child_name),
+                    size: 0,
+                    is_dir: true,
+                    modified: None,
+                    children: vec![],
+                    ..Default::default()
+                },
+                0u64,
+            )),
+            Err(e) => Err(e),
+        }
+    } else {
+        match build_tree(&child_path, &child_name, depth + 1, walk, chain, opts) {
+            Ok((node, cnt)) => {
+                if let Some(history) = walk.history {
+                    history.forget(&child_path);
+                }
+                Ok((node, cnt))
+            }
+            Err(DiskAnalyzerError::PermissionDenied(_)) => {
+                walk.record_denied(&child_path);
+                Ok((
+                    FileNode {
+                        path: child_path.display().to_string(),
+                        name: format!("{} [无权限]", child_name),
+                        size: 0,
+                        is_dir: child_path.is_dir(),
+                        modified: None,
+                        children: vec![],
+                        ..Default::default()
+                    },
+                    0u64,
+                ))
+            }
+            Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok((
+                FileNode {
+                    path: child_path.display().to_string(),
+                    name: format!("{} [损坏]", child_name),
+                    size: 0,
+                    is_dir: child_path.is_dir(),
+                    modified: None,
+                    children: vec![],
+                    ..Default::default()
+                },
+                0u64,
+            )),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// 按阶段计时：每段耗时由累计毫秒数相减得出，各段之和恰好等于总耗时
+pub(crate) struct PhaseClock {
+    start: Instant,
+    elapsed_ms: u64,
+    phases: Vec<PhaseTiming>,
+}
+
+impl PhaseClock {
+    pub fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed_ms: 0,
+            phases: Vec::new(),
+        }
+    }
+
+    /// 结束一个阶段：上一阶段结束至今的时间记入 `phase`
+    pub fn lap(&mut self, phase: TimingPhase) {
+        let now = self.start.elapsed().as_millis() as u64;
+        self.phases.push(PhaseTiming {
+            phase,
+            ms: now - self.elapsed_ms,
+        });
+        self.elapsed_ms = now;
+    }
+
+    /// 截至最近一个阶段结束的总耗时
+    pub fn elapsed_ms(&self) -> u64 {
+        self.elapsed_ms
+    }
+
+    pub fn into_timing(self, records: u64) -> ScanTiming {
+        ScanTiming {
+            phases: self.phases,
+            records,
+            ..ScanTiming::default()
+        }
+    }
+}
+
+/// 当前 Unix 时间（秒）
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 /// 规范化路径（支持正斜杠、去除首尾空白）
 pub(crate) fn normalize_path(path: &str) -> std::path::PathBuf {
     let s = path.trim();
@@ -343,7 +842,7 @@ pub fn scan_will_use_mft(path: &str, use_mft: bool) -> bool {
         Err(_) => return false,
     };
     #[cfg(windows)]
-    return crate::mft_scan::is_windows_volume_root(&canonical);
+    return crate::volume::is_windows_volume_root(&canonical);
     #[cfg(not(windows))]
     {
         let _ = canonical;
@@ -354,13 +853,402 @@ pub fn scan_will_use_mft(path: &str, use_mft: bool) -> bool {
 /// 执行磁盘扫描（支持进度回调；shallow_dirs 为 true 时对 node_modules/.git 等只计大小不递归）。
 /// 当 use_mft 为 true 且路径为 Windows 磁盘卷根（如 C:\）时，优先使用 MFT 加速扫描。
 /// 返回 `(ScanResult, used_mft)`，其中 `used_mft` 表示本次是否成功使用了 MFT。
+#[deprecated(
+    note = "使用 `scan(path, &ScanOptions::builder().shallow_dirs(..).use_mft(..).build())`"
+)]
 pub fn scan_path_with_progress(
     path: &str,
     progress: Option<&ProgressCbArc>,
     shallow_dirs: bool,
     use_mft: bool,
 ) -> Result<(ScanResult, bool), DiskAnalyzerError> {
-    let start = Instant::now();
+    let opts = ScanOptions::builder()
+        .shallow_dirs(shallow_dirs)
+        .use_mft(use_mft)
+        .build();
+    scan_with_backend(path, progress, None, &opts, mft_backend)
+}
+
+/// 按 `ScanOptions` 执行磁盘扫描，返回 `(ScanResult, used_mft)`
+#[deprecated(note = "进度回调放入 `ScanOptions::progress` 后使用 `scan`")]
+pub fn scan_path_with_options(
+    path: &str,
+    progress: Option<&ProgressCbArc>,
+    opts: &ScanOptions,
+) -> Result<(ScanResult, bool), DiskAnalyzerError> {
+    scan_with_backend(path, progress, None, opts, mft_backend)
+}
+
+/// 同 `scan_path_with_options`，`opts.estimate_progress` 开启时额外通过 `on_percent` 上报百分比
+#[deprecated(note = "回调放入 `ScanOptions::progress` / `ScanOptions::on_percent` 后使用 `scan`")]
+pub fn scan_path_with_percent(
+    path: &str,
+    progress: Option<&ProgressCbArc>,
+    on_percent: Option<&PercentCb>,
+    opts: &ScanOptions,
+) -> Result<(ScanResult, bool), DiskAnalyzerError> {
+    scan_with_backend(path, progress, on_percent, opts, mft_backend)
+}
+
+/// 统一扫描入口：按 `scan_strategy` 选择 MFT 或普通遍历，MFT 失败（未提权、非 NTFS 等）时
+/// 自动回退到普通遍历并在 `scan_warning` 中记录原因；进度、百分比与取消均取自 `options`。
+/// 实际使用的策略见结果的 `meta.strategy`
+pub fn scan(path: &str, options: &ScanOptions) -> Result<ScanResult, DiskAnalyzerError> {
+    // 保留 shallow 目录的内容：完整扫描后再折叠
+    if options.shallow_dirs && options.retain_shallow_children {
+        let full = ScanOptions {
+            shallow_dirs: false,
+            ..options.clone()
+        };
+        let mut result = scan(path, &full)?;
+        result.set_shallow(true);
+        return Ok(result);
+    }
+    scan_with_backend(
+        path,
+        options.progress.as_ref(),
+        options.on_percent.as_deref(),
+        options,
+        mft_backend,
+    )
+    .map(|(result, _)| result)
+}
+
+/// 一次扫描多个根路径（如下载、桌面与另一块盘）并合并为一个结果：根节点是路径为空的合成目录，
+/// 子节点为各路径的树，`total_size` 与 `file_count` 为各路径之和。与其他路径相同或位于其下的路径
+/// 不再单独扫描，避免重复计数；某个路径扫描失败不影响其余路径，失败原因与跳过的重叠路径记在
+/// `scan_warning` 中，全部失败时返回第一个错误。进度回调收到的是各路径累计的文件数
+pub fn scan_paths(paths: &[&str], options: &ScanOptions) -> Result<ScanResult, DiskAnalyzerError> {
+    let started = Instant::now();
+    let started_at = unix_now();
+    let case = CaseSensitivity::native();
+    let mut resolved: Vec<(&str, String)> = paths
+        .iter()
+        .map(|&p| {
+            let full = normalize_path(p);
+            let full = std::fs::canonicalize(&full).unwrap_or(full);
+            (p, full.display().to_string())
+        })
+        .collect();
+    // 较短的路径先保留，位于其下的路径随后被识别为重叠
+    resolved.sort_by_key(|(_, full)| components(full).len());
+    let mut warnings = Vec::new();
+    let mut roots: Vec<(&str, String)> = Vec::new();
+    for (p, full) in resolved {
+        match roots.iter().find(|(_, kept)| is_under(&full, kept, case)) {
+            Some((kept, _)) => warnings.push(format!("{} 已包含在 {} 中，未重复扫描", p, kept)),
+            None => roots.push((p, full)),
+        }
+    }
+    roots.sort_by_key(|(p, _)| paths.iter().position(|q| q == p));
+
+    let mut results: Vec<ScanResult> = Vec::new();
+    let mut first_error = None;
+    let mut files_done = 0u64;
+    for (i, (p, _)) in roots.iter().enumerate() {
+        let (base, count) = (files_done, roots.len());
+        let opts = ScanOptions {
+            progress: options
+                .progress
+                .clone()
+                .map(|cb| -> ProgressCbArc { Arc::new(Box::new(move |n, at| cb(base + n, at))) }),
+            on_percent: options.on_percent.clone().map(|cb| -> Arc<PercentCb> {
+                Arc::new(Box::new(move |pct| {
+                    cb(((i * 100 + usize::from(pct)) / count) as u8);
+                }))
+            }),
+            ..options.clone()
+        };
+        match scan(p, &opts) {
+            Ok(result) => {
+                files_done += result.file_count;
+                results.push(result);
+            }
+            Err(_) if options.is_cancelled() => return Err(DiskAnalyzerError::Cancelled),
+            Err(e) => {
+                warnings.push(format!("{}: {}", p, e));
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if results.is_empty() {
+        return Err(first_error
+            .unwrap_or_else(|| DiskAnalyzerError::InvalidPath("没有要扫描的路径".to_string())));
+    }
+
+    for result in &results {
+        if let Some(warning) = &result.scan_warning {
+            warnings.push(format!("{}: {}", result.root.path, warning));
+        }
+    }
+    let sum = |field: fn(&ScanResult) -> Option<u64>| -> Option<u64> {
+        results.iter().filter_map(field).reduce(|a, b| a + b)
+    };
+    let file_count = results.iter().map(|r| r.file_count).sum();
+    let total_size = results.iter().map(|r| r.total_size).sum();
+    let naive_total_size = results
+        .iter()
+        .any(|r| r.naive_total_size.is_some())
+        .then(|| {
+            results
+                .iter()
+                .map(|r| r.naive_total_size.unwrap_or(r.total_size))
+                .sum()
+        });
+    let (system_reserved_bytes, denied_dirs, skipped_paths) = (
+        sum(|r| r.system_reserved_bytes),
+        sum(|r| r.denied_dirs),
+        sum(|r| r.skipped_paths),
+    );
+    let denied_paths = results.iter().any(|r| r.denied_paths.is_some()).then(|| {
+        results
+            .iter_mut()
+            .flat_map(|r| r.denied_paths.take().unwrap_or_default())
+            .collect()
+    });
+    let all_mft = results.iter().all(|r| {
+        r.meta
+            .as_ref()
+            .is_some_and(|m| m.strategy == ScanStrategy::Mft)
+    });
+    let mut children: Vec<FileNode> = results.into_iter().map(|r| r.root).collect();
+    if options.sort_children_by_size {
+        children.sort_by(FileNode::cmp_size_desc);
+    }
+    let root = FileNode {
+        path: String::new(),
+        name: format!("{} 个位置", children.len()),
+        size: total_size,
+        is_dir: true,
+        modified: children.iter().filter_map(|c| c.modified).max(),
+        file_count: options.dirs_only.then_some(file_count),
+        children,
+        ..Default::default()
+    };
+    let strategy = if all_mft {
+        ScanStrategy::Mft
+    } else {
+        ScanStrategy::Walk
+    };
+    Ok(ScanResult {
+        root,
+        scan_time_ms: started.elapsed().as_millis() as u64,
+        file_count,
+        total_size,
+        scan_warning: (!warnings.is_empty()).then(|| warnings.join("; ")),
+        volume_total_bytes: None,
+        volume_free_bytes: None,
+        top_files: None,
+        system_reserved_bytes,
+        naive_total_size,
+        denied_dirs,
+        skipped_paths,
+        denied_paths,
+        meta: Some(options.scan_meta(strategy, "", started_at)),
+        timing: None,
+    })
+}
+
+/// 该路径在给定选项下会优先采用的扫描策略
+pub fn scan_strategy(path: &str, options: &ScanOptions) -> ScanStrategy {
+    if scan_will_use_mft(path, options.use_mft) {
+        ScanStrategy::Mft
+    } else {
+        ScanStrategy::Walk
+    }
+}
+
+/// 只列出 `path` 的直接子项（按需展开树时使用）：子目录带递归大小与文件数（`file_count`）
+/// 但不含子节点；排序与完整扫描一致（目录在前、按名称，或按 `sort_children_by_size`）。
+/// `dirs_only` 时不返回文件
+pub fn list_children(
+    path: &str,
+    options: &ScanOptions,
+) -> Result<Vec<FileNode>, DiskAnalyzerError> {
+    let path_buf = normalize_path(path);
+    let excludes = options.resolved_excludes();
+    let patterns = options.compiled_exclude_patterns()?;
+    let entries = match std::fs::read_dir(&path_buf) {
+        Ok(iter) => iter,
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            return Err(DiskAnalyzerError::PermissionDenied(path.to_string()));
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(DiskAnalyzerError::InvalidPath(format!(
+                "路径不存在: {}",
+                path
+            )));
+        }
+        Err(e) => return Err(walk_error(e, "list children", &path_buf)),
+    };
+    let mut entries: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter(|entry| !excludes.contains(&entry.path()) && !patterns.matches_path(&entry.path()))
+        .map(|entry| (entry.path().is_dir(), entry))
+        .filter(|(is_dir, _)| *is_dir || !options.dirs_only)
+        .collect();
+    entries.sort_by(|(a_dir, a), (b_dir, b)| {
+        b_dir
+            .cmp(a_dir)
+            .then_with(|| a.file_name().cmp(&b.file_name()))
+    });
+
+    let (counter, denied, skipped) = (AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0));
+    let real = std::fs::canonicalize(&path_buf).unwrap_or(path_buf.clone());
+    let followed = FollowedTargets::new(real.clone());
+    let walk = Walk::new(
+        &counter, &denied, &skipped, &excludes, &patterns, &followed, options,
+    );
+    let ancestors = Ancestors::root(real);
+    entries
+        .par_iter()
+        .map(|(is_dir, entry)| {
+            if options.is_cancelled() {
+                return Err(DiskAnalyzerError::Cancelled);
+            }
+            let child_path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let metadata = match stat_path(&child_path) {
+                Ok(m) => m,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(_) => {
+                    return Ok(Some(FileNode {
+                        path: child_path.display().to_string(),
+                        name: format!("{} [无权限]", name),
+                        is_dir: *is_dir,
+                        ..Default::default()
+                    }))
+                }
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+            let is_link = entry.file_type().is_ok_and(|t| t.is_symlink());
+            let chain = if is_link {
+                match walk.follow_link(entry, &ancestors) {
+                    Some(chain) => chain,
+                    None => return Ok(Some(link_node(&child_path, &name))),
+                }
+            } else {
+                ancestors.child(ancestors.real.join(entry.file_name()))
+            };
+            let zero = is_link && walk.symlinks == SymlinkPolicy::CountAsZero;
+            let (size, file_count) = if zero {
+                (0, is_dir.then_some(0))
+            } else if *is_dir {
+                let (size, files) = dir_size_only(&child_path, &walk, &chain)?;
+                (size, Some(files))
+            } else {
+                (metadata.len(), None)
+            };
+            Ok(Some(FileNode {
+                path: child_path.display().to_string(),
+                name,
+                size,
+                is_dir: *is_dir,
+                modified,
+                children: vec![],
+                file_count,
+                archive: (options.peek_archives && !*is_dir)
+                    .then(|| peek_archive(&child_path))
+                    .flatten(),
+                system_managed: !*is_dir && is_system_managed_file(&child_path.to_string_lossy()),
+                children_count: None,
+                has_more: false,
+                raw_path: None,
+                collapsed_children: vec![],
+                is_link,
+            }))
+        })
+        .filter_map(Result::transpose)
+        .collect::<Result<Vec<_>, _>>()
+        .map(|mut children| {
+            if options.sort_children_by_size {
+                children.sort_by(FileNode::cmp_size_desc);
+            }
+            children
+        })
+}
+
+/// MFT 扫描实现：路径不适用 MFT 时返回 None
+type MftBackend = fn(
+    &Path,
+    &str,
+    Option<&ProgressCbArc>,
+    &ScanOptions,
+) -> Option<Result<ScanResult, DiskAnalyzerError>>;
+
+#[cfg(windows)]
+fn mft_backend(
+    canonical: &Path,
+    path: &str,
+    progress: Option<&ProgressCbArc>,
+    opts: &ScanOptions,
+) -> Option<Result<ScanResult, DiskAnalyzerError>> {
+    if !(opts.use_mft && crate::volume::is_windows_volume_root(canonical)) {
+        return None;
+    }
+    eprintln!(
+        "[scan] path is volume root, attempting MFT full scan: {}",
+        canonical.display()
+    );
+    Some(crate::mft_scan::scan_volume_mft(
+        path,
+        progress.cloned(),
+        opts,
+    ))
+}
+
+#[cfg(not(windows))]
+fn mft_backend(
+    _canonical: &Path,
+    _path: &str,
+    _progress: Option<&ProgressCbArc>,
+    _opts: &ScanOptions,
+) -> Option<Result<ScanResult, DiskAnalyzerError>> {
+    None
+}
+
+/// 执行扫描并上报遥测事件（耗时、文件数与错误数）
+fn scan_with_backend(
+    path: &str,
+    progress: Option<&ProgressCbArc>,
+    on_percent: Option<&PercentCb>,
+    opts: &ScanOptions,
+    mft: MftBackend,
+) -> Result<(ScanResult, bool), DiskAnalyzerError> {
+    let started = std::time::Instant::now();
+    let outcome = run_scan(path, progress, on_percent, opts, mft);
+    match &outcome {
+        Ok((result, used_mft)) => telemetry::record_event(TelemetryEvent::ScanCompleted {
+            strategy: if *used_mft { "mft" } else { "walk" },
+            duration_ms: result.scan_time_ms,
+            file_count: result.file_count,
+            error_count: result.denied_dirs.unwrap_or(0) + result.skipped_paths.unwrap_or(0),
+        }),
+        Err(DiskAnalyzerError::Cancelled) => {}
+        Err(e) => telemetry::record_event(TelemetryEvent::ScanFailed {
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: e.kind(),
+        }),
+    }
+    outcome
+}
+
+fn run_scan(
+    path: &str,
+    progress: Option<&ProgressCbArc>,
+    on_percent: Option<&PercentCb>,
+    opts: &ScanOptions,
+    mft: MftBackend,
+) -> Result<(ScanResult, bool), DiskAnalyzerError> {
+    let on_percent = on_percent.filter(|_| opts.estimate_progress);
+    let mut clock = PhaseClock::start();
+    let started_at = unix_now();
+    let span = telemetry::scan_span(path);
+    let _enter = span.enter();
     let path_buf = normalize_path(path);
 
     if !path_buf.exists() {
@@ -373,26 +1261,40 @@ pub fn scan_path_with_progress(
     let path_buf = std::fs::canonicalize(&path_buf)
         .map_err(|e| DiskAnalyzerError::InvalidPath(format!("无法解析路径: {}", e)))?;
 
-    #[allow(unused_mut, unused_assignments)]
     let mut mft_fallback_reason: Option<String> = None;
-    let _ = use_mft; // used only on windows
-    #[cfg(windows)]
-    if use_mft && crate::mft_scan::is_windows_volume_root(&path_buf) {
-        eprintln!(
-            "[scan] path is volume root, attempting MFT full scan: {}",
-            path_buf.display()
-        );
-        match crate::mft_scan::scan_volume_mft(path, progress.cloned(), shallow_dirs) {
-            Ok(result) => return Ok((result, true)),
-            Err(e) => {
-                let msg: String = e.to_string();
-                eprintln!(
-                    "[scan] MFT scan unavailable, falling back to normal walk. reason: {} (on Windows, reading $MFT often needs admin)",
-                    msg
-                );
-                mft_fallback_reason = Some(msg);
+    match mft(&path_buf, path, progress, opts) {
+        Some(Ok(mut result)) => {
+            if opts.sort_children_by_size {
+                result.root.sort_children_by_size_desc();
+            }
+            if let Some(cb) = on_percent {
+                cb(100);
+            }
+            // MFT 扫描整棵树同时建成，第一层子目录在此一并回调
+            if let Some(cb) = &opts.on_subtree {
+                result
+                    .root
+                    .children
+                    .iter()
+                    .filter(|c| c.is_dir)
+                    .for_each(|c| cb(c));
             }
+            span.record("strategy", "mft");
+            span.record("file_count", result.file_count);
+            span.record("total_size", result.total_size);
+            return Ok((result, true));
         }
+        Some(Err(_)) if opts.is_cancelled() => return Err(DiskAnalyzerError::Cancelled),
+        Some(Err(e)) => {
+            let msg: String = e.to_string();
+            eprintln!(
+                "[scan] MFT scan unavailable, falling back to normal walk. reason: {} (on Windows, reading $MFT often needs admin)",
+                msg
+            );
+            mft_fallback_reason = Some(msg);
+            clock.lap(TimingPhase::ReadMft);
+        }
+        None => {}
     }
 
     eprintln!("[scan] using normal directory walk: {}", path_buf.display());
@@ -402,19 +1304,61 @@ pub fn scan_path_with_progress(
         .unwrap_or(path)
         .to_string();
 
-    let counter = AtomicU64::new(0);
-    let (root, file_count) = build_tree(
-        &path_buf,
-        &name,
-        0,
-        &counter,
-        progress.map(std::sync::Arc::as_ref),
-        shallow_dirs,
-    )?;
-    let scan_time_ms = start.elapsed().as_millis() as u64;
+    let estimate = on_percent.map(|cb| WalkEstimate::new(count_walk_dirs(&path_buf, 0, opts), cb));
+    if estimate.is_some() {
+        clock.lap(TimingPhase::CountDirs);
+    }
+    let (counter, denied, skipped) = (AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0));
+    let links = opts.realistic_sizes.then(LinkDedup::default);
+    let excludes = opts.resolved_excludes();
+    let patterns = opts.compiled_exclude_patterns()?;
+    let followed = FollowedTargets::new(path_buf.clone());
+    let denied_paths = opts.collect_denied_paths.then(Mutex::default);
+    let walk = Walk {
+        progress: progress.map(std::sync::Arc::as_ref),
+        estimate: estimate.as_ref(),
+        links: links.as_ref(),
+        denied_paths: denied_paths.as_ref(),
+        ..Walk::new(
+            &counter, &denied, &skipped, &excludes, &patterns, &followed, opts,
+        )
+    };
+    let ancestors = Ancestors::root(path_buf.clone());
+    let (root, file_count) = in_walk_pool(opts, || {
+        build_tree(&path_buf, &name, 0, &walk, &ancestors, opts)
+    })??;
+    if let Some(est) = &estimate {
+        est.finish();
+    }
+    clock.lap(TimingPhase::Walk);
     let total_size = root.size;
+    let naive_total_size = links.as_ref().map(|l| {
+        eprintln!(
+            "[scan] realistic sizes: {} bytes of repeated hard links, {} reparse points not followed",
+            l.shared_bytes(),
+            l.reparse_points()
+        );
+        total_size.saturating_add(l.shared_bytes())
+    });
+    span.record("strategy", "walk");
+    span.record("file_count", file_count);
+    span.record("total_size", total_size);
 
     let (volume_total_bytes, volume_free_bytes) = get_volume_space_for_result_path(&path_buf);
+    let skipped_paths = opts
+        .error_history
+        .is_some()
+        .then(|| skipped.load(Ordering::Relaxed));
+    let denied_dirs = denied.load(Ordering::Relaxed);
+    let scan_warning = [
+        mft_fallback_reason,
+        denied_warning(denied_dirs + skipped_paths.unwrap_or(0)),
+    ]
+    .into_iter()
+    .flatten()
+    .reduce(|a, b| format!("{}; {}", a, b));
+    clock.lap(TimingPhase::Finish);
+    let scan_time_ms = clock.elapsed_ms();
 
     Ok((
         ScanResult {
@@ -422,25 +1366,50 @@ pub fn scan_path_with_progress(
             scan_time_ms,
             file_count,
             total_size,
-            scan_warning: mft_fallback_reason,
+            scan_warning,
             volume_total_bytes,
             volume_free_bytes,
             top_files: None,
+            system_reserved_bytes: None,
+            naive_total_size,
+            denied_dirs: Some(denied_dirs),
+            skipped_paths,
+            denied_paths: denied_paths.map(|m| m.into_inner().unwrap_or_else(|e| e.into_inner())),
+            meta: Some(opts.scan_meta(
+                ScanStrategy::Walk,
+                &path_buf.display().to_string(),
+                started_at,
+            )),
+            timing: Some(clock.into_timing(counter.load(Ordering::Relaxed))),
         },
         false,
     ))
 }
 
+/// 因权限不足未能读取（含因之前拒绝访问而跳过）的目录数不为 0 时的提示
+fn denied_warning(count: u64) -> Option<String> {
+    (count > 0).then(|| format!("因权限不足跳过了 {} 个目录，结果不完整", count))
+}
+
 /// 执行磁盘扫描（无进度；默认开启 shallow_dirs；默认开启 MFT 加速卷根）
 pub fn scan_path(path: &str) -> Result<ScanResult, DiskAnalyzerError> {
-    scan_path_with_progress(path, None::<&ProgressCbArc>, true, true).map(|(r, _)| r)
+    scan(path, &ScanOptions::default())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error_history::DEFAULT_RETRY_AFTER_SECS;
+    use crate::filters::ScanFilters;
     use std::fs::{self, File};
     use std::io::Write;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    /// `stat_path` 对这些路径返回 NotFound
+    pub(super) static VANISHED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    /// `stat_path` 对这些路径返回 PermissionDenied
+    pub(super) static DENIED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
 
     fn create_test_dir() -> (tempfile::TempDir, String) {
         let dir = tempfile::tempdir().expect("create temp dir");
@@ -493,6 +1462,612 @@ mod tests {
         assert!(!result.root.children.is_empty());
     }
 
+    #[test]
+    fn test_scan_options_builder() {
+        let (_guard, path) = create_test_dir();
+        let deep = std::path::Path::new(&path).join("l1").join("l2").join("l3");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(deep.join("deep.bin"), [0u8; 64]).unwrap();
+        fs::create_dir_all(std::path::Path::new(&path).join("node_modules").join("pkg")).unwrap();
+
+        // 默认构建结果与 `ScanOptions::default()` 一致
+        let built = ScanOptions::builder().build();
+        assert_eq!(
+            format!("{:?}", built),
+            format!("{:?}", ScanOptions::default())
+        );
+        let legacy = scan_path(&path).unwrap();
+        let via_builder = scan(&path, &built).unwrap();
+        assert_eq!(legacy.total_size, via_builder.total_size);
+        assert_eq!(legacy.file_count, via_builder.file_count);
+        assert_eq!(
+            format!("{:?}", legacy.root.children),
+            format!("{:?}", via_builder.root.children)
+        );
+
+        // max_depth：l2 只计大小、不展开
+        let opts = ScanOptions::builder()
+            .shallow_dirs(false)
+            .use_mft(false)
+            .max_depth(2)
+            .build();
+        let limited = scan(&path, &opts).unwrap();
+        let l1 = limited
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "l1")
+            .unwrap();
+        let l2 = &l1.children[0];
+        assert!(l2.children.is_empty());
+        assert_eq!(l2.size, 64);
+        assert_eq!(limited.total_size, legacy.total_size);
+        let meta = limited.meta.unwrap();
+        assert!(!meta.shallow_dirs);
+        assert_eq!(meta.filters_summary, "max_depth=2");
+        let node_modules = limited
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "node_modules")
+            .unwrap();
+        assert_eq!(node_modules.children.len(), 1);
+
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let cancelled = scan(&path, &ScanOptions::builder().cancel(cancel).build());
+        assert!(matches!(cancelled, Err(DiskAnalyzerError::Cancelled)));
+    }
+
+    #[test]
+    fn test_file_vanishing_before_stat_is_skipped() {
+        let (dir, path) = create_test_dir();
+        let gone = dir.path().join("subdir").join("gone.tmp");
+        File::create(&gone).unwrap().write_all(b"temp").unwrap();
+        VANISHED.lock().unwrap().push(gone.clone());
+        let result = scan(&path, &ScanOptions::default());
+        VANISHED.lock().unwrap().retain(|p| p != &gone);
+
+        let result = result.unwrap();
+        let sub = result
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "subdir")
+            .unwrap();
+        assert_eq!(sub.children.len(), 1);
+        assert_eq!(sub.children[0].name, "a.txt");
+        assert_eq!((result.file_count, result.total_size), (2, 10));
+    }
+
+    #[test]
+    fn test_scan_uses_walk_for_non_volume_root() {
+        let (_guard, path) = create_test_dir();
+        let opts = ScanOptions::default();
+        assert_eq!(scan_strategy(&path, &opts), ScanStrategy::Walk);
+        let result = scan(&path, &opts).unwrap();
+        assert_eq!(result.meta.unwrap().strategy, ScanStrategy::Walk);
+        assert_eq!((result.file_count, result.total_size), (2, 10));
+        assert!(result.scan_warning.is_none());
+    }
+
+    #[test]
+    fn test_scan_falls_back_when_mft_fails() {
+        let (_guard, path) = create_test_dir();
+        let failing_mft: MftBackend = |_, _, _, _| {
+            Some(Err(DiskAnalyzerError::VolumeNotNtfs(
+                r"E:\ (exFAT)".to_string(),
+            )))
+        };
+        let (result, used_mft) =
+            scan_with_backend(&path, None, None, &ScanOptions::default(), failing_mft).unwrap();
+        assert!(!used_mft);
+        assert_eq!(result.meta.unwrap().strategy, ScanStrategy::Walk);
+        assert_eq!(result.file_count, 2);
+        assert!(result.scan_warning.unwrap().contains("Volume is not NTFS"));
+
+        let opts = ScanOptions {
+            cancel: Some(std::sync::Arc::new(std::sync::atomic::AtomicBool::new(
+                true,
+            ))),
+            ..ScanOptions::default()
+        };
+        assert!(matches!(
+            scan_with_backend(&path, None, None, &opts, failing_mft),
+            Err(DiskAnalyzerError::Cancelled)
+        ));
+        assert!(matches!(
+            scan(&path, &opts),
+            Err(DiskAnalyzerError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_scan_dirs_only_matches_full_scan() {
+        let (_guard, path) = create_test_dir();
+        let nested = std::path::Path::new(&path).join("subdir").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("c.bin"), [0u8; 100]).unwrap();
+        let modules = std::path::Path::new(&path).join("node_modules");
+        fs::create_dir(&modules).unwrap();
+        for name in ["x.js", "y.js", "z.js"] {
+            fs::write(modules.join(name), [0u8; 10]).unwrap();
+        }
+
+        let full = scan(&path, &ScanOptions::default()).unwrap();
+        let opts = ScanOptions {
+            dirs_only: true,
+            ..ScanOptions::default()
+        };
+        let dirs = scan(&path, &opts).unwrap();
+
+        fn assert_dirs_only(node: &FileNode, full: &FileNode) {
+            assert!(node.is_dir, "unexpected file node {}", node.path);
+            assert_eq!(node.size, full.size, "{}", node.path);
+            for child in &node.children {
+                let full_child = full.children.iter().find(|c| c.path == child.path).unwrap();
+                assert_dirs_only(child, full_child);
+            }
+        }
+        assert_dirs_only(&dirs.root, &full.root);
+        assert_eq!(dirs.total_size, full.total_size);
+        // 完整扫描时 shallow 目录计 1；只扫描目录时 file_count 为实际文件数，与根节点一致
+        assert_eq!(full.file_count, 4);
+        assert_eq!(dirs.file_count, 6);
+        assert_eq!(dirs.root.file_count, Some(dirs.file_count));
+        let modules = dirs.root.children.iter().find(|c| c.name == "node_modules");
+        assert_eq!(modules.unwrap().file_count, Some(3));
+        let sub = dirs
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "subdir")
+            .unwrap();
+        assert_eq!(sub.file_count, Some(2));
+        assert_eq!(sub.children.len(), 1);
+
+        let meta = dirs.meta.unwrap();
+        assert_eq!(meta.strategy, ai_disk_domain::ScanStrategy::Walk);
+        assert_eq!(meta.filters_summary, "dirs_only");
+        assert!(!meta.is_comparable(full.meta.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn test_list_children_matches_full_scan_level() {
+        let (_guard, path) = create_test_dir();
+        let nested = std::path::Path::new(&path).join("subdir").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("c.bin"), [0u8; 100]).unwrap();
+
+        let full = scan(&path, &ScanOptions::default()).unwrap();
+        for dir in [&full.root, &full.root.children[0]] {
+            let children = list_children(&dir.path, &ScanOptions::default()).unwrap();
+            let listed: Vec<_> = children
+                .iter()
+                .map(|c| (c.path.as_str(), c.name.as_str(), c.size, c.is_dir))
+                .collect();
+            let expected: Vec<_> = dir
+                .children
+                .iter()
+                .map(|c| (c.path.as_str(), c.name.as_str(), c.size, c.is_dir))
+                .collect();
+            assert_eq!(listed, expected);
+            assert!(children.iter().all(|c| c.children.is_empty()));
+        }
+
+        let opts = ScanOptions {
+            dirs_only: true,
+            ..ScanOptions::default()
+        };
+        let dirs = list_children(&path, &opts).unwrap();
+        assert_eq!(dirs.len(), 1);
+        assert_eq!(dirs[0].file_count, Some(2));
+    }
+
+    #[test]
+    fn test_scan_meta_records_options() {
+        let (_guard, path) = create_test_dir();
+        let opts = ScanOptions {
+            shallow_dirs: false,
+            ..ScanOptions::default()
+        };
+        let before = unix_now();
+        let result = scan(&path, &opts).unwrap();
+        let meta = result.meta.unwrap();
+        assert_eq!(meta.strategy, ai_disk_domain::ScanStrategy::Walk);
+        assert!(!meta.shallow_dirs);
+        assert_eq!(meta.filters_summary, "");
+        assert_eq!(meta.root, result.root.path);
+        assert!(meta.timestamp >= before && meta.timestamp <= unix_now());
+
+        let again = scan_path(&path).unwrap().meta.unwrap();
+        assert!(again.shallow_dirs);
+        assert!(!meta.is_comparable(&again));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_realistic_sizes_dedup_hard_links_and_skip_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let sys = dir.path().join("Windows");
+        fs::create_dir_all(sys.join("WinSxS")).unwrap();
+        fs::create_dir_all(sys.join("System32")).unwrap();
+        fs::write(sys.join("WinSxS").join("kernel32.dll"), [0u8; 4000]).unwrap();
+        fs::hard_link(
+            sys.join("WinSxS").join("kernel32.dll"),
+            sys.join("System32").join("kernel32.dll"),
+        )
+        .unwrap();
+        fs::write(sys.join("notepad.exe"), [0u8; 100]).unwrap();
+        std::os::unix::fs::symlink(&sys, dir.path().join("link")).unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+
+        // 默认不展开符号链接，但硬链接各计一次
+        let naive = scan(&path, &ScanOptions::default()).unwrap();
+        assert_eq!(naive.total_size, 4000 * 2 + 100);
+        assert_eq!(naive.naive_total_size, None);
+
+        let opts = ScanOptions::builder().realistic_sizes(true).build();
+        let realistic = scan(&path, &opts).unwrap();
+        assert_eq!(realistic.total_size, 4000 + 100);
+        assert_eq!(realistic.naive_total_size, Some(4000 * 2 + 100));
+        let link = realistic
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "link")
+            .unwrap();
+        assert!(!link.is_dir && link.children.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policies_break_cycles_and_count_targets_once() {
+        use std::os::unix::fs::symlink;
+        let dir = tempfile::tempdir().unwrap();
+        let (root, outside) = (dir.path().join("root"), dir.path().join("outside"));
+        let data = root.join("data");
+        fs::create_dir_all(&data).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(data.join("a.bin"), [0u8; 1000]).unwrap();
+        fs::write(outside.join("e.bin"), [0u8; 500]).unwrap();
+        // 指向自身与指回祖先的链接，以及两个指向扫描根之外同一目录的链接
+        symlink(&data, data.join("self")).unwrap();
+        symlink(&root, data.join("up")).unwrap();
+        symlink(&outside, root.join("ext1")).unwrap();
+        symlink(&outside, root.join("ext2")).unwrap();
+        // shallow 目录中的循环
+        fs::create_dir(root.join("node_modules")).unwrap();
+        symlink(&root, root.join("node_modules").join("up")).unwrap();
+        let path = root.to_string_lossy().into_owned();
+        let scan_with = |policy| {
+            let opts = ScanOptions::builder()
+                .use_mft(false)
+                .symlinks(policy)
+                .build();
+            scan(&path, &opts).unwrap()
+        };
+        let child = |node: &FileNode, name: &str| -> FileNode {
+            node.children
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .clone()
+        };
+
+        let skip = scan_with(SymlinkPolicy::Skip);
+        assert_eq!((skip.total_size, skip.file_count), (1000, 2));
+        let data_node = child(&skip.root, "data");
+        for link in [child(&data_node, "self"), child(&skip.root, "ext1")] {
+            assert!(link.is_link && !link.is_dir && link.size == 0);
+        }
+        let listed = list_children(&path, &ScanOptions::default()).unwrap();
+        assert!(listed.iter().any(|c| c.name == "ext2" && c.is_link));
+
+        // 根外目标只计一次；指向根内的链接不展开
+        let once = scan_with(SymlinkPolicy::FollowOnce);
+        assert_eq!(once.total_size, 1000 + 500);
+        let ext = [child(&once.root, "ext1"), child(&once.root, "ext2")];
+        assert!(ext.iter().all(|e| e.is_link));
+        let mut sizes: Vec<u64> = ext.iter().map(|e| e.size).collect();
+        sizes.sort();
+        assert_eq!(sizes, [0, 500]);
+        assert!(child(&child(&once.root, "data"), "up").children.is_empty());
+
+        // 展开的内容可浏览但不计大小；循环仍被截断
+        let zero = scan_with(SymlinkPolicy::CountAsZero);
+        assert_eq!(zero.total_size, 1000);
+        let expanded = child(&zero.root, "ext1");
+        let expanded = if expanded.children.is_empty() {
+            child(&zero.root, "ext2")
+        } else {
+            expanded
+        };
+        assert_eq!(expanded.children[0].name, "e.bin");
+        assert_eq!(expanded.children[0].size, 0);
+        let data_node = child(&zero.root, "data");
+        assert!(child(&data_node, "self").children.is_empty());
+        assert!(child(&data_node, "up").children.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_progress_is_monotonic_and_completes() {
+        let (_guard, path) = create_test_dir();
+        for i in 0..20 {
+            let dir = std::path::Path::new(&path)
+                .join(format!("d{}", i))
+                .join("inner");
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("f.txt"), b"x").unwrap();
+        }
+        fs::create_dir_all(std::path::Path::new(&path).join("node_modules").join("pkg")).unwrap();
+
+        let seen = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let opts = ScanOptions::builder()
+            .on_percent(Box::new(move |p| sink.lock().unwrap().push(p)))
+            .build();
+        scan(&path, &opts).unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.first(), Some(&0));
+        assert_eq!(seen.last(), Some(&100));
+        assert!(seen.windows(2).all(|w| w[0] < w[1]), "{:?}", seen);
+        // 44 个目录：中间应有足够多的百分比上报，而不是直接跳到 100
+        assert!(seen.len() > 10, "{:?}", seen);
+    }
+
+    #[test]
+    fn test_scan_skips_app_data_dir() {
+        let (guard, path) = create_test_dir();
+        let data_dir = guard.path().join("app-data");
+        let staging = data_dir.join("staging");
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("undo.bin"), vec![0u8; 4096]).unwrap();
+        let config = ai_disk_common::AppConfig {
+            data_dir: Some(data_dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let opts = ScanOptions::builder()
+            .exclude_dirs(config.scan_exclude_dirs())
+            .build();
+
+        let result = scan(&path, &opts).unwrap();
+        assert_eq!(result.file_count, 2);
+        assert_eq!(result.total_size, 10);
+        assert!(result.root.children.iter().all(|c| c.name != "app-data"));
+        let listed = list_children(&path, &opts).unwrap();
+        assert!(listed.iter().all(|c| c.name != "app-data"));
+
+        let plan = ai_disk_engine::plan_to_free(
+            &result,
+            u64::MAX,
+            ai_disk_domain::RiskLevel::High,
+            &ai_disk_engine::KeepList::default(),
+        );
+        let data_dir = data_dir.to_string_lossy();
+        assert!(!plan.actions.is_empty());
+        assert!(plan
+            .actions
+            .iter()
+            .all(|a| !a.target_path().contains(data_dir.as_ref())));
+
+        // 未排除时会被扫描到
+        let all = scan(&path, &ScanOptions::builder().exclude_dirs(vec![]).build()).unwrap();
+        assert_eq!(all.total_size, 10 + 4096);
+    }
+
+    #[test]
+    fn test_scan_filters_exclude_patterns_and_depth() {
+        let (guard, path) = create_test_dir();
+        let target = guard.path().join("crate").join("target");
+        fs::create_dir_all(target.join("debug")).unwrap();
+        fs::write(target.join("debug").join("app.bin"), vec![0u8; 4096]).unwrap();
+        fs::write(guard.path().join("crate").join("lib.rs"), [0u8; 7]).unwrap();
+        let l3 = guard.path().join("deep").join("l2").join("l3");
+        fs::create_dir_all(l3.join("l4")).unwrap();
+        fs::write(l3.join("l4").join("f.bin"), [0u8; 5]).unwrap();
+        let filters = ScanFilters {
+            exclude_patterns: vec!["*/target/*".to_string()],
+            max_depth: Some(3),
+        };
+        let opts = ScanOptions::builder()
+            .shallow_dirs(false)
+            .use_mft(false)
+            .filters(&filters)
+            .build();
+
+        let result = scan(&path, &opts).unwrap();
+        fn tree_depth(node: &FileNode) -> usize {
+            node.children
+                .iter()
+                .map(|c| tree_depth(c) + 1)
+                .max()
+                .unwrap_or(0)
+        }
+        // 第 3 层的目录只计大小、不展开
+        assert_eq!(tree_depth(&result.root), 3);
+        let l3_node = result.find(&l3.to_string_lossy()).unwrap();
+        assert!(l3_node.children.is_empty());
+        assert_eq!(l3_node.size, 5);
+        assert_eq!(result.total_size, 10 + 7 + 5);
+        assert_eq!(result.file_count, 4);
+        let target_node = result.find(&target.to_string_lossy()).unwrap();
+        assert!(target_node.children.is_empty());
+        assert_eq!(target_node.size, 0);
+        assert_eq!(opts.max_depth, 3);
+        assert_eq!(
+            result.meta.unwrap().filters_summary,
+            "max_depth=3,exclude=*/target/*"
+        );
+        let listed = list_children(&target.to_string_lossy(), &opts).unwrap();
+        assert!(listed.is_empty());
+
+        let invalid = ScanOptions::builder()
+            .exclude_patterns(vec!["[".to_string()])
+            .build();
+        assert!(matches!(
+            scan(&path, &invalid),
+            Err(DiskAnalyzerError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_cancel_midway_returns_cancelled_promptly() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = 400u64;
+        for i in 0..dirs {
+            let sub = dir.path().join(format!("d{:03}", i));
+            fs::create_dir(&sub).unwrap();
+            for j in 0..5 {
+                fs::write(sub.join(format!("f{}", j)), [0u8; 16]).unwrap();
+            }
+        }
+        let cancel = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let ticks = Arc::new(AtomicU64::new(0));
+        let (flag, seen) = (cancel.clone(), ticks.clone());
+        let progress: ProgressCbArc = Arc::new(Box::new(move |count, _: &str| {
+            seen.fetch_max(count, Ordering::Relaxed);
+            if count >= 20 {
+                flag.store(true, Ordering::Relaxed);
+            }
+        }));
+        let opts = ScanOptions::builder()
+            .use_mft(false)
+            .progress(progress)
+            .cancel(cancel)
+            .build();
+
+        let path = dir.path().to_string_lossy().into_owned();
+        let handle = std::thread::spawn(move || scan(&path, &opts));
+        let result = handle.join().unwrap();
+        assert!(matches!(result, Err(DiskAnalyzerError::Cancelled)));
+        // 取消后只有已在进行中的目录会完成
+        assert!(ticks.load(Ordering::Relaxed) < dirs / 2);
+    }
+
+    #[test]
+    fn test_scan_timing_phases_sum_to_scan_time() {
+        let (_guard, path) = create_test_dir();
+        let slow_failing_mft: MftBackend = |_, _, _, _| {
+            std::thread::sleep(std::time::Duration::from_millis(30));
+            Some(Err(DiskAnalyzerError::Io(std::io::Error::other(
+                "access denied",
+            ))))
+        };
+        let opts = ScanOptions {
+            estimate_progress: true,
+            ..ScanOptions::default()
+        };
+        let on_percent: PercentCb = Box::new(|_| {});
+        let (result, _) =
+            scan_with_backend(&path, None, Some(&on_percent), &opts, slow_failing_mft).unwrap();
+
+        let timing = result.timing.unwrap();
+        let phases: Vec<TimingPhase> = timing.phases.iter().map(|p| p.phase).collect();
+        assert_eq!(
+            phases,
+            [
+                TimingPhase::ReadMft,
+                TimingPhase::CountDirs,
+                TimingPhase::Walk,
+                TimingPhase::Finish
+            ]
+        );
+        assert_eq!(timing.total_ms(), result.scan_time_ms);
+        assert!(timing.phases[0].ms >= 30);
+        assert!(timing.records >= result.file_count);
+        assert!(!timing.degraded);
+        let slowest = timing.slowest().unwrap();
+        assert_eq!(slowest.phase, TimingPhase::ReadMft);
+        assert!(timing.explain().unwrap().starts_with("读取 MFT 耗时"));
+
+        // 不请求进度预估时只有遍历与整理两个阶段
+        let plain = scan(&path, &ScanOptions::default()).unwrap();
+        let timing = plain.timing.unwrap();
+        assert_eq!(timing.phases.len(), 2);
+        assert_eq!(timing.total_ms(), plain.scan_time_ms);
+    }
+
+    #[test]
+    fn test_errored_path_is_skipped_until_retry_window_elapses() {
+        let (dir, path) = create_test_dir();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("secret.bin"), [0u8; 100]).unwrap();
+        let file = dir.path().join("scan_errors.json");
+        let scan_with = |history: &Arc<ErrorHistory>| {
+            let opts = ScanOptions::builder()
+                .error_history(history.clone())
+                .exclude_dirs(vec![file.clone()])
+                .collect_denied_paths(true)
+                .build();
+            scan(&path, &opts).unwrap()
+        };
+        let locked_node = |result: &ScanResult| {
+            result
+                .root
+                .children
+                .iter()
+                .find(|c| c.path == locked.display().to_string())
+                .cloned()
+                .unwrap()
+        };
+
+        // 第一次：拒绝访问，记入记录
+        DENIED.lock().unwrap().push(locked.clone());
+        let history = Arc::new(ErrorHistory::load(&file));
+        let first = scan_with(&history);
+        DENIED.lock().unwrap().retain(|p| p != &locked);
+        assert_eq!((first.denied_dirs, first.skipped_paths), (Some(1), Some(0)));
+        assert_eq!(first.denied_paths, Some(vec![locked.display().to_string()]));
+        assert_eq!(
+            first.scan_warning.as_deref(),
+            Some("因权限不足跳过了 1 个目录，结果不完整")
+        );
+        assert_eq!(history.len(), 1);
+        history.save(&file).unwrap();
+
+        // 第二次：等待期内直接跳过（即使现在已能访问），只计入跳过数
+        let history = Arc::new(ErrorHistory::load(&file));
+        let second = scan_with(&history);
+        assert_eq!(
+            (second.denied_dirs, second.skipped_paths),
+            (Some(0), Some(1))
+        );
+        let skipped = locked_node(&second);
+        assert!(skipped.name.ends_with("[已跳过]"));
+        assert!(skipped.is_dir);
+        assert_eq!(second.total_size, 10);
+        assert_eq!(second.denied_paths, Some(vec![]));
+        assert!(second.scan_warning.is_some());
+        // 强制重试时不跳过
+        let forced = scan(
+            &path,
+            &ScanOptions::builder()
+                .error_history(Arc::new(ErrorHistory::load(&file)))
+                .retry_errored_paths(true)
+                .exclude_dirs(vec![file.clone()])
+                .build(),
+        )
+        .unwrap();
+        assert_eq!((forced.skipped_paths, forced.total_size), (Some(0), 110));
+        assert!(forced.scan_warning.is_none() && forced.denied_paths.is_none());
+
+        // 等待期过后重试，成功后移除记录
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+        for entry in json["entries"].as_object_mut().unwrap().values_mut() {
+            let last = entry["last_error"].as_u64().unwrap();
+            entry["last_error"] = (last - DEFAULT_RETRY_AFTER_SECS - 1).into();
+        }
+        fs::write(&file, serde_json::to_vec(&json).unwrap()).unwrap();
+        let history = Arc::new(ErrorHistory::load(&file));
+        let third = scan_with(&history);
+        assert_eq!(third.skipped_paths, Some(0));
+        assert_eq!(locked_node(&third).size, 100);
+        assert!(history.is_empty());
+    }
+
     #[test]
     #[cfg(windows)]
     fn test_scan_academic_path() {
@@ -502,4 +2077,87 @@ mod tests {
             assert!(result.root.name == "Academic" || !result.root.path.is_empty());
         }
     }
+
+    #[test]
+    fn test_scan_paths_merges_roots_and_skips_overlaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::create_dir_all(a.join("inner")).unwrap();
+        fs::create_dir(&b).unwrap();
+        fs::write(a.join("inner").join("x.bin"), [0u8; 100]).unwrap();
+        fs::write(a.join("y.bin"), [0u8; 20]).unwrap();
+        fs::write(b.join("z.bin"), [0u8; 3]).unwrap();
+        let missing = dir.path().join("missing");
+        let (a, b, inner, missing) = (
+            a.to_string_lossy().into_owned(),
+            b.to_string_lossy().into_owned(),
+            a.join("inner").to_string_lossy().into_owned(),
+            missing.to_string_lossy().into_owned(),
+        );
+        // 进度回调收到的最大计数
+        let max_progress = |paths: &[&str]| {
+            let seen = Arc::new(AtomicU64::new(0));
+            let max_seen = seen.clone();
+            let progress: ProgressCbArc = Arc::new(Box::new(move |count, _: &str| {
+                max_seen.fetch_max(count, Ordering::Relaxed);
+            }));
+            let opts = ScanOptions::builder()
+                .use_mft(false)
+                .progress(progress)
+                .build();
+            let result = scan_paths(paths, &opts);
+            (result, seen.load(Ordering::Relaxed))
+        };
+
+        let (result, combined) = max_progress(&[&inner, &b, &missing, &a]);
+        let result = result.unwrap();
+        let roots: Vec<&str> = result
+            .root
+            .children
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(roots, ["b", "a"]);
+        assert_eq!((result.total_size, result.file_count), (123, 3));
+        assert_eq!(result.root.size, 123);
+        assert_eq!(combined, max_progress(&[&a]).1 + max_progress(&[&b]).1);
+        let warning = result.scan_warning.as_deref().unwrap();
+        assert!(warning.contains(&format!("{} 已包含在 {} 中", inner, a)));
+        assert!(warning.contains(&missing));
+        assert!(result.find(&inner).is_some());
+
+        assert!(max_progress(&[&missing]).0.is_err());
+    }
+
+    #[test]
+    fn test_on_subtree_fires_once_per_top_level_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, len) in [("docs", 10), ("music", 20), ("video", 30)] {
+            let sub = dir.path().join(name).join("nested");
+            fs::create_dir_all(&sub).unwrap();
+            fs::write(sub.join("f.bin"), vec![0u8; len]).unwrap();
+        }
+        fs::write(dir.path().join("top.txt"), b"not a dir").unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let opts = ScanOptions::builder()
+            .use_mft(false)
+            .on_subtree(Box::new(move |node: &FileNode| {
+                sink.lock().unwrap().push((node.name.clone(), node.size));
+            }))
+            .build();
+
+        let result = scan(&dir.path().to_string_lossy(), &opts).unwrap();
+        let mut events = events.lock().unwrap().clone();
+        events.sort();
+        assert_eq!(
+            events,
+            [
+                ("docs".to_string(), 10),
+                ("music".to_string(), 20),
+                ("video".to_string(), 30)
+            ]
+        );
+        assert_eq!(result.root.children.len(), 4);
+    }
 }
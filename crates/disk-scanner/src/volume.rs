@@ -0,0 +1,194 @@
+//! Windows 卷根路径解析：盘符卷（`C:\`、`\\?\C:\`）与无盘符的 GUID 卷（`\\?\Volume{GUID}\`，
+//! 常见于挂载到文件夹的磁盘）。纯字符串处理，不依赖 Windows API，便于在所有平台上测试。
+
+use std::path::Path;
+
+/// 卷根标识：由盘符或卷 GUID 确定，用于打开卷设备并规范化 MFT 路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VolumeRoot {
+    /// 盘符卷，保存大写盘符（如 `"C"`）
+    Letter(String),
+    /// GUID 卷，保存 `Volume{...}` 部分（GUID 统一为小写）
+    Guid(String),
+}
+
+/// 校验并规范化 `Volume{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}`，返回小写形式
+fn parse_volume_guid(s: &str) -> Option<String> {
+    let inner = s
+        .get(..7)
+        .filter(|p| p.eq_ignore_ascii_case("Volume{"))
+        .and_then(|_| s[7..].strip_suffix('}'))?;
+    let groups: Vec<&str> = inner.split('-').collect();
+    let lens = [8usize, 4, 4, 4, 12];
+    if groups.len() != lens.len()
+        || groups
+            .iter()
+            .zip(lens)
+            .any(|(g, n)| g.len() != n || !g.bytes().all(|b| b.is_ascii_hexdigit()))
+    {
+        return None;
+    }
+    Some(format!("Volume{{{}}}", inner.to_ascii_lowercase()))
+}
+
+/// 去掉 Win32 设备前缀 `\\?\` 或 `\\.\`
+fn strip_device_prefix(s: &str) -> Option<&str> {
+    s.strip_prefix("\\\\?\\")
+        .or_else(|| s.strip_prefix("\\\\.\\"))
+}
+
+impl VolumeRoot {
+    /// 解析卷根路径（如 `C:\`、`\\?\C:\`、`\\?\Volume{GUID}\`），非卷根返回 None
+    pub fn parse(path: &Path) -> Option<Self> {
+        Self::parse_str(&path.to_string_lossy())
+    }
+
+    pub fn parse_str(s: &str) -> Option<Self> {
+        let s = s.trim_end_matches('\\');
+        let rest = strip_device_prefix(s).unwrap_or(s);
+        let b = rest.as_bytes();
+        if b.len() == 2 && b[0].is_ascii_alphabetic() && b[1] == b':' {
+            return Some(VolumeRoot::Letter(rest[..1].to_ascii_uppercase()));
+        }
+        if rest.len() != s.len() {
+            return parse_volume_guid(rest).map(VolumeRoot::Guid);
+        }
+        None
+    }
+
+    /// 打开卷时使用的设备路径：`\\.\C:` 或 `\\?\Volume{GUID}`（均不带尾部反斜杠）
+    pub fn device_path(&self) -> String {
+        match self {
+            VolumeRoot::Letter(d) => format!(r"\\.\{}:", d),
+            VolumeRoot::Guid(g) => format!(r"\\?\{}", g),
+        }
+    }
+
+    /// 扫描结果中路径的卷前缀（不带尾部反斜杠）：`C:` 或 `\\?\Volume{GUID}`
+    pub fn path_prefix(&self) -> String {
+        match self {
+            VolumeRoot::Letter(d) => format!("{}:", d),
+            VolumeRoot::Guid(g) => format!(r"\\?\{}", g),
+        }
+    }
+
+    /// 卷根路径（带尾部反斜杠）：`C:\` 或 `\\?\Volume{GUID}\`
+    pub fn root_path(&self) -> String {
+        format!("{}\\", self.path_prefix())
+    }
+
+    /// Normalize path from ntfs-reader (e.g. `\\.\F:\dir\file`、`C:\dir\file` 或
+    /// `\\?\Volume{GUID}\dir\file`) 为以 `path_prefix()` 开头的形式，保证卷前缀后必有反斜杠
+    /// 以便正确做父路径切分（如 `C:\Windows` 的 parent 为 `C:\`）。不属于本卷的路径原样返回。
+    pub fn normalize_path(&self, path_str: &str) -> String {
+        let path_str = path_str.replace('/', "\\");
+        let path_str = path_str.trim_end_matches('\\').to_string();
+        let rest = match self {
+            VolumeRoot::Letter(d) => {
+                let body = strip_device_prefix(&path_str).unwrap_or(&path_str);
+                match body.get(..2) {
+                    Some(p) if p.eq_ignore_ascii_case(&format!("{}:", d)) => &body[2..],
+                    _ => return path_str,
+                }
+            }
+            VolumeRoot::Guid(g) => match strip_device_prefix(&path_str) {
+                Some(body)
+                    if body
+                        .get(..g.len())
+                        .is_some_and(|p| p.eq_ignore_ascii_case(g)) =>
+                {
+                    &body[g.len()..]
+                }
+                _ => return path_str,
+            },
+        };
+        if !rest.is_empty() && !rest.starts_with('\\') {
+            return path_str;
+        }
+        let rest = rest.trim_start_matches('\\');
+        if rest.is_empty() {
+            self.root_path()
+        } else {
+            format!(r"{}\{}", self.path_prefix(), rest)
+        }
+    }
+}
+
+/// Whether path is a Windows volume root (e.g. `C:\`, `D:\`, `\\?\Volume{GUID}\`).
+pub fn is_windows_volume_root(path: &Path) -> bool {
+    VolumeRoot::parse(path).is_some()
+}
+
+/// 是否为系统卷的根：Windows 上为 `%SystemDrive%`（默认 `C:`）的卷根，其他平台为 `/`。
+/// 系统卷上硬链接与重解析点较多，扫描时宜开启 `ScanOptions::realistic_sizes`
+pub fn is_system_volume_root(path: &Path) -> bool {
+    if cfg!(windows) {
+        let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        VolumeRoot::parse(path).is_some_and(|root| Some(root) == VolumeRoot::parse_str(&drive))
+    } else {
+        path == Path::new("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUID: &str = "Volume{0b1c2d3e-4f50-6172-8394-a5b6c7d8e9f0}";
+
+    #[test]
+    fn test_parse_letter_roots() {
+        for s in ["C:", "c:\\", "\\\\?\\C:\\", "\\\\.\\C:"] {
+            assert_eq!(
+                VolumeRoot::parse_str(s),
+                Some(VolumeRoot::Letter("C".to_string())),
+                "{s}"
+            );
+        }
+        assert_eq!(VolumeRoot::parse_str("C:\\Users"), None);
+        assert_eq!(VolumeRoot::parse_str("1:\\"), None);
+    }
+
+    #[test]
+    fn test_parse_guid_roots() {
+        let upper = format!("\\\\?\\{}\\", GUID.to_ascii_uppercase());
+        let root = VolumeRoot::parse_str(&upper).unwrap();
+        assert_eq!(root, VolumeRoot::Guid(GUID.to_string()));
+        assert_eq!(root.device_path(), format!("\\\\?\\{}", GUID));
+        assert_eq!(root.root_path(), format!("\\\\?\\{}\\", GUID));
+        assert!(VolumeRoot::parse_str(&format!("\\\\.\\{}", GUID)).is_some());
+
+        // 无设备前缀、GUID 格式错误、GUID 下的子目录都不是卷根
+        assert_eq!(VolumeRoot::parse_str(GUID), None);
+        assert_eq!(VolumeRoot::parse_str("\\\\?\\Volume{1234}\\"), None);
+        assert_eq!(
+            VolumeRoot::parse_str(&format!("\\\\?\\{}\\Users", GUID)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_normalize_letter_paths() {
+        let root = VolumeRoot::Letter("F".to_string());
+        assert_eq!(root.normalize_path("\\\\.\\F:\\dir\\file"), "F:\\dir\\file");
+        assert_eq!(root.normalize_path("f:/dir/"), "F:\\dir");
+        assert_eq!(root.normalize_path("\\\\.\\F:"), "F:\\");
+        assert_eq!(root.normalize_path("\\\\?\\F:\\"), "F:\\");
+        assert_eq!(root.normalize_path("C:\\other"), "C:\\other");
+    }
+
+    #[test]
+    fn test_normalize_guid_paths() {
+        let root = VolumeRoot::Guid(GUID.to_string());
+        let prefix = format!("\\\\?\\{}", GUID);
+        assert_eq!(
+            root.normalize_path(&format!("\\\\.\\{}\\dir\\file", GUID)),
+            format!("{}\\dir\\file", prefix)
+        );
+        assert_eq!(
+            root.normalize_path(&format!("{}\\", prefix.to_ascii_uppercase())),
+            format!("{}\\", prefix)
+        );
+        assert_eq!(root.normalize_path("C:\\dir"), "C:\\dir");
+    }
+}
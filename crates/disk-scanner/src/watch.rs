@@ -0,0 +1,461 @@
+//! 监视已扫描目录的文件系统变化，增量更新内存中的 `FileNode` 树，无需重新扫描。
+//! 事件先按路径去抖（`DEBOUNCE` 内的连续事件合并），再按扫描时的选项重新扫描每个路径：存在则插入/更新节点，
+//! 不存在则移除，并沿途更新祖先目录大小。事件位于只计大小的目录（shallow 目录、达到 `max_depth` 的目录）
+//! 或子项被截断的目录（`has_more`）之下时，改为重新扫描该目录本身，避免重复计入大小。
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, UNIX_EPOCH};
+
+use ai_disk_common::path::{names_equal, CaseSensitivity};
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::{is_shallow_dir_name, FileNode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::options::ScanOptions;
+use crate::scanner::scan;
+
+/// 事件去抖窗口：最后一个事件之后静默这么久才应用一批变化
+const DEBOUNCE: Duration = Duration::from_millis(200);
+/// 工作线程检查停止标记的间隔
+const POLL: Duration = Duration::from_millis(50);
+
+/// 树节点的变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Removed,
+    Modified,
+}
+
+/// 一次应用到树上的变化
+#[derive(Debug, Clone)]
+pub struct TreeChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    /// 变化后该节点的大小（移除时为 0）
+    pub size: u64,
+    /// 变化后根节点的总大小
+    pub root_size: u64,
+}
+
+pub type ChangeCb = Box<dyn Fn(&[TreeChange]) + Send>;
+
+/// 监视句柄，drop 时停止监视
+pub struct WatchHandle {
+    tree: Arc<Mutex<FileNode>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl WatchHandle {
+    /// 当前树的快照
+    pub fn snapshot(&self) -> FileNode {
+        self.tree.lock().unwrap().clone()
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// 监视 `root.path`（应为以 `options` 扫描所得结果的根节点），每批去抖后的变化应用到树上并回调 `on_change`；
+/// 变化的子树按 `options` 的 shallow 目录、深度与子项数限制重新扫描
+pub fn watch_path(
+    root: FileNode,
+    options: &ScanOptions,
+    on_change: ChangeCb,
+) -> Result<WatchHandle, DiskAnalyzerError> {
+    let root_path = PathBuf::from(&root.path);
+    let opts = ScanOptions {
+        use_mft: false,
+        retain_shallow_children: false,
+        estimate_progress: false,
+        progress: None,
+        on_percent: None,
+        on_subtree: None,
+        cancel: None,
+        ..options.clone()
+    };
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            for path in event.paths {
+                let _ = tx.send(path);
+            }
+        }
+    })
+    .map_err(to_disk_analyzer_error)?;
+    watcher
+        .watch(&root_path, RecursiveMode::Recursive)
+        .map_err(to_disk_analyzer_error)?;
+
+    let tree = Arc::new(Mutex::new(root));
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let tree = tree.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            let mut pending = BTreeSet::new();
+            let mut quiet = Duration::ZERO;
+            while !stop.load(Ordering::Relaxed) {
+                match rx.recv_timeout(POLL) {
+                    Ok(path) => {
+                        pending.insert(path);
+                        quiet = Duration::ZERO;
+                    }
+                    Err(RecvTimeoutError::Timeout) => quiet += POLL,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if !pending.is_empty() && quiet >= DEBOUNCE {
+                    let mut tree = tree.lock().unwrap();
+                    let changes: Vec<TreeChange> = std::mem::take(&mut pending)
+                        .iter()
+                        .filter_map(|p| apply_path(&mut tree, &root_path, p, &opts))
+                        .collect();
+                    if !changes.is_empty() {
+                        on_change(&changes);
+                    }
+                }
+            }
+        })
+    };
+
+    Ok(WatchHandle {
+        tree,
+        stop,
+        worker: Some(worker),
+        _watcher: watcher,
+    })
+}
+
+fn to_disk_analyzer_error(e: notify::Error) -> DiskAnalyzerError {
+    match e.kind {
+        notify::ErrorKind::Io(io) => DiskAnalyzerError::Io(io),
+        notify::ErrorKind::PathNotFound => DiskAnalyzerError::InvalidPath(format!(
+            "路径不存在: {}",
+            e.paths
+                .first()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        )),
+        kind => DiskAnalyzerError::Io(std::io::Error::other(format!("{:?}", kind))),
+    }
+}
+
+/// 树中节点名与事件路径分段是否指向同一文件（按本机文件系统的大小写规则）
+fn same_name(a: &str, b: &str) -> bool {
+    names_equal(a, b, CaseSensitivity::native())
+}
+
+/// 位于 `depth` 的目录节点是否只计大小、不含子节点（与扫描时的判断一致）
+fn is_collapsed(node: &FileNode, depth: usize, opts: &ScanOptions) -> bool {
+    node.is_dir
+        && depth > 0
+        && (depth >= opts.max_depth || (opts.shallow_dirs && is_shallow_dir_name(&node.name)))
+}
+
+/// 按路径当前状态重新扫描并更新树。重新扫描的单位为事件路径在树中对应的节点，以下情况改为其所在目录：
+/// 中间目录不在树中（以第一个缺失的目录为单位）、途经只计大小的目录、所在目录的子项被截断，
+/// 以及只扫描目录模式下的文件。
+fn apply_path(
+    tree: &mut FileNode,
+    root_path: &Path,
+    path: &Path,
+    opts: &ScanOptions,
+) -> Option<TreeChange> {
+    let rel = path.strip_prefix(root_path).ok()?;
+    let mut names: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+    let mut node: &FileNode = tree;
+    let mut collapsed = false;
+    let mut parent_has_more = false;
+    for (i, name) in names.iter().enumerate() {
+        if is_collapsed(node, i, opts) {
+            names.truncate(i);
+            collapsed = true;
+            break;
+        }
+        match node.children.iter().find(|c| same_name(&c.name, name)) {
+            Some(child) => {
+                parent_has_more = node.has_more;
+                node = child;
+            }
+            None => {
+                // 截断的目录中缺失的项可能只是未列出，新增的项也可能超出子项数限制
+                let full = node.has_more || node.children.len() >= opts.max_children_per_dir;
+                names.truncate(if full { i } else { i + 1 });
+                break;
+            }
+        }
+    }
+    let mut target = names.iter().fold(root_path.to_path_buf(), |p, n| p.join(n));
+    // 截断的目录中有子项被删除时，原本未列出的项可能补上
+    let gone = std::fs::symlink_metadata(&target).is_err();
+    let file_in_dirs_only = opts.dirs_only && !collapsed && !target.is_dir();
+    if !names.is_empty() && (file_in_dirs_only || (gone && parent_has_more)) {
+        names.pop();
+        target.pop();
+    }
+    let fresh = rescan(&target, names.len(), collapsed, opts).ok()?;
+    let existed = if names.is_empty() {
+        let node = fresh.clone()?;
+        *tree = FileNode {
+            path: tree.path.clone(),
+            name: tree.name.clone(),
+            ..node
+        };
+        true
+    } else {
+        replace_node(tree, &names, fresh.clone()).0
+    };
+    let kind = match (existed, &fresh) {
+        (false, None) => return None,
+        (false, Some(_)) => ChangeKind::Created,
+        (true, None) => ChangeKind::Removed,
+        (true, Some(_)) => ChangeKind::Modified,
+    };
+    Some(TreeChange {
+        path: target.display().to_string(),
+        kind,
+        size: fresh.map(|n| n.size).unwrap_or(0),
+        root_size: tree.size,
+    })
+}
+
+/// 在 `names` 指向的位置替换/插入/删除节点，并沿途调整祖先大小。返回 (原本是否存在, 大小变化)
+fn replace_node(parent: &mut FileNode, names: &[String], fresh: Option<FileNode>) -> (bool, i128) {
+    let (name, rest) = match names.split_first() {
+        Some(split) => split,
+        None => return (false, 0),
+    };
+    let pos = parent
+        .children
+        .iter()
+        .position(|c| same_name(&c.name, name));
+    let (existed, delta) = match (pos, rest.is_empty()) {
+        (Some(i), false) => replace_node(&mut parent.children[i], rest, fresh),
+        (None, false) => (false, 0),
+        (Some(i), true) => {
+            let old = parent.children[i].size as i128;
+            match fresh {
+                Some(node) => {
+                    let delta = node.size as i128 - old;
+                    parent.children[i] = node;
+                    (true, delta)
+                }
+                None => {
+                    parent.children.remove(i);
+                    (true, -old)
+                }
+            }
+        }
+        (None, true) => match fresh {
+            Some(node) => {
+                let delta = node.size as i128;
+                parent.children.push(node);
+                (false, delta)
+            }
+            None => (false, 0),
+        },
+    };
+    parent.size = (parent.size as i128 + delta).max(0) as u64;
+    (existed, delta)
+}
+
+/// 重新扫描位于 `depth` 的路径并构建节点：目录按 `opts` 的剩余深度扫描子树，`collapsed` 时只计大小；
+/// 路径已不存在时返回 None
+fn rescan(
+    path: &Path,
+    depth: usize,
+    collapsed: bool,
+    opts: &ScanOptions,
+) -> Result<Option<FileNode>, DiskAnalyzerError> {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return Ok(None);
+    };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !metadata.is_dir() {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        return Ok(Some(FileNode {
+            path: path.display().to_string(),
+            name,
+            size: metadata.len(),
+            is_dir: false,
+            modified,
+            ..Default::default()
+        }));
+    }
+    // 只计大小的目录：第一层子项全部只计大小、不受子项数限制，扫描后丢弃子节点
+    let sub_opts = if collapsed {
+        ScanOptions {
+            max_depth: 1,
+            max_children_per_dir: usize::MAX,
+            ..opts.clone()
+        }
+    } else {
+        ScanOptions {
+            max_depth: opts.max_depth.saturating_sub(depth).max(1),
+            ..opts.clone()
+        }
+    };
+    let mut node = scan(&path.to_string_lossy(), &sub_opts)?.root;
+    if collapsed {
+        node.children.clear();
+        node.children_count = None;
+        node.has_more = false;
+    }
+    Ok(Some(FileNode {
+        path: path.display().to_string(),
+        name,
+        ..node
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scan, ScanOptions};
+    use std::time::Instant;
+
+    fn wait_for(handle: &WatchHandle, expected: u64) -> u64 {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let size = handle.snapshot().size;
+            if size == expected || Instant::now() > deadline {
+                return size;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn test_watch_updates_totals_on_create_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        std::fs::write(sub.join("a.txt"), [0u8; 10]).unwrap();
+        let opts = ScanOptions::default();
+        let scan = scan(&dir.path().to_string_lossy(), &opts).unwrap();
+        assert_eq!(scan.root.size, 10);
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = changes.clone();
+        let handle = watch_path(
+            scan.root,
+            &opts,
+            Box::new(move |batch: &[TreeChange]| sink.lock().unwrap().extend_from_slice(batch)),
+        )
+        .unwrap();
+
+        let root = std::path::PathBuf::from(&handle.snapshot().path);
+        std::fs::write(root.join("sub").join("b.bin"), [0u8; 100]).unwrap();
+        std::fs::create_dir_all(root.join("new").join("deep")).unwrap();
+        std::fs::write(root.join("new").join("deep").join("c.bin"), [0u8; 1000]).unwrap();
+        assert_eq!(wait_for(&handle, 1110), 1110);
+        let tree = handle.snapshot();
+        let sub = tree.children.iter().find(|c| c.name == "sub").unwrap();
+        assert_eq!(sub.size, 110);
+
+        std::fs::remove_file(root.join("sub").join("a.txt")).unwrap();
+        std::fs::remove_dir_all(root.join("new")).unwrap();
+        assert_eq!(wait_for(&handle, 100), 100);
+        let tree = handle.snapshot();
+        assert!(tree.children.iter().all(|c| c.name != "new"));
+
+        let changes = changes.lock().unwrap();
+        assert!(changes.iter().any(|c| c.kind == ChangeKind::Created));
+        assert!(changes.iter().any(|c| c.kind == ChangeKind::Removed));
+        assert_eq!(changes.last().unwrap().root_size, 100);
+    }
+
+    #[test]
+    fn test_watch_does_not_double_count_under_collapsed_or_truncated_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let pkg = dir.path().join("node_modules").join("pkg");
+        std::fs::create_dir_all(&pkg).unwrap();
+        std::fs::write(pkg.join("a.js"), [0u8; 10]).unwrap();
+        let deep = dir.path().join("src").join("x");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("y.rs"), [0u8; 5]).unwrap();
+
+        // 默认选项：node_modules 为 shallow 目录，只计大小
+        let opts = ScanOptions::default();
+        let result = scan(&dir.path().to_string_lossy(), &opts).unwrap();
+        assert_eq!(result.root.size, 15);
+        let handle = watch_path(result.root, &opts, Box::new(|_: &[TreeChange]| {})).unwrap();
+        let root = std::path::PathBuf::from(&handle.snapshot().path);
+        std::fs::write(
+            root.join("node_modules").join("pkg").join("b.js"),
+            [0u8; 100],
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("node_modules").join("new")).unwrap();
+        std::fs::write(
+            root.join("node_modules").join("new").join("c.js"),
+            [0u8; 1000],
+        )
+        .unwrap();
+        assert_eq!(wait_for(&handle, 1115), 1115);
+        let tree = handle.snapshot();
+        let modules = tree
+            .children
+            .iter()
+            .find(|c| c.name == "node_modules")
+            .unwrap();
+        assert_eq!(modules.size, 1110);
+        assert!(modules.children.is_empty());
+        drop(handle);
+
+        // 达到 max_depth 的目录与子项被截断的目录：重新扫描该目录本身
+        let many = dir.path().join("many");
+        std::fs::create_dir(&many).unwrap();
+        for (name, len) in [("a.bin", 1), ("b.bin", 2), ("c.bin", 4)] {
+            std::fs::write(many.join(name), vec![0u8; len]).unwrap();
+        }
+        std::fs::remove_dir_all(dir.path().join("node_modules")).unwrap();
+        let opts = ScanOptions::builder()
+            .use_mft(false)
+            .max_depth(2)
+            .max_children_per_dir(2)
+            .build();
+        let result = scan(&dir.path().to_string_lossy(), &opts).unwrap();
+        assert_eq!(result.root.size, 5 + 3);
+        let handle = watch_path(result.root, &opts, Box::new(|_: &[TreeChange]| {})).unwrap();
+        let root = std::path::PathBuf::from(&handle.snapshot().path);
+        std::fs::write(root.join("many").join("0.bin"), [0u8; 8]).unwrap();
+        assert_eq!(wait_for(&handle, 5 + 9), 5 + 9);
+        std::fs::write(root.join("src").join("x").join("z.rs"), [0u8; 32]).unwrap();
+        assert_eq!(wait_for(&handle, 37 + 9), 37 + 9);
+        let tree = handle.snapshot();
+        let many = tree.children.iter().find(|c| c.name == "many").unwrap();
+        let names: Vec<&str> = many.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["0.bin", "a.bin"]);
+        assert!(many.has_more);
+        let src = tree.children.iter().find(|c| c.name == "src").unwrap();
+        assert_eq!(src.children[0].size, 37);
+        assert!(src.children[0].children.is_empty());
+    }
+}
@@ -25,6 +25,7 @@
 //!   或关闭正在运行的 disk_usage_comparison 进程后再试
 
 #![cfg(windows)]
+#![allow(deprecated)] // 直接比较 `scan_path_with_progress` 返回的 used_mft
 
 use ai_disk_scanner::{get_volume_space_bytes, scan_path_with_progress};
 use std::sync::Arc;
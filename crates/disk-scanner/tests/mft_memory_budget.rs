@@ -0,0 +1,43 @@
+#![cfg(feature = "mem-harness")]
+//! MFT 内存预算测试：用计数全局分配器记录峰值分配，向 MFT 汇总喂入大量合成记录，
+//! 断言超出预算后进入降级模式且峰值内存保持在上限内，防止重新引入无界分配。
+//!
+//! 运行：
+//!   cargo test -p ai-disk-scanner --features mem-harness --test mft_memory_budget
+
+use ai_disk_scanner::mft_harness::{aggregate, measure_peak, synthetic_records, CountingAlloc};
+use ai_disk_scanner::MftBudget;
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const FILES: u64 = 1_000_000;
+const DIRS: u64 = 500;
+const PEAK_LIMIT: usize = 16 * 1024 * 1024;
+
+#[test]
+fn test_budget_bounds_peak_allocation() {
+    let expected_size: u64 = (0..FILES).map(|i| i % 4096 + 1).sum();
+    let budget = MftBudget {
+        max_file_records: 20_000,
+        max_path_cache: 10_000,
+    };
+    let (report, peak) = measure_peak(|| aggregate(synthetic_records(FILES, DIRS), &budget));
+
+    assert!(report.degraded);
+    assert_eq!(report.records_kept, 20_000 + DIRS as usize);
+    assert_eq!(report.folded_files, FILES - 20_000);
+    // 降级后目录大小仍包含所有文件
+    assert_eq!(report.root_size, expected_size);
+    assert!(
+        peak < PEAK_LIMIT,
+        "peak allocation {} bytes exceeds {} bytes",
+        peak,
+        PEAK_LIMIT
+    );
+
+    // 预算足够时不降级
+    let (small, _) = measure_peak(|| aggregate(synthetic_records(1_000, 10), &budget));
+    assert!(!small.degraded);
+    assert_eq!(small.folded_files, 0);
+}
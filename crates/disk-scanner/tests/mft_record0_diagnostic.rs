@@ -17,6 +17,7 @@
 use std::io::{Read, Seek, SeekFrom};
 
 use ai_disk_scanner::mft_scan::scan_volume_mft;
+use ai_disk_scanner::ScanOptions;
 use ntfs_reader::api::SECTOR_SIZE;
 use ntfs_reader::mft::Mft;
 use ntfs_reader::volume::Volume;
@@ -221,7 +222,7 @@ fn mft_scan_volume_mft_with_progress() {
         path.trim_end_matches(':').trim_start_matches(r"\\.\")
     );
     eprintln!(
-        "[mft_scan] 调用 scan_volume_mft({:?}, progress, true) 共 2 次",
+        "[mft_scan] 调用 scan_volume_mft({:?}, progress, 默认选项) 共 2 次",
         path_str
     );
 
@@ -231,7 +232,11 @@ fn mft_scan_volume_mft_with_progress() {
 
     for iter in 0..2 {
         eprintln!("[mft_scan] ---------- iter {} ----------", iter);
-        match scan_volume_mft(path_str.as_str(), Some(progress.clone()), true) {
+        match scan_volume_mft(
+            path_str.as_str(),
+            Some(progress.clone()),
+            &ScanOptions::default(),
+        ) {
             Ok(result) => eprintln!(
                 "[mft_scan] iter {} 成功: file_count={}",
                 iter, result.file_count
@@ -0,0 +1,81 @@
+#![cfg(all(windows, feature = "mem-harness"))]
+//! 流式读取 $MFT 与一次性加载的对比：分别用 `scan_volume_mft_streaming` 与 `scan_volume_mft_top_files`
+//! 取前 N 大文件，输出耗时、首次进度回调的时间与峰值分配，并检查两者结果一致。
+//! 需管理员权限；只在设置 `MFT_TIMING` 时运行，默认扫 C 盘：
+//!   $env:MFT_TIMING = '1'; $env:SCAN_PATH = 'D:\'
+//!   cargo test -p ai-disk-scanner --features mem-harness --test mft_streaming_memory -- --nocapture
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ai_disk_scanner::mft_harness::{measure_peak, CountingAlloc};
+use ai_disk_scanner::{
+    scan_volume_mft_streaming, scan_volume_mft_top_files, ProgressCb, TopFilesFilter,
+    TOP_FILES_DEFAULT_N,
+};
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// 执行 `f`，返回其结果、耗时、首次进度回调的时间与期间相对起点的峰值分配字节数
+fn measure<T>(f: impl FnOnce(&ProgressCb) -> T) -> (T, Duration, Option<Duration>, usize) {
+    let started = Instant::now();
+    let first_progress = Arc::new(Mutex::new(None));
+    let first = first_progress.clone();
+    let progress: ProgressCb = Box::new(move |_, _| {
+        first
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| started.elapsed());
+    });
+    let (out, peak) = measure_peak(|| f(&progress));
+    let first = *first_progress.lock().unwrap();
+    (out, started.elapsed(), first, peak)
+}
+
+#[test]
+fn streaming_vs_loaded_mft_top_files() {
+    if std::env::var("MFT_TIMING").is_err() {
+        eprintln!("[mft_streaming] MFT_TIMING not set, skipping");
+        return;
+    }
+    let path = std::env::var("SCAN_PATH").unwrap_or_else(|_| r"C:\".to_string());
+    let n = TOP_FILES_DEFAULT_N;
+    let filter = TopFilesFilter::default();
+
+    let (loaded, loaded_time, loaded_first, loaded_peak) =
+        measure(|cb| scan_volume_mft_top_files(&path, n, &filter, Some(cb)));
+    let (streamed, streamed_time, streamed_first, streamed_peak) =
+        measure(|cb| scan_volume_mft_streaming(&path, n, &filter, Some(cb)));
+    let (loaded, streamed) = match (loaded, streamed) {
+        (Ok(loaded), Ok(streamed)) => (loaded, streamed),
+        (loaded, streamed) => {
+            eprintln!(
+                "[mft_streaming] scan failed (admin required?): loaded={:?} streamed={:?}",
+                loaded.err(),
+                streamed.err()
+            );
+            return;
+        }
+    };
+
+    let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    eprintln!(
+        "[mft_streaming] loaded:   {:>8} ms, first progress {:?}, peak {:.1} MiB",
+        loaded_time.as_millis(),
+        loaded_first,
+        mib(loaded_peak)
+    );
+    eprintln!(
+        "[mft_streaming] streamed: {:>8} ms, first progress {:?}, peak {:.1} MiB",
+        streamed_time.as_millis(),
+        streamed_first,
+        mib(streamed_peak)
+    );
+
+    let sizes = |files: &[ai_disk_scanner::TopFileEntry]| -> Vec<u64> {
+        files.iter().map(|f| f.size).collect()
+    };
+    assert_eq!(sizes(&streamed), sizes(&loaded));
+    assert!(streamed_peak < loaded_peak);
+}
@@ -0,0 +1,62 @@
+#![cfg(unix)]
+//! 遍历中途遇到无权限的目录：其余内容照常返回，`scan_warning` 说明跳过的目录数，
+//! 开启 `collect_denied_paths` 时记录这些目录的路径。
+//!
+//! 以 root 运行时权限位不起作用，测试直接跳过。
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use ai_disk_scanner::{scan, ScanOptions};
+
+#[test]
+fn denied_directory_yields_partial_result_with_warning() {
+    let dir = tempfile::tempdir().unwrap();
+    let readable = dir.path().join("docs");
+    fs::create_dir(&readable).unwrap();
+    fs::write(readable.join("a.txt"), [0u8; 40]).unwrap();
+    fs::write(dir.path().join("b.txt"), [0u8; 2]).unwrap();
+    let locked = dir.path().join("locked");
+    fs::create_dir(&locked).unwrap();
+    fs::write(locked.join("secret.bin"), [0u8; 1_000]).unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read_dir(&locked).is_ok() {
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        eprintln!("跳过：当前用户不受目录权限限制");
+        return;
+    }
+
+    let opts = ScanOptions::builder()
+        .use_mft(false)
+        .collect_denied_paths(true)
+        .build();
+    let result = scan(&dir.path().to_string_lossy(), &opts);
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    let result = result.unwrap();
+
+    assert_eq!(result.denied_dirs, Some(1));
+    assert_eq!(
+        result.scan_warning.as_deref(),
+        Some("因权限不足跳过了 1 个目录，结果不完整")
+    );
+    assert_eq!(
+        result.denied_paths,
+        Some(vec![locked.display().to_string()])
+    );
+    // 其余内容完整
+    assert_eq!((result.file_count, result.total_size), (2, 42));
+    let names: Vec<&str> = result
+        .root
+        .children
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert!(names.contains(&"docs") && names.contains(&"b.txt"));
+    let docs = result
+        .root
+        .children
+        .iter()
+        .find(|c| c.name == "docs")
+        .unwrap();
+    assert_eq!(docs.children[0].name, "a.txt");
+}
@@ -1,4 +1,5 @@
 #![cfg(windows)]
+#![allow(deprecated)] // 直接比较 `scan_path_with_progress` 返回的 used_mft
 //! 扫描耗时测试：对指定卷（**默认 F 盘** `F:\`）分别使用 MFT 与普通遍历扫描，统计并输出耗时。
 //!
 //! - **MFT 扫描**：使用已实现的 `scan_path_with_progress(..., use_mft: true)`（卷根时走 MFT，需管理员权限）。
@@ -37,9 +38,9 @@ use std::fs;
 use std::path::Path;
 use std::time::Instant;
 
-#[cfg(windows)]
-use ai_disk_scanner::scan_volume_mft_top_files;
 use ai_disk_scanner::{scan_path_with_progress, FileNode, ScanResult};
+#[cfg(windows)]
+use ai_disk_scanner::{scan_volume_mft_top_files, TopFilesFilter};
 
 /// 默认扫描盘符：F 盘
 const DEFAULT_SCAN_PATH: &str = "F:\\";
@@ -144,14 +145,9 @@ fn scan_timing_mft_vs_normal() {
                         is_dir: true,
                         modified: None,
                         children: vec![],
+                        ..Default::default()
                     },
-                    scan_time_ms: 0,
-                    file_count: 0,
-                    total_size: 0,
-                    scan_warning: None,
-                    volume_total_bytes: None,
-                    volume_free_bytes: None,
-                    top_files: None,
+                    ..Default::default()
                 },
                 false,
             )),
@@ -396,7 +392,7 @@ fn scan_timing_top500_c_and_f() {
         eprintln!("[top500] ---------- {} ----------", path);
 
         let t0 = Instant::now();
-        let res_mft = scan_volume_mft_top_files(path, TOP_N, None);
+        let res_mft = scan_volume_mft_top_files(path, TOP_N, &TopFilesFilter::default(), None);
         let mft_ms = t0.elapsed().as_millis() as u64;
         match &res_mft {
             Ok(list) => {
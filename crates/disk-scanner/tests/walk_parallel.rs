@@ -0,0 +1,130 @@
+//! 普通遍历的并行扫描：与单线程遍历（`scan_threads(1)`）结果逐项一致，进度计数等于文件数，
+//! 指回祖先的符号链接不会导致无限递归。
+//!
+//! 吞吐量对比只在设置 `WALK_BENCH` 时运行（合成目录树较大，需几秒）：
+//!   WALK_BENCH=1 cargo test -p ai-disk-scanner --test walk_parallel -- --nocapture
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use ai_disk_scanner::{scan, ProgressCbArc, ScanOptions, ScanResult, SymlinkPolicy};
+
+/// 每层 `fanout` 个子目录、共 `depth` 层，每个目录含 `files` 个不同大小的文件
+fn synthetic_tree(dir: &Path, fanout: usize, depth: usize, files: usize) {
+    for i in 0..files {
+        fs::write(dir.join(format!("f{}.bin", i)), vec![0u8; 10 * i + depth]).unwrap();
+    }
+    if depth == 0 {
+        return;
+    }
+    for i in 0..fanout {
+        let sub = dir.join(format!("d{}", i));
+        fs::create_dir(&sub).unwrap();
+        synthetic_tree(&sub, fanout, depth - 1, files);
+    }
+}
+
+fn walk(path: &Path, threads: Option<usize>) -> (ScanResult, u64) {
+    let seen = Arc::new(AtomicU64::new(0));
+    let max_seen = seen.clone();
+    let progress: ProgressCbArc = Arc::new(Box::new(move |count, _: &str| {
+        max_seen.fetch_max(count, Ordering::Relaxed);
+    }));
+    // 展开链接（内容计 0），以检验指回祖先的链接不会被展开
+    let mut builder = ScanOptions::builder()
+        .use_mft(false)
+        .symlinks(SymlinkPolicy::CountAsZero)
+        .progress(progress);
+    if let Some(threads) = threads {
+        builder = builder.scan_threads(threads);
+    }
+    let result = scan(&path.to_string_lossy(), &builder.build()).unwrap();
+    let seen = seen.load(Ordering::Relaxed);
+    (result, seen)
+}
+
+#[test]
+fn parallel_walk_matches_single_threaded() {
+    let dir = tempfile::tempdir().unwrap();
+    synthetic_tree(dir.path(), 3, 4, 3);
+    let modules = dir.path().join("d0").join("node_modules");
+    fs::create_dir(&modules).unwrap();
+    fs::write(modules.join("index.js"), b"module.exports = 1;").unwrap();
+    #[cfg(unix)]
+    {
+        // 指回祖先的链接：展开的目录中与 shallow 目录中各一个
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("d1").join("up")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("d0"), modules.join("parent")).unwrap();
+    }
+
+    let (single, single_progress) = walk(dir.path(), Some(1));
+    let (parallel, parallel_progress) = walk(dir.path(), Some(4));
+
+    assert_eq!(parallel.total_size, single.total_size);
+    assert_eq!(parallel.file_count, single.file_count);
+    assert_eq!(
+        serde_json::to_value(&parallel.root).unwrap(),
+        serde_json::to_value(&single.root).unwrap()
+    );
+    // 121 个目录各 3 个文件，加 node_modules（计为 1 个）
+    assert_eq!(single.file_count, 121 * 3 + 1);
+    // 进度按文件计数，每个文件只计一次
+    assert_eq!(single_progress, single.file_count);
+    assert_eq!(parallel_progress, parallel.file_count);
+    assert_eq!(parallel.timing.unwrap().records, parallel.file_count);
+
+    #[cfg(unix)]
+    {
+        let up = parallel
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "d1")
+            .and_then(|d1| d1.children.iter().find(|c| c.name == "up"))
+            .unwrap();
+        assert!(up.is_link && up.children.is_empty());
+    }
+}
+
+#[test]
+fn walk_throughput_parallel_vs_single() {
+    if std::env::var_os("WALK_BENCH").is_none() {
+        eprintln!("跳过：设置 WALK_BENCH 后运行");
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    synthetic_tree(dir.path(), 6, 5, 5);
+
+    let mut timings = Vec::new();
+    for threads in [Some(1), None] {
+        // 先扫一遍预热目录缓存，再取三次中最快的一次
+        walk(dir.path(), threads);
+        let (ms, files) = (0..3)
+            .map(|_| {
+                let start = Instant::now();
+                let (result, _) = walk(dir.path(), threads);
+                (start.elapsed().as_secs_f64() * 1000.0, result.file_count)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap();
+        eprintln!(
+            "[walk_bench] threads={:?}: {:.1} ms, {:.0} files/s",
+            threads,
+            ms,
+            files as f64 / ms * 1000.0
+        );
+        timings.push(ms);
+    }
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    eprintln!(
+        "[walk_bench] {} 核，加速 {:.2}x",
+        cores,
+        timings[0] / timings[1]
+    );
+    if cores > 1 {
+        assert!(timings[1] < timings[0], "{:?}", timings);
+    }
+}
@@ -3,6 +3,47 @@ use serde::{Deserialize, Serialize};
 /// 执行动作
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Action {
-    Delete { path: String },
-    Move { from: String, to: String },
+    Delete {
+        path: String,
+    },
+    Move {
+        from: String,
+        to: String,
+    },
+    /// 移到回收站
+    Trash {
+        path: String,
+    },
+    /// 清空目录内容，保留目录本身
+    Empty {
+        path: String,
+    },
+    /// 用户标记保留：不做任何操作，记入保留列表后不再被建议清理
+    MarkKeep {
+        path: String,
+    },
+}
+
+impl Action {
+    /// 动作作用的路径（Move 为源路径）
+    pub fn target_path(&self) -> &str {
+        match self {
+            Action::Delete { path }
+            | Action::Trash { path }
+            | Action::Empty { path }
+            | Action::MarkKeep { path } => path,
+            Action::Move { from, .. } => from,
+        }
+    }
+
+    /// 破坏性等级，越小越安全：保留 < 移动 < 回收站 < 清空 < 删除
+    pub fn severity(&self) -> u8 {
+        match self {
+            Action::MarkKeep { .. } => 0,
+            Action::Move { .. } => 1,
+            Action::Trash { .. } => 2,
+            Action::Empty { .. } => 3,
+            Action::Delete { .. } => 4,
+        }
+    }
 }
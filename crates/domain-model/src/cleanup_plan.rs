@@ -1,10 +1,425 @@
+use std::collections::HashMap;
+
+use ai_disk_common::format::format_bytes;
+use ai_disk_common::path::{components, is_under, names_equal, CaseSensitivity};
 use serde::{Deserialize, Serialize};
 
 use crate::action::Action;
+use crate::risk::{is_system_path, is_user_document_path};
 
 /// 清理计划
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CleanupPlan {
     pub actions: Vec<Action>,
     pub estimated_space: u64,
+    /// 各动作目标路径的预计释放空间（已知时），用于合并/对比计划时重新计算 `estimated_space`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sizes: HashMap<String, u64>,
+    /// 按目标空间生成计划但无法达成时，距目标还差的字节数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortfall: Option<u64>,
+    /// 预计释放空间远超用户目标时记录该目标（字节），见 `flag_overshoot`；执行前需要额外确认
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overshoot_goal: Option<u64>,
+}
+
+impl CleanupPlan {
+    /// 合并另一份计划（如规则计划 + AI 计划）：同一路径只保留一个动作，冲突时保留更安全的那个；
+    /// 仅在 `other` 中出现的路径按其顺序追加，其预计空间计入 `estimated_space`。
+    /// 目标位于另一动作目标之下（或包含另一动作目标）时只计外层一次，见 `counted_space`
+    pub fn merge(&mut self, other: CleanupPlan) {
+        let before = counted_space(&self.actions, &self.sizes);
+        for action in other.actions {
+            let path = action.target_path().to_string();
+            match self
+                .actions
+                .iter_mut()
+                .find(|a| same_path(a.target_path(), &path))
+            {
+                Some(existing) => {
+                    if action.severity() < existing.severity() {
+                        *existing = action;
+                    }
+                }
+                None => {
+                    if let Some(&size) = other.sizes.get(&path) {
+                        self.sizes.insert(path, size);
+                    }
+                    self.actions.push(action);
+                }
+            }
+        }
+        let after = counted_space(&self.actions, &self.sizes);
+        self.estimated_space = (self.estimated_space + after).saturating_sub(before);
+    }
+
+    /// 是否需要在常规确认之外再次确认（预计释放远超目标）
+    pub fn requires_extra_confirmation(&self) -> bool {
+        self.overshoot_goal.is_some()
+    }
+
+    /// 返回本计划中目标路径不在 `other` 里的动作（如 AI 计划相对规则计划新增的建议）
+    pub fn diff(&self, other: &CleanupPlan) -> CleanupPlan {
+        let mut plan = CleanupPlan::default();
+        for action in &self.actions {
+            let path = action.target_path();
+            if other
+                .actions
+                .iter()
+                .any(|a| same_path(a.target_path(), path))
+            {
+                continue;
+            }
+            if let Some(&size) = self.sizes.get(path) {
+                plan.sizes.insert(path.to_string(), size);
+            }
+            plan.actions.push(action.clone());
+        }
+        plan.estimated_space = counted_space(&plan.actions, &plan.sizes);
+        plan
+    }
+
+    /// 整份计划的一段说明文字，由动作集合确定性地生成（不额外调用 LLM），如：
+    /// 「本计划预计释放 83.00 GiB：清空 3 个目录、删除 1204 项、移动 12 项到 D:。
+    /// 不会改动文档、桌面、图片及系统目录中的内容。」
+    pub fn summary_text(&self) -> String {
+        let mut empty = 0;
+        let mut delete = 0;
+        let mut trash = 0;
+        let mut keep = 0;
+        let mut move_dests: Vec<&str> = Vec::new();
+        let mut in_documents = 0;
+        let mut in_system = 0;
+        for action in &self.actions {
+            match action {
+                Action::Empty { .. } => empty += 1,
+                Action::Delete { .. } => delete += 1,
+                Action::Trash { .. } => trash += 1,
+                Action::MarkKeep { .. } => keep += 1,
+                Action::Move { to, .. } => move_dests.push(parent_dir(to)),
+            }
+            if matches!(action, Action::MarkKeep { .. }) {
+                continue;
+            }
+            let path = action.target_path();
+            if is_system_path(path) {
+                in_system += 1;
+            } else if is_user_document_path(path) {
+                in_documents += 1;
+            }
+        }
+
+        let mut parts = Vec::new();
+        if empty > 0 {
+            parts.push(format!("清空 {} 个目录", empty));
+        }
+        if delete > 0 {
+            parts.push(format!("删除 {} 项", delete));
+        }
+        if trash > 0 {
+            parts.push(format!("移到回收站 {} 项", trash));
+        }
+        if !move_dests.is_empty() {
+            parts.push(format!(
+                "移动 {} 项到 {}",
+                move_dests.len(),
+                common_destination(&move_dests)
+            ));
+        }
+        if parts.is_empty() {
+            let mut text = "本计划没有需要执行的清理操作。".to_string();
+            if keep > 0 {
+                text.push_str(&format!("{} 项已标记为保留。", keep));
+            }
+            return text;
+        }
+
+        let mut text = format!(
+            "本计划预计释放 {}：{}。",
+            format_bytes(self.estimated_space),
+            parts.join("、")
+        );
+        if keep > 0 {
+            text.push_str(&format!("另有 {} 项标记为保留。", keep));
+        }
+        match (in_system, in_documents) {
+            (0, 0) => text.push_str("不会改动文档、桌面、图片及系统目录中的内容。"),
+            (0, n) => text.push_str(&format!("其中 {} 项位于文档、桌面或图片目录，请确认。", n)),
+            (n, _) => text.push_str(&format!("其中 {} 项位于系统目录，请仔细确认。", n)),
+        }
+        if let Some(shortfall) = self.shortfall {
+            text.push_str(&format!("距目标仍差 {}。", format_bytes(shortfall)));
+        }
+        if let Some(goal) = self.overshoot_goal {
+            text.push_str(&format!(
+                "目标仅需释放 {}，本计划远超目标，请逐项确认后再执行。",
+                format_bytes(goal)
+            ));
+        }
+        text
+    }
+}
+
+/// 两个路径是否指向同一位置（按分段比较，忽略尾部分隔符，大小写按本机文件系统规则）
+fn same_path(a: &str, b: &str) -> bool {
+    let (a, b) = (components(a), components(b));
+    a.len() == b.len()
+        && a.iter()
+            .zip(&b)
+            .all(|(x, y)| names_equal(x, y, CaseSensitivity::native()))
+}
+
+/// 各动作已知预计空间之和；目标位于另一个（非保留）动作目标之下的动作已被外层计入，跳过
+fn counted_space(actions: &[Action], sizes: &HashMap<String, u64>) -> u64 {
+    let frees = |a: &&Action| !matches!(a, Action::MarkKeep { .. });
+    actions
+        .iter()
+        .filter(frees)
+        .filter(|a| {
+            let path = a.target_path();
+            !actions.iter().filter(frees).any(|outer| {
+                let dir = outer.target_path();
+                !same_path(dir, path) && is_under(path, dir, CaseSensitivity::native())
+            })
+        })
+        .filter_map(|a| sizes.get(a.target_path()))
+        .sum()
+}
+
+/// 路径的父目录（无分隔符时返回原路径）
+fn parent_dir(path: &str) -> &str {
+    path.trim_end_matches(['/', '\\'])
+        .rsplit_once(['/', '\\'])
+        .map(|(parent, _)| if parent.is_empty() { "/" } else { parent })
+        .unwrap_or(path)
+}
+
+/// 移动目标的描述：同一目录时为该目录，同一盘符/根时为根，否则为位置数
+fn common_destination(dests: &[&str]) -> String {
+    let first = dests[0];
+    if dests.iter().all(|d| *d == first) {
+        return first.to_string();
+    }
+    let shared_root = ai_disk_common::path::root(first).filter(|r| {
+        dests
+            .iter()
+            .all(|d| ai_disk_common::path::root(d) == Some(*r))
+    });
+    if let Some(root) = shared_root {
+        return root.to_string();
+    }
+    let mut unique = dests.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+    format!("{} 个位置", unique.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(actions: Vec<(Action, u64)>) -> CleanupPlan {
+        let mut plan = CleanupPlan::default();
+        for (action, size) in actions {
+            plan.estimated_space += size;
+            plan.sizes.insert(action.target_path().to_string(), size);
+            plan.actions.push(action);
+        }
+        plan
+    }
+
+    #[test]
+    fn test_merge_keeps_safer_action_and_diff() {
+        let path = |p: &str| p.to_string();
+        let rules = plan(vec![
+            (
+                Action::Trash {
+                    path: path("/tmp/a"),
+                },
+                100,
+            ),
+            (
+                Action::Empty {
+                    path: path("/cache"),
+                },
+                50,
+            ),
+        ]);
+        let ai = plan(vec![
+            (
+                Action::Delete {
+                    path: path("/tmp/a"),
+                },
+                100,
+            ),
+            (
+                Action::Delete {
+                    path: path("/old.iso"),
+                },
+                700,
+            ),
+        ]);
+
+        let added = ai.diff(&rules);
+        assert_eq!(added.actions.len(), 1);
+        assert_eq!(added.actions[0].target_path(), "/old.iso");
+        assert_eq!(added.estimated_space, 700);
+
+        let mut merged = ai;
+        merged.merge(rules);
+        let summary: Vec<String> = merged.actions.iter().map(|a| format!("{:?}", a)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                r#"Trash { path: "/tmp/a" }"#,
+                r#"Delete { path: "/old.iso" }"#,
+                r#"Empty { path: "/cache" }"#,
+            ]
+        );
+        assert_eq!(merged.estimated_space, 850);
+    }
+
+    #[test]
+    fn test_merge_counts_nested_targets_once() {
+        let path = |p: &str| p.to_string();
+        let rules = plan(vec![(
+            Action::Empty {
+                path: path("/cache"),
+            },
+            500,
+        )]);
+        let ai = plan(vec![
+            (
+                Action::Delete {
+                    path: path("/cache/npm"),
+                },
+                300,
+            ),
+            (
+                Action::Delete {
+                    path: path("/cache-old"),
+                },
+                40,
+            ),
+        ]);
+
+        // 规则计划已清空 /cache，AI 建议的 /cache/npm 不再重复计入
+        let mut merged = rules.clone();
+        merged.merge(ai.clone());
+        assert_eq!(merged.actions.len(), 3);
+        assert_eq!(merged.estimated_space, 540);
+
+        // 反向合并：外层目录取代已计入的子目录
+        let mut merged = ai.clone();
+        merged.merge(rules.clone());
+        assert_eq!(merged.estimated_space, 540);
+
+        // 保留标记不释放空间，也不遮蔽其下的动作
+        let mut kept = plan(vec![(
+            Action::MarkKeep {
+                path: path("/cache"),
+            },
+            0,
+        )]);
+        kept.merge(ai.clone());
+        assert_eq!(kept.estimated_space, 340);
+
+        let mut nested = ai;
+        nested.actions.push(Action::Delete {
+            path: path("/cache/npm/_cacache"),
+        });
+        nested.sizes.insert(path("/cache/npm/_cacache"), 200);
+        assert_eq!(nested.diff(&rules).estimated_space, 340);
+    }
+
+    #[test]
+    fn test_merge_matches_paths_with_trailing_separator() {
+        let path = |p: &str| p.to_string();
+        let mut rules = plan(vec![(
+            Action::Trash {
+                path: path("/tmp/x"),
+            },
+            100,
+        )]);
+        let ai = plan(vec![(
+            Action::Delete {
+                path: path("/tmp/x/"),
+            },
+            100,
+        )]);
+        assert!(ai.diff(&rules).actions.is_empty());
+
+        rules.merge(ai);
+        assert_eq!(rules.actions.len(), 1);
+        assert_eq!(rules.actions[0].severity(), 2);
+        assert_eq!(rules.estimated_space, 100);
+    }
+
+    #[test]
+    fn test_summary_text_reflects_actions() {
+        let path = |p: &str| p.to_string();
+        let mut actions = vec![
+            (
+                Action::Empty {
+                    path: path(r"C:\Users\u\AppData\Local\npm-cache"),
+                },
+                3 << 30,
+            ),
+            (
+                Action::Delete {
+                    path: path(r"C:\Users\u\AppData\Local\Temp\a.tmp"),
+                },
+                1 << 30,
+            ),
+            (
+                Action::Delete {
+                    path: path(r"C:\Users\u\AppData\Local\Temp\b.tmp"),
+                },
+                1 << 30,
+            ),
+            (
+                Action::Move {
+                    from: path(r"C:\Users\u\Videos\2019.mp4"),
+                    to: path(r"D:\Archive\2019.mp4"),
+                },
+                2 << 30,
+            ),
+            (
+                Action::Move {
+                    from: path(r"C:\Users\u\Videos\2020.mp4"),
+                    to: path(r"D:\Old\2020.mp4"),
+                },
+                1 << 30,
+            ),
+        ];
+        actions.push((
+            Action::MarkKeep {
+                path: path(r"C:\Users\u\Videos\wedding"),
+            },
+            0,
+        ));
+        let summary = plan(actions).summary_text();
+        assert_eq!(
+            summary,
+            "本计划预计释放 8.00 GiB：清空 1 个目录、删除 2 项、移动 2 项到 D:。\
+             另有 1 项标记为保留。不会改动文档、桌面、图片及系统目录中的内容。"
+        );
+
+        let mut risky = plan(vec![(
+            Action::Trash {
+                path: path("/home/u/Documents/draft.docx"),
+            },
+            2048,
+        )]);
+        risky.shortfall = Some(1 << 20);
+        assert_eq!(
+            risky.summary_text(),
+            "本计划预计释放 2.00 KiB：移到回收站 1 项。\
+             其中 1 项位于文档、桌面或图片目录，请确认。距目标仍差 1.00 MiB。"
+        );
+        assert_eq!(
+            CleanupPlan::default().summary_text(),
+            "本计划没有需要执行的清理操作。"
+        );
+    }
 }
@@ -0,0 +1,269 @@
+//! 两次扫描结果的对比：清理后重新扫描时，列出新增、消失与大小变化的路径。
+//! 路径按分段规范化后匹配（`/` 与 `\` 等价，按本机文件系统的大小写规则），
+//! 整个消失或新增的目录只记为一项，不再逐个列出其中的文件。
+
+use std::collections::HashMap;
+
+use ai_disk_common::path::{self, CaseSensitivity};
+use serde::{Deserialize, Serialize};
+
+use crate::{FileNode, ScanResult};
+
+/// 只在一侧出现的路径
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffEntry {
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+/// 两侧都有、大小不同的路径；目录的 `delta` 由其下各项变化汇总得出
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeChange {
+    pub path: String,
+    pub is_dir: bool,
+    pub before: u64,
+    pub after: u64,
+    pub delta: i64,
+}
+
+/// `diff_scans` 的结果：`added`、`removed` 按大小降序，`changed` 按变化量绝对值降序
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanDiff {
+    pub added: Vec<DiffEntry>,
+    pub removed: Vec<DiffEntry>,
+    pub changed: Vec<SizeChange>,
+    /// 整体大小变化（字节）
+    pub net_delta: i64,
+}
+
+impl ScanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// 对比两次扫描，见模块说明
+pub fn diff_scans(before: &ScanResult, after: &ScanResult) -> ScanDiff {
+    let case = CaseSensitivity::native();
+    let mut diff = ScanDiff::default();
+    if normalized(&before.root.path, case) == normalized(&after.root.path, case) {
+        diff.net_delta = diff_node(&before.root, &after.root, case, &mut diff);
+    } else {
+        diff.removed.push(entry(&before.root));
+        diff.added.push(entry(&after.root));
+        diff.net_delta = after.root.size as i64 - before.root.size as i64;
+    }
+    diff.added
+        .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    diff.removed
+        .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    diff.changed.sort_by(|a, b| {
+        b.delta
+            .unsigned_abs()
+            .cmp(&a.delta.unsigned_abs())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    diff
+}
+
+/// 比较键：各分段以 `/` 连接，不区分大小写时转为小写
+fn normalized(p: &str, case: CaseSensitivity) -> String {
+    let joined = path::components(p).join("/");
+    match case {
+        CaseSensitivity::Sensitive => joined,
+        CaseSensitivity::Insensitive => joined.to_lowercase(),
+    }
+}
+
+fn entry(node: &FileNode) -> DiffEntry {
+    DiffEntry {
+        path: node.path.clone(),
+        size: node.size,
+        is_dir: node.is_dir,
+    }
+}
+
+/// 对比路径相同的两个节点，返回大小变化（目录由子项汇总）
+fn diff_node(
+    before: &FileNode,
+    after: &FileNode,
+    case: CaseSensitivity,
+    out: &mut ScanDiff,
+) -> i64 {
+    if before.is_dir != after.is_dir {
+        out.removed.push(entry(before));
+        out.added.push(entry(after));
+        return after.size as i64 - before.size as i64;
+    }
+    // 文件或未展开的目录（shallow 目录、截断处）只能比较自身大小
+    let delta = if !before.is_dir || (before.children.is_empty() && after.children.is_empty()) {
+        after.size as i64 - before.size as i64
+    } else {
+        let mut olds: HashMap<String, &FileNode> = before
+            .children
+            .iter()
+            .map(|c| (normalized(&c.path, case), c))
+            .collect();
+        let mut delta = 0i64;
+        for child in &after.children {
+            match olds.remove(&normalized(&child.path, case)) {
+                Some(old) => delta += diff_node(old, child, case, out),
+                None => {
+                    delta += child.size as i64;
+                    out.added.push(entry(child));
+                }
+            }
+        }
+        for old in olds.into_values() {
+            delta -= old.size as i64;
+            out.removed.push(entry(old));
+        }
+        delta
+    };
+    if delta != 0 {
+        out.changed.push(SizeChange {
+            path: after.path.clone(),
+            is_dir: after.is_dir,
+            before: before.size,
+            after: after.size,
+            delta,
+        });
+    }
+    delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: path::file_name(path).to_string(),
+            size: size + children.iter().map(|c| c.size).sum::<u64>(),
+            is_dir: !children.is_empty() || !path.contains('.'),
+            children,
+            ..Default::default()
+        }
+    }
+
+    fn scan(root: FileNode) -> ScanResult {
+        ScanResult {
+            file_count: root.total_files(),
+            total_size: root.size,
+            root,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_scans_add_remove_and_grow() {
+        let before = scan(node(
+            "/data",
+            0,
+            vec![
+                node(
+                    "/data/cache",
+                    0,
+                    vec![
+                        node("/data/cache/a.bin", 3_000, vec![]),
+                        node("/data/cache/b.bin", 2_000, vec![]),
+                        node(
+                            "/data/cache/old",
+                            0,
+                            vec![node("/data/cache/old/c.bin", 500, vec![])],
+                        ),
+                    ],
+                ),
+                node(
+                    "/data/docs",
+                    0,
+                    vec![
+                        node("/data/docs/report.pdf", 100, vec![]),
+                        node("/data/docs/notes.txt", 10, vec![]),
+                    ],
+                ),
+            ],
+        ));
+        // 根路径带尾部分隔符也能匹配
+        let after = scan(node(
+            "/data/",
+            0,
+            vec![
+                node(
+                    "/data/docs",
+                    0,
+                    vec![
+                        node("/data/docs/report.pdf", 400, vec![]),
+                        node("/data/docs/notes.txt", 10, vec![]),
+                        node("/data/docs/new.md", 50, vec![]),
+                    ],
+                ),
+                node("/data/video.mp4", 1_000, vec![]),
+            ],
+        ));
+
+        let diff = diff_scans(&before, &after);
+
+        // 整个删除的目录只出现一次
+        assert_eq!(
+            diff.removed,
+            [DiffEntry {
+                path: "/data/cache".to_string(),
+                size: 5_500,
+                is_dir: true,
+            }]
+        );
+        let added: Vec<(&str, u64)> = diff
+            .added
+            .iter()
+            .map(|e| (e.path.as_str(), e.size))
+            .collect();
+        assert_eq!(
+            added,
+            [("/data/video.mp4", 1_000), ("/data/docs/new.md", 50)]
+        );
+        let changed: Vec<(&str, i64)> = diff
+            .changed
+            .iter()
+            .map(|c| (c.path.as_str(), c.delta))
+            .collect();
+        // 目录的变化由文件汇总：docs = +300 + 50，根 = -5500 + 350 + 1000
+        assert_eq!(
+            changed,
+            [
+                ("/data/", -4_150),
+                ("/data/docs", 350),
+                ("/data/docs/report.pdf", 300)
+            ]
+        );
+        assert_eq!(
+            diff.net_delta,
+            after.total_size as i64 - before.total_size as i64
+        );
+        assert!(diff_scans(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_diff_scans_type_change_is_remove_plus_add() {
+        let before = scan(node(
+            "/p",
+            0,
+            vec![node("/p/build", 0, vec![node("/p/build/x.o", 70, vec![])])],
+        ));
+        let after = scan(node(
+            "/p",
+            0,
+            vec![FileNode {
+                is_dir: false,
+                ..node("/p/build", 20, vec![])
+            }],
+        ));
+
+        let diff = diff_scans(&before, &after);
+        assert_eq!((diff.removed[0].is_dir, diff.removed[0].size), (true, 70));
+        assert_eq!((diff.added[0].is_dir, diff.added[0].size), (false, 20));
+        assert_eq!(diff.net_delta, -50);
+    }
+}
@@ -0,0 +1,212 @@
+//! 把扫描结果导出为 CSV 或 JSON，供脚本处理。每个计入 `file_count` 的条目一行：
+//! 文件，以及未展开的 shallow 目录（`is_dir` 为 true，`size` 为其递归大小）。
+//! 汇总节点（见 `FileNode::compact`）没有真实路径，不导出。
+//! 列依次为 `path,size,is_dir,modified,depth`，`depth` 为相对扫描根的层级（根的直接子项为 1）。
+
+use std::io::{BufWriter, Write};
+
+use ai_disk_common::DiskAnalyzerError;
+use serde::Serialize;
+
+use crate::{is_shallow_dir_name, FileNode, ScanResult};
+
+/// 导出的一行
+#[derive(Debug, Clone, Serialize)]
+struct ExportRow<'a> {
+    path: &'a str,
+    size: u64,
+    is_dir: bool,
+    modified: Option<u64>,
+    depth: usize,
+}
+
+/// 以 CSV 写出不小于 `min_size` 的条目（含表头），返回写入的行数（不含表头）
+pub fn to_csv(
+    result: &ScanResult,
+    writer: impl Write,
+    min_size: u64,
+) -> Result<u64, DiskAnalyzerError> {
+    let mut out = BufWriter::new(writer);
+    writeln!(out, "path,size,is_dir,modified,depth")?;
+    let rows = for_each_row(&result.root, min_size, |row| {
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            csv_field(row.path),
+            row.size,
+            row.is_dir,
+            row.modified.map(|m| m.to_string()).unwrap_or_default(),
+            row.depth
+        )
+    })?;
+    out.flush()?;
+    Ok(rows)
+}
+
+/// 以 JSON 数组写出不小于 `min_size` 的条目，每项为 `{path, size, is_dir, modified, depth}`，
+/// 返回写入的项数。逐项写出，不在内存中构建整个数组
+pub fn to_json(
+    result: &ScanResult,
+    writer: impl Write,
+    min_size: u64,
+) -> Result<u64, DiskAnalyzerError> {
+    let mut out = BufWriter::new(writer);
+    out.write_all(b"[")?;
+    let mut first = true;
+    let rows = for_each_row(&result.root, min_size, |row| {
+        if !std::mem::take(&mut first) {
+            out.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut out, &row).map_err(std::io::Error::other)
+    })?;
+    out.write_all(b"]")?;
+    out.flush()?;
+    Ok(rows)
+}
+
+/// 按树的顺序（先序）对每个导出条目调用 `f`，返回条目数
+fn for_each_row(
+    root: &FileNode,
+    min_size: u64,
+    mut f: impl FnMut(ExportRow<'_>) -> std::io::Result<()>,
+) -> Result<u64, DiskAnalyzerError> {
+    let mut rows = 0u64;
+    let mut stack = vec![(root, 0usize)];
+    while let Some((node, depth)) = stack.pop() {
+        if node.is_aggregate() {
+            continue;
+        }
+        let collapsed = node.is_dir && node.children.is_empty() && is_shallow_dir_name(&node.name);
+        if (!node.is_dir || collapsed) && node.size >= min_size {
+            f(ExportRow {
+                path: &node.path,
+                size: node.size,
+                is_dir: node.is_dir,
+                modified: node.modified,
+                depth,
+            })?;
+            rows += 1;
+        }
+        stack.extend(node.children.iter().rev().map(|c| (c, depth + 1)));
+    }
+    Ok(rows)
+}
+
+/// 含逗号、引号或换行的字段用双引号括起，内部引号写成两个
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size: size + children.iter().map(|c| c.size).sum::<u64>(),
+            is_dir: !children.is_empty() || !path.contains('.'),
+            modified: Some(1_700_000_000),
+            children,
+            ..Default::default()
+        }
+    }
+
+    fn sample() -> ScanResult {
+        let root = node(
+            "/data",
+            0,
+            vec![
+                node(
+                    "/data/music",
+                    0,
+                    vec![
+                        node("/data/music/Tom, Jerry.mp3", 4_000, vec![]),
+                        node("/data/music/say \"hi\".flac", 9_000, vec![]),
+                    ],
+                ),
+                // shallow 目录：未展开，计为一个文件
+                node("/data/node_modules", 2_000, vec![]),
+                node("/data/a.txt", 5, vec![]),
+            ],
+        );
+        ScanResult {
+            file_count: 4,
+            total_size: root.size,
+            root,
+            ..Default::default()
+        }
+    }
+
+    /// 按 RFC 4180 拆分一行（测试用，不处理字段内换行）
+    fn parse_csv_line(line: &str) -> Vec<String> {
+        let mut fields = vec![String::new()];
+        let (mut quoted, mut chars) = (false, line.chars().peekable());
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push('"');
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => fields.push(String::new()),
+                _ => fields.last_mut().unwrap().push(c),
+            }
+        }
+        fields
+    }
+
+    #[test]
+    fn test_to_csv_quotes_paths_and_rows_match_file_count() {
+        let result = sample();
+        let mut buf = Vec::new();
+        assert_eq!(to_csv(&result, &mut buf, 0).unwrap(), 4);
+
+        let text = String::from_utf8(buf).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("path,size,is_dir,modified,depth"));
+        let rows: Vec<Vec<String>> = lines.map(parse_csv_line).collect();
+        assert_eq!(rows.len() as u64, result.file_count);
+        assert!(rows.iter().all(|r| r.len() == 5));
+        assert_eq!(
+            rows[0],
+            [
+                "/data/music/Tom, Jerry.mp3",
+                "4000",
+                "false",
+                "1700000000",
+                "2"
+            ]
+        );
+        assert_eq!(rows[1][0], "/data/music/say \"hi\".flac");
+        assert_eq!(&rows[2][..3], ["/data/node_modules", "2000", "true"]);
+        let total: u64 = rows.iter().map(|r| r[1].parse::<u64>().unwrap()).sum();
+        assert_eq!(total, result.total_size);
+
+        let mut buf = Vec::new();
+        assert_eq!(to_csv(&result, &mut buf, 3_000).unwrap(), 2);
+        assert_eq!(String::from_utf8(buf).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_to_json_flat_array() {
+        let result = sample();
+        let mut buf = Vec::new();
+        assert_eq!(to_json(&result, &mut buf, 0).unwrap(), 4);
+
+        let rows: Vec<serde_json::Value> = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(rows.len() as u64, result.file_count);
+        assert_eq!(rows[1]["path"], "/data/music/say \"hi\".flac");
+        assert_eq!(rows[3]["depth"], 1);
+        assert_eq!(rows[3]["is_dir"], false);
+
+        let mut buf = Vec::new();
+        assert_eq!(to_json(&result, &mut buf, u64::MAX).unwrap(), 0);
+        assert_eq!(buf, b"[]");
+    }
+}
@@ -1,7 +1,34 @@
+use std::cmp::Ordering;
+
+use ai_disk_common::path;
 use serde::{Deserialize, Serialize};
 
+/// shallow 目录：扫描时遇到这些目录名只统计总大小，不展开子项（常见包管理器/缓存目录）
+pub const SHALLOW_DIR_NAMES: &[&str] = &[
+    "node_modules",
+    ".git",
+    ".github",
+    ".venv",
+    "venv",
+    "__pycache__",
+    "target",
+    "vendor",
+    ".npm",
+    ".yarn",
+    ".pnpm",
+    "bower_components",
+    "jspm_packages",
+];
+
+/// 目录名是否属于 `SHALLOW_DIR_NAMES`（不区分大小写）
+pub fn is_shallow_dir_name(name: &str) -> bool {
+    SHALLOW_DIR_NAMES
+        .iter()
+        .any(|s| s.eq_ignore_ascii_case(name))
+}
+
 /// 文件树节点
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FileNode {
     pub path: String,
     pub name: String,
@@ -12,4 +39,349 @@ pub struct FileNode {
     pub modified: Option<u64>,
     #[serde(default)]
     pub children: Vec<FileNode>,
+    /// 目录下（递归）的文件数，仅「只扫描目录」模式下填充；
+    /// 对非目录节点表示这是合并了多个被省略小文件的汇总节点（见 `FileNode::compact`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_count: Option<u64>,
+    /// 压缩包内容摘要，仅在扫描时开启 `peek_archives` 且能解析文件头时填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveInfo>,
+    /// 由系统管理的文件（页面文件、休眠文件等）：照常显示大小，但不计入可释放空间、不会被建议清理
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub system_managed: bool,
+    /// 直接子节点数，仅在子节点被 `clone_to_depth` 截断时填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children_count: Option<u64>,
+    /// 子节点已被 `clone_to_depth` 截断，展开时需向后端加载更深的层级
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub has_more: bool,
+    /// 原始 UTF-16 路径，仅当文件名含未配对代理项、`path` 中已被替换为 U+FFFD 时填充（MFT 扫描）。
+    /// 删除、移动等操作应使用 `fs_path()`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_path: Option<Vec<u16>>,
+    /// shallow 目录被折叠时保留的子节点，仅在扫描时开启 `retain_shallow_children` 才填充；
+    /// 切换 shallow 设置时据此展开，见 `ScanResult::rebuild_with_shallow`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collapsed_children: Vec<FileNode>,
+    /// 符号链接或目录联接（普通遍历）：未展开时大小为 0，见 `SymlinkPolicy`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_link: bool,
+}
+
+/// 压缩包（zip/tar）的内容摘要：只读取目录/文件头得出，不解压
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveInfo {
+    /// 条目数（含目录条目）
+    pub entries: u64,
+    /// 解压后的总大小
+    pub uncompressed_size: u64,
+}
+
+impl FileNode {
+    /// 由路径得出的显示名（同时识别 `/` 与 `\`，处理卷根、UNC 与尾部分隔符）
+    pub fn display_name(&self) -> &str {
+        path::file_name(&self.path)
+    }
+
+    /// 路径的各个分段，见 `ai_disk_common::path::components`
+    pub fn path_components(&self) -> Vec<&str> {
+        path::components(&self.path)
+    }
+
+    /// 操作文件时使用的真实路径（见 `raw_path`）
+    pub fn fs_path(&self) -> std::path::PathBuf {
+        path::fs_path(&self.path, self.raw_path.as_deref())
+    }
+
+    /// 是否为由系统管理的文件：扫描时已标记，或按路径识别（见 `is_system_managed_file`）
+    pub fn is_system_managed(&self) -> bool {
+        self.system_managed || (!self.is_dir && crate::is_system_managed_file(&self.path))
+    }
+
+    /// 是否为汇总被省略小文件的节点
+    pub fn is_aggregate(&self) -> bool {
+        !self.is_dir && self.file_count.is_some()
+    }
+
+    /// 精简副本：保留所有目录，只保留不小于 `min_file_size` 的文件，
+    /// 每个目录中被省略的文件合并为一个汇总节点（size 为省略的字节数，file_count 为省略的文件数）
+    pub fn compact(&self, min_file_size: u64) -> FileNode {
+        let mut children = Vec::new();
+        let (mut omitted, mut omitted_bytes) = (0u64, 0u64);
+        for child in &self.children {
+            if child.is_dir {
+                children.push(child.compact(min_file_size));
+            } else if child.size >= min_file_size {
+                children.push(child.clone());
+            } else {
+                omitted += child.file_count.unwrap_or(1);
+                omitted_bytes += child.size;
+            }
+        }
+        if omitted > 0 {
+            children.push(self.aggregate_child(
+                format!("[{} 个小文件]", omitted),
+                omitted,
+                omitted_bytes,
+            ));
+        }
+        FileNode {
+            children,
+            ..self.clone_without_children()
+        }
+    }
+
+    /// 截断到 `max_depth` 层的副本（根为第 0 层），用于先把浅层树发给前端快速渲染、展开时再加载。
+    /// 被截断的目录保留递归 `size`，`children` 为空，并填充 `children_count` 与 `has_more`
+    pub fn clone_to_depth(&self, max_depth: usize) -> FileNode {
+        let mut node = self.clone_without_children();
+        if max_depth == 0 {
+            if !self.children.is_empty() {
+                node.children_count = Some(self.children.len() as u64);
+                node.has_more = true;
+            }
+        } else {
+            node.children = self
+                .children
+                .iter()
+                .map(|c| c.clone_to_depth(max_depth - 1))
+                .collect();
+        }
+        node
+    }
+
+    /// 把占 `total`（通常为卷容量或根大小）比例低于 `fraction` 的子目录逐层合并为一个汇总节点，
+    /// 让 Treemap 只显示有分量的目录；阈值随卷大小自适应。各层 size 不变
+    pub fn prune_below_fraction(&mut self, total: u64, fraction: f64) {
+        let threshold = (total as f64 * fraction) as u64;
+        let (mut dirs, mut files, mut bytes) = (0u64, 0u64, 0u64);
+        let mut kept = Vec::with_capacity(self.children.len());
+        for mut child in std::mem::take(&mut self.children) {
+            if child.is_dir && child.size < threshold {
+                dirs += 1;
+                files += child.total_files();
+                bytes += child.size;
+            } else {
+                if child.is_dir {
+                    child.prune_below_fraction(total, fraction);
+                }
+                kept.push(child);
+            }
+        }
+        if dirs > 0 {
+            kept.push(self.aggregate_child(format!("[{} 个小目录]", dirs), files, bytes));
+        }
+        self.children = kept;
+    }
+
+    /// 大小降序排列的比较：同大小按名称、再按路径，排序结果在多次扫描间稳定
+    pub fn cmp_size_desc(a: &FileNode, b: &FileNode) -> Ordering {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.path.cmp(&b.path))
+    }
+
+    /// 逐层把 `children` 按大小从大到小排列（见 `cmp_size_desc`）
+    pub fn sort_children_by_size_desc(&mut self) {
+        self.children.sort_by(FileNode::cmp_size_desc);
+        for child in &mut self.children {
+            child.sort_children_by_size_desc();
+        }
+    }
+
+    /// 子树中的文件数（汇总节点按其 file_count 计，只扫描目录模式下直接取目录的 file_count）
+    pub fn total_files(&self) -> u64 {
+        match (self.is_dir, self.file_count) {
+            (false, count) => count.unwrap_or(1),
+            (true, Some(count)) => count,
+            (true, None) => self.children.iter().map(FileNode::total_files).sum(),
+        }
+    }
+
+    /// 本目录下代表 `count` 个文件、共 `bytes` 字节的汇总子节点
+    fn aggregate_child(&self, name: String, count: u64, bytes: u64) -> FileNode {
+        let sep = if self.path.contains('\\') { '\\' } else { '/' };
+        FileNode {
+            path: format!("{}{}*", self.path.trim_end_matches(['/', '\\']), sep),
+            name,
+            size: bytes,
+            is_dir: false,
+            file_count: Some(count),
+            ..Default::default()
+        }
+    }
+
+    fn clone_without_children(&self) -> FileNode {
+        FileNode {
+            path: self.path.clone(),
+            name: self.name.clone(),
+            size: self.size,
+            is_dir: self.is_dir,
+            modified: self.modified,
+            children: Vec::new(),
+            file_count: self.file_count,
+            archive: self.archive,
+            system_managed: self.system_managed,
+            children_count: self.children_count,
+            has_more: self.has_more,
+            raw_path: self.raw_path.clone(),
+            collapsed_children: self.collapsed_children.clone(),
+            is_link: self.is_link,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: path::file_name(path).to_string(),
+            size: size + children.iter().map(|c| c.size).sum::<u64>(),
+            is_dir: !children.is_empty() || !path.contains('.'),
+            children,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_prune_below_fraction_aggregates_small_dirs() {
+        let mut root = node(
+            "/vol",
+            0,
+            vec![
+                node(
+                    "/vol/media",
+                    0,
+                    vec![
+                        node("/vol/media/movie.mkv", 9_000, vec![]),
+                        node(
+                            "/vol/media/subs",
+                            0,
+                            vec![node("/vol/media/subs/a.srt", 30, vec![])],
+                        ),
+                    ],
+                ),
+                node(
+                    "/vol/etc",
+                    0,
+                    vec![
+                        node("/vol/etc/a.conf", 20, vec![]),
+                        node("/vol/etc/b.conf", 10, vec![]),
+                    ],
+                ),
+                node("/vol/tiny", 0, vec![node("/vol/tiny/x.txt", 5, vec![])]),
+                node("/vol/notes.txt", 3, vec![]),
+            ],
+        );
+        let before = (root.size, root.total_files());
+
+        root.prune_below_fraction(10_000, 0.005);
+
+        assert_eq!((root.size, root.total_files()), before);
+        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["media", "notes.txt", "[2 个小目录]"]);
+        let small = &root.children[2];
+        assert!(small.is_aggregate());
+        assert_eq!((small.size, small.file_count), (35, Some(3)));
+        // 递归：media 下不足 50 字节的 subs 也被合并
+        let media = &root.children[0];
+        assert_eq!(media.children[1].name, "[1 个小目录]");
+        assert_eq!(media.size, 9_030);
+    }
+
+    #[test]
+    fn test_clone_to_depth_keeps_sizes_at_cut() {
+        let root = node(
+            "/vol",
+            0,
+            vec![
+                node(
+                    "/vol/media",
+                    0,
+                    vec![
+                        node("/vol/media/movie.mkv", 9_000, vec![]),
+                        node(
+                            "/vol/media/subs",
+                            0,
+                            vec![node("/vol/media/subs/a.srt", 30, vec![])],
+                        ),
+                    ],
+                ),
+                node("/vol/empty", 0, vec![]),
+                node("/vol/notes.txt", 3, vec![]),
+            ],
+        );
+
+        let top = root.clone_to_depth(0);
+        assert!(top.children.is_empty() && top.has_more);
+        assert_eq!((top.size, top.children_count), (9_033, Some(3)));
+
+        let shallow = root.clone_to_depth(1);
+        assert!(!shallow.has_more && shallow.children_count.is_none());
+        let media = &shallow.children[0];
+        // 截断处保留递归大小，并告知前端还有 2 个子节点待加载
+        assert!(media.children.is_empty() && media.has_more);
+        assert_eq!((media.size, media.children_count), (9_030, Some(2)));
+        // 空目录与文件没有可加载的内容
+        assert!(!shallow.children[1].has_more && !shallow.children[2].has_more);
+
+        let deep = root.clone_to_depth(2);
+        let subs = &deep.children[0].children[1];
+        assert_eq!((subs.size, subs.children_count), (30, Some(1)));
+        assert!(!deep.children[0].children[0].has_more);
+        assert!(root.clone_to_depth(3).children[0].children[1].children[0]
+            .children
+            .is_empty());
+        let json = serde_json::to_string(&deep.children[1]).unwrap();
+        assert!(!json.contains("has_more") && !json.contains("children_count"));
+    }
+
+    #[test]
+    fn test_sort_children_by_size_desc_at_every_level() {
+        let mut root = node(
+            "/vol",
+            0,
+            vec![
+                node("/vol/b.txt", 50, vec![]),
+                node(
+                    "/vol/media",
+                    0,
+                    vec![
+                        node("/vol/media/a.srt", 30, vec![]),
+                        node(
+                            "/vol/media/clips",
+                            0,
+                            vec![
+                                node("/vol/media/clips/x.mp4", 10, vec![]),
+                                node("/vol/media/clips/y.mp4", 700, vec![]),
+                            ],
+                        ),
+                        node("/vol/media/movie.mkv", 9_000, vec![]),
+                    ],
+                ),
+                node("/vol/a.txt", 50, vec![]),
+            ],
+        );
+
+        root.sort_children_by_size_desc();
+
+        fn check(node: &FileNode) {
+            assert!(node
+                .children
+                .windows(2)
+                .all(|w| FileNode::cmp_size_desc(&w[0], &w[1]).is_lt()));
+            node.children.iter().for_each(check);
+        }
+        check(&root);
+        let names =
+            |n: &FileNode| -> Vec<String> { n.children.iter().map(|c| c.name.clone()).collect() };
+        // 同大小按名称排列
+        assert_eq!(names(&root), ["media", "a.txt", "b.txt"]);
+        assert_eq!(names(&root.children[0]), ["movie.mkv", "clips", "a.srt"]);
+        assert_eq!(names(&root.children[0].children[1]), ["y.mp4", "x.mp4"]);
+    }
 }
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+use crate::TopFileEntry;
+
+/// 按所在目录分组的文件（如按扩展名查询整卷的 RAW/视频文件），组内按大小降序
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderGroup {
+    pub folder: String,
+    pub total_size: u64,
+    pub files: Vec<TopFileEntry>,
+}
@@ -1,13 +1,25 @@
 pub mod action;
 pub mod cleanup_plan;
+pub mod diff;
+pub mod export;
 pub mod file_tree;
+pub mod folder_group;
+pub mod path_index;
+pub mod plan_export;
+pub mod progress_event;
 pub mod risk;
 pub mod scan_result;
+pub mod sqlite_export;
 pub mod top_file_entry;
 
 pub use action::*;
 pub use cleanup_plan::*;
+pub use diff::*;
 pub use file_tree::*;
+pub use folder_group::*;
+pub use path_index::*;
+pub use plan_export::*;
+pub use progress_event::*;
 pub use risk::*;
 pub use scan_result::*;
 pub use top_file_entry::*;
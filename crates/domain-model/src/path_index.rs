@@ -0,0 +1,207 @@
+//! 路径搜索索引：把文件树压平成按路径排序的列表，所有路径存放在一整块字符串中、各项只记偏移，
+//! 支持前缀（二分查找）与子串查询。前端输入即搜时只返回命中项，无需传输整棵树。
+
+use std::ops::Range;
+
+use ai_disk_common::path::CaseSensitivity;
+
+use crate::{FileNode, TopFileEntry};
+
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    /// 原始路径在 `paths` 中的位置
+    path: Range<usize>,
+    /// 比较用路径（不区分大小写时为小写）在 `folded` 中的位置
+    key: Range<usize>,
+    size: u64,
+    modified: Option<u64>,
+}
+
+/// 文件树的路径索引，见 `ScanResult::search`
+#[derive(Debug, Clone)]
+pub struct PathIndex {
+    paths: String,
+    /// 区分大小写时为空，比较键直接取自 `paths`
+    folded: String,
+    /// 按比较键升序
+    entries: Vec<IndexEntry>,
+    case: CaseSensitivity,
+}
+
+impl PathIndex {
+    /// 收录树中所有节点（含目录，不含汇总节点）
+    pub fn build(root: &FileNode, case: CaseSensitivity) -> Self {
+        let mut index = PathIndex {
+            paths: String::new(),
+            folded: String::new(),
+            entries: Vec::new(),
+            case,
+        };
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            stack.extend(&node.children);
+            if node.is_aggregate() {
+                continue;
+            }
+            let start = index.paths.len();
+            index.paths.push_str(&node.path);
+            let path = start..index.paths.len();
+            let key = match case {
+                CaseSensitivity::Sensitive => path.clone(),
+                CaseSensitivity::Insensitive => {
+                    let start = index.folded.len();
+                    index.folded.push_str(&node.path.to_lowercase());
+                    start..index.folded.len()
+                }
+            };
+            index.entries.push(IndexEntry {
+                path,
+                key,
+                size: node.size,
+                modified: node.modified,
+            });
+        }
+        let mut entries = std::mem::take(&mut index.entries);
+        let keys = index.keys();
+        entries.sort_by(|a, b| keys[a.key.clone()].cmp(&keys[b.key.clone()]));
+        index.entries = entries;
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 查找路径以 `query` 开头或包含 `query` 的节点：前缀命中排在子串命中之前，
+    /// 同类中按大小降序、再按路径升序，最多 `limit` 项。空查询不返回结果
+    pub fn search(&self, query: &str, limit: usize) -> Vec<TopFileEntry> {
+        if query.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+        let query = match self.case {
+            CaseSensitivity::Sensitive => query.to_string(),
+            CaseSensitivity::Insensitive => query.to_lowercase(),
+        };
+        let keys = self.keys();
+        let key = |e: &IndexEntry| &keys[e.key.clone()];
+
+        // 前缀命中在排序后的列表中是连续的一段
+        let start = self.entries.partition_point(|e| key(e) < query.as_str());
+        let end = start
+            + self.entries[start..]
+                .iter()
+                .take_while(|e| key(e).starts_with(&query))
+                .count();
+        let mut prefix: Vec<&IndexEntry> = self.entries[start..end].iter().collect();
+        let mut substring: Vec<&IndexEntry> = self.entries[..start]
+            .iter()
+            .chain(&self.entries[end..])
+            .filter(|e| key(e).contains(&query))
+            .collect();
+
+        let by_size =
+            |a: &&IndexEntry, b: &&IndexEntry| b.size.cmp(&a.size).then_with(|| key(a).cmp(key(b)));
+        prefix.sort_by(by_size);
+        substring.sort_by(by_size);
+        prefix
+            .into_iter()
+            .chain(substring)
+            .take(limit)
+            .map(|e| TopFileEntry {
+                path: self.paths[e.path.clone()].to_string(),
+                size: e.size,
+                modified: e.modified,
+            })
+            .collect()
+    }
+
+    fn keys(&self) -> &str {
+        match self.case {
+            CaseSensitivity::Sensitive => &self.paths,
+            CaseSensitivity::Insensitive => &self.folded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size,
+            is_dir: !children.is_empty(),
+            children,
+            ..Default::default()
+        }
+    }
+
+    fn paths(results: &[TopFileEntry]) -> Vec<&str> {
+        results.iter().map(|r| r.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_prefix_substring_and_case_insensitive_search() {
+        let root = node(
+            r"C:\Users",
+            0,
+            vec![
+                node(
+                    r"C:\Users\Ann",
+                    0,
+                    vec![
+                        node(r"C:\Users\Ann\Videos.lnk", 1, vec![]),
+                        node(
+                            r"C:\Users\Ann\Videos",
+                            0,
+                            vec![
+                                node(r"C:\Users\Ann\Videos\trip.MP4", 900, vec![]),
+                                node(r"C:\Users\Ann\Videos\cat.mp4", 300, vec![]),
+                            ],
+                        ),
+                    ],
+                ),
+                node(r"C:\Users\Bob\clip.mp4", 500, vec![]),
+            ],
+        );
+
+        let sensitive = PathIndex::build(&root, CaseSensitivity::Sensitive);
+        assert_eq!(sensitive.len(), 7);
+        // 前缀：命中的整段按大小降序
+        assert_eq!(
+            paths(&sensitive.search(r"C:\Users\Ann\Videos", 10)),
+            [
+                r"C:\Users\Ann\Videos\trip.MP4",
+                r"C:\Users\Ann\Videos\cat.mp4",
+                r"C:\Users\Ann\Videos.lnk",
+                r"C:\Users\Ann\Videos",
+            ]
+        );
+        // 子串，区分大小写时 trip.MP4 不命中
+        assert_eq!(
+            paths(&sensitive.search(".mp4", 10)),
+            [r"C:\Users\Bob\clip.mp4", r"C:\Users\Ann\Videos\cat.mp4"]
+        );
+
+        let insensitive = PathIndex::build(&root, CaseSensitivity::Insensitive);
+        assert_eq!(
+            paths(&insensitive.search(".MP4", 2)),
+            [r"C:\Users\Ann\Videos\trip.MP4", r"C:\Users\Bob\clip.mp4"]
+        );
+        // 不区分大小写的前缀
+        let bob = insensitive.search(r"c:\USERS\bob", 10);
+        assert_eq!(paths(&bob), [r"C:\Users\Bob\clip.mp4"]);
+        assert_eq!(bob[0].size, 500);
+        assert_eq!(
+            paths(&insensitive.search("c", 2)),
+            [r"C:\Users\Ann\Videos\trip.MP4", r"C:\Users\Bob\clip.mp4"]
+        );
+        assert!(insensitive.search("", 10).is_empty());
+    }
+}
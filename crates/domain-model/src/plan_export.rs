@@ -0,0 +1,159 @@
+//! 清理计划的导出/导入格式：在一台机器上生成并审阅计划，稍后或在另一台相似的机器上执行。
+//! 导出的 JSON 带格式版本与来源扫描信息；导入时检查各动作的路径是否仍存在，
+//! 已不存在的动作被标记出来，由调用方剔除或重新确认，而不是直接执行。
+
+use std::path::Path;
+
+use ai_disk_common::DiskAnalyzerError;
+use serde::{Deserialize, Serialize};
+
+use crate::{Action, CleanupPlan, ScanMeta};
+
+/// 当前导出格式版本，格式不兼容时递增
+pub const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// 导出文件的外层结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanEnvelope {
+    pub version: u32,
+    /// 生成计划所依据的扫描
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan: Option<ScanMeta>,
+    pub plan: CleanupPlan,
+}
+
+/// 导入的计划及校验结果
+#[derive(Debug, Clone)]
+pub struct ImportedPlan {
+    pub plan: CleanupPlan,
+    pub scan: Option<ScanMeta>,
+    /// 路径已不存在的动作（Move 为源路径），按计划中的顺序
+    pub stale: Vec<String>,
+}
+
+impl ImportedPlan {
+    pub fn is_stale(&self) -> bool {
+        !self.stale.is_empty()
+    }
+
+    /// 去掉失效动作后的计划，`estimated_space` 随之扣减
+    pub fn without_stale(&self) -> CleanupPlan {
+        let mut plan = CleanupPlan::default();
+        for action in &self.plan.actions {
+            let path = action.target_path();
+            if self.stale.iter().any(|s| s == path) {
+                continue;
+            }
+            if let Some(&size) = self.plan.sizes.get(path) {
+                plan.estimated_space += size;
+                plan.sizes.insert(path.to_string(), size);
+            }
+            plan.actions.push(action.clone());
+        }
+        plan
+    }
+}
+
+impl CleanupPlan {
+    /// 导出为带版本与来源扫描信息的 JSON
+    pub fn to_json(&self, scan: Option<&ScanMeta>) -> Result<String, DiskAnalyzerError> {
+        let envelope = PlanEnvelope {
+            version: PLAN_FORMAT_VERSION,
+            scan: scan.cloned(),
+            plan: self.clone(),
+        };
+        serde_json::to_string_pretty(&envelope)
+            .map_err(|e| DiskAnalyzerError::Config(format!("导出计划失败: {}", e)))
+    }
+
+    /// 导入 `to_json` 导出的计划，并检查各动作的路径在本机上是否仍存在
+    pub fn from_json(json: &str) -> Result<ImportedPlan, DiskAnalyzerError> {
+        let envelope: PlanEnvelope = serde_json::from_str(json)
+            .map_err(|e| DiskAnalyzerError::Config(format!("计划文件格式错误: {}", e)))?;
+        if envelope.version > PLAN_FORMAT_VERSION {
+            return Err(DiskAnalyzerError::Config(format!(
+                "计划文件版本 {} 高于支持的版本 {}，请升级应用",
+                envelope.version, PLAN_FORMAT_VERSION
+            )));
+        }
+        let stale = envelope
+            .plan
+            .actions
+            .iter()
+            // 保留标记不改动文件，路径不存在也无妨
+            .filter(|a| !matches!(a, Action::MarkKeep { .. }))
+            .map(Action::target_path)
+            .filter(|p| !Path::new(p).exists())
+            .map(str::to_string)
+            .collect();
+        Ok(ImportedPlan {
+            plan: envelope.plan,
+            scan: envelope.scan,
+            stale,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScanStrategy;
+
+    #[test]
+    fn test_round_trip_and_flag_missing_paths() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let present = dir.join("Cargo.toml").to_string_lossy().to_string();
+        let missing = dir.join("gone.iso").to_string_lossy().to_string();
+        let mut plan = CleanupPlan::default();
+        for (action, size) in [
+            (
+                Action::Delete {
+                    path: present.clone(),
+                },
+                100,
+            ),
+            (
+                Action::Trash {
+                    path: missing.clone(),
+                },
+                700,
+            ),
+            (
+                Action::MarkKeep {
+                    path: dir.join("kept").to_string_lossy().to_string(),
+                },
+                0,
+            ),
+        ] {
+            plan.estimated_space += size;
+            plan.sizes.insert(action.target_path().to_string(), size);
+            plan.actions.push(action);
+        }
+        let meta = ScanMeta {
+            strategy: ScanStrategy::Walk,
+            shallow_dirs: true,
+            filters_summary: String::new(),
+            root: dir.to_string_lossy().to_string(),
+            timestamp: 1_700_000_000,
+        };
+
+        let json = plan.to_json(Some(&meta)).unwrap();
+        let imported = CleanupPlan::from_json(&json).unwrap();
+        assert_eq!(imported.scan, Some(meta));
+        assert_eq!(imported.plan.actions.len(), 3);
+        assert_eq!(imported.plan.estimated_space, 800);
+        // 失效动作被标记出来，而不是混在计划里等待执行
+        assert!(imported.is_stale());
+        assert_eq!(imported.stale, [missing]);
+        let runnable = imported.without_stale();
+        assert_eq!(runnable.actions.len(), 2);
+        assert_eq!(runnable.actions[0].target_path(), present);
+        assert_eq!(runnable.estimated_space, 100);
+
+        let newer = json.replacen("\"version\": 1", "\"version\": 99", 1);
+        assert!(matches!(
+            CleanupPlan::from_json(&newer),
+            Err(DiskAnalyzerError::Config(_))
+        ));
+    }
+}
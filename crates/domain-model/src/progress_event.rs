@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{FileNode, ScanResult, ScanStrategy};
+
+/// 扫描事件的结构版本，字段有不兼容变化时递增
+pub const PROGRESS_EVENT_VERSION: u32 = 1;
+
+/// 事件名：扫描进度（`ScanProgress`）
+pub const SCAN_PROGRESS_EVENT: &str = "scan://progress";
+/// 事件名：扫描阶段变化（`PhaseChange`）
+pub const SCAN_PHASE_EVENT: &str = "scan://phase";
+/// 事件名：扫描完成（`ScanDone`）
+pub const SCAN_DONE_EVENT: &str = "scan://done";
+/// 事件名：一个第一层子目录扫描完成（`ScanSubtree`）
+pub const SCAN_SUBTREE_EVENT: &str = "scan://subtree";
+
+/// 扫描进度
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanProgress {
+    pub version: u32,
+    /// 已处理的文件/目录数
+    pub scanned: u64,
+    /// 最近处理的路径
+    pub current_path: String,
+    /// 估算的完成百分比（开启 `estimate_progress` 时）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u8>,
+    /// 事件顺序编号（严格递增），前端据此丢弃晚到的旧事件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+}
+
+impl ScanProgress {
+    pub fn new(scanned: u64, current_path: impl Into<String>) -> Self {
+        Self {
+            version: PROGRESS_EVENT_VERSION,
+            scanned,
+            current_path: current_path.into(),
+            percent: None,
+            seq: None,
+        }
+    }
+
+    pub fn with_percent(mut self, percent: u8) -> Self {
+        self.percent = Some(percent);
+        self
+    }
+
+    pub fn with_seq(mut self, seq: u64) -> Self {
+        self.seq = Some(seq);
+        self
+    }
+}
+
+/// 扫描阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanPhase {
+    /// 读取 MFT
+    Mft,
+    /// 普通目录遍历
+    Walk,
+}
+
+/// 扫描阶段变化
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseChange {
+    pub version: u32,
+    pub phase: ScanPhase,
+    pub path: String,
+}
+
+impl PhaseChange {
+    pub fn new(phase: ScanPhase, path: impl Into<String>) -> Self {
+        Self {
+            version: PROGRESS_EVENT_VERSION,
+            phase,
+            path: path.into(),
+        }
+    }
+}
+
+/// 第一层子目录扫描完成，前端可先渲染这部分 Treemap；最终结果仍由命令返回值携带
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSubtree {
+    pub version: u32,
+    pub node: FileNode,
+}
+
+impl ScanSubtree {
+    pub fn new(node: FileNode) -> Self {
+        Self {
+            version: PROGRESS_EVENT_VERSION,
+            node,
+        }
+    }
+}
+
+/// 扫描完成摘要（完整结果由命令返回值携带）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanDone {
+    pub version: u32,
+    pub path: String,
+    /// 实际使用的扫描策略
+    pub strategy: ScanStrategy,
+    pub file_count: u64,
+    pub total_size: u64,
+    pub scan_time_ms: u64,
+    /// MFT 失败回退到普通扫描时的原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    /// 以管理员身份重新扫描可能看到更多内容（由调用方按 `needs_elevation_for` 设置）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suggest_elevation: bool,
+}
+
+impl ScanDone {
+    pub fn new(path: impl Into<String>, result: &ScanResult, used_mft: bool) -> Self {
+        Self {
+            version: PROGRESS_EVENT_VERSION,
+            path: path.into(),
+            strategy: if used_mft {
+                ScanStrategy::Mft
+            } else {
+                ScanStrategy::Walk
+            },
+            file_count: result.file_count,
+            total_size: result.total_size,
+            scan_time_ms: result.scan_time_ms,
+            warning: result.scan_warning.clone(),
+            suggest_elevation: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_events_serialize_to_frontend_shape() {
+        let progress = ScanProgress::new(42, "/home/u/a");
+        assert_eq!(
+            serde_json::to_value(&progress).unwrap(),
+            json!({ "version": 1, "scanned": 42, "current_path": "/home/u/a" })
+        );
+        assert_eq!(
+            serde_json::to_value(progress.with_percent(30)).unwrap()["percent"],
+            json!(30)
+        );
+
+        assert_eq!(
+            serde_json::to_value(PhaseChange::new(ScanPhase::Mft, r"C:\")).unwrap(),
+            json!({ "version": 1, "phase": "mft", "path": r"C:\" })
+        );
+
+        let result = ScanResult {
+            root: FileNode::default(),
+            scan_time_ms: 1500,
+            file_count: 10,
+            total_size: 2048,
+            scan_warning: Some("MFT unavailable".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(ScanDone::new("/data", &result, false)).unwrap(),
+            json!({
+                "version": 1,
+                "path": "/data",
+                "strategy": "walk",
+                "file_count": 10,
+                "total_size": 2048,
+                "scan_time_ms": 1500,
+                "warning": "MFT unavailable"
+            })
+        );
+    }
+}
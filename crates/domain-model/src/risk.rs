@@ -1,9 +1,590 @@
 use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// 风险评估等级
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+use crate::FileNode;
+
+/// 风险评估等级（按 Low < Medium < High 排序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum RiskLevel {
     Low,
     Medium,
     High,
 }
+
+/// 风险评估结果及其依据，供 UI 提示与规则审计使用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskExplanation {
+    pub level: RiskLevel,
+    /// 稳定的因素代码，如 `system_dir`、`recently_modified`、`protected_pattern:*.kdbx`
+    pub factors: Vec<String>,
+}
+
+impl RiskExplanation {
+    /// 是否为受保护路径（系统目录或受保护文件），任何清理计划都不应包含
+    pub fn is_protected(&self) -> bool {
+        self.factors.iter().any(|f| {
+            f == FACTOR_SYSTEM_DIR
+                || f == FACTOR_SYSTEM_MANAGED
+                || f.strip_prefix(FACTOR_PROTECTED_PATTERN)
+                    .is_some_and(|rest| rest.starts_with(':'))
+        })
+    }
+}
+
+/// 因素代码：位于系统目录下
+pub const FACTOR_SYSTEM_DIR: &str = "system_dir";
+/// 因素代码：由操作系统管理的文件（页面文件、休眠文件、交换文件），只能在系统设置中调整
+pub const FACTOR_SYSTEM_MANAGED: &str = "system_managed";
+/// 因素代码：匹配受保护文件模式（完整代码为 `protected_pattern:<模式>`）
+pub const FACTOR_PROTECTED_PATTERN: &str = "protected_pattern";
+/// 因素代码：最近修改过（见 `RiskAging::recent_days`）
+pub const FACTOR_RECENTLY_MODIFIED: &str = "recently_modified";
+/// 因素代码：刚创建或修改的大文件（如当天下载的安装包），在时间窗口前半段按高风险保护
+pub const FACTOR_RECENT_LARGE_FILE: &str = "recent_large_file";
+/// 因素代码：位于用户文档类目录（文档、桌面、图片）
+pub const FACTOR_USER_DOCUMENTS: &str = "user_documents";
+/// 因素代码：目录（删除会波及其全部内容）
+pub const FACTOR_DIRECTORY: &str = "directory";
+/// 因素代码：位于临时/缓存目录，或临时文件、崩溃转储、未完成的下载
+pub const FACTOR_TEMP_OR_CACHE: &str = "temp_or_cache";
+/// 因素代码：文档文件（如 `.docx`、`.pdf`）
+pub const FACTOR_DOCUMENT_FILE: &str = "document_file";
+/// 因素代码：构建产物或依赖包（如 `node_modules`、`target`、`.o`），可重新生成
+pub const FACTOR_BUILD_ARTIFACT: &str = "build_artifact";
+
+/// 系统目录前缀（小写、`/` 分隔、已去掉盘符）
+const SYSTEM_DIRS: &[&str] = &[
+    "/windows",
+    "/program files",
+    "/program files (x86)",
+    "/programdata",
+    "/system volume information",
+    "/system",
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/etc",
+    "/var",
+    "/library",
+    // macOS 上 /etc、/var 经 canonicalize 后位于 /private 下
+    "/private",
+];
+
+/// 系统目录下可以清理的临时目录，不算作系统目录（`/var/folders` 为 macOS 的用户临时目录）
+const SYSTEM_DIR_EXCEPTIONS: &[&str] = &[
+    "/windows/temp",
+    "/var/tmp",
+    "/var/folders",
+    "/private/tmp",
+    "/private/var/tmp",
+    "/private/var/folders",
+];
+
+/// 受保护文件模式：`*.ext` 按扩展名匹配，否则按文件名精确匹配（均不区分大小写）
+const PROTECTED_PATTERNS: &[&str] = &[
+    "*.kdbx",
+    "*.pem",
+    "*.key",
+    "*.pfx",
+    "*.ovpn",
+    "id_rsa",
+    "id_ed25519",
+    "wallet.dat",
+];
+
+/// 由系统管理、位于卷根目录的文件名（不区分大小写）
+const SYSTEM_MANAGED_FILES: &[&str] = &[
+    "pagefile.sys",
+    "hiberfil.sys",
+    "swapfile.sys",
+    "swapfile",
+    "swap.img",
+];
+
+const USER_DOCUMENT_DIRS: &[&str] = &["documents", "desktop", "pictures"];
+
+const TEMP_DIRS: &[&str] = &["temp", "tmp", "cache", "caches", ".cache"];
+
+/// 临时文件扩展名（不含点，只匹配文件）
+const TEMP_EXTENSIONS: &[&str] = &["tmp", "temp", "dmp", "crdownload"];
+
+/// 文档文件扩展名
+const DOCUMENT_EXTENSIONS: &[&str] = &["docx", "doc", "xlsx", "pptx", "pdf"];
+
+/// 构建产物与依赖包目录
+const BUILD_ARTIFACT_DIRS: &[&str] = &[
+    "node_modules",
+    "__pycache__",
+    ".gradle",
+    ".next",
+    "target",
+    "build",
+    "dist",
+    "obj",
+];
+
+/// 编译中间文件扩展名
+const BUILD_ARTIFACT_EXTENSIONS: &[&str] = &["o", "obj", "pyc"];
+
+/// 各因素代码对应的说明（按 `explain` 中因素的顺序取第一个作为条目的说明）
+const FACTOR_REASONS: &[(&str, &str)] = &[
+    (FACTOR_SYSTEM_DIR, "系统目录下的文件"),
+    (
+        FACTOR_SYSTEM_MANAGED,
+        "由系统管理的文件（虚拟内存、休眠或交换文件）",
+    ),
+    (FACTOR_PROTECTED_PATTERN, "凭据、密钥或钱包等受保护文件"),
+    (FACTOR_RECENT_LARGE_FILE, "刚下载或修改的大文件"),
+    (FACTOR_DOCUMENT_FILE, "文档文件"),
+    (FACTOR_RECENTLY_MODIFIED, "最近修改过"),
+    (FACTOR_USER_DOCUMENTS, "位于用户文档、桌面或图片目录"),
+    (FACTOR_DIRECTORY, "目录，删除会波及其全部内容"),
+    (FACTOR_BUILD_ARTIFACT, "构建产物或依赖包，可重新生成"),
+    (FACTOR_TEMP_OR_CACHE, "临时或缓存文件"),
+];
+
+/// 没有任何风险因素时的说明
+pub const NO_RISK_FACTOR_REASON: &str = "未发现风险因素";
+
+/// 风险的时间衰减：窗口内修改过的文件风险上调，随时间线性衰减，窗口结束后回到基线
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskAging {
+    /// 「最近修改」的时间窗口（天）
+    pub recent_days: u64,
+    /// 大文件阈值（字节）：达到阈值且衰减权重不低于一半时升为高风险
+    pub large_file_bytes: u64,
+}
+
+impl Default for RiskAging {
+    fn default() -> Self {
+        Self {
+            recent_days: 7,
+            large_file_bytes: 1 << 30,
+        }
+    }
+}
+
+impl RiskAging {
+    /// 修改时间的衰减权重：刚修改为 1.0，线性降到窗口结束时的 0.0；未来时间按刚修改处理
+    pub fn recency_weight(&self, modified: u64, now: u64) -> f64 {
+        let window = self.recent_days.saturating_mul(24 * 3600);
+        if window == 0 {
+            return 0.0;
+        }
+        let age = now.saturating_sub(modified);
+        1.0 - age.min(window) as f64 / window as f64
+    }
+}
+
+/// 小写、统一为 `/` 分隔并去掉 Windows 盘符，如 `C:\Windows\x` -> `/windows/x`
+fn normalize(path: &str) -> String {
+    let p = path.replace('\\', "/").to_lowercase();
+    match p.as_bytes() {
+        [d, b':', ..] if d.is_ascii_alphabetic() => p[2..].to_string(),
+        _ => p,
+    }
+}
+
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    match pattern.strip_prefix('*') {
+        Some(suffix) => name.to_lowercase().ends_with(suffix),
+        None => name.eq_ignore_ascii_case(pattern),
+    }
+}
+
+/// 规范化路径 `path` 是否为 `dir` 或位于其下
+fn is_under_dir(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// 文件扩展名（小写、不含点）；隐藏文件（如 `.pdf`）没有扩展名
+fn extension(name: &str) -> Option<String> {
+    name.rsplit_once('.')
+        .filter(|(stem, _)| !stem.is_empty())
+        .map(|(_, ext)| ext.to_lowercase())
+}
+
+/// 路径是否位于系统目录下（`C:\Windows\Temp` 等可清理的临时目录除外）
+pub fn is_system_path(path: &str) -> bool {
+    let path = normalize(path);
+    SYSTEM_DIRS.iter().any(|dir| is_under_dir(&path, dir))
+        && !SYSTEM_DIR_EXCEPTIONS
+            .iter()
+            .any(|dir| is_under_dir(&path, dir))
+}
+
+/// 是否为卷根目录下由系统管理的文件，如 `C:\pagefile.sys`、`/swapfile`
+pub fn is_system_managed_file(path: &str) -> bool {
+    let path = normalize(path);
+    match path.strip_prefix('/') {
+        Some(name) => SYSTEM_MANAGED_FILES.contains(&name),
+        None => false,
+    }
+}
+
+/// 由系统管理的文件的调整方式提示
+pub fn system_managed_tip(path: &str) -> Option<&'static str> {
+    if !is_system_managed_file(path) {
+        return None;
+    }
+    let tip = match ai_disk_common::path::file_name(path)
+        .to_lowercase()
+        .as_str()
+    {
+        "hiberfil.sys" => "休眠文件，由系统管理：可在电源设置中关闭休眠（powercfg /hibernate off）",
+        "pagefile.sys" | "swapfile.sys" => {
+            "虚拟内存文件，由系统管理：可在「系统设置 > 高级系统设置 > 性能 > 虚拟内存」中调整"
+        }
+        _ => "交换文件，由系统管理：可用 swapoff 停用后在 /etc/fstab 中调整",
+    };
+    Some(tip)
+}
+
+/// 路径是否位于用户文档类目录（文档、桌面、图片）下
+pub fn is_user_document_path(path: &str) -> bool {
+    normalize(path)
+        .split('/')
+        .any(|s| USER_DOCUMENT_DIRS.contains(&s))
+}
+
+/// 评估删除该节点的风险等级
+pub fn assess(node: &FileNode) -> RiskLevel {
+    explain(node).level
+}
+
+/// 评估风险等级并列出促成该等级的因素
+pub fn explain(node: &FileNode) -> RiskExplanation {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    explain_at(node, now)
+}
+
+/// 同 `explain`，以给定的当前时间（Unix 秒）判断是否最近修改
+pub fn explain_at(node: &FileNode, now: u64) -> RiskExplanation {
+    explain_with(node, now, &RiskAging::default())
+}
+
+/// 同 `explain_at`，使用自定义的时间衰减配置
+pub fn explain_with(node: &FileNode, now: u64, aging: &RiskAging) -> RiskExplanation {
+    let path = normalize(&node.path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let mut high = Vec::new();
+    let mut medium = Vec::new();
+    let mut low = Vec::new();
+
+    if is_system_path(&node.path) {
+        high.push(FACTOR_SYSTEM_DIR.to_string());
+    }
+    if node.is_system_managed() {
+        high.push(FACTOR_SYSTEM_MANAGED.to_string());
+    }
+    if let Some(pattern) = PROTECTED_PATTERNS
+        .iter()
+        .find(|p| matches_pattern(&node.name, p))
+    {
+        high.push(format!("{}:{}", FACTOR_PROTECTED_PATTERN, pattern));
+    }
+    let recency = node.modified.map_or(0.0, |m| aging.recency_weight(m, now));
+    if recency >= 0.5 && !node.is_dir && node.size >= aging.large_file_bytes {
+        high.push(FACTOR_RECENT_LARGE_FILE.to_string());
+    }
+    let ext = (!node.is_dir).then(|| extension(&node.name)).flatten();
+    let has_ext = |exts: &[&str]| ext.as_deref().is_some_and(|e| exts.contains(&e));
+    if has_ext(DOCUMENT_EXTENSIONS) {
+        high.push(FACTOR_DOCUMENT_FILE.to_string());
+    }
+    if recency > 0.0 {
+        medium.push(FACTOR_RECENTLY_MODIFIED.to_string());
+    }
+    if is_user_document_path(&node.path) {
+        medium.push(FACTOR_USER_DOCUMENTS.to_string());
+    }
+    if node.is_dir {
+        medium.push(FACTOR_DIRECTORY.to_string());
+    }
+    if segments.iter().any(|s| BUILD_ARTIFACT_DIRS.contains(s))
+        || has_ext(BUILD_ARTIFACT_EXTENSIONS)
+    {
+        low.push(FACTOR_BUILD_ARTIFACT.to_string());
+    }
+    if segments.iter().any(|s| TEMP_DIRS.contains(s)) || has_ext(TEMP_EXTENSIONS) {
+        low.push(FACTOR_TEMP_OR_CACHE.to_string());
+    }
+
+    let level = if !high.is_empty() {
+        RiskLevel::High
+    } else if !medium.is_empty() {
+        RiskLevel::Medium
+    } else {
+        RiskLevel::Low
+    };
+    high.extend(medium);
+    high.extend(low);
+    RiskExplanation {
+        level,
+        factors: high,
+    }
+}
+
+/// 报告中的一项：文件（或未展开的目录）及其风险
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskEntry {
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+    pub level: RiskLevel,
+    pub reason: String,
+}
+
+/// `risk_report` 的结果：`level` 为各项中最高的风险
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskReport {
+    pub level: RiskLevel,
+    pub entries: Vec<RiskEntry>,
+}
+
+impl RiskExplanation {
+    /// 展示给用户的说明：取最主要（排在最前）的因素
+    pub fn reason(&self) -> &'static str {
+        self.factors.first().map_or(NO_RISK_FACTOR_REASON, |f| {
+            let code = f.split(':').next().unwrap_or(f);
+            FACTOR_REASONS
+                .iter()
+                .find(|(c, _)| *c == code)
+                .map_or(NO_RISK_FACTOR_REASON, |(_, reason)| reason)
+        })
+    }
+}
+
+/// 删除该节点是否安全：节点为目录时取其下最高的风险，见 `risk_report`
+pub fn assess_risk(node: &FileNode) -> RiskLevel {
+    risk_report(node).level
+}
+
+/// 逐个文件用 `explain` 评估并附上说明：目录展开到文件，没有子节点的目录（如未展开的 shallow 目录）按自身路径计一项；
+/// 汇总节点没有真实路径，不计入
+pub fn risk_report(node: &FileNode) -> RiskReport {
+    let mut entries = Vec::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        if node.is_aggregate() {
+            continue;
+        }
+        if node.is_dir && !node.children.is_empty() {
+            stack.extend(node.children.iter().rev());
+            continue;
+        }
+        let risk = explain(node);
+        entries.push(RiskEntry {
+            path: node.path.clone(),
+            size: node.size,
+            is_dir: node.is_dir,
+            level: risk.level,
+            reason: risk.reason().to_string(),
+        });
+    }
+    RiskReport {
+        level: entries
+            .iter()
+            .map(|e| e.level)
+            .max()
+            .unwrap_or(RiskLevel::Low),
+        entries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn file(path: &str, modified: Option<u64>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size: 1024,
+            is_dir: false,
+            modified,
+            children: vec![],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_explain_factor_codes() {
+        let sys = explain_at(&file(r"C:\Windows\System32\drivers\x.sys", None), NOW);
+        assert_eq!(sys.level, RiskLevel::High);
+        assert_eq!(sys.factors, vec!["system_dir"]);
+
+        let vault = explain_at(
+            &file("/home/u/Documents/passwords.KDBX", Some(NOW - 86_400)),
+            NOW,
+        );
+        assert_eq!(vault.level, RiskLevel::High);
+        assert_eq!(
+            vault.factors,
+            vec![
+                "protected_pattern:*.kdbx",
+                "recently_modified",
+                "user_documents"
+            ]
+        );
+
+        let cache = explain_at(
+            &file(
+                r"C:\Users\u\AppData\Local\Temp\setup.log",
+                Some(NOW - 90 * 86_400),
+            ),
+            NOW,
+        );
+        assert_eq!(cache.level, RiskLevel::Low);
+        assert_eq!(cache.factors, vec!["temp_or_cache"]);
+        assert_eq!(assess(&file("/var/tmp/old.bin", Some(0))), RiskLevel::Low);
+    }
+
+    #[test]
+    fn test_system_managed_files_at_volume_root() {
+        let pagefile = file(r"C:\pagefile.sys", Some(NOW));
+        let risk = explain_at(&pagefile, NOW);
+        assert!(risk.is_protected());
+        assert_eq!(risk.factors, vec!["system_managed", "recently_modified"]);
+        assert!(system_managed_tip(r"D:\HIBERFIL.SYS")
+            .unwrap()
+            .contains("powercfg"));
+        assert!(is_system_managed_file("/swapfile"));
+        // 只识别卷根目录下的文件
+        assert!(!is_system_managed_file(r"C:\backup\pagefile.sys"));
+        assert!(system_managed_tip("/home/u/swapfile").is_none());
+    }
+
+    #[test]
+    fn test_system_dir_requires_segment_boundary() {
+        let node = file(r"D:\Windowsbackup\a.zip", None);
+        assert!(explain_at(&node, NOW).factors.is_empty());
+        let node = file("/usr", None);
+        assert_eq!(explain_at(&node, NOW).factors, vec!["system_dir"]);
+    }
+
+    #[test]
+    fn test_recent_large_file_risk_decays_with_age() {
+        let aging = RiskAging::default();
+        let installer = |modified| FileNode {
+            size: 20 << 30,
+            ..file(r"D:\Downloads\game-setup.exe", Some(modified))
+        };
+        let today = explain_with(&installer(NOW - 3_600), NOW, &aging);
+        let year_ago = explain_with(&installer(NOW - 365 * 86_400), NOW, &aging);
+        assert!(today.level > year_ago.level);
+        assert_eq!(today.level, RiskLevel::High);
+        assert_eq!(
+            today.factors,
+            vec!["recent_large_file", "recently_modified"]
+        );
+        assert!(!today.is_protected());
+        assert_eq!(year_ago.level, RiskLevel::Low);
+
+        // 窗口后半段降为中风险，窗口结束后回到基线
+        let five_days = explain_with(&installer(NOW - 5 * 86_400), NOW, &aging);
+        assert_eq!(five_days.factors, vec!["recently_modified"]);
+        let week = explain_with(&installer(NOW - 7 * 86_400), NOW, &aging);
+        assert!(week.factors.is_empty());
+
+        // 窗口可配置；小文件不受大文件规则影响
+        let month = RiskAging {
+            recent_days: 30,
+            ..aging
+        };
+        let level = explain_with(&installer(NOW - 10 * 86_400), NOW, &month).level;
+        assert_eq!(level, RiskLevel::High);
+        let small = explain_with(&file("/home/u/a.exe", Some(NOW)), NOW, &aging);
+        assert_eq!(small.level, RiskLevel::Medium);
+        assert_eq!(aging.recency_weight(NOW + 60, NOW), 1.0);
+        assert_eq!(
+            RiskAging {
+                recent_days: 0,
+                ..aging
+            }
+            .recency_weight(NOW, NOW),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_assess_risk_by_rule_table() {
+        let tmp = file(r"C:\Windows\Temp\foo.tmp", None);
+        assert_eq!(assess_risk(&tmp), RiskLevel::Low);
+        let thesis = file(r"C:\Users\me\Documents\thesis.docx", None);
+        assert_eq!(assess_risk(&thesis), RiskLevel::High);
+        assert!(!is_system_path(r"C:\Windows\Temp"));
+        assert!(is_system_path(r"C:\Windows\TempFiles\x.sys"));
+        assert!(is_system_path("/var/lib/dpkg"));
+        assert!(is_system_path("/private/etc/hosts"));
+        assert!(is_system_path("/private/var/db"));
+        assert!(!is_system_path("/private/tmp/x"));
+        assert!(!is_system_path("/private/var/tmp/x"));
+        assert!(!is_system_path("/private/var/folders/ab/T/x"));
+        assert!(!is_system_path("/variant/x"));
+        let driver = explain(&file(r"C:\Windows\System32\drivers\x.sys", None));
+        assert_eq!(
+            (driver.level, driver.reason()),
+            (RiskLevel::High, "系统目录下的文件")
+        );
+        let notes = explain(&file("/home/me/misc/notes", None));
+        assert_eq!(
+            (notes.level, notes.reason()),
+            (RiskLevel::Low, NO_RISK_FACTOR_REASON)
+        );
+        assert_eq!(
+            explain(&file("/home/me/app/target/debug/main.o", None)).factors,
+            [FACTOR_BUILD_ARTIFACT]
+        );
+        // 文档目录中的构建目录不会被当成可清理的垃圾；扩展名规则不匹配目录与隐藏文件
+        assert_eq!(
+            explain(&file(r"C:\Users\me\Documents\x\build\out.bin", None)).level,
+            RiskLevel::Medium
+        );
+        let pdf_dir = FileNode {
+            is_dir: true,
+            ..file("/srv/data.pdf", None)
+        };
+        assert_eq!(explain(&pdf_dir).factors, [FACTOR_DIRECTORY]);
+        assert!(explain(&file("/srv/.pdf", None)).factors.is_empty());
+
+        // 目录按文件逐项说明，整体取最高风险
+        let project = FileNode {
+            path: "/home/me/proj".to_string(),
+            is_dir: true,
+            children: vec![
+                FileNode {
+                    is_dir: true,
+                    ..file("/home/me/proj/node_modules", None)
+                },
+                file("/home/me/proj/report.pdf", None),
+            ],
+            ..Default::default()
+        };
+        let report = risk_report(&project);
+        assert_eq!(report.level, RiskLevel::High);
+        let reasons: Vec<(&str, RiskLevel, &str)> = report
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.level, e.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (
+                    "/home/me/proj/node_modules",
+                    RiskLevel::Medium,
+                    "目录，删除会波及其全部内容"
+                ),
+                ("/home/me/proj/report.pdf", RiskLevel::High, "文档文件"),
+            ]
+        );
+    }
+}
@@ -1,17 +1,22 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
-use crate::FileNode;
+use ai_disk_common::path::{self, CaseSensitivity};
+
+use crate::PathIndex;
 use crate::TopFileEntry;
+use crate::{is_shallow_dir_name, FileNode};
 
 /// 扫描结果，包含树结构与各项指标
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanResult {
     pub root: FileNode,
     pub scan_time_ms: u64,
+    /// 扫描计入的文件数；只扫描目录模式下为实际文件数，与 `root.file_count` 相同
     pub file_count: u64,
     /// 本次扫描到的文件总大小（非卷容量）
     pub total_size: u64,
-    /// 当 MFT 扫描失败（如 I/O 错误）并回退到普通扫描时，在此标注错误信息，前端可提示「此磁盘的扫描有错误」
+    /// 当 MFT 扫描失败（如 I/O 错误）并回退到普通扫描时，在此标注错误信息，前端可提示「此磁盘的扫描有错误」；
+    /// 普通遍历因权限不足跳过了目录时也在此说明（结果仍包含其余可读取的内容）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub scan_warning: Option<String>,
     /// 卷总容量（字节），由操作系统 API 获取，仅 Windows 卷根扫描时可能为 Some
@@ -23,4 +28,536 @@ pub struct ScanResult {
     /// 按大小排序的前 N 个文件（MFT 扫描时填充），供前端摘要与 AI 分析使用，避免遍历整棵树
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub top_files: Option<Vec<TopFileEntry>>,
+    /// NTFS 系统元文件（$MFT、$LogFile 等）与卷根目录自身占用的字节数（MFT 扫描时填充），
+    /// 不计入 `total_size`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_reserved_bytes: Option<u64>,
+    /// 开启真实大小（硬链接去重）的普通遍历时填充：每个硬链接都计入的朴素总大小，
+    /// `total_size` 为去重后的大小
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub naive_total_size: Option<u64>,
+    /// 普通遍历时填充：因权限不足未能读取的目录数，见 `needs_elevation_for`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denied_dirs: Option<u64>,
+    /// 普通遍历且提供了出错路径记录时填充：之前出错、仍在重试等待期内而直接跳过的路径数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped_paths: Option<u64>,
+    /// 开启 `ScanOptions::collect_denied_paths` 时填充：因权限不足未能读取的目录路径，供排查问题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denied_paths: Option<Vec<String>>,
+    /// 产生该结果的扫描策略与选项
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ScanMeta>,
+    /// 各阶段耗时，用于解释扫描为何较慢
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<ScanTiming>,
+}
+
+impl ScanResult {
+    /// 精简副本，见 `FileNode::compact`；各项汇总指标不变
+    pub fn compact(&self, min_file_size: u64) -> ScanResult {
+        ScanResult {
+            root: self.root.compact(min_file_size),
+            top_files: self.top_files.clone(),
+            scan_warning: self.scan_warning.clone(),
+            denied_paths: self.denied_paths.clone(),
+            meta: self.meta.clone(),
+            timing: self.timing.clone(),
+            ..*self
+        }
+    }
+
+    /// 序列化精简后的结果（发往前端或保存报告用），内存中的完整树不受影响
+    pub fn serialize_compact<S: Serializer>(
+        &self,
+        min_file_size: u64,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        self.compact(min_file_size).serialize(serializer)
+    }
+
+    /// 路径搜索索引（按本机文件系统的大小写规则），可保留下来反复查询
+    pub fn path_index(&self) -> PathIndex {
+        PathIndex::build(&self.root, CaseSensitivity::native())
+    }
+
+    /// 查找路径以 `query` 开头或包含 `query` 的节点，见 `PathIndex::search`；
+    /// 需要多次查询时先用 `path_index` 建好索引
+    pub fn search(&self, query: &str, limit: usize) -> Vec<TopFileEntry> {
+        self.path_index().search(query, limit)
+    }
+
+    /// 路径对应的节点（按本机文件系统的大小写规则，`/` 与 `\` 等价），不在树中时为 None
+    pub fn find(&self, target: &str) -> Option<&FileNode> {
+        let case = CaseSensitivity::native();
+        if !path::is_under(target, &self.root.path, case) {
+            return None;
+        }
+        let depth = path::components(target).len();
+        let mut node = &self.root;
+        while path::components(&node.path).len() < depth {
+            node = node
+                .children
+                .iter()
+                .find(|c| !c.is_aggregate() && path::is_under(target, &c.path, case))?;
+        }
+        Some(node)
+    }
+
+    /// 以 `path` 为根的独立副本，可脱离整个扫描结果传递（单独重扫、生成计划、导出某个文件夹）；
+    /// 根节点的显示名按路径重新得出
+    pub fn subtree(&self, path: &str) -> Option<FileNode> {
+        let mut node = self.find(path)?.clone();
+        node.name = node.display_name().to_string();
+        Some(node)
+    }
+
+    /// 切换 shallow 目录设置后的结果，无需重新扫描：`shallow` 为 true 时把 shallow 目录的子节点折叠起来，
+    /// 为 false 时展开此前折叠的子节点。展开需要扫描时开启了 `retain_shallow_children`，
+    /// 有非空的 shallow 目录没有保留子节点时返回 None。`file_count` 按普通遍历的计数方式调整
+    pub fn rebuild_with_shallow(&self, shallow: bool) -> Option<ScanResult> {
+        let mut result = self.clone();
+        result.set_shallow(shallow).then_some(result)
+    }
+
+    /// 原地切换 shallow 设置，见 `rebuild_with_shallow`；无法展开时不做任何改动并返回 false
+    pub fn set_shallow(&mut self, shallow: bool) -> bool {
+        if !shallow && !can_expand(&self.root) {
+            return false;
+        }
+        let delta = toggle_shallow(&mut self.root, shallow);
+        self.file_count = self.file_count.saturating_add_signed(delta);
+        if let Some(meta) = &mut self.meta {
+            meta.shallow_dirs = shallow;
+        }
+        true
+    }
+
+    /// 文件数异常多的目录（如 npm 缓存、缩略图缓存）：直接包含的文件数达到 `min_files` 的目录，
+    /// 未展开的目录（shallow 目录、只扫描目录模式的叶子）按其递归文件数计。按文件数降序
+    pub fn file_count_hotspots(&self, min_files: u64) -> Vec<(String, u64)> {
+        let mut hotspots = Vec::new();
+        collect_hotspots(&self.root, min_files, &mut hotspots);
+        hotspots.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        hotspots
+    }
+}
+
+/// 折叠后的 shallow 目录本身计为一个文件（只扫描目录模式下为其递归文件数）
+fn collapsed_file_count(node: &FileNode) -> u64 {
+    node.file_count.unwrap_or(1)
+}
+
+fn is_shallow_stub(node: &FileNode) -> bool {
+    node.is_dir && node.children.is_empty() && is_shallow_dir_name(&node.name)
+}
+
+/// 所有折叠的 shallow 目录都保留了子节点（或本就为空）
+fn can_expand(node: &FileNode) -> bool {
+    if is_shallow_stub(node) {
+        return !node.collapsed_children.is_empty() || node.size == 0;
+    }
+    node.children.iter().all(can_expand)
+}
+
+/// 折叠或展开子树中最外层的 shallow 目录，返回文件数的变化
+fn toggle_shallow(node: &mut FileNode, shallow: bool) -> i64 {
+    let mut delta = 0i64;
+    for child in &mut node.children {
+        if !child.is_dir {
+            continue;
+        }
+        if !is_shallow_dir_name(&child.name) {
+            delta += toggle_shallow(child, shallow);
+        } else if shallow && !child.children.is_empty() {
+            delta -= child.total_files() as i64;
+            child.collapsed_children = std::mem::take(&mut child.children);
+            delta += collapsed_file_count(child) as i64;
+        } else if !shallow && !child.collapsed_children.is_empty() {
+            delta -= collapsed_file_count(child) as i64;
+            child.children = std::mem::take(&mut child.collapsed_children);
+            delta += child.total_files() as i64;
+        }
+    }
+    delta
+}
+
+fn collect_hotspots(node: &FileNode, min_files: u64, out: &mut Vec<(String, u64)>) {
+    if !node.is_dir {
+        return;
+    }
+    let in_subdirs: u64 = node
+        .children
+        .iter()
+        .filter(|c| c.is_dir)
+        .map(FileNode::total_files)
+        .sum();
+    let direct = node.total_files().saturating_sub(in_subdirs);
+    if direct >= min_files {
+        out.push((node.path.clone(), direct));
+    }
+    for child in &node.children {
+        collect_hotspots(child, min_files, out);
+    }
+}
+
+/// 扫描策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanStrategy {
+    Mft,
+    Walk,
+}
+
+/// 扫描方式与选项，供对比、增量扫描与缓存校验判断两份结果是否可比
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanMeta {
+    pub strategy: ScanStrategy,
+    pub shallow_dirs: bool,
+    /// 影响树内容的其他选项摘要，如 `dirs_only`；无则为空
+    pub filters_summary: String,
+    /// 扫描根路径（规范化后）
+    pub root: String,
+    /// 扫描开始时间（Unix 秒）
+    pub timestamp: u64,
+}
+
+/// 扫描阶段（耗时统计用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimingPhase {
+    /// 打开卷并把 $MFT 读入内存
+    ReadMft,
+    /// 逐条解析 MFT 记录、还原路径
+    Enumerate,
+    /// 汇总目录的递归大小等索引
+    Index,
+    /// 构建目录树
+    BuildTree,
+    /// 预估进度时额外的一次目录计数
+    CountDirs,
+    /// 普通目录遍历
+    Walk,
+    /// 查询卷空间、整理结果
+    Finish,
+}
+
+impl TimingPhase {
+    /// 阶段名称
+    pub fn label(self) -> &'static str {
+        match self {
+            TimingPhase::ReadMft => "读取 MFT",
+            TimingPhase::Enumerate => "解析 MFT 记录",
+            TimingPhase::Index => "汇总目录大小",
+            TimingPhase::BuildTree => "构建目录树",
+            TimingPhase::CountDirs => "预估进度",
+            TimingPhase::Walk => "遍历目录",
+            TimingPhase::Finish => "整理结果",
+        }
+    }
+
+    /// 该阶段耗时主要受什么限制
+    pub fn bound(self) -> &'static str {
+        match self {
+            TimingPhase::ReadMft => "磁盘 I/O",
+            TimingPhase::Enumerate => "单线程解析",
+            TimingPhase::Index | TimingPhase::BuildTree | TimingPhase::Finish => "CPU",
+            TimingPhase::CountDirs | TimingPhase::Walk => "文件系统元数据读取",
+        }
+    }
+}
+
+/// 一个阶段的耗时
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub phase: TimingPhase,
+    pub ms: u64,
+}
+
+/// 扫描耗时分析：各阶段耗时之和等于 `ScanResult::scan_time_ms`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanTiming {
+    /// 按执行顺序
+    pub phases: Vec<PhaseTiming>,
+    /// 处理的记录数（MFT 记录或遍历到的条目）
+    pub records: u64,
+    /// MFT 路径缓存达到上限后被清空重建的次数，越多则解析阶段越慢
+    #[serde(default)]
+    pub cache_resets: u64,
+    /// 是否因超出内存预算走了降级路径（见 `MftBudget`）
+    #[serde(default)]
+    pub degraded: bool,
+}
+
+impl ScanTiming {
+    /// 各阶段耗时之和
+    pub fn total_ms(&self) -> u64 {
+        self.phases.iter().map(|p| p.ms).sum()
+    }
+
+    /// 耗时最长的阶段
+    pub fn slowest(&self) -> Option<PhaseTiming> {
+        self.phases.iter().copied().max_by_key(|p| p.ms)
+    }
+
+    /// 一句话说明耗时最长的阶段，如「读取 MFT 耗时 12.0 秒（磁盘 I/O 瓶颈）」
+    pub fn explain(&self) -> Option<String> {
+        let slowest = self.slowest()?;
+        let mut text = format!(
+            "{} 耗时 {:.1} 秒（{}瓶颈）",
+            slowest.phase.label(),
+            slowest.ms as f64 / 1000.0,
+            slowest.phase.bound()
+        );
+        if self.degraded {
+            text.push_str("；文件数超出内存预算，已降级处理");
+        }
+        Some(text)
+    }
+}
+
+impl ScanMeta {
+    /// 两份结果是否由同一根路径、同一策略与选项产生（时间可不同）
+    pub fn is_comparable(&self, other: &ScanMeta) -> bool {
+        self.root == other.root
+            && self.strategy == other.strategy
+            && self.shallow_dirs == other.shallow_dirs
+            && self.filters_summary == other.filters_summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size: size + children.iter().map(|c| c.size).sum::<u64>(),
+            is_dir: !children.is_empty() || !path.contains('.'),
+            children,
+            ..Default::default()
+        }
+    }
+
+    /// 重建 (文件数, 文件字节数)：汇总节点按其 file_count 计
+    fn totals(node: &FileNode) -> (u64, u64) {
+        if node.is_dir {
+            node.children
+                .iter()
+                .map(totals)
+                .fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+        } else {
+            (node.file_count.unwrap_or(1), node.size)
+        }
+    }
+
+    #[test]
+    fn test_compact_totals_reconstruct() {
+        let root = node(
+            r"D:\data",
+            0,
+            vec![
+                node(r"D:\data\big.iso", 5_000, vec![]),
+                node(r"D:\data\a.txt", 10, vec![]),
+                node(r"D:\data\b.txt", 20, vec![]),
+                node(
+                    r"D:\data\src",
+                    0,
+                    vec![
+                        node(r"D:\data\src\lib.rs", 300, vec![]),
+                        node(r"D:\data\src\mod.rs", 40, vec![]),
+                    ],
+                ),
+                node(r"D:\data\empty", 0, vec![]),
+            ],
+        );
+        let scan = ScanResult {
+            file_count: 5,
+            total_size: root.size,
+            root,
+            ..Default::default()
+        };
+
+        let json = scan
+            .serialize_compact(100, serde_json::value::Serializer)
+            .unwrap();
+        let compact: ScanResult = serde_json::from_value(json).unwrap();
+        assert_eq!(totals(&compact.root), totals(&scan.root));
+        assert_eq!(totals(&compact.root), (5, 5_370));
+        assert_eq!(compact.total_size, scan.total_size);
+
+        let names: Vec<&str> = compact
+            .root
+            .children
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["big.iso", "src", "empty", "[2 个小文件]"]);
+        let small = &compact.root.children[3];
+        assert!(small.is_aggregate());
+        assert_eq!((small.path.as_str(), small.size), (r"D:\data\*", 30));
+        let src = &compact.root.children[1];
+        assert_eq!(src.children.len(), 2);
+        assert!(src.children[1].is_aggregate());
+    }
+
+    #[test]
+    fn test_file_count_hotspots_flags_crowded_dirs() {
+        let thumbs: Vec<FileNode> = (0..300)
+            .map(|i| node(&format!("/home/u/.cache/thumbs/{}.png", i), 1, vec![]))
+            .collect();
+        let npm = FileNode {
+            path: "/home/u/.npm".to_string(),
+            name: ".npm".to_string(),
+            size: 5_000,
+            is_dir: true,
+            // shallow 目录：只有递归文件数，没有子节点
+            file_count: Some(120_000),
+            ..Default::default()
+        };
+        let root = node(
+            "/home/u",
+            0,
+            vec![
+                node(
+                    "/home/u/.cache",
+                    0,
+                    vec![node("/home/u/.cache/thumbs", 0, thumbs)],
+                ),
+                npm,
+                node(
+                    "/home/u/docs",
+                    0,
+                    vec![node("/home/u/docs/a.txt", 10, vec![])],
+                ),
+            ],
+        );
+        let scan = ScanResult {
+            file_count: root.total_files(),
+            total_size: root.size,
+            root,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            scan.file_count_hotspots(100),
+            vec![
+                ("/home/u/.npm".to_string(), 120_000),
+                ("/home/u/.cache/thumbs".to_string(), 300),
+            ]
+        );
+        // 祖先目录不会因子目录的文件多而被重复标记
+        assert!(scan.file_count_hotspots(1_000_000).is_empty());
+    }
+
+    #[test]
+    fn test_subtree_is_owned_copy_with_matching_totals() {
+        let root = node(
+            "/data",
+            0,
+            vec![
+                node(
+                    "/data/videos",
+                    0,
+                    vec![
+                        node("/data/videos/a.mp4", 7_000, vec![]),
+                        node("/data/videos/b.mkv", 2_000, vec![]),
+                    ],
+                ),
+                node("/data/notes.txt", 40, vec![]),
+            ],
+        );
+        let scan = ScanResult {
+            file_count: 3,
+            total_size: root.size,
+            root,
+            ..Default::default()
+        };
+
+        let found = scan.find("/data/videos/").unwrap();
+        let mut videos = scan.subtree("/data/videos").unwrap();
+        assert_eq!(videos.path, "/data/videos");
+        assert_eq!(videos.name, "videos");
+        assert_eq!((videos.size, videos.total_files()), (9_000, 2));
+        assert_eq!(
+            (videos.size, videos.total_files()),
+            (found.size, found.total_files())
+        );
+
+        videos.children.clear();
+        videos.size = 0;
+        let original = scan.find("/data/videos").unwrap();
+        assert_eq!((original.size, original.children.len()), (9_000, 2));
+
+        assert_eq!(scan.subtree("/data/notes.txt").unwrap().size, 40);
+        assert_eq!(scan.subtree("/data").unwrap().size, scan.total_size);
+        assert!(scan.subtree("/data/missing").is_none());
+        assert!(scan.subtree("/other").is_none());
+    }
+
+    #[test]
+    fn test_rebuild_with_shallow_expands_retained_children() {
+        let node_modules = node(
+            "/p/web/node_modules",
+            0,
+            vec![
+                node(
+                    "/p/web/node_modules/react",
+                    0,
+                    vec![node("/p/web/node_modules/react/index.js", 700, vec![])],
+                ),
+                node("/p/web/node_modules/.package-lock.json", 300, vec![]),
+            ],
+        );
+        let root = node(
+            "/p",
+            0,
+            vec![node(
+                "/p/web",
+                0,
+                vec![node_modules, node("/p/web/app.js", 50, vec![])],
+            )],
+        );
+        let full = ScanResult {
+            file_count: 3,
+            total_size: root.size,
+            root,
+            ..Default::default()
+        };
+
+        // 折叠：node_modules 计为一项，子节点保留在 collapsed_children 中
+        let shallow = full.rebuild_with_shallow(true).unwrap();
+        let stub = &shallow.root.children[0].children[0];
+        assert!(stub.children.is_empty());
+        assert_eq!((stub.size, stub.collapsed_children.len()), (1_000, 2));
+        assert_eq!((shallow.file_count, shallow.total_size), (2, 1_050));
+        let json = serde_json::to_string(&shallow).unwrap();
+        let shallow: ScanResult = serde_json::from_str(&json).unwrap();
+
+        // 展开：子节点与大小恢复
+        let expanded = shallow.rebuild_with_shallow(false).unwrap();
+        let modules = &expanded.root.children[0].children[0];
+        assert!(modules.collapsed_children.is_empty());
+        let children: Vec<(&str, u64)> = modules
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.size))
+            .collect();
+        assert_eq!(children, [("react", 700), (".package-lock.json", 300)]);
+        assert_eq!(modules.children[0].children[0].name, "index.js");
+        assert_eq!(
+            (expanded.file_count, expanded.total_size),
+            (full.file_count, full.total_size)
+        );
+
+        // 未保留子节点的 shallow 目录无法展开
+        let mut stubbed = shallow;
+        stubbed.root.children[0].children[0]
+            .collapsed_children
+            .clear();
+        assert!(stubbed.rebuild_with_shallow(false).is_none());
+        assert!(!stubbed.set_shallow(false));
+    }
 }
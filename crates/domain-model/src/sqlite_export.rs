@@ -0,0 +1,172 @@
+//! 把扫描结果导出为 SQLite 数据库，供外部用 SQL 查询，例如：
+//!
+//! ```sql
+//! SELECT parent, SUM(size) FROM files WHERE is_dir = 0 GROUP BY parent ORDER BY 2 DESC LIMIT 20;
+//! SELECT category, SUM(size) FROM files WHERE is_dir = 0 GROUP BY category;
+//! ```
+//!
+//! `files` 表每个节点一行：目录的 `size` 为递归大小，汇总节点（见 `FileNode::compact`）按文件计入，
+//! 因此 `SUM(size) WHERE is_dir = 0` 等于 `total_size`。
+
+use std::path::Path;
+
+use ai_disk_common::{file_category, file_extension, DiskAnalyzerError};
+use rusqlite::{params, Connection};
+
+use crate::{FileNode, ScanResult};
+
+const SCHEMA: &str = "
+CREATE TABLE files (
+    id        INTEGER PRIMARY KEY,
+    path      TEXT NOT NULL,
+    parent    TEXT,
+    name      TEXT NOT NULL,
+    size      INTEGER NOT NULL,
+    is_dir    INTEGER NOT NULL,
+    modified  INTEGER,
+    extension TEXT,
+    category  TEXT
+);
+CREATE INDEX idx_files_size ON files(size);
+CREATE INDEX idx_files_parent ON files(parent);
+";
+
+impl ScanResult {
+    /// 导出到 `path`（已存在时覆盖），返回写入的行数
+    pub fn export_sqlite(&self, path: &Path) -> Result<u64, DiskAnalyzerError> {
+        match std::fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        let mut conn = Connection::open(path).map_err(db_error)?;
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        let tx = conn.transaction().map_err(db_error)?;
+        let mut rows = 0u64;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO files (path, parent, name, size, is_dir, modified, extension, category)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                )
+                .map_err(db_error)?;
+            let mut stack: Vec<(&FileNode, Option<&str>)> = vec![(&self.root, None)];
+            while let Some((node, parent)) = stack.pop() {
+                let is_file = !node.is_dir && !node.is_aggregate();
+                insert
+                    .execute(params![
+                        node.path,
+                        parent,
+                        node.name,
+                        node.size as i64,
+                        node.is_dir,
+                        node.modified.map(|m| m as i64),
+                        is_file.then(|| file_extension(&node.path)).flatten(),
+                        is_file.then(|| file_category(&node.path)).flatten(),
+                    ])
+                    .map_err(db_error)?;
+                rows += 1;
+                stack.extend(node.children.iter().map(|c| (c, Some(node.path.as_str()))));
+            }
+        }
+        tx.commit().map_err(db_error)?;
+        Ok(rows)
+    }
+}
+
+fn db_error(e: rusqlite::Error) -> DiskAnalyzerError {
+    DiskAnalyzerError::Io(std::io::Error::other(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: ai_disk_common::path::file_name(path).to_string(),
+            size: size + children.iter().map(|c| c.size).sum::<u64>(),
+            is_dir: !children.is_empty(),
+            modified: Some(1_700_000_000),
+            children,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_sqlite_aggregates_match_total() {
+        let root = node(
+            "/data",
+            0,
+            vec![
+                node(
+                    "/data/videos",
+                    0,
+                    vec![
+                        node("/data/videos/a.MP4", 7_000, vec![]),
+                        node("/data/videos/b.mkv", 2_000, vec![]),
+                    ],
+                ),
+                node(
+                    "/data/docs",
+                    0,
+                    vec![
+                        node("/data/docs/report.docx", 300, vec![]),
+                        node("/data/docs/notes", 40, vec![]),
+                    ],
+                ),
+            ],
+        );
+        let mut result = ScanResult {
+            total_size: root.size,
+            file_count: 4,
+            root,
+            ..Default::default()
+        };
+        // 汇总节点也按文件计入
+        result.root = result.root.compact(1_000);
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("scan.db");
+        std::fs::write(&db, "stale").unwrap();
+
+        assert_eq!(result.export_sqlite(&db).unwrap(), 6);
+        let conn = Connection::open(&db).unwrap();
+        let total: i64 = conn
+            .query_row("SELECT SUM(size) FROM files WHERE is_dir = 0", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(total as u64, result.total_size);
+        let videos: (i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), SUM(size) FROM files WHERE parent = '/data/videos'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(videos, (2, 9_000));
+        let by_category: Vec<(Option<String>, i64)> = conn
+            .prepare(
+                "SELECT category, SUM(size) FROM files WHERE is_dir = 0 \
+                 GROUP BY category ORDER BY 2 DESC",
+            )
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            by_category,
+            [(Some("video".to_string()), 9_000), (None, 340)]
+        );
+        let ext: String = conn
+            .query_row(
+                "SELECT extension FROM files WHERE name = 'a.MP4'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(ext, "mp4");
+    }
+}
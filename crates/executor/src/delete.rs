@@ -1,7 +1,206 @@
-use ai_disk_common::DiskAnalyzerError;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-/// 删除执行（预留）
-pub async fn delete_file(path: &str) -> Result<(), DiskAnalyzerError> {
-    let _ = path;
-    Ok(())
+use ai_disk_common::{telemetry, DiskAnalyzerError};
+
+use crate::quarantine::{Quarantine, QuarantineEntry};
+use crate::r#move::ExecuteOptions;
+use crate::trash::move_to_trash;
+
+/// 每删除这么多个文件上报一次进度
+const PROGRESS_EVERY: u64 = 256;
+
+/// 删除方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// 永久删除，不可恢复
+    #[default]
+    Permanent,
+    /// 移到系统回收站，可在回收站中还原
+    Trash,
+}
+
+/// 删除文件或目录（目录连同其内容）
+pub async fn delete_file(path: &str, mode: DeleteMode) -> Result<(), DiskAnalyzerError> {
+    let _span = telemetry::execute_span("delete", path).entered();
+    let path = Path::new(path);
+    match mode {
+        DeleteMode::Trash => move_to_trash(path),
+        DeleteMode::Permanent if std::fs::symlink_metadata(path)?.is_dir() => {
+            Ok(std::fs::remove_dir_all(path)?)
+        }
+        DeleteMode::Permanent => Ok(std::fs::remove_file(path)?),
+    }
+}
+
+/// 递归删除的累计进度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeleteProgress {
+    pub files_removed: u64,
+    pub bytes_removed: u64,
+}
+
+/// 递归删除进度回调
+pub type DeleteProgressCb = Box<dyn Fn(DeleteProgress) + Send + Sync>;
+
+/// 目录删除的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeleteOutcome {
+    /// 已移入隔离区，到期前可恢复
+    Quarantined(QuarantineEntry),
+    /// 整个目录已移到回收站（不逐个遍历）
+    Trashed,
+    /// 已永久删除
+    Removed(DeleteProgress),
+}
+
+/// 删除目录。配置了 `quarantine` 时整个目录移入隔离区；开启 `use_trash` 时直接把顶层目录移到回收站；否则逐个删除文件并上报进度，
+/// 目录在其内容删完后删除。`cancel` 置位后停止并返回 `DiskAnalyzerError::Cancelled`：
+/// 已删除的文件不可恢复，剩余文件均完整保留，剩余目录都仍含未删除的内容，
+/// 再次调用即从剩余部分继续
+pub fn delete_dir_recursive(
+    path: &Path,
+    opts: &ExecuteOptions,
+    progress: Option<&DeleteProgressCb>,
+    cancel: Option<&AtomicBool>,
+) -> Result<DeleteOutcome, DiskAnalyzerError> {
+    let path_str = path.to_string_lossy();
+    opts.ensure_confirmed(&path_str)?;
+    let span = telemetry::execute_span("delete_dir", &path_str).entered();
+    if let Some(policy) = &opts.quarantine {
+        let entry = Quarantine::open(policy)?.quarantine(path)?;
+        return Ok(DeleteOutcome::Quarantined(entry));
+    }
+    if opts.delete_mode() == DeleteMode::Trash {
+        move_to_trash(path)?;
+        return Ok(DeleteOutcome::Trashed);
+    }
+
+    let mut done = DeleteProgress::default();
+    let result = remove_tree(path, progress, cancel, &mut done);
+    span.record("bytes_freed", done.bytes_removed);
+    if let Some(cb) = progress {
+        cb(done);
+    }
+    result.map(|()| DeleteOutcome::Removed(done))
+}
+
+fn remove_tree(
+    dir: &Path,
+    progress: Option<&DeleteProgressCb>,
+    cancel: Option<&AtomicBool>,
+    done: &mut DeleteProgress,
+) -> Result<(), DiskAnalyzerError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // 上次中断后已被删除
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(DiskAnalyzerError::Cancelled);
+        }
+        let entry = entry?;
+        let child = entry.path();
+        // 不跟随符号链接：链接本身按文件删除
+        if entry.file_type()?.is_dir() {
+            remove_tree(&child, progress, cancel, done)?;
+            continue;
+        }
+        let len = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        match std::fs::remove_file(&child) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+        done.files_removed += 1;
+        done.bytes_removed += len;
+        if done.files_removed.is_multiple_of(PROGRESS_EVERY) {
+            if let Some(cb) = progress {
+                cb(*done);
+            }
+        }
+    }
+    match std::fs::remove_dir(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// 在 `root` 下建 4 个子目录、各 200 个 10 字节文件
+    fn populate(root: &Path) {
+        for d in 0..4 {
+            let dir = root.join(format!("d{}", d)).join("nested");
+            std::fs::create_dir_all(&dir).unwrap();
+            for f in 0..200 {
+                std::fs::write(dir.join(format!("{}.bin", f)), [0u8; 10]).unwrap();
+            }
+        }
+    }
+
+    fn count_files(dir: &Path) -> u64 {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| {
+                let e = e.unwrap();
+                if e.file_type().unwrap().is_dir() {
+                    count_files(&e.path())
+                } else {
+                    1
+                }
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_delete_dir_reports_progress_and_resumes_after_cancel() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("huge");
+        populate(&root);
+        let opts = ExecuteOptions::default();
+
+        // 第一次进度上报时取消
+        let cancel = Arc::new(AtomicBool::new(false));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let cb: DeleteProgressCb = {
+            let (cancel, events) = (cancel.clone(), events.clone());
+            Box::new(move |p| {
+                events.lock().unwrap().push(p);
+                cancel.store(true, Ordering::Relaxed);
+            })
+        };
+        let result = delete_dir_recursive(&root, &opts, Some(&cb), Some(&cancel));
+        assert!(matches!(result, Err(DiskAnalyzerError::Cancelled)));
+        let first = *events.lock().unwrap().last().unwrap();
+        assert_eq!(first.files_removed, PROGRESS_EVERY);
+        assert_eq!(first.bytes_removed, PROGRESS_EVERY * 10);
+        assert_eq!(count_files(&root), 800 - PROGRESS_EVERY);
+
+        // 重新调用从剩余部分继续，进度单调递增
+        events.lock().unwrap().clear();
+        cancel.store(false, Ordering::Relaxed);
+        let events_cb = events.clone();
+        let cb: DeleteProgressCb = Box::new(move |p| events_cb.lock().unwrap().push(p));
+        let outcome = delete_dir_recursive(&root, &opts, Some(&cb), None).unwrap();
+        let rest = 800 - PROGRESS_EVERY;
+        assert_eq!(
+            outcome,
+            DeleteOutcome::Removed(DeleteProgress {
+                files_removed: rest,
+                bytes_removed: rest * 10,
+            })
+        );
+        let events = events.lock().unwrap();
+        assert!(events.len() >= 2);
+        assert!(events
+            .windows(2)
+            .all(|w| w[0].files_removed <= w[1].files_removed));
+        assert!(!root.exists());
+    }
 }
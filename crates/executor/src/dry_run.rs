@@ -1,4 +1,218 @@
+//! 计划预演：逐个动作检查路径是否存在、权限是否足够、移动的目的地是否已被占用，
+//! 并估算可释放的空间，不修改任何文件。报告可序列化，直接返回给前端展示。
+
+use std::path::Path;
+
+use ai_disk_domain::{Action, CleanupPlan};
+use serde::{Deserialize, Serialize};
+
+use crate::permission::{check_plan_permissions, PermissionIssue};
+use crate::r#move::logical_size;
+
 /// 模拟执行（预留）
 pub fn simulate_actions(_dry_run: bool) -> bool {
     true
 }
+
+/// 预演结果中的单个动作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunItem {
+    pub action: Action,
+    /// 预计释放的空间：计划中已知时取计划的值，否则按当前的逻辑大小估算（见 `logical_size`）
+    pub bytes: u64,
+    /// 预计失败的原因；None 表示预计成功
+    pub failure: Option<PermissionIssue>,
+    /// `failure` 的说明文字
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl DryRunItem {
+    pub fn would_succeed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// 计划预演报告：结合权限预检，如实标出会失败的动作
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunReport {
+    pub items: Vec<DryRunItem>,
+    /// 预计成功的动作可释放的空间
+    pub reclaimable_bytes: u64,
+    pub failed_count: usize,
+}
+
+/// 预演清理计划，不修改任何文件
+pub fn dry_run_plan(plan: &CleanupPlan) -> DryRunReport {
+    let mut report = DryRunReport::default();
+    for (action, check) in plan.actions.iter().zip(check_plan_permissions(plan)) {
+        let bytes = match action {
+            Action::MarkKeep { .. } => 0,
+            _ => plan
+                .sizes
+                .get(&check.path)
+                .copied()
+                .unwrap_or_else(|| logical_size(Path::new(&check.path))),
+        };
+        if check.issue.is_none() {
+            report.reclaimable_bytes += bytes;
+        } else {
+            report.failed_count += 1;
+        }
+        report.items.push(DryRunItem {
+            action: action.clone(),
+            bytes,
+            failure: check.issue,
+            reason: check.issue.map(|issue| issue.to_string()),
+        });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dry_run_marks_read_only_and_protected_targets() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let free = dir.path().join("free.bin");
+        std::fs::write(&free, [0u8; 10]).unwrap();
+        let locked_dir = dir.path().join("locked");
+        std::fs::create_dir(&locked_dir).unwrap();
+        let locked = locked_dir.join("stuck.bin");
+        std::fs::write(&locked, [0u8; 20]).unwrap();
+        std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        let mut plan = CleanupPlan::default();
+        for (path, size) in [
+            (free.to_string_lossy().to_string(), 10),
+            (locked.to_string_lossy().to_string(), 20),
+            ("/usr/lib/libc.so.6".to_string(), 30),
+            (
+                dir.path().join("gone.tmp").to_string_lossy().to_string(),
+                40,
+            ),
+        ] {
+            plan.sizes.insert(path.clone(), size);
+            plan.actions.push(Action::Delete { path });
+        }
+
+        let report = dry_run_plan(&plan);
+        // root 不受权限位限制
+        let privileged = std::fs::write(locked_dir.join("probe"), b"").is_ok();
+        std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let failures: Vec<Option<PermissionIssue>> =
+            report.items.iter().map(|i| i.failure).collect();
+        let locked_issue = (!privileged).then_some(PermissionIssue::NeedsElevation);
+        assert_eq!(
+            failures,
+            vec![
+                None,
+                locked_issue,
+                Some(PermissionIssue::Protected),
+                Some(PermissionIssue::NotFound),
+            ]
+        );
+        if !privileged {
+            assert_eq!(report.failed_count, 3);
+            assert_eq!(report.reclaimable_bytes, 10);
+            assert_eq!(
+                report.items[1].failure.unwrap().to_string(),
+                "所在目录不可写，需要管理员权限"
+            );
+        }
+        assert!(free.exists() && locked.exists());
+    }
+
+    #[test]
+    fn test_dry_run_plan_totals_and_read_only_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let known = dir.path().join("known.bin");
+        std::fs::write(&known, [0u8; 10]).unwrap();
+        let cache = dir.path().join("cache");
+        std::fs::create_dir_all(cache.join("sub")).unwrap();
+        std::fs::write(cache.join("a.bin"), [0u8; 100]).unwrap();
+        std::fs::write(cache.join("sub").join("b.bin"), [0u8; 50]).unwrap();
+        let moved = dir.path().join("moved.bin");
+        std::fs::write(&moved, [0u8; 7]).unwrap();
+        let taken = dir.path().join("taken.bin");
+        std::fs::write(&taken, [0u8; 1]).unwrap();
+        // 只读的临时文件：Windows 上只读属性即无法删除，Unix 上取决于所在目录是否可写
+        let locked_dir = dir.path().join("locked");
+        std::fs::create_dir(&locked_dir).unwrap();
+        let locked = locked_dir.join("stuck.tmp");
+        std::fs::write(&locked, [0u8; 20]).unwrap();
+        let mut perms = std::fs::metadata(&locked).unwrap().permissions();
+        perms.set_readonly(true);
+        std::fs::set_permissions(&locked, perms).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+        }
+
+        let path = |p: &Path| p.to_string_lossy().to_string();
+        let mut plan = CleanupPlan::default();
+        plan.sizes.insert(path(&known), 10);
+        plan.actions = vec![
+            Action::Delete { path: path(&known) },
+            // 计划中没有大小时按当前大小估算
+            Action::Empty { path: path(&cache) },
+            Action::Delete {
+                path: path(&locked),
+            },
+            Action::Move {
+                from: path(&moved),
+                to: path(&taken),
+            },
+            Action::Trash {
+                path: path(&dir.path().join("gone.tmp")),
+            },
+        ];
+
+        let report = dry_run_plan(&plan);
+        // root 不受权限位限制
+        let privileged = cfg!(unix) && std::fs::write(locked_dir.join("probe"), b"").is_ok();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let outcomes: Vec<(u64, Option<PermissionIssue>)> =
+            report.items.iter().map(|i| (i.bytes, i.failure)).collect();
+        let read_only = if cfg!(windows) {
+            Some(PermissionIssue::ReadOnly)
+        } else {
+            (!privileged).then_some(PermissionIssue::NeedsElevation)
+        };
+        assert_eq!(
+            outcomes,
+            [
+                (10, None),
+                (150, None),
+                (20, read_only),
+                (7, Some(PermissionIssue::TargetExists)),
+                (0, Some(PermissionIssue::NotFound)),
+            ]
+        );
+        if !privileged {
+            assert_eq!(report.reclaimable_bytes, 160);
+            assert_eq!(report.failed_count, 3);
+        }
+
+        // 可序列化，失败项附带说明文字
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["items"][3]["failure"], "target_exists");
+        assert_eq!(json["items"][3]["reason"], "目标位置已存在同名文件");
+        assert!(json["items"][0].get("reason").is_none());
+        // 没有修改任何文件
+        assert!(known.exists() && locked.exists() && moved.exists());
+        assert_eq!(std::fs::read(&taken).unwrap().len(), 1);
+    }
+}
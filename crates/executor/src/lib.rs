@@ -2,8 +2,12 @@ pub mod delete;
 pub mod dry_run;
 pub mod r#move;
 pub mod permission;
+pub mod quarantine;
+pub mod trash;
 
 pub use delete::*;
 pub use dry_run::*;
 pub use permission::*;
+pub use quarantine::*;
 pub use r#move::*;
+pub use trash::move_to_trash;
@@ -1,7 +1,605 @@
-use ai_disk_common::DiskAnalyzerError;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
-/// 移动执行（预留）
+use ai_disk_common::path::CaseSensitivity;
+use ai_disk_common::{path, telemetry, DiskAnalyzerError};
+use serde::{Deserialize, Serialize};
+
+use crate::delete::DeleteMode;
+use crate::permission::sensitive_root;
+use crate::quarantine::QuarantinePolicy;
+
+/// 执行选项
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions {
+    /// 批量移动/归档时在目标根目录下重建源文件的相对目录结构（相对所有源文件的公共上级目录），
+    /// 否则直接平铺到目标根目录
+    pub preserve_structure: bool,
+    /// 敏感目录（通常取自 `AppConfig::sensitive_roots`），其中的源路径需要 `confirm_sensitive`
+    pub sensitive_roots: Vec<String>,
+    /// 用户已二次确认操作敏感目录中的文件
+    pub confirm_sensitive: bool,
+    /// 删除时移到回收站而不是永久删除
+    pub use_trash: bool,
+    /// 删除时移入隔离区，宽限期过后才永久删除；优先于 `use_trash`
+    pub quarantine: Option<QuarantinePolicy>,
+}
+
+impl ExecuteOptions {
+    /// 不使用隔离区时的删除方式
+    pub fn delete_mode(&self) -> DeleteMode {
+        if self.use_trash {
+            DeleteMode::Trash
+        } else {
+            DeleteMode::Permanent
+        }
+    }
+
+    /// 未确认时拒绝位于敏感目录中的路径；系统目录的硬性保护见 `check_plan_permissions`
+    pub fn ensure_confirmed(&self, path: &str) -> Result<(), DiskAnalyzerError> {
+        match sensitive_root(path, &self.sensitive_roots) {
+            Some(root) if !self.confirm_sensitive => Err(DiskAnalyzerError::PermissionDenied(
+                format!("{} 位于敏感目录 {} 中，需要再次确认", path, root),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 目标路径已存在时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// 替换已有的文件或目录
+    Overwrite,
+    /// 不移动，源路径保持不动
+    #[default]
+    Skip,
+    /// 在文件名后追加 ` (1)`、` (2)` 等序号
+    Rename,
+}
+
+/// `move_item` 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveOutcome {
+    /// 已移动到该路径（`Rename` 时为追加序号后的路径）
+    Moved(PathBuf),
+    /// 目标已存在，按 `ConflictPolicy::Skip` 跳过
+    Skipped,
+}
+
+/// 移动单个文件到 `to`（自动创建上级目录），已存在时覆盖，见 `move_item`
 pub async fn move_file(from: &str, to: &str) -> Result<(), DiskAnalyzerError> {
-    let _ = (from, to);
+    move_item(Path::new(from), Path::new(to), ConflictPolicy::Overwrite).map(|_| ())
+}
+
+/// 移动文件或目录到 `dst`（自动创建上级目录），目标已存在时按 `conflict` 处理。
+/// 同卷时直接重命名；跨卷时先复制到目标旁的临时路径并核对大小，再改名为 `dst`、删除源路径，
+/// 复制失败时不留下不完整的目标，源路径保持不动。覆盖目录时先把旧目标移到一旁，成功后再删除
+pub fn move_item(
+    src: &Path,
+    dst: &Path,
+    conflict: ConflictPolicy,
+) -> Result<MoveOutcome, DiskAnalyzerError> {
+    move_item_with(src, dst, conflict, |a, b| std::fs::rename(a, b))
+}
+
+fn move_item_with(
+    src: &Path,
+    dst: &Path,
+    conflict: ConflictPolicy,
+    rename: impl Fn(&Path, &Path) -> std::io::Result<()>,
+) -> Result<MoveOutcome, DiskAnalyzerError> {
+    let _span = telemetry::execute_span("move", &src.to_string_lossy()).entered();
+    std::fs::symlink_metadata(src)?;
+    let exists = std::fs::symlink_metadata(dst).is_ok();
+    let dst = match conflict {
+        _ if !exists => dst.to_path_buf(),
+        ConflictPolicy::Skip => return Ok(MoveOutcome::Skipped),
+        ConflictPolicy::Rename => unique_path(dst, &mut HashSet::new()),
+        ConflictPolicy::Overwrite => dst.to_path_buf(),
+    };
+    let replace = exists && conflict == ConflictPolicy::Overwrite;
+    if let Some(parent) = dst.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    match replace_with(src, &dst, replace, &rename) {
+        Ok(()) => return Ok(MoveOutcome::Moved(dst)),
+        Err(e) if e.kind() != std::io::ErrorKind::CrossesDevices => return Err(e.into()),
+        Err(_) => {}
+    }
+    let staging = side_path(&dst, "moving");
+    if let Err(e) = copy_tree(src, &staging) {
+        let _ = remove_any(&staging);
+        return Err(e);
+    }
+    if let Err(e) = replace_with(&staging, &dst, replace, &|a, b| std::fs::rename(a, b)) {
+        let _ = remove_any(&staging);
+        return Err(e.into());
+    }
+    remove_any(src)?;
+    Ok(MoveOutcome::Moved(dst))
+}
+
+/// 把 `src` 重命名为 `dst`。文件替换文件时由重命名直接替换；替换目录（或类型不同）时
+/// 先把旧目标移到一旁，重命名失败则还原
+fn replace_with(
+    src: &Path,
+    dst: &Path,
+    replace: bool,
+    rename: &impl Fn(&Path, &Path) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let is_dir = |p: &Path| std::fs::symlink_metadata(p).is_ok_and(|m| m.is_dir());
+    if !replace || (!is_dir(src) && !is_dir(dst)) {
+        return rename(src, dst);
+    }
+    let backup = side_path(dst, "old");
+    std::fs::rename(dst, &backup)?;
+    match rename(src, dst) {
+        Ok(()) => {
+            let _ = remove_any(&backup);
+            Ok(())
+        }
+        Err(e) => {
+            let _ = std::fs::rename(&backup, dst);
+            Err(e)
+        }
+    }
+}
+
+/// 与 `path` 同目录的临时路径，如 `a.txt` -> `.a.txt.moving`
+fn side_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    unique_path(
+        &path.with_file_name(format!(".{}.{}", name, suffix)),
+        &mut HashSet::new(),
+    )
+}
+
+fn remove_any(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// 递归复制并逐个核对文件大小；符号链接按链接本身复制
+fn copy_tree(from: &Path, to: &Path) -> Result<(), DiskAnalyzerError> {
+    let metadata = std::fs::symlink_metadata(from)?;
+    if metadata.is_symlink() {
+        return copy_link(from, to);
+    }
+    if !metadata.is_dir() {
+        return copy_verified(from, to, |a, b| std::fs::copy(a, b));
+    }
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_link(from: &Path, to: &Path) -> Result<(), DiskAnalyzerError> {
+    std::os::unix::fs::symlink(std::fs::read_link(from)?, to)?;
     Ok(())
 }
+
+#[cfg(not(unix))]
+fn copy_link(from: &Path, _to: &Path) -> Result<(), DiskAnalyzerError> {
+    Err(DiskAnalyzerError::Io(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("跨卷移动不支持符号链接: {}", from.display()),
+    )))
+}
+
+/// 文件或目录（递归）的逻辑大小之和：按 `len()` 计，稀疏、压缩文件的实际占用可能更小；
+/// 不跟随符号链接，不存在或无法读取的部分计 0
+pub(crate) fn logical_size(path: &Path) -> u64 {
+    match std::fs::symlink_metadata(path) {
+        Ok(m) if m.is_dir() => std::fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|e| logical_size(&e.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(m) => m.len(),
+        Err(_) => 0,
+    }
+}
+
+/// 复制文件并核对大小，失败时删除不完整的目标文件
+fn copy_verified(
+    from: &Path,
+    to: &Path,
+    copy: impl Fn(&Path, &Path) -> std::io::Result<u64>,
+) -> Result<(), DiskAnalyzerError> {
+    let expected = std::fs::metadata(from)?.len();
+    if let Err(e) = copy(from, to) {
+        let _ = std::fs::remove_file(to);
+        return Err(e.into());
+    }
+    let actual = std::fs::metadata(to).map(|m| m.len()).unwrap_or(0);
+    if actual != expected {
+        let _ = std::fs::remove_file(to);
+        return Err(DiskAnalyzerError::Io(std::io::Error::other(format!(
+            "复制 {} 到 {} 后大小不一致（{} / {} 字节），已保留源文件",
+            from.display(),
+            to.display(),
+            actual,
+            expected
+        ))));
+    }
+    Ok(())
+}
+
+/// 把多个文件移动到 `dest_root` 下，返回各自的目标路径（顺序与 `sources` 一致）
+pub async fn move_files(
+    sources: &[&str],
+    dest_root: &Path,
+    opts: &ExecuteOptions,
+) -> Result<Vec<PathBuf>, DiskAnalyzerError> {
+    for from in sources {
+        opts.ensure_confirmed(from)?;
+    }
+    let targets = plan_destinations(sources, dest_root, opts);
+    for (from, to) in sources.iter().zip(&targets) {
+        move_file(from, &to.to_string_lossy()).await?;
+    }
+    Ok(targets)
+}
+
+/// 计算每个源文件在 `dest_root` 下的目标路径。保留结构时，若源文件来自不同的卷/根，
+/// 先按根分到各自的子目录（如 `C`、`server_share`），再在其中保留相对结构。
+/// 与已存在或本批次中重复的路径冲突时追加 ` (n)` 序号
+pub fn plan_destinations(
+    sources: &[&str],
+    dest_root: &Path,
+    opts: &ExecuteOptions,
+) -> Vec<PathBuf> {
+    let relative: Vec<Vec<String>> = if opts.preserve_structure {
+        relative_to_common_base(sources)
+    } else {
+        sources
+            .iter()
+            .map(|s| vec![path::file_name(s).to_string()])
+            .collect()
+    };
+    let mut taken = HashSet::new();
+    relative
+        .into_iter()
+        .map(|segments| {
+            let target = segments
+                .iter()
+                .fold(dest_root.to_path_buf(), |p, s| p.join(s));
+            unique_path(&target, &mut taken)
+        })
+        .collect()
+}
+
+/// 各源文件相对其所在根分组的公共上级目录的路径分段；多个根时以根标签开头
+fn relative_to_common_base(sources: &[&str]) -> Vec<Vec<String>> {
+    let split: Vec<(Option<String>, Vec<&str>)> = sources
+        .iter()
+        .map(|s| {
+            let root = path::root(s);
+            let mut segments = path::components(s);
+            if root.is_some() {
+                segments.remove(0);
+            }
+            (root.map(root_label), segments)
+        })
+        .collect();
+    let roots: HashSet<&Option<String>> = split.iter().map(|(root, _)| root).collect();
+    let multi_root = roots.len() > 1;
+
+    split
+        .iter()
+        .map(|(root, segments)| {
+            // 与同根各文件所在目录的最短公共前缀
+            let dir = &segments[..segments.len().saturating_sub(1)];
+            let base = split
+                .iter()
+                .filter(|(r, _)| r == root)
+                .map(|(_, other)| {
+                    dir.iter()
+                        .zip(&other[..other.len().saturating_sub(1)])
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                })
+                .min()
+                .unwrap_or(0);
+            let label = multi_root.then(|| root.clone().unwrap_or_else(|| "root".to_string()));
+            label
+                .into_iter()
+                .chain(segments[base..].iter().map(|s| s.to_string()))
+                .collect()
+        })
+        .collect()
+}
+
+/// 根的目录名：`C:` -> `C`，`\\?\C:` -> `C`，`\\server\share` -> `server_share`
+fn root_label(root: &str) -> String {
+    let label: Vec<&str> = root
+        .split(['\\', '/', ':'])
+        .filter(|seg| is_label_segment(seg))
+        .collect();
+    if label.is_empty() {
+        "volume".to_string()
+    } else {
+        label.join("_")
+    }
+}
+
+fn is_label_segment(seg: &str) -> bool {
+    !seg.is_empty() && seg != "?" && seg != "." && !seg.eq_ignore_ascii_case("UNC")
+}
+
+/// 本批次已占用路径的比较键：不区分大小写的文件系统上 `A.txt` 与 `a.txt` 冲突
+fn taken_key(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    match CaseSensitivity::native() {
+        CaseSensitivity::Sensitive => path.to_string(),
+        CaseSensitivity::Insensitive => path.to_lowercase(),
+    }
+}
+
+fn unique_path(target: &Path, taken: &mut HashSet<String>) -> PathBuf {
+    let mut candidate = target.to_path_buf();
+    let mut n = 1;
+    while taken.contains(&taken_key(&candidate)) || candidate.exists() {
+        let stem = target
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = match target.extension() {
+            Some(ext) => format!("{} ({}).{}", stem, n, ext.to_string_lossy()),
+            None => format!("{} ({})", stem, n),
+        };
+        candidate = target.with_file_name(name);
+        n += 1;
+    }
+    taken.insert(taken_key(&candidate));
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_move_preserves_nested_structure() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let files = ["a/x.txt", "a/b/y.txt", "c/z.txt"];
+        let sources: Vec<String> = files
+            .iter()
+            .map(|f| {
+                let p = src.path().join("proj").join(f);
+                std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+                std::fs::write(&p, f).unwrap();
+                p.to_string_lossy().to_string()
+            })
+            .collect();
+        let sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+
+        let opts = ExecuteOptions {
+            preserve_structure: true,
+            ..Default::default()
+        };
+        let moved = move_files(&sources, dest.path(), &opts).await.unwrap();
+        for (file, target) in files.iter().zip(&moved) {
+            let expected = file
+                .split('/')
+                .fold(dest.path().to_path_buf(), |p, s| p.join(s));
+            assert_eq!(target, &expected);
+            assert_eq!(std::fs::read_to_string(target).unwrap(), *file);
+        }
+        assert!(sources.iter().all(|s| !Path::new(s).exists()));
+    }
+
+    #[test]
+    fn test_short_copy_keeps_source_and_removes_partial() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let from = src.path().join("video.mp4");
+        let to = dest.path().join("video.mp4");
+        std::fs::write(&from, vec![7u8; 4096]).unwrap();
+
+        // 模拟磁盘写满：只写入一半就「成功」返回
+        let short_write = |a: &Path, b: &Path| {
+            let data = std::fs::read(a)?;
+            std::fs::write(b, &data[..data.len() / 2])?;
+            Ok(data.len() as u64)
+        };
+        let err = copy_verified(&from, &to, short_write).unwrap_err();
+        assert!(err.to_string().contains("大小不一致"), "{}", err);
+        assert_eq!(std::fs::read(&from).unwrap().len(), 4096);
+        assert!(!to.exists());
+
+        copy_verified(&from, &to, |a, b| std::fs::copy(a, b)).unwrap();
+        assert_eq!(std::fs::read(&to).unwrap().len(), 4096);
+    }
+
+    #[test]
+    fn test_plan_destinations_flatten_and_multiple_roots() {
+        let dest = Path::new("/archive");
+        let flat = plan_destinations(
+            &["/a/report.txt", "/b/report.txt", "/b/c"],
+            dest,
+            &ExecuteOptions::default(),
+        );
+        assert_eq!(
+            flat,
+            [
+                dest.join("report.txt"),
+                dest.join("report (1).txt"),
+                dest.join("c")
+            ]
+        );
+
+        let opts = ExecuteOptions {
+            preserve_structure: true,
+            ..Default::default()
+        };
+        let planned = plan_destinations(
+            &[
+                r"C:\Users\u\a.txt",
+                r"C:\Users\v\b.txt",
+                r"D:\data\c.txt",
+                r"\\nas\share\d.txt",
+            ],
+            dest,
+            &opts,
+        );
+        assert_eq!(
+            planned,
+            [
+                dest.join("C").join("u").join("a.txt"),
+                dest.join("C").join("v").join("b.txt"),
+                dest.join("D").join("c.txt"),
+                dest.join("nas_share").join("d.txt"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sensitive_root_requires_confirmation() {
+        let home = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let docs = home.path().join("Documents");
+        let file = docs.join("thesis.docx");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(&file, "draft").unwrap();
+        let source = file.to_string_lossy().to_string();
+
+        let mut opts = ExecuteOptions {
+            sensitive_roots: vec![docs.to_string_lossy().to_string()],
+            ..Default::default()
+        };
+        let refused = move_files(&[&source], dest.path(), &opts).await;
+        assert!(matches!(
+            refused,
+            Err(DiskAnalyzerError::PermissionDenied(_))
+        ));
+        assert!(file.exists());
+
+        opts.confirm_sensitive = true;
+        let moved = move_files(&[&source], dest.path(), &opts).await.unwrap();
+        assert_eq!(moved, [dest.path().join("thesis.docx")]);
+        assert!(!file.exists());
+    }
+
+    /// 模拟跨卷：重命名总是返回 `CrossesDevices`
+    fn cross_device(_: &Path, _: &Path) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::CrossesDevices.into())
+    }
+
+    #[test]
+    fn test_move_item_same_volume_and_cross_device_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("a.bin");
+        std::fs::write(&from, [1u8; 64]).unwrap();
+        let to = dir.path().join("nested").join("b.bin");
+        assert_eq!(
+            move_item(&from, &to, ConflictPolicy::Skip).unwrap(),
+            MoveOutcome::Moved(to.clone())
+        );
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(&to).unwrap(), [1u8; 64]);
+
+        // 跨卷时复制整个目录后删除源目录，不留下临时路径
+        let src = dir.path().join("proj");
+        std::fs::create_dir_all(src.join("sub")).unwrap();
+        std::fs::write(src.join("x.txt"), "x").unwrap();
+        std::fs::write(src.join("sub").join("y.txt"), "yy").unwrap();
+        let dst = dir.path().join("other").join("proj");
+        let outcome = move_item_with(&src, &dst, ConflictPolicy::Skip, cross_device).unwrap();
+        assert_eq!(outcome, MoveOutcome::Moved(dst.clone()));
+        assert!(!src.exists());
+        assert_eq!(
+            std::fs::read_to_string(dst.join("sub").join("y.txt")).unwrap(),
+            "yy"
+        );
+        let names: Vec<String> = std::fs::read_dir(dir.path().join("other"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["proj"]);
+
+        // 其他重命名错误不回退为复制
+        let denied = |_: &Path, _: &Path| -> std::io::Result<()> {
+            Err(std::io::ErrorKind::PermissionDenied.into())
+        };
+        let back = dir.path().join("back");
+        assert!(move_item_with(&dst, &back, ConflictPolicy::Skip, denied).is_err());
+        assert!(dst.exists() && !back.exists());
+    }
+
+    #[test]
+    fn test_move_item_conflict_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, text: &str| {
+            let p = dir.path().join(name);
+            std::fs::write(&p, text).unwrap();
+            p
+        };
+        let dst = write("report.txt", "old");
+
+        let src = write("new.txt", "new");
+        assert_eq!(
+            move_item(&src, &dst, ConflictPolicy::Skip).unwrap(),
+            MoveOutcome::Skipped
+        );
+        assert_eq!(std::fs::read_to_string(&src).unwrap(), "new");
+
+        for expected in ["report (1).txt", "report (2).txt"] {
+            let src = write("new.txt", expected);
+            let renamed = dir.path().join(expected);
+            assert_eq!(
+                move_item(&src, &dst, ConflictPolicy::Rename).unwrap(),
+                MoveOutcome::Moved(renamed.clone())
+            );
+            assert_eq!(std::fs::read_to_string(&renamed).unwrap(), expected);
+        }
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "old");
+
+        let src = write("new.txt", "new");
+        move_item(&src, &dst, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "new");
+        assert!(!src.exists());
+
+        // 跨卷覆盖目录：旧目录整体被替换
+        let old_dir = dir.path().join("out");
+        std::fs::create_dir(&old_dir).unwrap();
+        std::fs::write(old_dir.join("stale.txt"), "stale").unwrap();
+        let new_dir = dir.path().join("in");
+        std::fs::create_dir(&new_dir).unwrap();
+        std::fs::write(new_dir.join("fresh.txt"), "fresh").unwrap();
+        move_item_with(&new_dir, &old_dir, ConflictPolicy::Overwrite, cross_device).unwrap();
+        assert!(!new_dir.exists() && !old_dir.join("stale.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(old_dir.join("fresh.txt")).unwrap(),
+            "fresh"
+        );
+        let leftovers = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with('.')
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+}
@@ -1,4 +1,379 @@
-/// 权限检查（预留）
-pub fn check_write_permission(_path: &str) -> bool {
-    true
+//! 执行前的权限预检：在真正删除/移动之前判断每个动作是否会因权限或保护规则失败。
+
+use std::fmt;
+use std::path::Path;
+
+use ai_disk_common::path::{is_under, CaseSensitivity};
+use ai_disk_domain::{explain, Action, CleanupPlan, FileNode};
+use serde::{Deserialize, Serialize};
+
+/// 动作预计失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionIssue {
+    /// 系统目录或受保护文件（见 `RiskExplanation::is_protected`）
+    Protected,
+    NotFound,
+    /// 文件带只读属性（Windows 上无法直接删除）
+    ReadOnly,
+    /// 所在目录不可写，需要管理员权限
+    NeedsElevation,
+    /// 提升权限也无法执行，如只读挂载的文件系统、ACL 拒绝管理员访问
+    Denied,
+    /// 移动的目的地已存在
+    TargetExists,
+}
+
+impl fmt::Display for PermissionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            PermissionIssue::Protected => "受保护路径，不允许清理",
+            PermissionIssue::NotFound => "路径不存在",
+            PermissionIssue::ReadOnly => "文件只读",
+            PermissionIssue::NeedsElevation => "所在目录不可写，需要管理员权限",
+            PermissionIssue::Denied => "无权限，提升权限也无法执行",
+            PermissionIssue::TargetExists => "目标位置已存在同名文件",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// 当前用户对路径的操作权限，供界面把无法执行的动作置灰
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionStatus {
+    Allowed,
+    /// 以管理员（root）身份运行后可以执行
+    NeedsElevation,
+    /// 提升权限也无法执行：路径不存在、只读属性（Windows）、只读挂载的文件系统等
+    Denied,
+}
+
+/// 单个动作的预检结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionCheck {
+    pub path: String,
+    /// None 表示预计可以执行
+    pub issue: Option<PermissionIssue>,
+}
+
+/// 权限检查：当前用户能否删除/修改该路径
+pub fn check_write_permission(path: &str) -> bool {
+    check_removable(Path::new(path)).is_none()
+}
+
+/// 能否删除（或移走）该路径：Unix 看所在目录的写权限与粘滞位下的所有权，
+/// Windows 看只读属性与 ACL 中的删除权限
+pub fn can_delete(path: &Path) -> PermissionStatus {
+    if std::fs::symlink_metadata(path).is_err() {
+        return PermissionStatus::Denied;
+    }
+    platform::can_delete(path)
+}
+
+/// 能否修改该路径；路径不存在时看能否在最近的已存在上级目录中创建
+pub fn can_write(path: &Path) -> PermissionStatus {
+    if std::fs::symlink_metadata(path).is_ok() {
+        return platform::can_write(path);
+    }
+    match path.ancestors().skip(1).find(|p| p.exists()) {
+        Some(dir) => platform::can_write(dir),
+        None => PermissionStatus::Denied,
+    }
+}
+
+/// `path` 所在的敏感目录（按本机文件系统的大小写规则比较）
+pub fn sensitive_root<'a>(path: &str, roots: &'a [String]) -> Option<&'a str> {
+    let case = CaseSensitivity::native();
+    roots
+        .iter()
+        .find(|root| !root.trim().is_empty() && is_under(path, root, case))
+        .map(String::as_str)
+}
+
+/// 逐个预检计划中的动作，顺序与 `plan.actions` 一致
+pub fn check_plan_permissions(plan: &CleanupPlan) -> Vec<PermissionCheck> {
+    plan.actions
+        .iter()
+        .map(|action| PermissionCheck {
+            path: action.target_path().to_string(),
+            issue: check_action(action),
+        })
+        .collect()
+}
+
+fn check_action(action: &Action) -> Option<PermissionIssue> {
+    let target = Path::new(action.target_path());
+    let is_dir = target.is_dir();
+    let node = FileNode {
+        path: action.target_path().to_string(),
+        name: ai_disk_common::path::file_name(action.target_path()).to_string(),
+        is_dir,
+        ..Default::default()
+    };
+    // 保留动作不触碰文件
+    if matches!(action, Action::MarkKeep { .. }) {
+        return None;
+    }
+    if explain(&node).is_protected() {
+        return Some(PermissionIssue::Protected);
+    }
+    match action {
+        Action::Empty { .. } => {
+            if !target.exists() {
+                Some(PermissionIssue::NotFound)
+            } else {
+                to_issue(can_write(target), target)
+            }
+        }
+        Action::Move { to, .. } => check_removable(target).or_else(|| {
+            let dest = Path::new(to);
+            if std::fs::symlink_metadata(dest).is_ok() {
+                return Some(PermissionIssue::TargetExists);
+            }
+            to_issue(can_write(dest), dest)
+        }),
+        Action::Delete { .. } | Action::Trash { .. } => check_removable(target),
+        Action::MarkKeep { .. } => None,
+    }
+}
+
+/// 删除/移走路径所需的权限，见 `can_delete`
+fn check_removable(path: &Path) -> Option<PermissionIssue> {
+    if std::fs::symlink_metadata(path).is_err() {
+        return Some(PermissionIssue::NotFound);
+    }
+    to_issue(can_delete(path), path)
+}
+
+/// 把 `can_delete`/`can_write` 的结果转为预检原因；Windows 上带只读属性的文件单独标出
+fn to_issue(status: PermissionStatus, path: &Path) -> Option<PermissionIssue> {
+    match status {
+        PermissionStatus::Allowed => None,
+        PermissionStatus::NeedsElevation => Some(PermissionIssue::NeedsElevation),
+        PermissionStatus::Denied
+            if cfg!(windows)
+                && std::fs::symlink_metadata(path).is_ok_and(|m| m.permissions().readonly()) =>
+        {
+            Some(PermissionIssue::ReadOnly)
+        }
+        PermissionStatus::Denied => Some(PermissionIssue::Denied),
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::fs::Metadata;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    use super::PermissionStatus;
+
+    pub(super) fn can_delete(path: &Path) -> PermissionStatus {
+        let parent = match path.parent() {
+            Some(p) if p.as_os_str().is_empty() => Path::new("."),
+            Some(p) => p,
+            None => return PermissionStatus::Denied,
+        };
+        let (Ok(file), Ok(dir)) = (std::fs::symlink_metadata(path), std::fs::metadata(parent))
+        else {
+            return PermissionStatus::Denied;
+        };
+        if is_read_only_fs(parent) {
+            return PermissionStatus::Denied;
+        }
+        let euid = effective_uid();
+        // 删除目录项需要所在目录的写和执行权限；带粘滞位的目录（如 /tmp）还要求是文件或目录的所有者
+        let sticky = dir.mode() & 0o1000 != 0 && file.uid() != euid && dir.uid() != euid;
+        if euid == 0 || (has_access(&dir, euid, 0o3) && !sticky) {
+            PermissionStatus::Allowed
+        } else {
+            PermissionStatus::NeedsElevation
+        }
+    }
+
+    pub(super) fn can_write(path: &Path) -> PermissionStatus {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return PermissionStatus::Denied;
+        };
+        if is_read_only_fs(path) {
+            return PermissionStatus::Denied;
+        }
+        let euid = effective_uid();
+        // 在目录中创建文件还需要执行权限
+        let need = if metadata.is_dir() { 0o3 } else { 0o2 };
+        if euid == 0 || has_access(&metadata, euid, need) {
+            PermissionStatus::Allowed
+        } else {
+            PermissionStatus::NeedsElevation
+        }
+    }
+
+    /// 按所有者、所属组、其他用户取适用的权限位，`need` 为 `wx` 位的组合
+    fn has_access(metadata: &Metadata, euid: u32, need: u32) -> bool {
+        let mode = metadata.mode();
+        let bits = if metadata.uid() == euid {
+            mode >> 6
+        } else if in_group(metadata.gid()) {
+            mode >> 3
+        } else {
+            mode
+        };
+        bits & need == need
+    }
+
+    #[allow(unsafe_code)]
+    pub(super) fn effective_uid() -> u32 {
+        unsafe { libc::geteuid() }
+    }
+
+    #[allow(unsafe_code)]
+    fn in_group(gid: u32) -> bool {
+        if unsafe { libc::getegid() } == gid {
+            return true;
+        }
+        let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+        if count <= 0 {
+            return false;
+        }
+        let mut groups: Vec<libc::gid_t> = vec![0; count as usize];
+        let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+        groups[..count.max(0) as usize].contains(&gid)
+    }
+
+    #[allow(unsafe_code)]
+    fn is_read_only_fs(path: &Path) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+        let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        unsafe {
+            libc::statvfs(path.as_ptr(), &mut stat) == 0 && stat.f_flag & libc::ST_RDONLY != 0
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::path::Path;
+
+    use super::PermissionStatus;
+
+    const DELETE: u32 = 0x0001_0000;
+    /// 文件为写入数据，目录为在其中创建文件（FILE_ADD_FILE）
+    const FILE_WRITE_DATA: u32 = 0x0002;
+    const FILE_SHARE_ALL: u32 = 0x7;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x0020_0000;
+
+    pub(super) fn can_delete(path: &Path) -> PermissionStatus {
+        match std::fs::symlink_metadata(path) {
+            Ok(m) if !m.permissions().readonly() => probe(path, DELETE),
+            _ => PermissionStatus::Denied,
+        }
+    }
+
+    pub(super) fn can_write(path: &Path) -> PermissionStatus {
+        match std::fs::metadata(path) {
+            Ok(m) if m.is_dir() || !m.permissions().readonly() => probe(path, FILE_WRITE_DATA),
+            _ => PermissionStatus::Denied,
+        }
+    }
+
+    /// 以指定访问权限打开（不读写内容）来检查 ACL；被拒绝时按是否已提升权限区分
+    fn probe(path: &Path, access: u32) -> PermissionStatus {
+        let opened = std::fs::OpenOptions::new()
+            .access_mode(access)
+            .share_mode(FILE_SHARE_ALL)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+            .open(path);
+        match opened {
+            Ok(_) => PermissionStatus::Allowed,
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                if is_elevated::is_elevated() {
+                    PermissionStatus::Denied
+                } else {
+                    PermissionStatus::NeedsElevation
+                }
+            }
+            // 被其他进程独占等情况无法据此判断权限，交给执行时处理
+            Err(_) => PermissionStatus::Allowed,
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::path::Path;
+
+    use super::PermissionStatus;
+
+    pub(super) fn can_delete(_path: &Path) -> PermissionStatus {
+        PermissionStatus::Allowed
+    }
+
+    pub(super) fn can_write(_path: &Path) -> PermissionStatus {
+        PermissionStatus::Allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_file_is_not_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        let locked_dir = dir.path().join("locked");
+        std::fs::create_dir(&locked_dir).unwrap();
+        let file = locked_dir.join("stuck.tmp");
+        std::fs::write(&file, [0u8; 8]).unwrap();
+        let mut perms = std::fs::metadata(&file).unwrap().permissions();
+        perms.set_readonly(true);
+        std::fs::set_permissions(&file, perms.clone()).unwrap();
+        // Unix 上能否删除取决于所在目录
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+        }
+
+        let status = (can_write(&file), can_delete(&file));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        #[allow(clippy::permissions_set_readonly_false)]
+        perms.set_readonly(false);
+        std::fs::set_permissions(&file, perms).unwrap();
+
+        #[cfg(windows)]
+        let expected = (PermissionStatus::Denied, PermissionStatus::Denied);
+        // root 不受权限位限制
+        #[cfg(unix)]
+        let expected = if platform::effective_uid() == 0 {
+            (PermissionStatus::Allowed, PermissionStatus::Allowed)
+        } else {
+            (
+                PermissionStatus::NeedsElevation,
+                PermissionStatus::NeedsElevation,
+            )
+        };
+        #[cfg(any(unix, windows))]
+        assert_eq!(status, expected);
+
+        assert_eq!(can_write(&file), PermissionStatus::Allowed);
+        assert_eq!(can_delete(&file), PermissionStatus::Allowed);
+        assert_eq!(
+            can_write(&dir.path().join("new").join("a.txt")),
+            PermissionStatus::Allowed
+        );
+        assert_eq!(
+            can_delete(&dir.path().join("missing.tmp")),
+            PermissionStatus::Denied
+        );
+    }
 }
@@ -0,0 +1,255 @@
+//! 隔离区删除：先把文件或目录移到应用管理的隔离目录并记录到期时间，宽限期内可原样恢复，
+//! 到期后由 `purge_expired`（启动时或后台定期调用）永久删除。比回收站更可控：
+//! 保留策略由应用决定，且不受系统清空回收站影响。
+//!
+//! 隔离目录结构：`index.json` 记录所有条目，`items/<id>/<原文件名>` 存放被隔离的内容。
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ai_disk_common::{path, telemetry, DiskAnalyzerError};
+use serde::{Deserialize, Serialize};
+
+use crate::r#move::{logical_size, move_item, ConflictPolicy, MoveOutcome};
+
+const INDEX_FILE: &str = "index.json";
+const ITEMS_DIR: &str = "items";
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// 当前时间（Unix 秒），测试中可注入固定时钟
+pub type Clock = Box<dyn Fn() -> u64 + Send + Sync>;
+
+/// 隔离删除策略（见 `ExecuteOptions::quarantine`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinePolicy {
+    /// 隔离目录；与被删除的文件位于同一卷时直接重命名，否则复制后删除源路径
+    pub dir: PathBuf,
+    /// 宽限天数，到期后永久删除
+    pub grace_days: u32,
+}
+
+/// 一个被隔离的文件或目录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub id: u64,
+    /// 隔离前的路径，恢复时移回此处
+    pub original: String,
+    pub is_dir: bool,
+    /// 逻辑大小（目录为递归之和，见 `logical_size`）
+    pub size: u64,
+    pub quarantined_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    next_id: u64,
+    entries: Vec<QuarantineEntry>,
+}
+
+/// 隔离区，每次修改后立即写回 `index.json`
+pub struct Quarantine {
+    dir: PathBuf,
+    grace_days: u32,
+    index: Index,
+    clock: Clock,
+}
+
+impl Quarantine {
+    /// 打开（必要时创建）隔离目录，读取已有的条目
+    pub fn open(policy: &QuarantinePolicy) -> Result<Self, DiskAnalyzerError> {
+        std::fs::create_dir_all(policy.dir.join(ITEMS_DIR))?;
+        let index = match std::fs::read_to_string(policy.dir.join(INDEX_FILE)) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| {
+                DiskAnalyzerError::Config(format!("隔离区索引损坏 {}: {}", policy.dir.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Index::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            dir: policy.dir.clone(),
+            grace_days: policy.grace_days,
+            index,
+            clock: Box::new(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0)
+            }),
+        })
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 尚在隔离区中的条目
+    pub fn entries(&self) -> &[QuarantineEntry] {
+        &self.index.entries
+    }
+
+    /// 把 `path` 移入隔离区，宽限期从现在算起
+    pub fn quarantine(&mut self, target: &Path) -> Result<QuarantineEntry, DiskAnalyzerError> {
+        let original = target.to_string_lossy().to_string();
+        let _span = telemetry::execute_span("quarantine", &original).entered();
+        let metadata = std::fs::symlink_metadata(target)?;
+        let id = self.index.next_id + 1;
+        let slot = self.dir.join(ITEMS_DIR).join(id.to_string());
+        std::fs::create_dir_all(&slot)?;
+        let stored = slot.join(path::file_name(&original));
+        if let Err(e) = relocate(target, &stored) {
+            let _ = std::fs::remove_dir(&slot);
+            return Err(e);
+        }
+        let now = (self.clock)();
+        let entry = QuarantineEntry {
+            id,
+            original,
+            is_dir: metadata.is_dir(),
+            size: logical_size(&stored),
+            quarantined_at: now,
+            expires_at: now + u64::from(self.grace_days) * SECS_PER_DAY,
+        };
+        self.index.next_id = id;
+        self.index.entries.push(entry.clone());
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// 把条目移回原路径；原路径已被占用时拒绝，条目保持隔离
+    pub fn restore(&mut self, id: u64) -> Result<QuarantineEntry, DiskAnalyzerError> {
+        let pos = self.position(id)?;
+        let entry = self.index.entries[pos].clone();
+        let original = Path::new(&entry.original);
+        if original.exists() {
+            return Err(DiskAnalyzerError::InvalidPath(format!(
+                "{} 已存在，无法恢复",
+                entry.original
+            )));
+        }
+        if let Some(parent) = original.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        relocate(&self.stored_path(&entry), original)?;
+        let _ = std::fs::remove_dir(self.slot(id));
+        self.index.entries.remove(pos);
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// 永久删除所有已过宽限期的条目，返回被删除的条目
+    pub fn purge_expired(&mut self) -> Result<Vec<QuarantineEntry>, DiskAnalyzerError> {
+        let now = (self.clock)();
+        let (expired, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.index.entries)
+            .into_iter()
+            .partition(|e| e.expires_at <= now);
+        self.index.entries = kept;
+        let mut purged = Vec::new();
+        for entry in expired {
+            match std::fs::remove_dir_all(self.slot(entry.id)) {
+                Ok(()) => purged.push(entry),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => purged.push(entry),
+                Err(e) => {
+                    // 删除失败的条目留待下次清理
+                    tracing::warn!("清理隔离条目 {} 失败: {}", entry.original, e);
+                    self.index.entries.push(entry);
+                }
+            }
+        }
+        self.save()?;
+        Ok(purged)
+    }
+
+    fn position(&self, id: u64) -> Result<usize, DiskAnalyzerError> {
+        self.index
+            .entries
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| DiskAnalyzerError::InvalidPath(format!("隔离区中没有条目 {}", id)))
+    }
+
+    fn slot(&self, id: u64) -> PathBuf {
+        self.dir.join(ITEMS_DIR).join(id.to_string())
+    }
+
+    fn stored_path(&self, entry: &QuarantineEntry) -> PathBuf {
+        self.slot(entry.id).join(path::file_name(&entry.original))
+    }
+
+    fn save(&self) -> Result<(), DiskAnalyzerError> {
+        let text = serde_json::to_string_pretty(&self.index)
+            .map_err(|e| DiskAnalyzerError::Config(format!("写入隔离区索引失败: {}", e)))?;
+        let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, self.dir.join(INDEX_FILE))?;
+        Ok(())
+    }
+}
+
+/// 移动到 `to`（不应已存在），跨卷时由 `move_item` 复制后删除源路径
+fn relocate(from: &Path, to: &Path) -> Result<(), DiskAnalyzerError> {
+    match move_item(from, to, ConflictPolicy::Skip)? {
+        MoveOutcome::Moved(_) => Ok(()),
+        MoveOutcome::Skipped => Err(DiskAnalyzerError::InvalidPath(format!(
+            "{} 已存在",
+            to.display()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_restore_before_expiry_and_purge_after() {
+        let home = tempfile::tempdir().unwrap();
+        let policy = QuarantinePolicy {
+            dir: home.path().join("quarantine"),
+            grace_days: 7,
+        };
+        let now = Arc::new(AtomicU64::new(1_000_000));
+        let clock = |now: &Arc<AtomicU64>| -> Clock {
+            let now = now.clone();
+            Box::new(move || now.load(Ordering::Relaxed))
+        };
+
+        let project = home.path().join("old-project");
+        std::fs::create_dir_all(project.join("build")).unwrap();
+        std::fs::write(project.join("build").join("app.bin"), [1u8; 300]).unwrap();
+        let video = home.path().join("clip.mp4");
+        std::fs::write(&video, [2u8; 500]).unwrap();
+
+        let mut q = Quarantine::open(&policy).unwrap().with_clock(clock(&now));
+        let dir_entry = q.quarantine(&project).unwrap();
+        let file_entry = q.quarantine(&video).unwrap();
+        assert!(!project.exists() && !video.exists());
+        assert_eq!((dir_entry.size, file_entry.size), (300, 500));
+        assert_eq!(dir_entry.expires_at, 1_000_000 + 7 * SECS_PER_DAY);
+
+        // 宽限期内：重新打开后仍能看到条目并恢复
+        now.fetch_add(6 * SECS_PER_DAY, Ordering::Relaxed);
+        let mut q = Quarantine::open(&policy).unwrap().with_clock(clock(&now));
+        assert!(q.purge_expired().unwrap().is_empty());
+        assert_eq!(q.entries().len(), 2);
+        q.restore(dir_entry.id).unwrap();
+        assert_eq!(
+            std::fs::read(project.join("build").join("app.bin")).unwrap(),
+            [1u8; 300]
+        );
+
+        // 到期后永久删除，无法再恢复
+        now.fetch_add(SECS_PER_DAY, Ordering::Relaxed);
+        let purged = q.purge_expired().unwrap();
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0], file_entry);
+        assert!(q.entries().is_empty());
+        assert!(!q.slot(file_entry.id).exists());
+        assert!(q.restore(file_entry.id).is_err());
+        assert!(!video.exists());
+    }
+}
@@ -0,0 +1,46 @@
+//! 移到回收站：Windows 使用系统回收站，Linux 按 XDG 回收站规范（`~/.local/share/Trash` 或所在卷的
+//! `.Trash-$uid`），macOS 移到废纸篓。与永久删除不同，用户误删后可在系统的回收站中还原。
+
+use std::path::Path;
+
+use ai_disk_common::{telemetry, DiskAnalyzerError};
+
+/// 把文件或目录（连同其内容）移到回收站；路径不存在时返回 NotFound
+pub fn move_to_trash(path: &Path) -> Result<(), DiskAnalyzerError> {
+    let _span = telemetry::execute_span("trash", &path.to_string_lossy()).entered();
+    if std::fs::symlink_metadata(path).is_err() {
+        return Err(DiskAnalyzerError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("路径不存在: {}", path.display()),
+        )));
+    }
+    ::trash::delete(path).map_err(|e| {
+        DiskAnalyzerError::Io(std::io::Error::other(format!(
+            "移到回收站失败 {}: {}",
+            path.display(),
+            e
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 会把文件移入当前用户真实的系统回收站，无回收站的无头 CI 上也会失败；
+    // 需要时用 `cargo test -p ai-disk-executor -- --ignored` 手动运行
+    #[test]
+    #[ignore = "moves a file into the real system trash"]
+    fn test_move_to_trash_removes_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("draft.txt");
+        std::fs::write(&file, "keep me recoverable").unwrap();
+
+        move_to_trash(&file).unwrap();
+        assert!(!file.exists());
+        assert!(matches!(
+            move_to_trash(&file),
+            Err(DiskAnalyzerError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+}